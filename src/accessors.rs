@@ -0,0 +1,220 @@
+/// this file contains the accessors of the states of a struct, which are generated by the `#[type_state]` macro:
+/// the data of the states that carry it (`state_data`), the names of the current states (`state_name`),
+/// along with their variants of the enum of the states (`current_state`), and the names of all the states (`ALL_STATES`)
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{punctuated::Punctuated, Fields, Generics, Ident, Token, Variant};
+
+use crate::{generic_args, state_enum_name, strip_generic_defaults, TypeStateArgs};
+
+/// Generates the `state_data` accessors of the states that carry data, for each slot they are allowed in
+/// (`state_data` for a single slot, and `<slot name>_state_data` or `state_data_<slot number>` otherwise):
+///
+/// `fn state_data(&self) -> &Connected` for `Session<Connected>`
+pub fn state_data_accessors(
+    struct_name: &Ident,
+    generics: &Generics,
+    state_idents: &[Ident],
+    type_state_args: &TypeStateArgs,
+    enum_variants: Option<&Punctuated<Variant, Token![,]>>,
+) -> Vec<TokenStream> {
+    let slots = &type_state_args.slots;
+    let state_field = &type_state_args.field;
+    type_state_args
+        .state_fields
+        .iter()
+        .flat_map(|(state, _)| {
+            (0..slots.len())
+                .filter(|&index| type_state_args.states_of_slot(index).contains(state))
+                .map(|index| (state.clone(), index))
+                .collect::<Vec<_>>()
+        })
+        .map(|(state, index)| {
+            let state_generics = type_state_args
+                .generics_of_state(&state)
+                .cloned()
+                .unwrap_or_default();
+            let (_, state_type_generics, _) = state_generics.split_for_impl();
+            let state_params = state_generics.params.iter();
+
+            let original_params = strip_generic_defaults(&generics.params);
+            let original_params = original_params.iter();
+            let original_args = generic_args(&generics.params);
+            let original_where_clause = &generics.where_clause;
+
+            let other_slots: Vec<_> = state_idents
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(other, ident)| {
+                    let sealer = type_state_args.sealer_of_slot(other, struct_name);
+                    quote!(#ident: #sealer)
+                })
+                .collect();
+            let slot_args = state_idents.iter().enumerate().map(|(other, ident)| {
+                if other == index {
+                    quote!(#state #state_type_generics)
+                } else {
+                    quote!(#ident)
+                }
+            });
+
+            // the `_state` field (or the last element of the tuple structs, or the `_state` of the variant for the enums)
+            let member = match type_state_args.state_member() {
+                Some(member) => quote!(#member),
+                None => quote!(#state_field),
+            };
+            let (accessor, field) = if slots.len() == 1 {
+                (Ident::new("state_data", state.span()), member)
+            } else {
+                let accessor = match &slots[index].name {
+                    Some(name) => format!("{}_state_data", name),
+                    None => format!("state_data_{}", index + 1),
+                };
+                let index = syn::Index::from(index);
+                (Ident::new(&accessor, state.span()), quote!(#member.#index))
+            };
+            let doc = format!(" The data of the `{}` state", state);
+            // each variant of the enums has its own `_state` field
+            let state_data = match enum_variants {
+                None => quote!(&self.#field),
+                Some(variants) => {
+                    let patterns = variants.iter().map(|variant| {
+                        let variant_name = &variant.ident;
+                        match variant.fields {
+                            Fields::Unnamed(_) => quote!(Self::#variant_name(.., #state_field)),
+                            _ => quote!(Self::#variant_name { #state_field, .. }),
+                        }
+                    });
+                    quote! {
+                        match self {
+                            #(#patterns => &#field,)*
+                        }
+                    }
+                }
+            };
+            let cfgs = type_state_args.cfgs_of_state(&state);
+
+            quote! {
+                #(#cfgs)*
+                impl<#(#original_params,)* #(#state_params,)* #(#other_slots),*>
+                    #struct_name<#(#original_args,)* #(#slot_args),*>
+                #original_where_clause
+                {
+                    #[doc = #doc]
+                    #[allow(dead_code)]
+                    pub fn #accessor(&self) -> &#state #state_type_generics {
+                        #state_data
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// Generates the `state_name` accessors, which tell the state of each slot by the names given by the sealers
+/// (`state_name` for a single slot, and `<slot name>_state_name` or `state_name_<slot number>` otherwise),
+/// along with the `current_state` accessors, which tell the variants of the enum of the states, with `state_enum = true`
+pub fn state_name_accessors(
+    struct_name: &Ident,
+    generics: &Generics,
+    state_idents: &[Ident],
+    type_state_args: &TypeStateArgs,
+) -> TokenStream {
+    let slots = &type_state_args.slots;
+    let state_enum_name = state_enum_name(struct_name);
+    let original_params = strip_generic_defaults(&generics.params);
+    let original_params = original_params.iter();
+    let original_args = generic_args(&generics.params);
+    let original_where_clause = &generics.where_clause;
+    let sealers: Vec<_> = (0..slots.len())
+        .map(|index| type_state_args.sealer_of_slot(index, struct_name))
+        .collect();
+    let accessors =
+        state_idents
+            .iter()
+            .zip(&sealers)
+            .enumerate()
+            .map(|(index, (state_ident, sealer))| {
+                let (accessor, doc) = if slots.len() == 1 {
+                    (
+                        "state_name".to_string(),
+                        " The name of the current state".to_string(),
+                    )
+                } else {
+                    match &slots[index].name {
+                        Some(name) => (
+                            format!("{}_state_name", name),
+                            format!(" The name of the current state of the `{}` slot", name),
+                        ),
+                        None => (
+                            format!("state_name_{}", index + 1),
+                            format!(" The name of the current state of the slot {}", index + 1),
+                        ),
+                    }
+                };
+                // the variants are given by the struct's own sealer only (not by the ones of the components)
+                let current_state = (type_state_args.state_enum
+                    && type_state_args.slot_component(index).is_none())
+                .then(|| {
+                    let accessor = Ident::new(
+                        &accessor.replace("state_name", "current_state"),
+                        struct_name.span(),
+                    );
+                    let doc = doc.replace("The name of the current state", "The current state");
+                    quote! {
+                        #[doc = #doc]
+                        #[allow(dead_code)]
+                        pub fn #accessor(&self) -> #state_enum_name {
+                            <#state_ident as #sealer>::STATE
+                        }
+                    }
+                });
+                let accessor = Ident::new(&accessor, struct_name.span());
+                quote! {
+                    #[doc = #doc]
+                    #[allow(dead_code)]
+                    pub fn #accessor(&self) -> &'static str {
+                        <#state_ident as #sealer>::STATE_NAME
+                    }
+
+                    #current_state
+                }
+            });
+    quote! {
+        impl<#(#original_params,)* #(#state_idents: #sealers),*>
+            #struct_name<#(#original_args,)* #(#state_idents),*>
+        #original_where_clause
+        {
+            #(#accessors)*
+        }
+    }
+}
+
+/// Generates the list of the declared states, for the default states only (like `transition_in_place`),
+/// so that it can be used without specifying the states: `Player::ALL_STATES`
+pub fn all_states(
+    struct_name: &Ident,
+    generics: &Generics,
+    type_state_args: &TypeStateArgs,
+) -> TokenStream {
+    let original_params = strip_generic_defaults(&generics.params);
+    let original_params = original_params.iter();
+    let original_args = generic_args(&generics.params);
+    let original_where_clause = &generics.where_clause;
+    let default_slots = type_state_args.slots.iter().map(|slot| &slot.default);
+    let names = type_state_args.states.iter().map(|state| {
+        let cfgs = type_state_args.cfgs_of_state(state);
+        let name = type_state_args.unprefixed_name(state).to_string();
+        quote!(#(#cfgs)* #name)
+    });
+    quote! {
+        impl<#(#original_params),*> #struct_name<#(#original_args,)* #(#default_slots),*>
+        #original_where_clause
+        {
+            /// The names of all the declared states, in the order of their declaration
+            #[allow(dead_code)]
+            pub const ALL_STATES: &'static [&'static str] = &[#(#names),*];
+        }
+    }
+}
@@ -0,0 +1,125 @@
+/// this file contains the type aliases of the struct in each of its states: `type IdlePlayer = Player<Idle>`,
+/// which are generated by the `#[type_state]` macro, when they are opted into with `aliases = true`
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{punctuated::Punctuated, GenericParam, Generics, Ident, Token, Visibility};
+
+use crate::{generic_args, strip_generic_defaults, TypeStateArgs};
+
+/// The naming of the aliases, when it is not given: `aliases = true` -> `IdlePlayer`
+pub const DEFAULT_ALIAS_NAMING: &str = "{state}{struct}";
+
+/// The generic parameters of an alias, without their bounds, which are not enforced for the type aliases:
+/// `T: Transport = Tcp` -> `T = Tcp`
+fn alias_params(params: &Punctuated<GenericParam, Token![,]>) -> Vec<TokenStream> {
+    params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Lifetime(lifetime) => {
+                let lifetime = &lifetime.lifetime;
+                quote!(#lifetime)
+            }
+            GenericParam::Type(type_param) => {
+                let ident = &type_param.ident;
+                let default = type_param
+                    .default
+                    .as_ref()
+                    .map(|default| quote!(= #default));
+                quote!(#ident #default)
+            }
+            GenericParam::Const(const_param) => {
+                let ident = &const_param.ident;
+                let ty = &const_param.ty;
+                let default = const_param
+                    .default
+                    .as_ref()
+                    .map(|default| quote!(= #default));
+                quote!(const #ident: #ty #default)
+            }
+        })
+        .collect()
+}
+
+/// Generates the type alias of the struct in each of its states, named after the unprefixed states:
+///
+/// `type IdlePlayer = Player<Idle>`, `type LoadedClient<T, U> = Client<T, Loaded<U>>`
+///
+/// the naming is given with `{state}` and `{struct}`: `aliases = "{struct}In{state}"` -> `PlayerInIdle`
+/// (the defaults of the generics of the struct are kept, unless the state is generic too)
+pub fn state_aliases(
+    struct_name: &Ident,
+    visibility: &Visibility,
+    generics: &Generics,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<TokenStream> {
+    let Some(naming) = &type_state_args.aliases else {
+        return Ok(quote!());
+    };
+    if type_state_args.slots.len() != 1 || type_state_args.slot_component(0).is_some() {
+        return Err(syn::Error::new(
+            struct_name.span(),
+            "`aliases` can only be used with a single slot of the struct's own states",
+        ));
+    }
+    if !naming.contains("{state}") {
+        return Err(syn::Error::new(
+            struct_name.span(),
+            format!(
+                "the naming of the aliases needs `{{state}}`, since each state has its own alias: `\"{}\"`",
+                DEFAULT_ALIAS_NAMING
+            ),
+        ));
+    }
+
+    let args = generic_args(&generics.params);
+    let aliases = type_state_args
+        .states_of_slot(0)
+        .iter()
+        .map(|state| {
+            let unprefixed = type_state_args.unprefixed_name(state);
+            let name = naming
+                .replace("{state}", &unprefixed.to_string())
+                .replace("{struct}", &struct_name.to_string());
+            let alias: Ident = syn::parse_str(&name).map_err(|_| {
+                syn::Error::new(
+                    struct_name.span(),
+                    format!(
+                        "the alias of `{}` is not a valid name: `{}`",
+                        unprefixed, name
+                    ),
+                )
+            })?;
+
+            let cfgs = type_state_args.cfgs_of_state(state);
+            let doc = format!(" `{}` in the `{}` state", struct_name, unprefixed);
+            let state_generics = type_state_args
+                .generics_of_state(state)
+                .cloned()
+                .unwrap_or_default();
+            let state_args = generic_args(&state_generics.params);
+            let state_type = if state_generics.params.is_empty() {
+                quote!(#state)
+            } else {
+                quote!(#state<#(#state_args),*>)
+            };
+            // the defaults are trailing only if no parameters of the state come after them
+            let struct_params = if state_generics.params.is_empty() {
+                alias_params(&generics.params)
+            } else {
+                alias_params(&strip_generic_defaults(&generics.params))
+            };
+            let params = struct_params
+                .into_iter()
+                .chain(alias_params(&state_generics.params));
+
+            Ok(quote! {
+                #(#cfgs)*
+                #[doc = #doc]
+                #[allow(dead_code)]
+                #visibility type #alias<#(#params),*> = #struct_name<#(#args,)* #state_type>;
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote!(#(#aliases)*))
+}
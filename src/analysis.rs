@@ -0,0 +1,1572 @@
+/// this file contains the analysis of the transitions declared in an `impl` block by `#[require]` and `#[switch_to]`,
+/// which reports the problems of the state machine as a whole (like the states that can never be reached),
+/// when they are opted into with `warn(...)` or `deny(...)` in `#[type_state]` (see `Lint`)
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use stringcase::{pascal_case, snake_case};
+use syn::{
+    punctuated::Punctuated, Attribute, FnArg, Ident, ImplItemFn, ReturnType, Signature, Token,
+    Type, Visibility,
+};
+
+use crate::{
+    any_state_enum_name, cfg_attrs, declared_transitions_fn, foreign_hook_check,
+    state_tests_macro_name, Lint, LintLevel, RequiredSlot, StateArg, StateHooks, StateInvariant,
+    TypeStateArgs,
+};
+
+/// An edge of the drawings of the state machine (see `TransitionGraph::diagram_slots`),
+/// labeled with the method, from the start if there is no `from`, and to the end if there is no `to`
+struct DiagramEdge<'a> {
+    from: Option<&'a Ident>,
+    to: Option<&'a Ident>,
+    method: Option<&'a Ident>,
+}
+
+/// The state machine as the model checkers see it (see `TransitionGraph::model`)
+struct Model<'a> {
+    /// the name of the variable of each slot, along with its states
+    variables: Vec<(String, &'a [Ident])>,
+    /// the states each variable can start in, for each way to build the struct
+    inits: Vec<Vec<&'a [Ident]>>,
+    actions: Vec<ModelAction<'a>>,
+    /// the terminal states of each variable
+    terminal: Vec<Vec<&'a Ident>>,
+}
+
+/// A method that transitions the struct, for the model checkers, with the states it can be called in
+/// (none if it can be called in any state) and the states it transitions into (none if it keeps the state), for each variable
+struct ModelAction<'a> {
+    method: &'a Ident,
+    slots: Vec<(&'a [Ident], &'a [Ident])>,
+}
+
+/// The transition of a slot by a method, for the table of the transitions (see `TransitionGraph::table`)
+struct MethodTransition {
+    method: Ident,
+    cfgs: Vec<Attribute>,
+    slot: usize,
+    /// the states the method can be called in (none for the constructors)
+    from: Vec<Ident>,
+    /// the states the method transitions into (none if it keeps the state of the slot)
+    to: Vec<Ident>,
+    /// whether the method switches the slot with `#[switch_to]` (the constructors without it only produce their states)
+    switched: bool,
+}
+
+/// A method that is called on the struct in its states, for the tests of `generate_state_tests!`
+/// (see `TransitionGraph::state_tests`)
+struct CallableMethod {
+    method: Ident,
+    /// the number of the arguments, besides `self`
+    args: usize,
+    /// the states the method can be called in, for each slot (none for the components)
+    from: Vec<Vec<Ident>>,
+}
+
+/// A method that can be taken as a step of the generated call sequences, for `#[impl_state(proptest)]`
+/// (see `TransitionGraph::proptest`)
+struct StepMethod {
+    method: Ident,
+    /// whether the method takes `self` by value (or by reference otherwise)
+    by_value: bool,
+    output: ReturnType,
+    /// the types of the arguments, besides `self`
+    args: Vec<Type>,
+    /// the states the method can be called in
+    from: Vec<Ident>,
+    /// the state the method transitions into (none if it keeps the state)
+    to: Option<Ident>,
+}
+
+/// A method that transitions the struct, for the dual of the transitions (see `TransitionGraph::dual`)
+struct DualMethod {
+    sig: Signature,
+    /// the states the method can be called in
+    from: Vec<Ident>,
+    /// the states the method transitions into
+    to: Vec<Ident>,
+    cfgs: Vec<Attribute>,
+}
+
+/// The transitions between the states of each slot, collected from the methods of an `impl` block
+///
+/// the slots are analyzed separately, since the methods transition them independently:
+/// `#[require(Idle, _)] #[switch_to(Running, _)]` is an edge from `Idle` to `Running` in the first slot only
+pub struct TransitionGraph<'a> {
+    type_state_args: &'a TypeStateArgs,
+    /// the states that the values start in: the default states, and the ones produced by the constructors
+    roots: Vec<(usize, Ident)>,
+    /// `(slot, from, to)`
+    edges: Vec<(usize, Ident, Ident)>,
+    /// the states in which a method (other than a constructor) can be called
+    callable: Vec<(usize, Ident)>,
+    /// the transitions of each method, in the order of the methods
+    methods: Vec<MethodTransition>,
+    /// the methods that can be called from the other crates, in the order of the methods
+    callable_methods: Vec<CallableMethod>,
+    /// the methods that can be taken as the steps of the call sequences, in the order of the methods
+    step_methods: Vec<StepMethod>,
+    /// the methods that transition the struct, in the order of the methods
+    dual_methods: Vec<DualMethod>,
+}
+
+impl<'a> TransitionGraph<'a> {
+    pub fn new(type_state_args: &'a TypeStateArgs) -> Self {
+        let roots = type_state_args
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.component.is_none())
+            .map(|(index, slot)| (index, slot.default.clone()))
+            .collect();
+
+        TransitionGraph {
+            type_state_args,
+            roots,
+            edges: Vec::new(),
+            callable: Vec::new(),
+            methods: Vec::new(),
+            callable_methods: Vec::new(),
+            step_methods: Vec::new(),
+            dual_methods: Vec::new(),
+        }
+    }
+
+    /// Adds the transitions of a method, with its arguments in the order of the slots
+    ///
+    /// the constructors (the methods without `self`) produce the states they return,
+    /// and the slots without `#[switch_to]` (or with `_`) keep their states
+    pub fn add_method(
+        &mut self,
+        method: &ImplItemFn,
+        require_args: &[RequiredSlot],
+        switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    ) {
+        let is_constructor = method.sig.receiver().is_none();
+        if is_callable(method) {
+            self.callable_methods.push(CallableMethod {
+                method: method.sig.ident.clone(),
+                args: method.sig.inputs.len() - 1,
+                from: require_args
+                    .iter()
+                    .enumerate()
+                    .map(
+                        |(index, required)| match self.type_state_args.slot_component(index) {
+                            Some(_) => Vec::new(),
+                            None => self.required_states(index, required),
+                        },
+                    )
+                    .collect(),
+            });
+        }
+        if let (Some(args), [required]) = (step_args(method), require_args) {
+            let to = switch_to_args
+                .and_then(|args| args[0].as_ref())
+                .map(|targets| self.expand(0, targets.iter()));
+            let by_value = method
+                .sig
+                .receiver()
+                .is_some_and(|receiver| receiver.reference.is_none());
+            // the branching transitions and the ones that take a reference are not steps
+            let to = match to.as_deref() {
+                None => Some(None),
+                Some([to]) if by_value => Some(Some(to.clone())),
+                Some(_) => None,
+            };
+            if let Some(to) = to {
+                self.step_methods.push(StepMethod {
+                    method: method.sig.ident.clone(),
+                    by_value,
+                    output: method.sig.output.clone(),
+                    args,
+                    from: self.required_states(0, required),
+                    to,
+                });
+            }
+        }
+        if let (false, [required], Some([Some(targets)])) =
+            (is_constructor, require_args, switch_to_args)
+        {
+            self.dual_methods.push(DualMethod {
+                sig: method.sig.clone(),
+                from: self.required_states(0, required),
+                to: self.expand(0, targets.iter()),
+                cfgs: cfg_attrs(&method.attrs).cloned().collect(),
+            });
+        }
+        for (index, required) in require_args.iter().enumerate() {
+            if self.type_state_args.slot_component(index).is_some() {
+                continue;
+            }
+            let from = if is_constructor {
+                Vec::new()
+            } else {
+                self.required_states(index, required)
+            };
+            self.callable
+                .extend(from.iter().map(|state| (index, state.clone())));
+            let switched = switch_to_args.is_some_and(|args| args[index].is_some());
+            let targets = match switch_to_args.and_then(|args| args[index].as_ref()) {
+                Some(targets) => self.expand(index, targets.iter()),
+                None if is_constructor => self.required_states(index, required),
+                None => Vec::new(),
+            };
+            if is_constructor {
+                self.roots
+                    .extend(targets.iter().map(|target| (index, target.clone())));
+            }
+            for state in &from {
+                for to in &targets {
+                    self.edges.push((index, state.clone(), to.clone()));
+                }
+            }
+
+            self.methods.push(MethodTransition {
+                method: method.sig.ident.clone(),
+                cfgs: cfg_attrs(&method.attrs).cloned().collect(),
+                slot: index,
+                from,
+                to: targets,
+                switched,
+            });
+        }
+    }
+
+    /// The states of the slot that the requirement accepts
+    fn required_states(&self, index: usize, required: &RequiredSlot) -> Vec<Ident> {
+        match required {
+            RequiredSlot::Any(_) => self.type_state_args.states_of_slot(index).to_vec(),
+            RequiredSlot::OneOf(states) => self.expand(index, states.iter()),
+            RequiredSlot::Not(excluded) => {
+                let excluded = self.expand(index, excluded.iter());
+                self.type_state_args
+                    .states_of_slot(index)
+                    .iter()
+                    .filter(|state| !excluded.contains(state))
+                    .cloned()
+                    .collect()
+            }
+        }
+    }
+
+    /// The states of the slot that the arguments stand for: the groups stand for their states,
+    /// and the generics (`A`) for all the states of the slot
+    fn expand<'s>(&self, index: usize, states: impl Iterator<Item = &'s StateArg>) -> Vec<Ident> {
+        let allowed = self.type_state_args.states_of_slot(index);
+        let mut expanded = Vec::new();
+        for state in states {
+            let group = self
+                .type_state_args
+                .groups
+                .iter()
+                .find(|group| group.name == state.ident);
+            match group {
+                Some(group) => expanded.extend(group.states.iter().cloned()),
+                None if state.is_generic() => expanded.extend(allowed.iter().cloned()),
+                None => expanded.push(state.ident.clone()),
+            }
+        }
+        expanded
+    }
+
+    /// The states that can be reached in each slot from the roots, through the edges
+    fn reachable(&self) -> Vec<(usize, Ident)> {
+        let mut reached = self.roots.clone();
+        let mut next = 0;
+        while next < reached.len() {
+            let (index, state) = reached[next].clone();
+            for (_, _, to) in self
+                .edges
+                .iter()
+                .filter(|(slot, from, _)| *slot == index && *from == state)
+            {
+                if !reached
+                    .iter()
+                    .any(|(slot, other)| *slot == index && other == to)
+                {
+                    reached.push((index, to.clone()));
+                }
+            }
+            next += 1;
+        }
+        reached
+    }
+
+    /// The declared states that are not reachable in any of the slots that allow them
+    fn unreachable_states(&self) -> Vec<&'a Ident> {
+        let reached = self.reachable();
+        self.type_state_args
+            .states
+            .iter()
+            .filter(|state| !reached.iter().any(|(_, reached)| reached == *state))
+            .collect()
+    }
+
+    /// The states that the methods transition into (in a slot), without any method that can be called in them,
+    /// so that the values in them are stuck (the terminal states are meant to be like this)
+    fn dead_end_states(&self) -> Vec<&'a Ident> {
+        let is_target = |state: &Ident| {
+            self.edges.iter().any(|(_, _, to)| to == state)
+                || self.roots[self.default_count()..]
+                    .iter()
+                    .any(|(_, root)| root == state)
+        };
+        self.type_state_args
+            .states
+            .iter()
+            .filter(|state| !self.type_state_args.terminal_states.contains(state))
+            .filter(|state| is_target(state))
+            .filter(|state| !self.callable.iter().any(|(_, callable)| callable == *state))
+            .collect()
+    }
+
+    /// The number of the roots that are the default states, they are followed by the states of the constructors
+    fn default_count(&self) -> usize {
+        self.type_state_args
+            .slots
+            .iter()
+            .filter(|slot| slot.component.is_none())
+            .count()
+    }
+
+    /// The table of the transitions, opted into with `#[impl_state(transitions)]`,
+    /// so that the state machine can be inspected at runtime (by the tests, the tools, ...):
+    /// `player_transitions::TRANSITIONS`, with a `Transition` for each slot of each method
+    pub fn table(&self, struct_name: &Ident) -> TokenStream {
+        let mod_name = format_ident!("{}_transitions", snake_case(&struct_name.to_string()));
+        let vis = &self.type_state_args.marker_vis;
+        let struct_doc = format!(
+            "A method of `{struct_name}`, with the states of a slot that it can be called in, and the ones it transitions into"
+        );
+        let table_doc = format!(
+            "The transitions of the methods of `{struct_name}`, in the order of the methods"
+        );
+        let transitions = self.methods.iter().map(
+            |MethodTransition {
+                 method,
+                 cfgs,
+                 slot,
+                 from,
+                 to,
+                 ..
+             }| {
+                let method = method.to_string();
+                let from = from.iter().map(Ident::to_string);
+                let to = to.iter().map(Ident::to_string);
+                quote! {
+                    #(#cfgs)*
+                    Transition {
+                        method: #method,
+                        slot: #slot,
+                        from: &[#(#from),*],
+                        to: &[#(#to),*],
+                    }
+                }
+            },
+        );
+
+        quote! {
+            #[doc = #table_doc]
+            #vis mod #mod_name {
+                #[doc = #struct_doc]
+                #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+                pub struct Transition {
+                    /// The name of the method
+                    pub method: &'static str,
+                    /// The index of the slot
+                    pub slot: usize,
+                    /// The states the method can be called in (none for the constructors)
+                    pub from: &'static [&'static str],
+                    /// The states the method transitions into (none if it keeps the state of the slot)
+                    pub to: &'static [&'static str],
+                }
+
+                #[doc = #table_doc]
+                pub const TRANSITIONS: &[Transition] = &[#(#transitions),*];
+            }
+        }
+    }
+
+    /// The hidden macro of the tests of the states, generated along with the table of the transitions,
+    /// which gives `generate_state_tests!` the states that each method can and cannot be called in:
+    /// `__state_shift_player_state_tests! { path::to::Player }`
+    /// -> `__generate_state_tests! { [start(0) [Idle] false; start(0) [Running] true;] path::to::Player }`
+    /// (see `state_tests_inner`)
+    ///
+    /// each method is called in the first of its states (which is expected to compile),
+    /// and in each of the other states of a slot, along with the first states of the other slots
+    pub fn state_tests(&self, struct_name: &Ident, has_generics: bool) -> TokenStream {
+        let macro_name = state_tests_macro_name(struct_name);
+        let type_state_args = self.type_state_args;
+        let unsupported = if has_generics {
+            Some("the generic parameters of the struct")
+        } else if type_state_args.reuse.is_some() {
+            Some("the reused states")
+        } else if type_state_args
+            .slots
+            .iter()
+            .any(|slot| slot.component.is_some())
+        {
+            Some("the components")
+        } else {
+            None
+        };
+        let body = match unsupported {
+            Some(unsupported) => {
+                let message = format!(
+                    "`generate_state_tests!` cannot be used with {}, since their types cannot be named in the tests",
+                    unsupported
+                );
+                quote!(::core::compile_error!(#message);)
+            }
+            None => {
+                // the generic states and the external states cannot be named in the tests either
+                let nameable = |state: &&Ident| {
+                    type_state_args.generics_of_state(state).is_none()
+                        && !type_state_args.external_states.contains(state)
+                };
+                let cases = self.callable_methods.iter().flat_map(|callable| {
+                    let Some(callable_in) = callable
+                        .from
+                        .iter()
+                        .map(|states| states.iter().find(nameable).cloned())
+                        .collect::<Option<Vec<_>>>()
+                    else {
+                        return Vec::new();
+                    };
+                    let CallableMethod { method, args, from } = callable;
+                    let mut cases = vec![quote!(#method(#args) [#(#callable_in),*] false;)];
+                    for (index, states) in from.iter().enumerate() {
+                        let others = type_state_args
+                            .states_of_slot(index)
+                            .iter()
+                            .filter(nameable)
+                            .filter(|state| !states.contains(state));
+                        for other in others {
+                            let mut states = callable_in.clone();
+                            states[index] = other.clone();
+                            cases.push(quote!(#method(#args) [#(#states),*] true;));
+                        }
+                    }
+                    cases
+                });
+                quote! {
+                    ::state_shift::__generate_state_tests! { [#(#cases)*] $($tokens)* }
+                }
+            }
+        };
+
+        quote! {
+            #[doc(hidden)]
+            #[allow(unused_macros)]
+            macro_rules! #macro_name {
+                ($($tokens:tt)*) => {
+                    #body
+                };
+            }
+
+            #[doc(hidden)]
+            #[allow(unused_imports)]
+            pub(crate) use #macro_name;
+        }
+    }
+
+    /// The `proptest` strategy of the call sequences, opted into with `#[impl_state(proptest)]` (with the `proptest` feature),
+    /// so that the method bodies can be property-tested along any legal path of the state machine:
+    /// `player_proptest::steps(&player, 0..20)` generates the sequences of `Step`s (a method with its arguments)
+    /// that can be taken from the state of `player`, and `player_proptest::run_all(player, &steps)` takes them
+    ///
+    /// the steps are the methods that take `self` by value and return the struct (in a single state),
+    /// and the ones that take a reference to it and keep its state (their results are dropped),
+    /// and the values are given as `AnyPlayer`, so it needs `any_state = true`
+    pub fn proptest(&self, struct_name: &Ident, has_generics: bool) -> TokenStream {
+        let type_state_args = self.type_state_args;
+        let unsupported = if !cfg!(feature = "proptest") {
+            Some(
+                "`proptest` needs the `proptest` feature of `state-shift`: \
+                 `state-shift = { version = \"..\", features = [\"proptest\"] }`"
+                    .to_string(),
+            )
+        } else if !type_state_args.any_state {
+            Some(format!(
+                "`proptest` takes the values as `{}`, which needs `any_state = true` in `#[type_state]`",
+                any_state_enum_name(struct_name)
+            ))
+        } else if has_generics {
+            Some("`proptest` cannot be used with the generic parameters of the struct".to_string())
+        } else {
+            None
+        };
+        if let Some(message) = unsupported {
+            return quote_spanned!(struct_name.span()=> ::core::compile_error!(#message););
+        }
+
+        // the gated states are left out, since the sequences cannot tell whether they are compiled
+        let is_gated = |state: &Ident| !type_state_args.cfgs_of_state(state).is_empty();
+        let steps: Vec<_> = self
+            .step_methods
+            .iter()
+            .filter(|step| {
+                keeps_value(&step.output, struct_name, step.by_value)
+                    && !step.to.as_ref().is_some_and(is_gated)
+            })
+            .collect();
+        if steps.is_empty() {
+            return quote_spanned!(struct_name.span()=> ::core::compile_error!(
+                "`proptest` expects a method that takes `self` by value and returns the struct, or that takes a reference to it"
+            ););
+        }
+
+        let any_state = any_state_enum_name(struct_name);
+        let mod_name = format_ident!("{}_proptest", snake_case(&struct_name.to_string()));
+        let vis = &type_state_args.marker_vis;
+        let variant = |step: &StepMethod| {
+            Ident::new(&pascal_case(&step.method.to_string()), step.method.span())
+        };
+        let arg_names = |step: &StepMethod| -> Vec<Ident> {
+            (0..step.args.len())
+                .map(|index| format_ident!("arg{}", index))
+                .collect()
+        };
+
+        let variants = steps.iter().map(|step| {
+            let variant = variant(step);
+            let doc = format!(" `{}`, with its arguments", step.method);
+            let args = &step.args;
+            if args.is_empty() {
+                quote!(#[doc = #doc] #variant)
+            } else {
+                quote!(#[doc = #doc] #variant(#(#args),*))
+            }
+        });
+        let strategies = steps.iter().map(|step| {
+            let variant = variant(step);
+            let args = &step.args;
+            let names = arg_names(step);
+            if args.is_empty() {
+                quote!(::proptest::strategy::Strategy::boxed(::proptest::strategy::Just(Step::#variant)))
+            } else {
+                quote! {
+                    ::proptest::strategy::Strategy::boxed(::proptest::strategy::Strategy::prop_map(
+                        (#(::proptest::arbitrary::any::<#args>(),)*),
+                        |(#(#names,)*)| Step::#variant(#(#names),*),
+                    ))
+                }
+            }
+        });
+        let from_states = |step: &StepMethod| -> Vec<Ident> {
+            step.from
+                .iter()
+                .filter(|state| !is_gated(state))
+                .map(|state| type_state_args.unprefixed_name(state))
+                .collect()
+        };
+        let next_states = steps.iter().flat_map(|step| {
+            let variant = variant(step);
+            let pattern = if step.args.is_empty() {
+                quote!(Step::#variant)
+            } else {
+                quote!(Step::#variant(..))
+            };
+            let to = step
+                .to
+                .as_ref()
+                .map(|to| type_state_args.unprefixed_name(to).to_string());
+            from_states(step).into_iter().map(move |from| {
+                let from_name = from.to_string();
+                let to = to.clone().unwrap_or_else(|| from_name.clone());
+                quote!((#pattern, #from_name) => ::core::option::Option::Some(#to),)
+            })
+        });
+        let runs = steps.iter().flat_map(|step| {
+            let variant = variant(step);
+            let method = &step.method;
+            let names = arg_names(step);
+            let pattern = if step.args.is_empty() {
+                quote!(Step::#variant)
+            } else {
+                quote!(Step::#variant(#(#names),*))
+            };
+            let by_value = step.by_value;
+            let any_state = &any_state;
+            from_states(step).into_iter().map(move |from| {
+                let call = quote!(value.#method(#(::core::clone::Clone::clone(#names)),*));
+                if by_value {
+                    quote! {
+                        (#any_state::#from(value), #pattern) => ::core::convert::From::from(#call),
+                    }
+                } else {
+                    quote! {
+                        (#any_state::#from(mut value), #pattern) => {
+                            let _ = #call;
+                            #any_state::#from(value)
+                        }
+                    }
+                }
+            })
+        });
+        let states = type_state_args
+            .states_of_slot(0)
+            .iter()
+            .filter(|state| !is_gated(state))
+            .map(|state| type_state_args.unprefixed_name(state));
+        let state_names = type_state_args
+            .states_of_slot(0)
+            .iter()
+            .filter(|state| !is_gated(state))
+            .map(|state| type_state_args.unprefixed_name(state).to_string());
+
+        let mod_doc = format!(
+            " The `proptest` strategy of the call sequences of `{}`, and the driver that takes them",
+            struct_name
+        );
+        let step_doc = format!(
+            " A method of `{}` that can be taken as a step of the call sequences",
+            struct_name
+        );
+        let steps_doc = format!(" The sequences of the steps that can be taken one after another from the state of `{}`", struct_name);
+        let run_doc = format!(" Takes the step with `{}` in its current state, which panics if the step cannot be taken in the state", struct_name);
+
+        quote! {
+            #[doc = #mod_doc]
+            #vis mod #mod_name {
+                #[allow(unused_imports)]
+                use super::*;
+
+                #[doc = #step_doc]
+                #[derive(Debug, Clone)]
+                pub enum Step {
+                    #(#variants,)*
+                }
+
+                impl Step {
+                    /// The state after the step, if it can be taken in the given state
+                    pub fn next_state(&self, state: &str) -> ::core::option::Option<&'static str> {
+                        match (self, state) {
+                            #(#next_states)*
+                            _ => ::core::option::Option::None,
+                        }
+                    }
+                }
+
+                /// The state of the value, by its unprefixed name
+                fn state_of(value: &#any_state) -> &'static str {
+                    match value {
+                        #(#any_state::#states(_) => #state_names,)*
+                    }
+                }
+
+                /// Any of the steps, which may not be taken in the current state
+                fn step() -> impl ::proptest::strategy::Strategy<Value = Step> {
+                    ::proptest::strategy::Union::new(::std::vec![#(#strategies),*])
+                }
+
+                #[doc = #steps_doc]
+                ///
+                /// (the steps that cannot be taken in the state they are generated in are left out,
+                /// so the sequences may be shorter than the given length)
+                pub fn steps(
+                    from: &#any_state,
+                    len: impl ::core::convert::Into<::proptest::collection::SizeRange>,
+                ) -> impl ::proptest::strategy::Strategy<Value = ::std::vec::Vec<Step>> {
+                    let initial = state_of(from);
+                    ::proptest::strategy::Strategy::prop_map(
+                        ::proptest::collection::vec(step(), len),
+                        move |steps| {
+                            let mut state = initial;
+                            steps
+                                .into_iter()
+                                .filter(|step| match step.next_state(state) {
+                                    ::core::option::Option::Some(next) => {
+                                        state = next;
+                                        true
+                                    }
+                                    ::core::option::Option::None => false,
+                                })
+                                .collect()
+                        },
+                    )
+                }
+
+                #[doc = #run_doc]
+                #[allow(unused_mut, unreachable_patterns)]
+                pub fn run(value: #any_state, step: &Step) -> #any_state {
+                    match (value, step) {
+                        #(#runs)*
+                        (_, step) => ::core::panic!("`{:?}` cannot be taken in this state", step),
+                    }
+                }
+
+                /// Takes the steps one after another, from the given value
+                pub fn run_all(value: #any_state, steps: &[Step]) -> #any_state {
+                    steps.iter().fold(value, run)
+                }
+            }
+        }
+    }
+
+    /// The proof harnesses of the transitions, opted into with `#[impl_state(kani)]`, for the Kani model checker:
+    /// a harness for each step (see `StepMethod`) in each of its states, which starts from any value in the state
+    /// (given by `kani::Arbitrary`) that holds the invariants of the state, takes the step with any arguments,
+    /// and asserts the invariants of the state it ends up in (see `take_state_invariants`)
+    ///
+    /// ```ignore
+    /// #[kani::proof]
+    /// fn start_from_idle() {
+    ///     let value: Player<Idle> = kani::any();
+    ///     kani::assume(value.is_stopped());
+    ///     let value = value.start(kani::any::<u8>());
+    ///     assert!(value.has_track());
+    /// }
+    /// ```
+    ///
+    /// (the harnesses are only compiled by `cargo kani`, with `#[cfg(kani)]`)
+    pub fn kani(
+        &self,
+        struct_name: &Ident,
+        has_generics: bool,
+        invariants: &[StateInvariant],
+    ) -> TokenStream {
+        let type_state_args = self.type_state_args;
+        if has_generics {
+            return quote_spanned!(struct_name.span()=> ::core::compile_error!(
+                "`kani` cannot be used with the generic parameters of the struct"
+            ););
+        }
+        let is_gated = |state: &Ident| !type_state_args.cfgs_of_state(state).is_empty();
+        let steps: Vec<_> = self
+            .step_methods
+            .iter()
+            .filter(|step| {
+                keeps_value(&step.output, struct_name, step.by_value)
+                    && !step.to.as_ref().is_some_and(is_gated)
+                    && !invariants
+                        .iter()
+                        .any(|invariant| invariant.method == step.method)
+            })
+            .collect();
+        if steps.is_empty() {
+            return quote_spanned!(struct_name.span()=> ::core::compile_error!(
+                "`kani` expects a method that takes `self` by value and returns the struct, or that takes a reference to it"
+            ););
+        }
+
+        let checks = |state: &Ident, check: &dyn Fn(&Ident) -> TokenStream| -> TokenStream {
+            let checks = invariants
+                .iter()
+                .filter(|invariant| invariant.state == *state)
+                .map(|invariant| {
+                    let cfgs = &invariant.cfgs;
+                    let check = check(&invariant.method);
+                    quote!(#(#cfgs)* #check;)
+                });
+            quote!(#(#checks)*)
+        };
+        let harnesses = steps.iter().flat_map(|step| {
+            step.from
+                .iter()
+                .filter(|state| !is_gated(state))
+                .map(|from| {
+                    let method = &step.method;
+                    let args = &step.args;
+                    let to = step.to.as_ref().unwrap_or(from);
+                    let harness = format_ident!(
+                        "{}_from_{}",
+                        method,
+                        snake_case(&type_state_args.unprefixed_name(from).to_string())
+                    );
+                    let assumptions = checks(
+                        from,
+                        &|invariant| quote!(::kani::assume(value.#invariant())),
+                    );
+                    let message = |invariant: &Ident| {
+                        format!(
+                            "`{}` does not hold after `{}` from `{}`",
+                            invariant,
+                            method,
+                            type_state_args.unprefixed_name(from)
+                        )
+                    };
+                    let assertions = checks(to, &|invariant| {
+                        let message = message(invariant);
+                        quote!(::core::assert!(value.#invariant(), #message))
+                    });
+                    let call = quote!(value.#method(#(::kani::any::<#args>()),*));
+                    let step = if step.by_value {
+                        quote!(let value = #call;)
+                    } else {
+                        quote!(let _ = #call;)
+                    };
+                    quote! {
+                        #[::kani::proof]
+                        #[allow(unused_mut)]
+                        fn #harness() {
+                            let mut value: #struct_name<#from> = ::kani::any();
+                            #assumptions
+                            #step
+                            #assertions
+                        }
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mod_name = format_ident!("{}_kani", snake_case(&struct_name.to_string()));
+        let mod_doc = format!(
+            " The proof harnesses of the transitions of `{}`, for `cargo kani`",
+            struct_name
+        );
+        quote! {
+            #[doc = #mod_doc]
+            #[cfg(kani)]
+            mod #mod_name {
+                #[allow(unused_imports)]
+                use super::*;
+
+                #(#harnesses)*
+            }
+        }
+    }
+
+    /// The dual of the transitions, opted into with `#[impl_state(dual = ServerProtocol)]`:
+    /// a type-state protocol (see `state_trait_inner`) for the other side of the protocol, in which the sends are receives
+    /// and the receives are sends, so that it can be implemented by another struct (which reuses the states of the struct)
+    ///
+    /// the arguments of each transition are returned along with the struct, and the rest of the returned tuple is taken:
+    ///
+    /// ```ignore
+    /// #[require(Idle)] #[switch_to(Waiting)] fn request(self, path: String) -> Self
+    /// #[require(Waiting)] #[switch_to(Idle)] fn reply(self) -> (Self, u16)
+    /// // ->
+    /// #[require(Idle)] #[switch_to(Waiting)] fn request(self) -> (Self, String);
+    /// #[require(Waiting)] #[switch_to(Idle)] fn reply(self, payload: u16) -> Self;
+    /// ```
+    ///
+    /// (the constructors and the methods that keep the state are local to each side, so they are left out)
+    pub fn dual(&self, trait_name: &Ident, struct_name: &Ident, has_generics: bool) -> TokenStream {
+        let type_state_args = self.type_state_args;
+        let unsupported = if has_generics {
+            Some("`dual` cannot be used with the generic parameters of the struct")
+        } else if type_state_args.slots.len() != 1 {
+            Some(
+                "`dual` expects a single slot, since the type-state protocols track a single state",
+            )
+        } else {
+            None
+        };
+        if let Some(message) = unsupported {
+            return quote_spanned!(trait_name.span()=> ::core::compile_error!(#message););
+        }
+
+        let mut states: Vec<&Ident> = Vec::new();
+        let mut methods = Vec::new();
+        for dual_method in &self.dual_methods {
+            let DualMethod {
+                sig,
+                from,
+                to,
+                cfgs,
+            } = dual_method;
+            let method = &sig.ident;
+            let (from, to) = match (from.as_slice(), to.as_slice()) {
+                ([from], [to]) => (from, to),
+                _ => {
+                    let message = format!(
+                        "the dual of `{}` expects a single state in `#[require]` and `#[switch_to]`",
+                        method
+                    );
+                    methods.push(quote_spanned!(method.span()=> ::core::compile_error!(#message);));
+                    continue;
+                }
+            };
+            let by_value = sig
+                .receiver()
+                .is_some_and(|receiver| receiver.reference.is_none());
+            if !by_value || !sig.generics.params.is_empty() {
+                let message = format!(
+                    "the dual of `{}` expects it to take `self` by value, without generic parameters",
+                    method
+                );
+                methods.push(quote_spanned!(method.span()=> ::core::compile_error!(#message);));
+                continue;
+            }
+            let Some(payload) = returned_payload(&sig.output, struct_name) else {
+                let message = format!(
+                    "the dual of `{}` expects it to return `Self`, or a tuple that starts with `Self`",
+                    method
+                );
+                methods.push(quote_spanned!(method.span()=> ::core::compile_error!(#message);));
+                continue;
+            };
+            for state in [from, to] {
+                if !states.contains(&state) {
+                    states.push(state);
+                }
+            }
+
+            let sent = sig.inputs.iter().filter_map(|arg| match arg {
+                FnArg::Typed(pat_type) => Some(&pat_type.ty),
+                FnArg::Receiver(_) => None,
+            });
+            let received = payload.iter().enumerate().map(|(index, ty)| {
+                let name = match payload.len() {
+                    1 => format_ident!("payload"),
+                    _ => format_ident!("payload_{}", index + 1),
+                };
+                quote!(#name: #ty)
+            });
+            let output = match sent.clone().count() {
+                0 => quote!(Self),
+                _ => quote!((Self, #(#sent),*)),
+            };
+            methods.push(quote! {
+                #(#cfgs)*
+                #[require(#from)]
+                #[switch_to(#to)]
+                fn #method(self #(, #received)*) -> #output;
+            });
+        }
+        // the trait is generic over the states, in the order of their declaration
+        states.sort_by_key(|state| {
+            type_state_args
+                .states
+                .iter()
+                .position(|declared| declared == *state)
+        });
+
+        let vis = &type_state_args.marker_vis;
+        let doc = format!(
+            " The dual of the transitions of `{}`: the sends of `{}` are the receives of the implementors, and the other way around",
+            struct_name, struct_name
+        );
+        quote! {
+            #[doc = #doc]
+            #[::state_shift::impl_state]
+            #vis trait #trait_name<#(#states),*> {
+                #(#methods)*
+            }
+        }
+    }
+
+    /// The edges of the drawings of each slot (see `DiagramEdge`), along with the name of the slot
+    ///
+    /// the constructors start from the start (and so do the default states, unless a constructor produces them),
+    /// and the terminal states end in the end
+    fn diagram_slots(&self) -> Vec<(String, Vec<DiagramEdge<'_>>)> {
+        let mut slots = Vec::new();
+        for (index, slot) in self.type_state_args.slots.iter().enumerate() {
+            if slot.component.is_some() {
+                continue;
+            }
+            let mut edges = Vec::new();
+            let constructed = self.methods.iter().any(|method| {
+                method.slot == index && method.from.is_empty() && method.to.contains(&slot.default)
+            });
+            if !constructed {
+                edges.push(DiagramEdge {
+                    from: None,
+                    to: Some(&slot.default),
+                    method: None,
+                });
+            }
+            for method in self.methods.iter().filter(|method| method.slot == index) {
+                let from = if method.from.is_empty() {
+                    vec![None]
+                } else {
+                    method.from.iter().map(Some).collect()
+                };
+                for from in from {
+                    edges.extend(method.to.iter().map(|to| DiagramEdge {
+                        from,
+                        to: Some(to),
+                        method: Some(&method.method),
+                    }));
+                }
+            }
+            edges.extend(
+                self.type_state_args
+                    .states_of_slot(index)
+                    .iter()
+                    .filter(|state| self.type_state_args.terminal_states.contains(state))
+                    .map(|state| DiagramEdge {
+                        from: Some(state),
+                        to: None,
+                        method: None,
+                    }),
+            );
+
+            let name = match &slot.name {
+                Some(name) => name.to_string(),
+                None => format!("slot_{}", index + 1),
+            };
+            slots.push((name, edges));
+        }
+        slots
+    }
+
+    /// The Mermaid state diagram of the transitions, opted into with `#[impl_state(diagram = "docs/player.mmd")]`
+    pub fn diagram(&self) -> String {
+        self.state_diagram("stateDiagram-v2\n", "", ": ")
+    }
+
+    /// The PlantUML state diagram of the transitions, opted into with `#[impl_state(plantuml = "docs/player.puml")]`
+    pub fn plantuml(&self) -> String {
+        self.state_diagram("@startuml\n", "@enduml\n", " : ")
+    }
+
+    /// The state diagrams of Mermaid and PlantUML, which only differ in their start, end, and labels
+    ///
+    /// the edges are labeled with the methods, the start and the end are `[*]`,
+    /// and each slot is drawn as a composite state if there are multiple slots
+    fn state_diagram(&self, header: &str, footer: &str, label_separator: &str) -> String {
+        let slots = self.diagram_slots();
+        let mut diagram = String::from(header);
+
+        for (name, edges) in &slots {
+            let indent = if slots.len() == 1 { "    " } else { "        " };
+            if slots.len() > 1 {
+                diagram.push_str(&format!("    state {name} {{\n"));
+            }
+            for DiagramEdge { from, to, method } in edges {
+                let from = from.map_or("[*]".to_string(), Ident::to_string);
+                let to = to.map_or("[*]".to_string(), Ident::to_string);
+                let label =
+                    method.map_or(String::new(), |method| format!("{label_separator}{method}"));
+                diagram.push_str(&format!("{indent}{from} --> {to}{label}\n"));
+            }
+            if slots.len() > 1 {
+                diagram.push_str("    }\n");
+            }
+        }
+
+        diagram.push_str(footer);
+        diagram
+    }
+
+    /// The Graphviz graph of the transitions, opted into with `#[impl_state(dot = "docs/player.dot")]`
+    ///
+    /// the edges are labeled with the methods, the start is a point and the end is a double circle,
+    /// and each slot is drawn as a cluster if there are multiple slots
+    /// (with its name before the names of its nodes, since the nodes are shared by the whole graph)
+    pub fn dot(&self, struct_name: &Ident) -> String {
+        let slots = self.diagram_slots();
+        let mut dot = format!("digraph {struct_name} {{\n");
+
+        for (name, edges) in &slots {
+            let (indent, prefix) = if slots.len() == 1 {
+                ("    ", String::new())
+            } else {
+                dot.push_str(&format!(
+                    "    subgraph cluster_{name} {{\n        label = \"{name}\";\n"
+                ));
+                ("        ", format!("{name}."))
+            };
+            dot.push_str(&format!("{indent}\"{prefix}start\" [shape = point];\n"));
+            if edges.iter().any(|edge| edge.to.is_none()) {
+                dot.push_str(&format!(
+                    "{indent}\"{prefix}end\" [shape = doublecircle, label = \"\"];\n"
+                ));
+            }
+            let mut states: Vec<&Ident> = Vec::new();
+            for state in edges.iter().flat_map(|edge| [edge.from, edge.to]).flatten() {
+                if !states.contains(&state) {
+                    states.push(state);
+                }
+            }
+            if !prefix.is_empty() {
+                for state in states {
+                    dot.push_str(&format!(
+                        "{indent}\"{prefix}{state}\" [label = \"{state}\"];\n"
+                    ));
+                }
+            }
+            for DiagramEdge { from, to, method } in edges {
+                let from = from.map_or("start".to_string(), Ident::to_string);
+                let to = to.map_or("end".to_string(), Ident::to_string);
+                let label =
+                    method.map_or(String::new(), |method| format!(" [label = \"{method}\"]"));
+                dot.push_str(&format!(
+                    "{indent}\"{prefix}{from}\" -> \"{prefix}{to}\"{label};\n"
+                ));
+            }
+            if slots.len() > 1 {
+                dot.push_str("    }\n");
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The state machine as the model checkers see it (see `TransitionGraph::tla` and `TransitionGraph::smv`)
+    ///
+    /// each slot is a variable (`state` if there is only one), the values start in the default states
+    /// or in the states produced by a constructor, and each method that transitions a slot is an action
+    fn model(&self) -> Model<'_> {
+        let slots: Vec<usize> = (0..self.type_state_args.slots.len())
+            .filter(|index| self.type_state_args.slot_component(*index).is_none())
+            .collect();
+        let variables = slots
+            .iter()
+            .map(|index| {
+                let name = match &self.type_state_args.slots[*index].name {
+                    _ if slots.len() == 1 => "state".to_string(),
+                    Some(name) => name.to_string(),
+                    None => format!("slot_{}", index + 1),
+                };
+                (name, self.type_state_args.states_of_slot(*index))
+            })
+            .collect();
+
+        let mut inits: Vec<Vec<&[Ident]>> = vec![slots
+            .iter()
+            .map(|index| std::slice::from_ref(&self.type_state_args.slots[*index].default))
+            .collect()];
+        let mut actions = Vec::new();
+        // the transitions of a method are pushed one slot after the other
+        for transitions in self.methods.chunk_by(|a, b| a.method == b.method) {
+            if transitions
+                .iter()
+                .all(|transition| transition.from.is_empty())
+            {
+                let init: Vec<&[Ident]> = transitions
+                    .iter()
+                    .map(|transition| transition.to.as_slice())
+                    .collect();
+                if !inits.contains(&init) {
+                    inits.push(init);
+                }
+            } else if transitions
+                .iter()
+                .any(|transition| !transition.to.is_empty())
+            {
+                actions.push(ModelAction {
+                    method: &transitions[0].method,
+                    slots: transitions
+                        .iter()
+                        .map(|transition| {
+                            // the states the slot can be in anyway are not a condition of the method
+                            let all = self.type_state_args.states_of_slot(transition.slot);
+                            let from = if transition.from.len() == all.len() {
+                                &[]
+                            } else {
+                                transition.from.as_slice()
+                            };
+                            (from, transition.to.as_slice())
+                        })
+                        .collect(),
+                });
+            }
+        }
+
+        let terminal = slots
+            .iter()
+            .map(|index| {
+                self.type_state_args
+                    .states_of_slot(*index)
+                    .iter()
+                    .filter(|state| self.type_state_args.terminal_states.contains(state))
+                    .collect()
+            })
+            .collect();
+
+        Model {
+            variables,
+            inits,
+            actions,
+            terminal,
+        }
+    }
+
+    /// The TLA+ module of the transitions, opted into with `#[impl_state(tla = "spec/player.tla")]`
+    ///
+    /// the states are strings, each method is an action of `Next` (with the slots it doesn't transition `UNCHANGED`),
+    /// and the terminal states can stutter in `Done`, so that only the other states without transitions are deadlocks
+    pub fn tla(&self, struct_name: &Ident) -> String {
+        let Model {
+            variables,
+            inits,
+            actions,
+            terminal,
+        } = self.model();
+        let set = |states: &[&Ident]| {
+            let states: Vec<String> = states.iter().map(|state| format!("\"{state}\"")).collect();
+            format!("{{{}}}", states.join(", "))
+        };
+        let is_in = |variable: &str, states: &[&Ident]| match states {
+            [state] => format!("{variable} = \"{state}\""),
+            states => format!("{variable} \\in {}", set(states)),
+        };
+        let names: Vec<&str> = variables.iter().map(|(name, _)| name.as_str()).collect();
+        let sets: Vec<String> = names
+            .iter()
+            .map(|name| match names.len() {
+                1 => "States".to_string(),
+                _ => format!("{}States", pascal_case(name)),
+            })
+            .collect();
+
+        let mut tla = format!("---- MODULE {struct_name} ----\n");
+        tla.push_str(&format!(
+            "\\* the transitions of `{struct_name}`, generated by `#[impl_state(tla = ...)]`\n\n"
+        ));
+        tla.push_str(&format!(
+            "VARIABLE{} {}\n\n",
+            if names.len() == 1 { "" } else { "S" },
+            names.join(", ")
+        ));
+        tla.push_str(&format!("vars == <<{}>>\n\n", names.join(", ")));
+        for (set_name, (_, states)) in sets.iter().zip(&variables) {
+            let states: Vec<&Ident> = states.iter().collect();
+            tla.push_str(&format!("{set_name} == {}\n", set(&states)));
+        }
+        let type_ok: Vec<String> = names
+            .iter()
+            .zip(&sets)
+            .map(|(name, set_name)| format!("{name} \\in {set_name}"))
+            .collect();
+        tla.push_str(&format!("\nTypeOK == {}\n\n", type_ok.join(" /\\ ")));
+
+        let inits: Vec<String> = inits
+            .iter()
+            .map(|init| {
+                let conditions: Vec<String> = names
+                    .iter()
+                    .zip(init)
+                    .map(|(name, states)| is_in(name, &states.iter().collect::<Vec<_>>()))
+                    .collect();
+                conditions.join(" /\\ ")
+            })
+            .collect();
+        match inits.as_slice() {
+            [init] => tla.push_str(&format!("Init == {init}\n\n")),
+            inits => {
+                tla.push_str("Init ==\n");
+                for init in inits {
+                    tla.push_str(&format!("    \\/ ({init})\n"));
+                }
+                tla.push('\n');
+            }
+        }
+
+        let mut next: Vec<String> = Vec::new();
+        for ModelAction { method, slots } in &actions {
+            let mut conditions = Vec::new();
+            for (name, (from, _)) in names.iter().zip(slots) {
+                if !from.is_empty() {
+                    conditions.push(is_in(name, &from.iter().collect::<Vec<_>>()));
+                }
+            }
+            for (name, (_, to)) in names.iter().zip(slots) {
+                if to.is_empty() {
+                    conditions.push(format!("UNCHANGED {name}"));
+                } else {
+                    conditions.push(is_in(&format!("{name}'"), &to.iter().collect::<Vec<_>>()));
+                }
+            }
+            tla.push_str(&format!("{method} == {}\n", conditions.join(" /\\ ")));
+            next.push(method.to_string());
+        }
+        let done: Vec<String> = names
+            .iter()
+            .zip(&terminal)
+            .filter(|(_, states)| !states.is_empty())
+            .map(|(name, states)| is_in(name, states))
+            .collect();
+        if !done.is_empty() {
+            let done = match done.as_slice() {
+                [done] => done.clone(),
+                done => format!("({})", done.join(" \\/ ")),
+            };
+            tla.push_str(&format!("Done == {done} /\\ UNCHANGED vars\n"));
+            next.push("Done".to_string());
+        }
+
+        match next.as_slice() {
+            [] => tla.push_str("\nNext == FALSE\n"),
+            next => {
+                tla.push_str("\nNext ==\n");
+                for action in next {
+                    tla.push_str(&format!("    \\/ {action}\n"));
+                }
+            }
+        }
+        tla.push_str("\nSpec == Init /\\ [][Next]_vars\n\n====\n");
+        tla
+    }
+
+    /// The NuSMV module of the transitions, opted into with `#[impl_state(smv = "spec/player.smv")]`
+    ///
+    /// the states are the values of enumerations, the method of each transition is the input variable `method`
+    /// (so that the properties can refer to the methods), and the terminal states can stutter
+    pub fn smv(&self, struct_name: &Ident) -> String {
+        let Model {
+            variables,
+            inits,
+            actions,
+            terminal,
+        } = self.model();
+        let set = |states: &[&Ident]| {
+            let states: Vec<String> = states.iter().map(|state| state.to_string()).collect();
+            format!("{{{}}}", states.join(", "))
+        };
+        let is_in = |variable: &str, states: &[&Ident]| match states {
+            [state] => format!("{variable} = {state}"),
+            states => format!("{variable} in {}", set(states)),
+        };
+        let names: Vec<&str> = variables.iter().map(|(name, _)| name.as_str()).collect();
+
+        let mut smv = format!(
+            "-- the transitions of `{struct_name}`, generated by `#[impl_state(smv = ...)]`\nMODULE main\n"
+        );
+        smv.push_str("VAR\n");
+        for (name, states) in &variables {
+            let states: Vec<&Ident> = states.iter().collect();
+            smv.push_str(&format!("    {name} : {};\n", set(&states)));
+        }
+        if !actions.is_empty() {
+            let methods: Vec<&Ident> = actions.iter().map(|action| action.method).collect();
+            smv.push_str(&format!("IVAR\n    method : {};\n", set(&methods)));
+        }
+
+        smv.push_str("INIT\n");
+        for (position, init) in inits.iter().enumerate() {
+            let conditions: Vec<String> = names
+                .iter()
+                .zip(init)
+                .map(|(name, states)| is_in(name, &states.iter().collect::<Vec<_>>()))
+                .collect();
+            let separator = if position == 0 { "  " } else { "| " };
+            smv.push_str(&format!("    {separator}({})\n", conditions.join(" & ")));
+        }
+
+        let mut transitions: Vec<String> = Vec::new();
+        for ModelAction { method, slots } in &actions {
+            let mut conditions = vec![format!("method = {method}")];
+            for (name, (from, _)) in names.iter().zip(slots) {
+                if !from.is_empty() {
+                    conditions.push(is_in(name, &from.iter().collect::<Vec<_>>()));
+                }
+            }
+            for (name, (_, to)) in names.iter().zip(slots) {
+                if to.is_empty() {
+                    conditions.push(format!("next({name}) = {name}"));
+                } else {
+                    conditions.push(is_in(
+                        &format!("next({name})"),
+                        &to.iter().collect::<Vec<_>>(),
+                    ));
+                }
+            }
+            transitions.push(conditions.join(" & "));
+        }
+        let done: Vec<String> = names
+            .iter()
+            .zip(&terminal)
+            .filter(|(_, states)| !states.is_empty())
+            .map(|(name, states)| is_in(name, states))
+            .collect();
+        if !done.is_empty() {
+            let unchanged = names.iter().map(|name| format!("next({name}) = {name}"));
+            let conditions: Vec<String> = std::iter::once(format!("({})", done.join(" | ")))
+                .chain(unchanged)
+                .collect();
+            transitions.push(conditions.join(" & "));
+        }
+
+        smv.push_str("TRANS\n");
+        if transitions.is_empty() {
+            smv.push_str("    FALSE\n");
+        }
+        for (position, transition) in transitions.iter().enumerate() {
+            let separator = if position == 0 { "  " } else { "| " };
+            smv.push_str(&format!("    {separator}({transition})\n"));
+        }
+        smv
+    }
+
+    /// The checks of the transitions of the methods against the ones declared with `transitions!`,
+    /// opted into with `declared_transitions = true`: a constant for each transition, which fails to compile
+    /// if the hidden function of `transitions!` doesn't declare it (the constructors and the methods that keep the states
+    /// of the slots are not transitions, so they are not checked)
+    pub fn declared_checks(&self, struct_name: &Ident) -> TokenStream {
+        if !self.type_state_args.declared_transitions {
+            return TokenStream::new();
+        }
+        let declares = declared_transitions_fn(struct_name);
+        let checks = self.methods.iter().flat_map(|transition| {
+            let declares = &declares;
+            transition.from.iter().flat_map(move |from| {
+                transition.to.iter().map(move |to| {
+                    let MethodTransition { method, cfgs, .. } = transition;
+                    let message = format!(
+                        "the transition of `{}` from `{}` to `{}` is not declared with `transitions!`",
+                        method, from, to
+                    );
+                    let (method, from, to) = (method.to_string(), from.to_string(), to.to_string());
+                    quote_spanned! {transition.method.span()=>
+                        #(#cfgs)*
+                        const _: () = ::core::assert!(#declares(#from, #to, #method), #message);
+                    }
+                })
+            })
+        });
+        quote!(#(#checks)*)
+    }
+
+    /// The checks of the transitions entering and leaving the states whose hooks are not declared in the `impl` block,
+    /// which fail if the hooks are declared in another block (see `foreign_hook_check`)
+    pub fn hook_checks(&self, struct_name: &Ident, hooks: &StateHooks) -> TokenStream {
+        let mut checked: Vec<(&Ident, &Ident, bool)> = Vec::new();
+        let mut checks = TokenStream::new();
+        for transition in self.methods.iter().filter(|transition| transition.switched) {
+            let enters = transition.to.iter().map(|state| (state, true));
+            let leaves = transition.from.iter().map(|state| (state, false));
+            for (state, on_enter) in enters.chain(leaves) {
+                if hooks.declares(state, on_enter)
+                    || checked.contains(&(&transition.method, state, on_enter))
+                {
+                    continue;
+                }
+                checked.push((&transition.method, state, on_enter));
+                checks.extend(foreign_hook_check(
+                    state,
+                    on_enter,
+                    &transition.method,
+                    &transition.cfgs,
+                    struct_name,
+                    self.type_state_args,
+                ));
+            }
+        }
+        checks
+    }
+
+    /// The warnings and the errors of the lints that are opted into
+    pub fn lints(&self) -> TokenStream {
+        let mut lints = TokenStream::new();
+
+        if let Some(level) = self.type_state_args.lint_level(Lint::Unreachable) {
+            for state in self.unreachable_states() {
+                lints.extend(report(
+                    Lint::Unreachable,
+                    level,
+                    state,
+                    format!(
+                        "the state `{}` cannot be reached from the default states through the methods of this `impl` block",
+                        state
+                    ),
+                ));
+            }
+        }
+        if let Some(level) = self.type_state_args.lint_level(Lint::DeadEnd) {
+            for state in self.dead_end_states() {
+                lints.extend(report(
+                    Lint::DeadEnd,
+                    level,
+                    state,
+                    format!(
+                        "the methods of this `impl` block transition into the state `{}`, but none of them can be called in it \
+                         (it can be declared as `terminal` if it is meant to be the end)",
+                        state
+                    ),
+                ));
+            }
+        }
+
+        lints
+    }
+}
+
+/// The types of the arguments of a method, if it can be taken as a step of the call sequences:
+/// the methods that take `self` (or a reference to it), without generic parameters, `#[cfg]` or `async`,
+/// and whose arguments don't refer to `Self`
+fn step_args(method: &ImplItemFn) -> Option<Vec<Type>> {
+    let receiver = method.sig.receiver()?;
+    if receiver.colon_token.is_some()
+        || method.sig.asyncness.is_some()
+        || method.sig.generics.type_params().next().is_some()
+        || method.sig.generics.const_params().next().is_some()
+        || cfg_attrs(&method.attrs).next().is_some()
+    {
+        return None;
+    }
+    method
+        .sig
+        .inputs
+        .iter()
+        .skip(1)
+        .map(|input| match input {
+            FnArg::Typed(typed) if !typed.ty.to_token_stream().to_string().contains("Self") => {
+                Some((*typed.ty).clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether the value of the sequence is kept by the step:
+/// the steps that take `self` by value are expected to return the struct
+fn keeps_value(output: &ReturnType, struct_name: &Ident, by_value: bool) -> bool {
+    if !by_value {
+        return true;
+    }
+    matches!(output, ReturnType::Type(_, ty) if matches!(&**ty, Type::Path(type_path)
+        if type_path.qself.is_none()
+            && (type_path.path.is_ident("Self")
+                || type_path.path.segments.last().is_some_and(|segment| segment.ident == *struct_name))))
+}
+
+/// The payload returned along with the struct by a transition, for its dual (see `TransitionGraph::dual`):
+/// `-> Self` -> `[]`, `-> (Self, u16)` -> `[u16]`, and none for the other return types
+fn returned_payload<'t>(output: &'t ReturnType, struct_name: &Ident) -> Option<Vec<&'t Type>> {
+    let is_struct = |ty: &Type| {
+        matches!(ty, Type::Path(type_path) if type_path.qself.is_none()
+            && (type_path.path.is_ident("Self")
+                || type_path.path.segments.last().is_some_and(|segment| segment.ident == *struct_name)))
+    };
+    match output {
+        ReturnType::Type(_, ty) if is_struct(ty) => Some(Vec::new()),
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Tuple(tuple) if tuple.elems.first().is_some_and(is_struct) => {
+                Some(tuple.elems.iter().skip(1).collect())
+            }
+            _ => None,
+        },
+        ReturnType::Default => None,
+    }
+}
+
+/// Whether a method can be called in the tests of `generate_state_tests!`, which are compiled as other crates:
+/// the public methods that take `self` (or a reference to it), without generic parameters or `#[cfg]`
+fn is_callable(method: &ImplItemFn) -> bool {
+    matches!(method.vis, Visibility::Public(_))
+        && method
+            .sig
+            .receiver()
+            .is_some_and(|receiver| receiver.colon_token.is_none())
+        && method.sig.generics.type_params().next().is_none()
+        && method.sig.generics.const_params().next().is_none()
+        && cfg_attrs(&method.attrs).next().is_none()
+}
+
+/// Reports a lint at the given state: an error for `deny(...)`,
+/// and a warning for `warn(...)`, which is the use of a deprecated item with the message as its note
+/// (since the procedural macros cannot emit warnings on stable Rust)
+fn report(lint: Lint, level: LintLevel, state: &Ident, message: String) -> TokenStream {
+    match level {
+        LintLevel::Deny => syn::Error::new(state.span(), message).to_compile_error(),
+        LintLevel::Warn => {
+            let lint_item = format_ident!("{}_state", lint.name(), span = state.span());
+            quote_spanned! {state.span()=>
+                const _: () = {
+                    #[deprecated(note = #message)]
+                    #[allow(non_upper_case_globals)]
+                    const #lint_item: () = ();
+                    #lint_item
+                };
+            }
+        }
+    }
+}
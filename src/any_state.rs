@@ -0,0 +1,177 @@
+/// this file contains the enum of a struct in any of its states: `AnyPlayer`,
+/// which is generated by the `#[type_state]` macro, when it is opted into with `any_state = true`,
+/// and the enum of the states themselves: `PlayerState`, when it is opted into with `state_enum = true`
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Generics, Ident, Visibility};
+
+use crate::{any_state_serde, generic_args, strip_generic_defaults, TypeStateArgs};
+
+/// Name of the enum of the struct in any of its states: `Player` -> `AnyPlayer`
+pub fn any_state_enum_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("Any{}", struct_name), struct_name.span())
+}
+
+/// Name of the enum of the states of the struct: `Player` -> `PlayerState`
+pub fn state_enum_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{}State", struct_name), struct_name.span())
+}
+
+/// Generates the enum of the states of the struct, with a unit variant for each declared state,
+/// which tells the state at runtime (for the logs, the metrics, or the states stored in a database):
+///
+/// `enum PlayerState { Idle, Running }`
+///
+/// the sealer gives the variant of each state (`<Idle as SealerPlayer>::STATE`), so that the struct can tell
+/// its current state in any of its states: `player.current_state()` (see `state_name_accessors`)
+pub fn state_enum(
+    struct_name: &Ident,
+    visibility: &Visibility,
+    states: &[Ident],
+    type_state_args: &TypeStateArgs,
+) -> TokenStream {
+    let enum_name = state_enum_name(struct_name);
+    let variants = states.iter().map(|state| {
+        let cfgs = type_state_args.cfgs_of_state(state);
+        let variant = type_state_args.unprefixed_name(state);
+        let doc = format!(" The `{}` state", variant);
+        quote! {
+            #(#cfgs)*
+            #[doc = #doc]
+            #variant
+        }
+    });
+    let doc = format!(
+        " The states of `{}`, to tell the state at runtime",
+        struct_name
+    );
+
+    quote! {
+        #[doc = #doc]
+        #[allow(dead_code)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #visibility enum #enum_name {
+            #(#variants,)*
+        }
+    }
+}
+
+/// Generates the enum of the struct in any of its states, with a variant for each state,
+/// so that the values in different states can be stored together (in a `Vec`, a `HashMap`, ...),
+/// and their states are recovered by matching:
+///
+/// `enum AnyPlayer { Idle(Player<Idle>), Running(Player<Running>) }`
+///
+/// along with `From<Player<Idle>> for AnyPlayer` for each state, so that they are erased with `.into()`,
+/// and the names of the variants: `AnyPlayer::VARIANTS`
+/// (and `AnyPlayer::current_state()` as well, with `state_enum = true`)
+///
+/// it needs a single slot (the variants of multiple slots would be every combination of their states),
+/// and the states cannot be generic (since the enum would need their arguments)
+pub fn any_state_enum(
+    struct_name: &Ident,
+    visibility: &Visibility,
+    generics: &Generics,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<TokenStream> {
+    if type_state_args.slots.len() != 1 || type_state_args.slot_component(0).is_some() {
+        return Err(syn::Error::new(
+            struct_name.span(),
+            "`any_state` can only be used with a single slot of the struct's own states",
+        ));
+    }
+    if let Some((state, _)) = type_state_args.state_generics.first() {
+        return Err(syn::Error::new(
+            state.span(),
+            format!(
+                "`any_state` cannot be used with the generic states, since the variant of `{}` would need its arguments",
+                state
+            ),
+        ));
+    }
+
+    let enum_name = any_state_enum_name(struct_name);
+    let params = strip_generic_defaults(&generics.params);
+    let params = params.iter().collect::<Vec<_>>();
+    let args = generic_args(&generics.params);
+    let where_clause = &generics.where_clause;
+    let states = type_state_args.states_of_slot(0);
+
+    // the values round-trip with their states, with `serde = true`: `{"state": "Running", ...}`
+    let (serde_derive, serde_variant) = any_state_serde(struct_name, generics, type_state_args);
+    let variants = states.iter().map(|state| {
+        let cfgs = type_state_args.cfgs_of_state(state);
+        let variant = type_state_args.unprefixed_name(state);
+        let doc = format!(" `{}` in the `{}` state", struct_name, variant);
+        quote! {
+            #(#cfgs)*
+            #[doc = #doc]
+            #serde_variant
+            #variant(#struct_name<#(#args,)* #state>)
+        }
+    });
+    let from_impls = states.iter().map(|state| {
+        let cfgs = type_state_args.cfgs_of_state(state);
+        let variant = type_state_args.unprefixed_name(state);
+        quote! {
+            #(#cfgs)*
+            impl<#(#params),*> ::core::convert::From<#struct_name<#(#args,)* #state>>
+                for #enum_name<#(#args),*>
+            #where_clause
+            {
+                fn from(value: #struct_name<#(#args,)* #state>) -> Self {
+                    Self::#variant(value)
+                }
+            }
+        }
+    });
+    let variant_names = states.iter().map(|state| {
+        let cfgs = type_state_args.cfgs_of_state(state);
+        let variant = type_state_args.unprefixed_name(state).to_string();
+        quote!(#(#cfgs)* #variant)
+    });
+    let current_state = type_state_args.state_enum.then(|| {
+        let state_enum_name = state_enum_name(struct_name);
+        let arms = states.iter().map(|state| {
+            let cfgs = type_state_args.cfgs_of_state(state);
+            let variant = type_state_args.unprefixed_name(state);
+            quote!(#(#cfgs)* Self::#variant(_) => #state_enum_name::#variant)
+        });
+        quote! {
+            /// The state of the value
+            #[allow(dead_code)]
+            pub fn current_state(&self) -> #state_enum_name {
+                match self {
+                    #(#arms,)*
+                }
+            }
+        }
+    });
+    let doc = format!(
+        " `{}` in any of its states, so that the values in different states can be stored together",
+        struct_name
+    );
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[allow(dead_code)]
+        #serde_derive
+        #visibility enum #enum_name<#(#params),*>
+        #where_clause
+        {
+            #(#variants,)*
+        }
+
+        impl<#(#params),*> #enum_name<#(#args),*>
+        #where_clause
+        {
+            /// The names of the variants, in the order of their declaration
+            #[allow(dead_code)]
+            pub const VARIANTS: &'static [&'static str] = &[#(#variant_names),*];
+
+            #current_state
+        }
+
+        #(#from_impls)*
+    })
+}
@@ -0,0 +1,258 @@
+/// this file contains the typed builders, which are generated by the `#[type_state_builder]` macro:
+/// each required field of the struct is a slot of the builder (`Unset` or `Set`), so that `build()` can only be called
+/// once all of them are set, and the builder itself is a type-state struct of this crate
+/// (the optional fields don't have a slot, they are filled with their defaults if they are not set)
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Expr, Fields, Ident, ItemStruct, Meta};
+
+use crate::{generic_args, strip_generic_defaults};
+
+/// Name of the builder of a struct: `Player` -> `PlayerBuilder`
+fn builder_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{}Builder", struct_name), struct_name.span())
+}
+
+/// How a field is filled by `build()`
+enum BuilderField {
+    /// the field has to be set, so it has a slot of the builder
+    Required,
+    /// `#[optional]`: the field is filled with `Default::default()` if it is not set
+    Optional,
+    /// `#[default = expr]`: the field is filled with the expression if it is not set
+    Default(Expr),
+}
+
+/// Takes the `#[optional]` and `#[default = expr]` attributes out of the field, which are not known to the compiler
+fn take_builder_field(attrs: &mut Vec<syn::Attribute>) -> syn::Result<BuilderField> {
+    let mut kind = BuilderField::Required;
+    let mut error = None;
+    attrs.retain(|attr| {
+        let given = match &attr.meta {
+            Meta::Path(path) if path.is_ident("optional") => BuilderField::Optional,
+            Meta::NameValue(name_value) if name_value.path.is_ident("default") => {
+                BuilderField::Default(name_value.value.clone())
+            }
+            meta if meta.path().is_ident("optional") || meta.path().is_ident("default") => {
+                error = Some(syn::Error::new(
+                    attr.span(),
+                    "expected `#[optional]` or `#[default = expr]`",
+                ));
+                return false;
+            }
+            _ => return true,
+        };
+        if !matches!(kind, BuilderField::Required) {
+            error = Some(syn::Error::new(
+                attr.span(),
+                "the field is already optional, `#[optional]` and `#[default = expr]` cannot be combined",
+            ));
+        }
+        kind = given;
+        false
+    });
+    match error {
+        Some(error) => Err(error),
+        None => Ok(kind),
+    }
+}
+
+/// Generates the typed builder of the struct, along with `Player::builder()`:
+///
+/// ```ignore
+/// #[type_state(states = (Unset, Set), slots = (name = Unset, level = Unset), prefix = "PlayerBuilder")]
+/// struct PlayerBuilder { name: Option<String>, level: Option<u8> }
+///
+/// #[impl_state]
+/// impl PlayerBuilder {
+///     #[require(name = Unset)]
+///     #[switch_to(name = Set)]
+///     fn name(self, name: String) -> Self { .. }
+///
+///     #[require(name = Set, level = Set)]
+///     fn build(self) -> Player { .. }
+/// }
+/// ```
+///
+/// (the states are prefixed with the name of the builder, so that the builders of a module don't collide)
+///
+/// the fields with `#[optional]` or `#[default = expr]` don't have a slot, they can be set in any state,
+/// and `build()` fills them with `Default::default()` or the expression if they are not set
+pub fn type_state_builder_inner(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let mut input = parse_macro_input!(input as ItemStruct);
+    // the struct is kept next to the error, so that the code using it still compiles
+    match builder(args.into(), &input) {
+        Ok(output) => output.into(),
+        Err(error) => {
+            for field in input.fields.iter_mut() {
+                field.attrs.retain(|attr| {
+                    !attr.path().is_ident("optional") && !attr.path().is_ident("default")
+                });
+            }
+            let error = error.to_compile_error();
+            quote!(#error #input).into()
+        }
+    }
+}
+
+/// The struct along with its builder, or the error of its declaration (a tuple struct, a gated field, ...)
+fn builder(args: TokenStream, input: &ItemStruct) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(syn::Error::new(
+            args.span(),
+            "`#[type_state_builder]` doesn't take any arguments",
+        ));
+    }
+    let mut input = input.clone();
+    let Fields::Named(fields) = &mut input.fields else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "`#[type_state_builder]` expects a struct with named fields, since each field is set by its name",
+        ));
+    };
+    let kinds = fields
+        .named
+        .iter_mut()
+        .map(|field| take_builder_field(&mut field.attrs))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let fields = fields.clone();
+    if !kinds
+        .iter()
+        .any(|kind| matches!(kind, BuilderField::Required))
+    {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "`#[type_state_builder]` expects a struct with at least one required field, since they are the slots of the builder",
+        ));
+    }
+    if let Some(attr) = fields
+        .named
+        .iter()
+        .flat_map(|field| &field.attrs)
+        .find(|attr| attr.path().is_ident("cfg"))
+    {
+        return Err(syn::Error::new(
+            attr.span(),
+            "the fields of `#[type_state_builder]` cannot be gated with `#[cfg]`, since each of them is set by a method of the builder",
+        ));
+    }
+
+    let struct_name = &input.ident;
+    let builder_name = builder_name(struct_name);
+    let visibility = &input.vis;
+    let generics = &input.generics;
+    let params = strip_generic_defaults(&generics.params);
+    let params: Vec<_> = params.iter().collect();
+    let args = generic_args(&generics.params);
+    let where_clause = &generics.where_clause;
+    let prefix = builder_name.to_string();
+
+    let names: Vec<&Ident> = fields
+        .named
+        .iter()
+        .filter_map(|field| field.ident.as_ref())
+        .collect();
+    let types: Vec<_> = fields.named.iter().map(|field| &field.ty).collect();
+    let slots: Vec<&Ident> = names
+        .iter()
+        .zip(&kinds)
+        .filter(|(_, kind)| matches!(kind, BuilderField::Required))
+        .map(|(name, _)| *name)
+        .collect();
+    // the optional fields are set in any state: `#[require(_, _)]`
+    let any_states = slots.iter().map(|_| quote!(_));
+
+    let setters = names
+        .iter()
+        .zip(&types)
+        .zip(&kinds)
+        .map(|((name, ty), kind)| {
+            let doc = format!(" Sets the `{}` field", name);
+            let others = names.iter().filter(|other| *other != name);
+            let transition = match kind {
+                BuilderField::Required => quote! {
+                    #[require(#name = Unset)]
+                    #[switch_to(#name = Set)]
+                },
+                _ => {
+                    let any_states = any_states.clone();
+                    quote!(#[require(#(#any_states),*)])
+                }
+            };
+            quote! {
+                #[doc = #doc]
+                #transition
+                #[allow(dead_code)]
+                #visibility fn #name(self, #name: #ty) -> Self {
+                    Self {
+                        #name: ::core::option::Option::Some(#name),
+                        #(#others: self.#others,)*
+                    }
+                }
+            }
+        });
+    let built_fields = names.iter().zip(&kinds).map(|(name, kind)| match kind {
+        BuilderField::Required => {
+            let message = format!("type safety ensures that `{}` is set", name);
+            quote!(#name: self.#name.expect(#message))
+        }
+        BuilderField::Optional => quote!(#name: self.#name.unwrap_or_default()),
+        BuilderField::Default(default) => quote!(#name: self.#name.unwrap_or_else(|| #default)),
+    });
+    let builder_doc = format!(
+        " The builder of `{}`, which can only build it once all of its fields are set",
+        struct_name
+    );
+    let builder_fn_doc = format!(
+        " Creates the builder of `{}`, with none of its fields set",
+        struct_name
+    );
+
+    Ok(quote! {
+        #input
+
+        #[doc = #builder_doc]
+        #[::state_shift::type_state(
+            states = (Unset, Set),
+            slots = (#(#slots = Unset),*),
+            prefix = #prefix
+        )]
+        #visibility struct #builder_name<#(#params),*> #where_clause {
+            #(#names: ::core::option::Option<#types>,)*
+        }
+
+        #[::state_shift::impl_state]
+        impl<#(#params),*> #builder_name<#(#args),*> #where_clause {
+            /// Creates the builder, with none of the fields set
+            #[require(#(#slots = Unset),*)]
+            #[allow(dead_code)]
+            #visibility fn new() -> Self {
+                Self {
+                    #(#names: ::core::option::Option::None,)*
+                }
+            }
+
+            #(#setters)*
+
+            /// Builds the struct, once all of its fields are set
+            #[require(#(#slots = Set),*)]
+            #[allow(dead_code)]
+            #visibility fn build(self) -> #struct_name<#(#args),*> {
+                #struct_name {
+                    #(#built_fields,)*
+                }
+            }
+        }
+
+        impl<#(#params),*> #struct_name<#(#args),*> #where_clause {
+            #[doc = #builder_fn_doc]
+            #[allow(dead_code)]
+            #visibility fn builder() -> #builder_name<#(#args),*> {
+                #builder_name::new()
+            }
+        }
+    })
+}
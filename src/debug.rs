@@ -0,0 +1,176 @@
+/// this file contains the `Debug` impl of the type-state structs, which is generated by the `#[type_state]` macro
+/// in place of `#[derive(Debug)]`, so that it shows the states instead of the hidden `_state` field
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    punctuated::Punctuated, Field, Fields, GenericParam, Generics, Ident, Token, Type, Variant,
+};
+
+use crate::{
+    cfg_attrs, generic_args, mentions_states, strip_generic_defaults, StructShape, TypeStateArgs,
+};
+
+/// Generates the `Debug` impl of the struct, which shows the states next to its name, and hides the `_state` field:
+///
+/// `Player<Running> { level: 3, .. }`, `Session<LoggedIn, Closed> { auth: Auth<LoggedIn> { .. }, .. }`
+///
+/// the names of the states are given by the sealers (see `STATE_NAME`), so it is implemented for the struct in any of its states,
+/// with the bounds of `#[derive(Debug)]` (`T: Debug` for the generics of the struct),
+/// along with the ones of the fields that depend on the states (the `#[only_in]` fields and the components)
+pub fn debug_impl(
+    struct_name: &Ident,
+    generics: &Generics,
+    state_idents: &[Ident],
+    type_state_args: &TypeStateArgs,
+    struct_fields: &Punctuated<Field, Token![,]>,
+    tuple_fields: Option<&Punctuated<Field, Token![,]>>,
+    enum_variants: Option<&Punctuated<Variant, Token![,]>>,
+) -> TokenStream {
+    let params = strip_generic_defaults(&generics.params);
+    let params = params.iter();
+    let args = generic_args(&generics.params);
+    let sealers: Vec<_> = (0..state_idents.len())
+        .map(|index| type_state_args.sealer_of_slot(index, struct_name))
+        .collect();
+
+    // the fields of the components, in the states of their slots: `auth: Auth<SessionState1>`
+    let components: Vec<(&Ident, Type)> = type_state_args
+        .slots
+        .iter()
+        .zip(state_idents)
+        .filter_map(|(slot, state_ident)| {
+            let component = slot.component.as_ref()?;
+            Some((
+                slot.name.as_ref()?,
+                syn::parse_quote!(#component<#state_ident>),
+            ))
+        })
+        .collect();
+
+    let mut field_types: Vec<&Type> = match (enum_variants, tuple_fields) {
+        (Some(variants), _) => variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .map(|field| &field.ty)
+            .collect(),
+        (None, Some(fields)) => fields.iter().map(|field| &field.ty).collect(),
+        (None, None) => struct_fields.iter().map(|field| &field.ty).collect(),
+    };
+    field_types.extend(components.iter().map(|(_, ty)| ty));
+    let field_bounds = field_types
+        .into_iter()
+        .filter(|ty| mentions_states(quote!(#ty), state_idents))
+        .map(|ty| quote!(#ty: ::core::fmt::Debug));
+    let param_bounds = generics.params.iter().filter_map(|param| match param {
+        GenericParam::Type(param) => {
+            let ident = &param.ident;
+            Some(quote!(#ident: ::core::fmt::Debug))
+        }
+        _ => None,
+    });
+    let where_predicates = generics
+        .where_clause
+        .iter()
+        .flat_map(|where_clause| where_clause.predicates.iter())
+        .map(|predicate| quote!(#predicate));
+
+    let named = |prefix: TokenStream, fields: &Punctuated<Field, Token![,]>| {
+        let fields = fields.iter().map(|field| {
+            let cfgs = cfg_attrs(&field.attrs);
+            let ident = &field.ident;
+            let name = ident.as_ref().map(ToString::to_string);
+            quote!(#(#cfgs)* debug.field(#name, &#prefix #ident);)
+        });
+        quote!(#(#fields)*)
+    };
+    let component_fields = components.iter().map(|(name, _)| {
+        let field = name.to_string();
+        quote!(debug.field(#field, &self.#name);)
+    });
+
+    let body = match (enum_variants, tuple_fields) {
+        (Some(variants), _) => {
+            let arms = variants.iter().map(|variant| {
+                let cfgs = cfg_attrs(&variant.attrs);
+                let variant_name = &variant.ident;
+                let variant_string = format!("::{}", variant_name);
+                match &variant.fields {
+                    Fields::Unnamed(fields) => {
+                        let bindings: Vec<_> = (0..fields.unnamed.len())
+                            .map(|index| {
+                                Ident::new(&format!("field_{}", index), variant_name.span())
+                            })
+                            .collect();
+                        quote! {
+                            #(#cfgs)*
+                            Self::#variant_name(#(#bindings,)* ..) => {
+                                let mut debug = f.debug_tuple(&(name + #variant_string));
+                                #(debug.field(#bindings);)*
+                                debug.finish()
+                            }
+                        }
+                    }
+                    Fields::Named(fields) => {
+                        let bindings = fields.named.iter().map(|field| {
+                            let cfgs = cfg_attrs(&field.attrs);
+                            let ident = &field.ident;
+                            quote!(#(#cfgs)* #ident)
+                        });
+                        let fields = named(quote!(*), &fields.named);
+                        quote! {
+                            #(#cfgs)*
+                            Self::#variant_name { #(#bindings,)* .. } => {
+                                let mut debug = f.debug_struct(&(name + #variant_string));
+                                #fields
+                                debug.finish_non_exhaustive()
+                            }
+                        }
+                    }
+                    Fields::Unit => quote!(),
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        (None, Some(fields)) => {
+            let indices = (0..fields.len()).map(syn::Index::from);
+            quote! {
+                let mut debug = f.debug_tuple(&name);
+                #(debug.field(&self.#indices);)*
+                debug.finish()
+            }
+        }
+        (None, None) if matches!(type_state_args.shape, StructShape::Unit) => {
+            quote!(f.write_str(&name))
+        }
+        (None, None) => {
+            let fields = named(quote!(self.), struct_fields);
+            quote! {
+                let mut debug = f.debug_struct(&name);
+                #fields
+                #(#component_fields)*
+                debug.finish_non_exhaustive()
+            }
+        }
+    };
+
+    let struct_string = struct_name.to_string();
+    quote! {
+        impl<#(#params,)* #(#state_idents),*> ::core::fmt::Debug for #struct_name<#(#args,)* #(#state_idents),*>
+        where
+            #(#where_predicates,)*
+            #(#state_idents: #sealers,)*
+            #(#param_bounds,)*
+            #(#field_bounds,)*
+        {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                let states: &[&str] = &[#(<#state_idents as #sealers>::STATE_NAME),*];
+                let name = ::std::format!("{}<{}>", #struct_string, states.join(", "));
+                #body
+            }
+        }
+    }
+}
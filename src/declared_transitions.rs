@@ -0,0 +1,268 @@
+/// this file contains the `transitions!` macro, which declares the transitions of a struct once:
+/// `transitions!(Player: Idle -> Running via start, Running -> Idle via stop)`,
+/// so that `#[impl_state]` checks the methods against them, with `declared_transitions = true` in `#[type_state]`
+/// (see `TransitionGraph::declared_checks`)
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use stringcase::snake_case;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Ident, Token,
+};
+
+use crate::{callback_macro_name, closest_name, TypeStateArgs};
+
+mod keyword {
+    syn::custom_keyword!(via);
+}
+
+/// Name of the hidden function generated by `transitions!` for the struct, which tells whether a transition is declared:
+/// `Player` -> `__state_shift_player_declares`
+pub fn declared_transitions_fn(struct_name: &Ident) -> Ident {
+    format_ident!(
+        "__state_shift_{}_declares",
+        snake_case(&struct_name.to_string()),
+        span = struct_name.span()
+    )
+}
+
+/// A state of a declared transition: `Idle`, or `_` for any state (only as the source of the transition)
+enum DeclaredState {
+    State(Ident),
+    Any,
+}
+
+impl Parse for DeclaredState {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![_]) {
+            input.parse::<Token![_]>()?;
+            Ok(DeclaredState::Any)
+        } else {
+            Ok(DeclaredState::State(input.parse()?))
+        }
+    }
+}
+
+/// A declared transition: `Idle | Paused -> Running via start`
+struct DeclaredTransition {
+    from: Vec<DeclaredState>,
+    to: Vec<Ident>,
+    method: Ident,
+}
+
+impl Parse for DeclaredTransition {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let from = Punctuated::<DeclaredState, Token![|]>::parse_separated_nonempty(input)?
+            .into_iter()
+            .collect();
+        input.parse::<Token![->]>()?;
+        let to = Punctuated::<Ident, Token![|]>::parse_separated_nonempty(input)?
+            .into_iter()
+            .collect();
+        input.parse::<keyword::via>()?;
+        Ok(DeclaredTransition {
+            from,
+            to,
+            method: input.parse()?,
+        })
+    }
+}
+
+/// Input of the `transitions!` macro: `Player: Idle -> Running via start, Running -> Idle via stop`
+struct TransitionsInput {
+    struct_name: Ident,
+    transitions: Punctuated<DeclaredTransition, Token![,]>,
+}
+
+impl Parse for TransitionsInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let struct_name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        Ok(TransitionsInput {
+            struct_name,
+            transitions: Punctuated::parse_terminated(input)?,
+        })
+    }
+}
+
+/// Input of the internal `__transitions` macro:
+/// `(<arguments of #[type_state]>) <input of transitions!>`
+struct TransitionsCallbackInput {
+    type_state_args: TypeStateArgs,
+    input: TransitionsInput,
+}
+
+impl Parse for TransitionsCallbackInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        parenthesized!(content in input);
+        Ok(TransitionsCallbackInput {
+            type_state_args: content.parse()?,
+            input: input.parse()?,
+        })
+    }
+}
+
+/// Forwards `transitions!` to the hidden macro generated by `#[type_state]` for the struct,
+/// which carries the arguments of `#[type_state]` back to it (see `__transitions`), like `match_state!`,
+/// so that the states of the transitions are checked against the declared ones
+///
+/// `transitions!(Player: Idle -> Running via start)` -> `__state_shift_player! { @transitions Player: Idle -> ... }`
+pub fn forward_transitions(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let tokens = TokenStream::from(input.clone());
+    let TransitionsInput { struct_name, .. } = parse_macro_input!(input as TransitionsInput);
+    let callback_name = callback_macro_name(&struct_name);
+
+    quote! {
+        #callback_name! { @transitions #tokens }
+    }
+    .into()
+}
+
+/// The declared state that is given in `transitions!` (by its unprefixed name, with `prefix`),
+/// or the error of an unknown one: "unknown state `Runing`; did you mean `Running`?"
+fn declared_state(state: &Ident, type_state_args: &TypeStateArgs) -> syn::Result<Ident> {
+    let resolved = type_state_args.resolve_name(state);
+    if type_state_args.states.contains(&resolved) {
+        return Ok(resolved);
+    }
+    let names: Vec<_> = type_state_args
+        .states
+        .iter()
+        .map(|state| type_state_args.unprefixed_name(state))
+        .collect();
+    let message = match closest_name(state, &names) {
+        Some(closest) => format!("unknown state `{}`; did you mean `{}`?", state, closest),
+        None => format!(
+            "unknown state `{}`, expected one of the declared states: {}",
+            state,
+            names
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    Err(syn::Error::new(state.span(), message))
+}
+
+/// Expands `transitions!` into the hidden function that tells whether a transition is declared,
+/// which is called in the constants of `#[impl_state]`, so that the undeclared transitions fail to compile
+/// (`_` is given as the source of the transitions from any state)
+pub fn transitions_inner(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let TransitionsCallbackInput {
+        type_state_args,
+        input,
+    } = parse_macro_input!(input as TransitionsCallbackInput);
+    let fn_name = declared_transitions_fn(&input.struct_name);
+
+    let mut edges = Vec::new();
+    let mut errors: Option<syn::Error> = None;
+    let mut resolved = |state: &Ident| match declared_state(state, &type_state_args) {
+        Ok(state) => state.to_string(),
+        Err(error) => {
+            match &mut errors {
+                Some(errors) => errors.combine(error),
+                None => errors = Some(error),
+            }
+            String::new()
+        }
+    };
+    for transition in &input.transitions {
+        let method = transition.method.to_string();
+        for from in &transition.from {
+            let from = match from {
+                DeclaredState::State(state) => resolved(state),
+                DeclaredState::Any => "_".to_string(),
+            };
+            for to in &transition.to {
+                let to = resolved(to);
+                edges.push(quote!((#from, #to, #method)));
+            }
+        }
+    }
+    // the methods are not checked against the transitions with the unknown states, which are reported instead
+    if let Some(errors) = errors {
+        let errors = errors.to_compile_error();
+        return quote! {
+            #errors
+
+            #[doc(hidden)]
+            #[allow(dead_code, unused_variables)]
+            pub(crate) const fn #fn_name(from: &str, to: &str, method: &str) -> bool {
+                true
+            }
+        }
+        .into();
+    }
+    let doc = format!(
+        " Whether the transition of `{}` is declared with `transitions!`",
+        input.struct_name
+    );
+
+    let expanded: TokenStream = quote! {
+        #[doc = #doc]
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub(crate) const fn #fn_name(from: &str, to: &str, method: &str) -> bool {
+            const fn eq(left: &str, right: &str) -> bool {
+                let (left, right) = (left.as_bytes(), right.as_bytes());
+                if left.len() != right.len() {
+                    return false;
+                }
+                let mut index = 0;
+                while index < left.len() {
+                    if left[index] != right[index] {
+                        return false;
+                    }
+                    index += 1;
+                }
+                true
+            }
+
+            const EDGES: &[(&str, &str, &str)] = &[#(#edges),*];
+            let mut index = 0;
+            while index < EDGES.len() {
+                let (edge_from, edge_to, edge_method) = EDGES[index];
+                if (eq(edge_from, "_") || eq(edge_from, from))
+                    && eq(edge_to, to)
+                    && eq(edge_method, method)
+                {
+                    return true;
+                }
+                index += 1;
+            }
+            false
+        }
+    };
+    expanded.into()
+}
+
+/// The function that stands for the one of `transitions!` while it is not given, with `declared_transitions = true`,
+/// so that the checks of `#[impl_state]` tell that it is missing, instead of the unknown function
+///
+/// it is imported by a glob, so the function of `transitions!` shadows it in the module of the struct
+pub fn missing_transitions_fallback(struct_name: &Ident) -> TokenStream {
+    let fn_name = declared_transitions_fn(struct_name);
+    let mod_name = format_ident!("{}_missing", fn_name);
+    let message = format!(
+        "`{}` has `declared_transitions = true`, so its transitions are declared with `transitions!({}: ...)` \
+         in the module of the struct",
+        struct_name, struct_name
+    );
+    quote! {
+        #[doc(hidden)]
+        mod #mod_name {
+            #[allow(dead_code, unused_variables)]
+            pub(crate) const fn #fn_name(from: &str, to: &str, method: &str) -> bool {
+                ::core::panic!(#message)
+            }
+        }
+
+        #[allow(unused_imports)]
+        use #mod_name::*;
+    }
+}
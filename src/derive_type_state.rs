@@ -0,0 +1,263 @@
+/// this file contains `#[derive(TypeState)]`, the alternative to `#[type_state]` that doesn't rewrite the struct:
+/// the struct declares the generics of the slots and the `_state` field itself, and the derive generates
+/// the rest of `#[type_state]` (the markers, the sealer, the hidden macro of `#[impl_state]`, ...)
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{
+    parse_macro_input, spanned::Spanned, visit_mut::VisitMut, Fields, GenericParam, Ident, Item,
+    ItemStruct, Type, WherePredicate,
+};
+
+use crate::{type_state_inner, TypeStateArgs};
+
+/// Renames the generics of the slots generated by `#[type_state]` to the ones declared by the struct:
+/// `PhantomData<fn() -> PlayerState1>` -> `PhantomData<fn() -> S>`
+struct SlotRenamer<'a> {
+    generated: &'a [Ident],
+    declared: &'a [Ident],
+}
+
+impl VisitMut for SlotRenamer<'_> {
+    fn visit_ident_mut(&mut self, ident: &mut Ident) {
+        if let Some(index) = self
+            .generated
+            .iter()
+            .position(|generated| generated == ident)
+        {
+            *ident = self.declared[index].clone();
+        }
+    }
+}
+
+/// The type of the `_state` field (or the last element of the tuple structs)
+fn state_field_type<'a>(fields: &'a Fields, field: &Ident) -> Option<&'a Type> {
+    match fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .find(|named| named.ident.as_ref() == Some(field))
+            .map(|named| &named.ty),
+        Fields::Unnamed(fields) => fields.unnamed.last().map(|unnamed| &unnamed.ty),
+        Fields::Unit => None,
+    }
+}
+
+/// Expands `#[derive(TypeState)]`, whose arguments are given with the `#[type_state(...)]` helper attribute:
+///
+/// ```ignore
+/// #[derive(TypeState)]
+/// #[type_state(states = (Idle, Running), slots = (Idle))]
+/// struct Player<S = Idle> {
+///     name: String,
+///     _state: PhantomData<fn() -> S>,
+/// }
+/// ```
+pub fn derive_type_state_inner(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(input as Item);
+    let result = match item {
+        Item::Struct(input_struct) => derive_type_state(input_struct),
+        item => Err(syn::Error::new(
+            item.span(),
+            "`#[derive(TypeState)]` expects a struct, the enums need `#[type_state]`",
+        )),
+    };
+    result.unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn derive_type_state(input_struct: ItemStruct) -> syn::Result<TokenStream> {
+    let struct_name = &input_struct.ident;
+    let mut helpers = input_struct
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("type_state"));
+    let Some(helper) = helpers.next() else {
+        return Err(syn::Error::new(
+            struct_name.span(),
+            "`#[derive(TypeState)]` expects the states of the struct: `#[type_state(states = (...), slots = (...))]`",
+        ));
+    };
+    if let Some(duplicate) = helpers.next() {
+        return Err(syn::Error::new(
+            duplicate.span(),
+            "the states of the struct are already given",
+        ));
+    }
+    let args = helper.meta.require_list()?.tokens.clone();
+    let type_state_args: TypeStateArgs = syn::parse2(args.clone())?;
+
+    // the options that add to the declaration of the struct are left to `#[type_state]`
+    if type_state_args.reuse.is_some() {
+        return Err(syn::Error::new(
+            helper.span(),
+            "`reuse` cannot be used with `#[derive(TypeState)]`, since the states are read from another macro",
+        ));
+    }
+    if type_state_args.serde.is_some() {
+        return Err(syn::Error::new(
+            helper.span(),
+            "`serde` cannot be used with `#[derive(TypeState)]`, since it puts `#[serde]` attributes on the struct",
+        ));
+    }
+    if let Some(component) = type_state_args
+        .slots
+        .iter()
+        .find_map(|slot| slot.name.as_ref().filter(|_| slot.component.is_some()))
+    {
+        return Err(syn::Error::new(
+            component.span(),
+            "the components cannot be used with `#[derive(TypeState)]`, since they are fields of the struct",
+        ));
+    }
+
+    // the generics of the slots are the last ones of the struct: `Player<T, S = Idle>`
+    let slot_count = type_state_args.slots.len();
+    let params: Vec<GenericParam> = input_struct.generics.params.iter().cloned().collect();
+    let (own_params, slot_params) = params.split_at(params.len().saturating_sub(slot_count));
+    let slot_params: Vec<_> = slot_params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(type_param) => Some(type_param),
+            _ => None,
+        })
+        .collect();
+    if slot_params.len() != slot_count {
+        return Err(syn::Error::new(
+            input_struct.generics.span(),
+            format!(
+                "`#[derive(TypeState)]` expects a generic parameter for each slot of the states, after the other ones: `{}<{}>`",
+                struct_name,
+                type_state_args
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .map(|(index, slot)| format!("S{} = {}", index + 1, slot.default))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ));
+    }
+    let declared: Vec<Ident> = slot_params
+        .iter()
+        .map(|param| param.ident.clone())
+        .collect();
+
+    // the struct as `#[type_state]` would see it, without the generics and the field of the states
+    let mut stripped = input_struct.clone();
+    stripped
+        .attrs
+        .retain(|attr| !attr.path().is_ident("type_state") && !attr.path().is_ident("derive"));
+    stripped.generics.params = own_params.iter().cloned().collect();
+    if let Some(where_clause) = &mut stripped.generics.where_clause {
+        where_clause.predicates = where_clause
+            .predicates
+            .iter()
+            .filter(|predicate| match predicate {
+                WherePredicate::Type(predicate_type) => !declared.iter().any(|ident| {
+                    matches!(&predicate_type.bounded_ty, Type::Path(path) if path.path.is_ident(ident))
+                }),
+                _ => true,
+            })
+            .cloned()
+            .collect();
+    }
+    let field = &type_state_args.field;
+    let declared_type = match &mut stripped.fields {
+        Fields::Named(fields) => {
+            let position = fields
+                .named
+                .iter()
+                .position(|named| named.ident.as_ref() == Some(field));
+            position.map(|position| {
+                let mut named: Vec<_> = fields.named.iter().cloned().collect();
+                let removed = named.remove(position);
+                fields.named = named.into_iter().collect();
+                removed.ty
+            })
+        }
+        Fields::Unnamed(fields) => {
+            let mut unnamed: Vec<_> = fields.unnamed.iter().cloned().collect();
+            let removed = unnamed.pop();
+            fields.unnamed = unnamed.into_iter().collect();
+            removed.map(|removed| removed.ty)
+        }
+        Fields::Unit => None,
+    };
+    let Some(declared_type) = declared_type else {
+        return Err(syn::Error::new(
+            struct_name.span(),
+            format!(
+                "`#[derive(TypeState)]` expects the field of the states: `{}: PhantomData<fn() -> S>` (or the last element of the tuple structs)",
+                field
+            ),
+        ));
+    };
+
+    // everything that `#[type_state]` generates, except for the struct itself, which is declared by the users
+    let expansion = TokenStream::from(type_state_inner(
+        args.into(),
+        stripped.into_token_stream().into(),
+    ));
+    let mut file: syn::File = syn::parse2(expansion)?;
+    let position = file.items.iter().position(
+        |item| matches!(item, Item::Struct(generated) if generated.ident == *struct_name),
+    );
+    let generated = position.map(|position| file.items.remove(position));
+
+    // the field and the defaults of the declared generics have to be the ones that `#[type_state]` would generate,
+    // since the generated code creates the struct with them: `Self { name, _state: PhantomData }`, `Player::default()`
+    let check = match generated {
+        Some(Item::Struct(generated)) => {
+            let generated_params: Vec<_> = generated
+                .generics
+                .type_params()
+                .skip(own_params.len())
+                .collect();
+            let generated_idents: Vec<Ident> = generated_params
+                .iter()
+                .map(|param| param.ident.clone())
+                .collect();
+            for (declared_param, generated_param) in slot_params.iter().zip(&generated_params) {
+                let declared_default = declared_param
+                    .default
+                    .as_ref()
+                    .map(|default| default.to_token_stream().to_string());
+                let generated_default = generated_param
+                    .default
+                    .as_ref()
+                    .map(|default| default.to_token_stream().to_string());
+                if declared_default != generated_default {
+                    return Err(syn::Error::new(
+                        declared_param.span(),
+                        format!(
+                            "the generic parameter of the slot is expected to default to its default state: `{} = {}`",
+                            declared_param.ident,
+                            generated_default.unwrap_or_default()
+                        ),
+                    ));
+                }
+            }
+            let mut expected_type = state_field_type(&generated.fields, field)
+                .cloned()
+                .expect("`#[type_state]` generates the field of the states");
+            SlotRenamer {
+                generated: &generated_idents,
+                declared: &declared,
+            }
+            .visit_type_mut(&mut expected_type);
+            quote_spanned! {declared_type.span()=>
+                const _: () = {
+                    #[allow(dead_code, clippy::type_complexity)]
+                    fn check_state_field<#(#declared),*>(state: #declared_type) -> #expected_type {
+                        state
+                    }
+                };
+            }
+        }
+        _ => quote!(),
+    };
+
+    Ok(quote! {
+        #file
+        #check
+    })
+}
@@ -0,0 +1,577 @@
+/// this file contains the impls that the `#[type_state]` macro generates in place of the derives of the struct:
+/// `#[derive(Clone, PartialEq, Hash)]`, since the derived impls would bound the states (`PlayerState1: Clone`),
+/// which the markers do not satisfy, and which the methods that accept any state cannot rely on
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use syn::{
+    parse::Parser, punctuated::Punctuated, Attribute, Field, Fields, FieldsNamed, FieldsUnnamed,
+    GenericParam, Generics, Ident, Member, Meta, Path, Token, Type, Variant,
+};
+
+use crate::{cfg_attrs, generic_args, strip_generic_defaults, TypeStateArgs};
+
+/// The derives that are replaced with the impls of the `#[type_state]` macro
+/// (`Debug` is replaced with its own impl, which shows the states, see `debug_impl`,
+/// and so is `Default` of the structs, which is implemented for the default states only)
+pub const REPLACED_DERIVES: &[&str] = &[
+    "Debug",
+    "Clone",
+    "Copy",
+    "PartialEq",
+    "Eq",
+    "Hash",
+    "PartialOrd",
+    "Ord",
+];
+
+/// Takes the given derives out of the derives of the struct: `#[derive(Debug, Clone, Default)]` -> `#[derive(Default)]`
+///
+/// returns the derives that were taken (by the last segment of their paths: `std::fmt::Debug` -> `Debug`)
+pub fn take_derives(attrs: &mut Vec<Attribute>, names: &[&str]) -> syn::Result<Vec<Ident>> {
+    let mut taken = Vec::new();
+    for attr in attrs
+        .iter_mut()
+        .filter(|attr| attr.path().is_ident("derive"))
+    {
+        let paths = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+        let (replaced, kept): (Vec<_>, Vec<_>) = paths.into_iter().partition(|path| {
+            path.segments
+                .last()
+                .is_some_and(|segment| names.iter().any(|name| segment.ident == name))
+        });
+        if !replaced.is_empty() {
+            taken.extend(
+                replaced
+                    .into_iter()
+                    .filter_map(|path| path.segments.last().map(|segment| segment.ident.clone())),
+            );
+            if let Meta::List(list) = &mut attr.meta {
+                list.tokens = quote!(#(#kept),*);
+            }
+        }
+    }
+    // the derives that had only the replaced ones are left empty: `#[derive()]`
+    attrs.retain(|attr| {
+        !(attr.path().is_ident("derive")
+            && matches!(&attr.meta, Meta::List(list) if list.tokens.is_empty()))
+    });
+    Ok(taken)
+}
+
+/// The builder derives of the struct, which would make the hidden `_state` field a field of the builder:
+/// `derive_builder::Builder` and `typed_builder::TypedBuilder`
+///
+/// returns the name of the derive, along with the attribute that skips the `_state` field in the builder,
+/// which is created with `Default` instead: `#[builder(setter(skip))]`
+pub fn builder_derive(attrs: &[Attribute]) -> syn::Result<Option<(Ident, TokenStream)>> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let paths = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+        for segment in paths.iter().filter_map(|path| path.segments.last()) {
+            let skipped = match segment.ident.to_string().as_str() {
+                "Builder" => quote!(#[builder(setter(skip))]),
+                "TypedBuilder" => quote!(#[builder(default, setter(skip))]),
+                _ => continue,
+            };
+            return Ok(Some((segment.ident.clone(), skipped)));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether the type depends on the states: `<PlayerState1 as __PlayerSocketIn>::Type<TcpStream>`, `Auth<SessionState1>`
+pub fn mentions_states(tokens: TokenStream, state_idents: &[Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => state_idents.contains(&ident),
+        TokenTree::Group(group) => mentions_states(group.stream(), state_idents),
+        _ => false,
+    })
+}
+
+/// A variant of the struct, with its `_state` field (the struct itself is a single variant: `Self`)
+struct DerivedVariant {
+    path: TokenStream,
+    cfgs: Vec<Attribute>,
+    named: bool,
+    /// the fields, along with their `#[cfg]` attributes
+    fields: Vec<(Member, Vec<Attribute>)>,
+}
+
+impl DerivedVariant {
+    fn new(path: TokenStream, attrs: &[Attribute], fields: &Fields, state_field: &Ident) -> Self {
+        let members = fields.iter().enumerate().map(|(index, field)| {
+            let member = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(index.into()),
+            };
+            (member, cfg_attrs(&field.attrs).cloned().collect())
+        });
+        let named = !matches!(fields, Fields::Unnamed(_));
+        let state_member = if named {
+            Member::Named(state_field.clone())
+        } else {
+            Member::Unnamed(fields.len().into())
+        };
+        DerivedVariant {
+            path,
+            cfgs: cfg_attrs(attrs).cloned().collect(),
+            named,
+            fields: members.chain([(state_member, Vec::new())]).collect(),
+        }
+    }
+
+    /// The bindings of the fields: `__self_0`, `__other_0`
+    fn bindings(&self, side: &str) -> Vec<Ident> {
+        (0..self.fields.len())
+            .map(|index| {
+                Ident::new(
+                    &format!("__{}_{}", side, index),
+                    proc_macro2::Span::call_site(),
+                )
+            })
+            .collect()
+    }
+
+    /// The pattern that binds the fields: `Self::Hello { version: __self_0, _state: __self_1 }`
+    fn pattern(&self, side: &str) -> TokenStream {
+        let path = &self.path;
+        let bindings = self.bindings(side);
+        if self.named {
+            let fields = self
+                .fields
+                .iter()
+                .zip(&bindings)
+                .map(|((member, cfgs), binding)| quote!(#(#cfgs)* #member: #binding));
+            quote!(#path { #(#fields),* })
+        } else {
+            quote!(#path(#(#bindings),*))
+        }
+    }
+
+    /// The pattern that only matches the variant: `Self::Hello { .. }`
+    fn wildcard(&self) -> TokenStream {
+        let path = &self.path;
+        if self.named {
+            quote!(#path { .. })
+        } else {
+            quote!(#path(..))
+        }
+    }
+
+    /// The statements for each field, which are given the bindings of the field in `self` and in `other`
+    fn statements(&self, statement: impl Fn(&Ident, &Ident) -> TokenStream) -> TokenStream {
+        let statements = self
+            .fields
+            .iter()
+            .zip(
+                self.bindings("self")
+                    .into_iter()
+                    .zip(self.bindings("other")),
+            )
+            .map(|((_, cfgs), (this, other))| {
+                let statement = statement(&this, &other);
+                quote!(#(#cfgs)* #statement)
+            });
+        quote!(#(#statements)*)
+    }
+}
+
+/// Generates the impls of the replaced derives (except `Debug`, see `debug_impl`) for the struct in any of its states,
+/// with the bounds of the derives on the generics of the struct (`T: Clone`), but not on the states:
+///
+/// `#[derive(Clone)]` -> `impl<T, PlayerState1: SealerPlayer> Clone for Player<T, PlayerState1> where T: Clone`
+///
+/// the fields that depend on the states are bounded instead (the `#[only_in]` fields, the components,
+/// and the `_state` field, whose `PhantomData` is always `Clone`, unless the states carry data and are stored in it)
+///
+/// `#[derive(Default)]` is implemented for the default states only, since the struct starts in them:
+/// `impl<T> Default for Player<T, Idle> where T: Default` (the other states are reached by the transitions)
+#[allow(clippy::too_many_arguments)]
+pub fn derive_impls(
+    derives: &[Ident],
+    struct_name: &Ident,
+    generics: &Generics,
+    state_idents: &[Ident],
+    type_state_args: &TypeStateArgs,
+    struct_fields: &Punctuated<Field, Token![,]>,
+    tuple_fields: Option<&Punctuated<Field, Token![,]>>,
+    enum_variants: Option<&Punctuated<Variant, Token![,]>>,
+    state_type: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let state_field = &type_state_args.field;
+
+    // the fields of the components, in the states of their slots: `auth: Auth<SessionState1>`
+    let components: Vec<(Ident, Type)> = type_state_args
+        .slots
+        .iter()
+        .zip(state_idents)
+        .filter_map(|(slot, state_ident)| {
+            let component = slot.component.as_ref()?;
+            Some((
+                slot.name.clone()?,
+                syn::parse_quote!(#component<#state_ident>),
+            ))
+        })
+        .collect();
+
+    let variants: Vec<DerivedVariant> = match (enum_variants, tuple_fields) {
+        (Some(variants), _) => variants
+            .iter()
+            .map(|variant| {
+                let name = &variant.ident;
+                DerivedVariant::new(
+                    quote!(Self::#name),
+                    &variant.attrs,
+                    &variant.fields,
+                    state_field,
+                )
+            })
+            .collect(),
+        (None, Some(fields)) => {
+            let fields = FieldsUnnamed {
+                paren_token: Default::default(),
+                unnamed: fields.clone(),
+            };
+            vec![DerivedVariant::new(
+                quote!(Self),
+                &[],
+                &Fields::Unnamed(fields),
+                state_field,
+            )]
+        }
+        // the unit structs have only the `_state` field
+        (None, None) => {
+            let mut named = struct_fields.clone();
+            for (name, ty) in &components {
+                named.push(Field::parse_named.parse2(quote!(#name: #ty)).unwrap());
+            }
+            let fields = FieldsNamed {
+                brace_token: Default::default(),
+                named,
+            };
+            vec![DerivedVariant::new(
+                quote!(Self),
+                &[],
+                &Fields::Named(fields),
+                state_field,
+            )]
+        }
+    };
+
+    let mut field_types: Vec<TokenStream> = match (enum_variants, tuple_fields) {
+        (Some(variants), _) => variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .map(|field| {
+                let ty = &field.ty;
+                quote!(#ty)
+            })
+            .collect(),
+        (None, Some(fields)) => fields
+            .iter()
+            .map(|field| {
+                let ty = &field.ty;
+                quote!(#ty)
+            })
+            .collect(),
+        (None, None) => struct_fields
+            .iter()
+            .map(|field| {
+                let ty = &field.ty;
+                quote!(#ty)
+            })
+            .collect(),
+    };
+    field_types.extend(components.iter().map(|(_, ty)| quote!(#ty)));
+    field_types.push(state_type.clone());
+    let field_types: Vec<_> = field_types
+        .into_iter()
+        .filter(|ty| mentions_states(ty.clone(), state_idents))
+        .collect();
+
+    let params = strip_generic_defaults(&generics.params);
+    let args = generic_args(&generics.params);
+    let sealers: Vec<_> = (0..state_idents.len())
+        .map(|index| type_state_args.sealer_of_slot(index, struct_name))
+        .collect();
+    let impl_for = |trait_path: TokenStream, items: TokenStream| {
+        let params = params.iter();
+        let where_predicates = generics
+            .where_clause
+            .iter()
+            .flat_map(|where_clause| where_clause.predicates.iter());
+        let param_bounds = generics.params.iter().filter_map(|param| match param {
+            GenericParam::Type(param) => {
+                let ident = &param.ident;
+                Some(quote!(#ident: #trait_path))
+            }
+            _ => None,
+        });
+        let field_types = field_types.iter();
+        quote! {
+            impl<#(#params,)* #(#state_idents),*> #trait_path for #struct_name<#(#args,)* #(#state_idents),*>
+            where
+                #(#where_predicates,)*
+                #(#state_idents: #sealers,)*
+                #(#param_bounds,)*
+                #(#field_types: #trait_path,)*
+            {
+                #items
+            }
+        }
+    };
+
+    // the other variant is compared by the order of the variants: `Self::Hello { .. } => 0`
+    let variant_index = {
+        let arms = variants.iter().enumerate().map(|(index, variant)| {
+            let cfgs = &variant.cfgs;
+            let wildcard = variant.wildcard();
+            quote!(#(#cfgs)* #wildcard => #index)
+        });
+        quote! {
+            let variant_index = |value: &Self| -> usize {
+                match value {
+                    #(#arms,)*
+                }
+            };
+        }
+    };
+    let multiple_variants = variants.len() > 1;
+    let arms = |body: &dyn Fn(&DerivedVariant) -> TokenStream, pair: bool| {
+        let arms = variants.iter().map(|variant| {
+            let cfgs = &variant.cfgs;
+            let this = variant.pattern("self");
+            let pattern = if pair {
+                let other = variant.pattern("other");
+                quote!((#this, #other))
+            } else {
+                this
+            };
+            let body = body(variant);
+            quote!(#(#cfgs)* #pattern => { #body })
+        });
+        quote!(#(#arms)*)
+    };
+
+    let mut impls = Vec::new();
+    for derive in derives {
+        let derived = match derive.to_string().as_str() {
+            "Clone" => {
+                let arms = arms(
+                    &|variant| {
+                        let path = &variant.path;
+                        let values = variant.fields.iter().zip(variant.bindings("self")).map(
+                            |((member, cfgs), binding)| {
+                                let value = quote!(::core::clone::Clone::clone(#binding));
+                                if variant.named {
+                                    quote!(#(#cfgs)* #member: #value)
+                                } else {
+                                    value
+                                }
+                            },
+                        );
+                        if variant.named {
+                            quote!(#path { #(#values),* })
+                        } else {
+                            quote!(#path(#(#values),*))
+                        }
+                    },
+                    false,
+                );
+                Some(impl_for(
+                    quote!(::core::clone::Clone),
+                    quote! {
+                        fn clone(&self) -> Self {
+                            match self {
+                                #arms
+                            }
+                        }
+                    },
+                ))
+            }
+            "Default" => {
+                let default_slots: Vec<_> = type_state_args
+                    .slots
+                    .iter()
+                    .map(|slot| &slot.default)
+                    .collect();
+                // the default states that carry data cannot be created from nothing
+                if let Some(state) = default_slots
+                    .iter()
+                    .find(|state| type_state_args.fields_of_state(state).is_some())
+                {
+                    return Err(syn::Error::new(
+                        derive.span(),
+                        format!(
+                            "`Default` cannot be derived, since the default state `{}` carries data",
+                            type_state_args.unprefixed_name(state)
+                        ),
+                    ));
+                }
+                let variant = &variants[0];
+                let path = &variant.path;
+                let values = variant.fields.iter().map(|(member, cfgs)| {
+                    let value = quote!(::core::default::Default::default());
+                    if variant.named {
+                        quote!(#(#cfgs)* #member: #value)
+                    } else {
+                        value
+                    }
+                });
+                let value = if variant.named {
+                    quote!(#path { #(#values),* })
+                } else {
+                    quote!(#path(#(#values),*))
+                };
+                let params = params.iter();
+                let where_predicates = generics
+                    .where_clause
+                    .iter()
+                    .flat_map(|where_clause| where_clause.predicates.iter());
+                let param_bounds = generics.params.iter().filter_map(|param| match param {
+                    GenericParam::Type(param) => {
+                        let ident = &param.ident;
+                        Some(quote!(#ident: ::core::default::Default))
+                    }
+                    _ => None,
+                });
+                Some(quote! {
+                    impl<#(#params),*> ::core::default::Default
+                        for #struct_name<#(#args,)* #(#default_slots),*>
+                    where
+                        #(#where_predicates,)*
+                        #(#param_bounds,)*
+                    {
+                        fn default() -> Self {
+                            #value
+                        }
+                    }
+                })
+            }
+            "Copy" => Some(impl_for(quote!(::core::marker::Copy), quote!())),
+            "Eq" => Some(impl_for(quote!(::core::cmp::Eq), quote!())),
+            "PartialEq" => {
+                let arms = arms(
+                    &|variant| {
+                        let statements = variant.statements(|this, other| {
+                            quote! {
+                                if #this != #other {
+                                    return false;
+                                }
+                            }
+                        });
+                        quote!(#statements true)
+                    },
+                    true,
+                );
+                let other_variants = multiple_variants.then(|| quote!(_ => false,));
+                Some(impl_for(
+                    quote!(::core::cmp::PartialEq),
+                    quote! {
+                        fn eq(&self, other: &Self) -> bool {
+                            match (self, other) {
+                                #arms
+                                #other_variants
+                            }
+                        }
+                    },
+                ))
+            }
+            "Hash" => {
+                let arms = arms(
+                    &|variant| {
+                        variant
+                            .statements(|this, _| quote!(::core::hash::Hash::hash(#this, state);))
+                    },
+                    false,
+                );
+                let discriminant = multiple_variants.then(
+                    || quote!(::core::hash::Hash::hash(&::core::mem::discriminant(self), state);),
+                );
+                Some(impl_for(
+                    quote!(::core::hash::Hash),
+                    quote! {
+                        fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                            #discriminant
+                            match self {
+                                #arms
+                            }
+                        }
+                    },
+                ))
+            }
+            "PartialOrd" => {
+                let arms = arms(
+                    &|variant| {
+                        let statements = variant.statements(|this, other| {
+                            quote! {
+                                match ::core::cmp::PartialOrd::partial_cmp(#this, #other) {
+                                    ::core::option::Option::Some(::core::cmp::Ordering::Equal) => {}
+                                    ordering => return ordering,
+                                }
+                            }
+                        });
+                        quote!(#statements ::core::option::Option::Some(::core::cmp::Ordering::Equal))
+                    },
+                    true,
+                );
+                let other_variants = multiple_variants.then(|| {
+                    quote! {
+                        _ => {
+                            #variant_index
+                            ::core::cmp::PartialOrd::partial_cmp(&variant_index(self), &variant_index(other))
+                        }
+                    }
+                });
+                Some(impl_for(
+                    quote!(::core::cmp::PartialOrd),
+                    quote! {
+                        fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                            match (self, other) {
+                                #arms
+                                #other_variants
+                            }
+                        }
+                    },
+                ))
+            }
+            "Ord" => {
+                let arms = arms(
+                    &|variant| {
+                        let statements = variant.statements(|this, other| {
+                            quote! {
+                                match ::core::cmp::Ord::cmp(#this, #other) {
+                                    ::core::cmp::Ordering::Equal => {}
+                                    ordering => return ordering,
+                                }
+                            }
+                        });
+                        quote!(#statements ::core::cmp::Ordering::Equal)
+                    },
+                    true,
+                );
+                let other_variants = multiple_variants.then(|| {
+                    quote! {
+                        _ => {
+                            #variant_index
+                            ::core::cmp::Ord::cmp(&variant_index(self), &variant_index(other))
+                        }
+                    }
+                });
+                Some(impl_for(
+                    quote!(::core::cmp::Ord),
+                    quote! {
+                        fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                            match (self, other) {
+                                #arms
+                                #other_variants
+                            }
+                        }
+                    },
+                ))
+            }
+            _ => None,
+        };
+        impls.extend(derived);
+    }
+    Ok(quote!(#(#impls)*))
+}
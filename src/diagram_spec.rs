@@ -0,0 +1,338 @@
+/// this file contains the reading of the diagrams given to `state_machine_from!` (see `spec.rs`), which is the inverse of
+/// `#[impl_state(diagram = ...)]` and `#[impl_state(dot = ...)]`: the states and the labeled transitions of a Mermaid
+/// or a Graphviz diagram become the machine of `state_shift!`, whose methods are labeled on the edges
+use std::str::FromStr;
+
+use proc_macro2::TokenStream;
+use syn::{
+    braced, bracketed,
+    ext::IdentExt,
+    parse::{ParseStream, Parser},
+    Ident, Lit, Token,
+};
+
+/// The machine drawn by a diagram
+pub struct DiagramMachine {
+    /// the name of the graph, if the diagram gives one: `digraph Door`
+    pub name: Option<String>,
+    /// the states, in the order they are drawn in, with the start first
+    pub states: Vec<String>,
+    pub transitions: Vec<DiagramTransition>,
+}
+
+/// A transition of a diagram, merged from the edges labeled with the same method
+pub struct DiagramTransition {
+    pub method: String,
+    pub from: Vec<String>,
+    pub to: String,
+}
+
+/// An edge of a diagram, between states or from the start (`None`), with its label
+struct Edge {
+    from: Option<String>,
+    to: String,
+    label: Option<String>,
+}
+
+/// Collects the states and the edges of a diagram, and merges them into the machine
+#[derive(Default)]
+struct DiagramBuilder {
+    states: Vec<String>,
+    edges: Vec<Edge>,
+}
+
+impl DiagramBuilder {
+    fn state(&mut self, state: &str) {
+        if !self.states.iter().any(|known| known == state) {
+            self.states.push(state.to_string());
+        }
+    }
+
+    /// Adds an edge, where `None` is the start for `from` and the end for `to` (the edges to the end are only drawn)
+    fn edge(&mut self, from: Option<&str>, to: Option<&str>, label: Option<&str>) {
+        if let Some(from) = from {
+            self.state(from);
+        }
+        let Some(to) = to else {
+            return;
+        };
+        self.state(to);
+        self.edges.push(Edge {
+            from: from.map(str::to_string),
+            to: to.to_string(),
+            label: label
+                .map(str::trim)
+                .filter(|label| !label.is_empty())
+                .map(str::to_string),
+        });
+    }
+
+    /// The machine of the diagram: the start is the state the start points to (or the first state if there is no start),
+    /// and the edges labeled with the same method are a single transition, which can only go to a single state
+    fn finish(mut self, name: Option<String>) -> Result<DiagramMachine, String> {
+        let mut starts: Vec<&String> = Vec::new();
+        for edge in self.edges.iter().filter(|edge| edge.from.is_none()) {
+            if !starts.contains(&&edge.to) {
+                starts.push(&edge.to);
+            }
+        }
+        if let [first, second, ..] = starts.as_slice() {
+            return Err(format!(
+                "the machine expects a single start, found `{}` and `{}`",
+                first, second
+            ));
+        }
+        if self.states.is_empty() {
+            return Err("the diagram has no states".to_string());
+        }
+        if let Some(start) = starts.first() {
+            let position = self
+                .states
+                .iter()
+                .position(|state| state == *start)
+                .unwrap();
+            let start = self.states.remove(position);
+            self.states.insert(0, start);
+        }
+
+        let mut transitions: Vec<DiagramTransition> = Vec::new();
+        for Edge { from, to, label } in &self.edges {
+            let Some(from) = from else {
+                continue;
+            };
+            let Some(method) = label else {
+                return Err(format!(
+                    "the edge from `{}` to `{}` expects a label, which is the name of its method",
+                    from, to
+                ));
+            };
+            match transitions
+                .iter_mut()
+                .find(|transition| transition.method == *method)
+            {
+                Some(transition) if transition.to != *to => {
+                    return Err(format!(
+                        "the method `{}` goes to `{}` and to `{}`, but a transition can only go to a single state",
+                        method, transition.to, to
+                    ))
+                }
+                Some(transition) => {
+                    if !transition.from.contains(from) {
+                        transition.from.push(from.clone());
+                    }
+                }
+                None => transitions.push(DiagramTransition {
+                    method: method.clone(),
+                    from: vec![from.clone()],
+                    to: to.clone(),
+                }),
+            }
+        }
+
+        Ok(DiagramMachine {
+            name,
+            states: self.states,
+            transitions,
+        })
+    }
+}
+
+/// Reads a Mermaid state diagram: `[*] --> Idle`, `Idle --> Running: start`, `Running --> [*]`
+///
+/// (the declarations of the states and their descriptions are read as states too,
+/// and the notes, the comments and the styles are skipped)
+pub fn mermaid(source: &str) -> Result<DiagramMachine, String> {
+    let mut builder = DiagramBuilder::default();
+    let mut in_note = false;
+    for line in source.lines().map(str::trim) {
+        if in_note {
+            in_note = line != "end note";
+            continue;
+        }
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+        match keyword {
+            "" | "stateDiagram" | "stateDiagram-v2" | "direction" | "classDef" | "class" => {
+                continue
+            }
+            _ if keyword.starts_with("%%") => continue,
+            "note" => {
+                in_note = !line.contains(':');
+                continue;
+            }
+            _ => {}
+        }
+        if line.ends_with('{') {
+            return Err(format!(
+                "the composite states are not supported, since the machine has a single slot: `{}`",
+                line
+            ));
+        }
+
+        if let Some((from, to)) = line.split_once("-->") {
+            let (to, label) = match to.split_once(':') {
+                Some((to, label)) => (to, Some(label)),
+                None => (to, None),
+            };
+            let (from, to) = (from.trim(), to.trim());
+            builder.edge(
+                (from != "[*]").then_some(from),
+                (to != "[*]").then_some(to),
+                label,
+            );
+        } else if let Some(declaration) = line.strip_prefix("state ") {
+            // `state Idle`, or `state "Waiting for input" as Idle`
+            let state = match declaration.rsplit_once(" as ") {
+                Some((_, state)) => state,
+                None => declaration,
+            };
+            builder.state(state.trim());
+        } else if let Some((state, _)) = line.split_once(':') {
+            builder.state(state.trim());
+        } else if !line.contains(char::is_whitespace) {
+            builder.state(line);
+        } else {
+            return Err(format!("cannot read the line `{}`", line));
+        }
+    }
+    builder.finish(None)
+}
+
+/// An identifier of Graphviz: `Idle`, `"Idle"` or `1`
+fn dot_id(input: ParseStream) -> syn::Result<String> {
+    if input.peek(Ident::peek_any) {
+        return Ok(input.call(Ident::parse_any)?.to_string());
+    }
+    match input.parse::<Lit>()? {
+        Lit::Str(string) => Ok(string.value()),
+        Lit::Int(int) => Ok(int.to_string()),
+        Lit::Float(float) => Ok(float.to_string()),
+        lit => Err(syn::Error::new(lit.span(), "expected an identifier")),
+    }
+}
+
+/// The attributes of a statement of Graphviz: `[shape = point, label = "start"]`
+fn dot_attrs(input: ParseStream) -> syn::Result<DotAttrs> {
+    let mut attrs = Vec::new();
+    while input.peek(syn::token::Bracket) {
+        let content;
+        bracketed!(content in input);
+        while !content.is_empty() {
+            let key = dot_id(&content)?;
+            content.parse::<Token![=]>()?;
+            attrs.push((key, dot_id(&content)?));
+            if content.peek(Token![,]) {
+                content.parse::<Token![,]>()?;
+            } else if content.peek(Token![;]) {
+                content.parse::<Token![;]>()?;
+            }
+        }
+    }
+    Ok(attrs)
+}
+
+fn dot_attr<'a>(attrs: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .rev()
+        .find(|(attr, _)| attr == key)
+        .map(|(_, value)| value.as_str())
+}
+
+/// The attributes of a node or an edge of Graphviz: `[shape = point]` -> `("shape", "point")`
+type DotAttrs = Vec<(String, String)>;
+
+/// A graph of Graphviz, with its nodes and its edges (and their attributes)
+struct DotGraph {
+    name: Option<String>,
+    nodes: Vec<(String, DotAttrs)>,
+    edges: Vec<(String, String, DotAttrs)>,
+}
+
+fn dot_graph(input: ParseStream) -> syn::Result<DotGraph> {
+    let keyword = input.call(Ident::parse_any)?;
+    let keyword = if keyword == "strict" {
+        input.call(Ident::parse_any)?
+    } else {
+        keyword
+    };
+    if keyword != "digraph" {
+        return Err(syn::Error::new(keyword.span(), "expected a `digraph`"));
+    }
+    let name = if input.peek(syn::token::Brace) {
+        None
+    } else {
+        Some(dot_id(input)?)
+    };
+    let content;
+    braced!(content in input);
+
+    let mut graph = DotGraph {
+        name,
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+    while !content.is_empty() {
+        if content.peek(syn::token::Brace)
+            || content.peek(Ident::peek_any) && content.fork().call(Ident::parse_any)? == "subgraph"
+        {
+            return Err(content
+                .error("the subgraphs are not supported, since the machine has a single slot"));
+        }
+        let id = dot_id(&content)?;
+        if matches!(id.as_str(), "graph" | "node" | "edge") && content.peek(syn::token::Bracket) {
+            dot_attrs(&content)?;
+        } else if content.peek(Token![=]) {
+            // an attribute of the graph: `rankdir = LR`
+            content.parse::<Token![=]>()?;
+            dot_id(&content)?;
+        } else if content.peek(Token![->]) {
+            let mut ids = vec![id];
+            while content.peek(Token![->]) {
+                content.parse::<Token![->]>()?;
+                ids.push(dot_id(&content)?);
+            }
+            let attrs = dot_attrs(&content)?;
+            for pair in ids.windows(2) {
+                graph
+                    .edges
+                    .push((pair[0].clone(), pair[1].clone(), attrs.clone()));
+            }
+        } else {
+            let attrs = dot_attrs(&content)?;
+            graph.nodes.push((id, attrs));
+        }
+        if content.peek(Token![;]) {
+            content.parse::<Token![;]>()?;
+        }
+    }
+    Ok(graph)
+}
+
+/// Reads a Graphviz graph: `"Idle" -> "Running" [label = "start"]`
+///
+/// the start is the node drawn as a point (`[shape = point]`), and the end is a node without a label (`[label = ""]`)
+pub fn dot(source: &str) -> Result<DiagramMachine, String> {
+    let tokens = TokenStream::from_str(source).map_err(|error| error.to_string())?;
+    let graph = dot_graph
+        .parse2(tokens)
+        .map_err(|error| error.to_string())?;
+
+    let mut starts = Vec::new();
+    let mut ends = Vec::new();
+    let mut builder = DiagramBuilder::default();
+    for (node, attrs) in &graph.nodes {
+        if dot_attr(attrs, "shape") == Some("point") {
+            starts.push(node);
+        } else if dot_attr(attrs, "label") == Some("") {
+            ends.push(node);
+        } else {
+            builder.state(node);
+        }
+    }
+    for (from, to, attrs) in &graph.edges {
+        let from = Some(from.as_str()).filter(|from| !starts.iter().any(|start| start == from));
+        let to = Some(to.as_str()).filter(|to| !ends.iter().any(|end| end == to));
+        builder.edge(from, to, dot_attr(attrs, "label"));
+    }
+    builder.finish(graph.name)
+}
@@ -0,0 +1,324 @@
+/// this file contains the `state_shift!` macro, which declares the state machines up front: the struct, its states
+/// and its transitions, and generates the type-state struct along with the methods of the transitions,
+/// whose bodies are given separately by implementing the `{Struct}Transitions` trait
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Attribute, FieldsNamed, Ident, Token, Type, Visibility,
+};
+
+use crate::closest_name;
+
+mod keyword {
+    syn::custom_keyword!(machine);
+    syn::custom_keyword!(fields);
+    syn::custom_keyword!(states);
+    syn::custom_keyword!(transitions);
+}
+
+/// An argument of a transition: `level: u8`
+struct TransitionArg {
+    name: Ident,
+    ty: Type,
+}
+
+impl Parse for TransitionArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        Ok(TransitionArg {
+            name,
+            ty: input.parse()?,
+        })
+    }
+}
+
+/// A transition of the machine: `start(level: u8): Idle -> Running`, or `stop: Running | Paused -> Idle` from several states
+struct Transition {
+    attrs: Vec<Attribute>,
+    name: Ident,
+    args: Vec<TransitionArg>,
+    from: Vec<Ident>,
+    to: Ident,
+}
+
+impl Parse for Transition {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let name = input.parse()?;
+        let args = if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Punctuated::<TransitionArg, Token![,]>::parse_terminated(&content)?
+                .into_iter()
+                .collect()
+        } else {
+            Vec::new()
+        };
+        input.parse::<Token![:]>()?;
+        let mut from = vec![input.parse()?];
+        while input.peek(Token![|]) {
+            input.parse::<Token![|]>()?;
+            from.push(input.parse()?);
+        }
+        input.parse::<Token![->]>()?;
+        Ok(Transition {
+            attrs,
+            name,
+            args,
+            from,
+            to: input.parse()?,
+        })
+    }
+}
+
+/// A machine of `state_shift!`:
+///
+/// ```ignore
+/// pub machine Player {
+///     fields { name: String }
+///     states { Idle, Running }
+///     transitions { start: Idle -> Running }
+/// }
+/// ```
+///
+/// (the fields are optional, and the first state is the state of the machine when it is created)
+struct Machine {
+    attrs: Vec<Attribute>,
+    visibility: Visibility,
+    name: Ident,
+    fields: Option<FieldsNamed>,
+    states: Vec<Ident>,
+    transitions: Vec<Transition>,
+}
+
+impl Parse for Machine {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let visibility = input.parse()?;
+        input.parse::<keyword::machine>()?;
+        let name: Ident = input.parse()?;
+        let content;
+        braced!(content in input);
+
+        let fields = if content.peek(keyword::fields) {
+            content.parse::<keyword::fields>()?;
+            Some(content.parse()?)
+        } else {
+            None
+        };
+
+        let keyword = content.parse::<keyword::states>()?;
+        let states_content;
+        braced!(states_content in content);
+        let states: Vec<Ident> = Punctuated::<Ident, Token![,]>::parse_terminated(&states_content)?
+            .into_iter()
+            .collect();
+        if states.is_empty() {
+            return Err(syn::Error::new(
+                keyword.span,
+                "the machine expects at least one state, the first one being its state when it is created",
+            ));
+        }
+
+        content.parse::<keyword::transitions>()?;
+        let transitions_content;
+        braced!(transitions_content in content);
+        let transitions =
+            Punctuated::<Transition, Token![,]>::parse_terminated(&transitions_content)?
+                .into_iter()
+                .collect();
+
+        Ok(Machine {
+            attrs,
+            visibility,
+            name,
+            fields,
+            states,
+            transitions,
+        })
+    }
+}
+
+/// Input of the `state_shift!` macro: one or more machines
+struct StateShiftInput {
+    machines: Vec<Machine>,
+}
+
+impl Parse for StateShiftInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut machines = Vec::new();
+        while !input.is_empty() {
+            machines.push(input.parse()?);
+        }
+        Ok(StateShiftInput { machines })
+    }
+}
+
+/// Checks that the state of a transition is one of the states of the machine
+fn check_state(state: &Ident, states: &[Ident]) -> syn::Result<()> {
+    if states.contains(state) {
+        return Ok(());
+    }
+    let message = match closest_name(state, states) {
+        Some(closest) => format!(
+            "`{}` is not a state of the machine, did you mean `{}`?",
+            state, closest
+        ),
+        None => format!("`{}` is not a state of the machine", state),
+    };
+    Err(syn::Error::new(state.span(), message))
+}
+
+/// Generates the machine:
+///
+/// ```ignore
+/// #[type_state(states = (Idle, Running), slots = (Idle))]
+/// pub struct Player { name: String }
+///
+/// pub trait PlayerTransitions {
+///     fn on_start(&mut self);
+/// }
+///
+/// #[impl_state]
+/// impl Player {
+///     #[switch_to(Idle)]
+///     pub fn new(name: String) -> Self { .. }
+///
+///     #[require(Idle)]
+///     #[switch_to(Running)]
+///     pub fn start(mut self) -> Self {
+///         PlayerTransitions::on_start(&mut self);
+///         Self { name: self.name }
+///     }
+/// }
+/// ```
+fn machine(machine: &Machine) -> syn::Result<TokenStream> {
+    for (index, transition) in machine.transitions.iter().enumerate() {
+        // each transition is a method of the struct and of the `{Struct}Transitions` trait
+        if machine.transitions[..index]
+            .iter()
+            .any(|declared| declared.name == transition.name)
+        {
+            return Err(syn::Error::new(
+                transition.name.span(),
+                format!(
+                    "the transition `{}` is declared more than once",
+                    transition.name
+                ),
+            ));
+        }
+        for from in &transition.from {
+            check_state(from, &machine.states)?;
+        }
+        check_state(&transition.to, &machine.states)?;
+    }
+
+    let Machine {
+        attrs,
+        visibility,
+        name,
+        states,
+        transitions,
+        ..
+    } = machine;
+    let initial_state = &states[0];
+    let fields: Vec<_> = machine
+        .fields
+        .iter()
+        .flat_map(|fields| &fields.named)
+        .collect();
+    let field_names: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let field_types: Vec<_> = fields.iter().map(|field| &field.ty).collect();
+    let trait_name = Ident::new(&format!("{}Transitions", name), name.span());
+
+    let hooks = transitions.iter().map(|transition| {
+        let hook = Ident::new(&format!("on_{}", transition.name), transition.name.span());
+        let doc = format!(
+            " Runs when `{}` is called, before `{}` switches from {} to `{}`",
+            transition.name,
+            name,
+            transition
+                .from
+                .iter()
+                .map(|from| format!("`{}`", from))
+                .collect::<Vec<_>>()
+                .join(" or "),
+            transition.to
+        );
+        let arg_names = transition.args.iter().map(|arg| &arg.name);
+        let arg_types = transition.args.iter().map(|arg| &arg.ty);
+        quote! {
+            #[doc = #doc]
+            fn #hook(&mut self, #(#arg_names: #arg_types),*);
+        }
+    });
+    let methods = transitions.iter().map(|transition| {
+        let Transition {
+            attrs,
+            name: method,
+            from,
+            to,
+            ..
+        } = transition;
+        let hook = Ident::new(&format!("on_{}", method), method.span());
+        let arg_names: Vec<_> = transition.args.iter().map(|arg| &arg.name).collect();
+        let arg_types = transition.args.iter().map(|arg| &arg.ty);
+        quote! {
+            #(#attrs)*
+            #[require(#(#from)|*)]
+            #[switch_to(#to)]
+            #visibility fn #method(mut self, #(#arg_names: #arg_types),*) -> Self {
+                #trait_name::#hook(&mut self, #(#arg_names),*);
+                Self {
+                    #(#field_names: self.#field_names,)*
+                }
+            }
+        }
+    });
+    let trait_doc = format!(
+        " The bodies of the transitions of `{}`, which are implemented for it in any state",
+        name
+    );
+    let new_doc = format!(" Creates `{}`, in the `{}` state", name, initial_state);
+
+    Ok(quote! {
+        #(#attrs)*
+        #[::state_shift::type_state(states = (#(#states),*), slots = (#initial_state))]
+        #visibility struct #name {
+            #(#fields,)*
+        }
+
+        #[doc = #trait_doc]
+        #visibility trait #trait_name {
+            #(#hooks)*
+        }
+
+        #[::state_shift::impl_state]
+        impl #name {
+            #[doc = #new_doc]
+            #[switch_to(#initial_state)]
+            #visibility fn new(#(#field_names: #field_types),*) -> Self {
+                Self {
+                    #(#field_names,)*
+                }
+            }
+
+            #(#methods)*
+        }
+    })
+}
+
+/// Expands the machines of `state_shift!` (see `machine`)
+pub fn state_shift_inner(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as StateShiftInput);
+    let machines = input
+        .machines
+        .iter()
+        .map(|machine_input| machine(machine_input).unwrap_or_else(syn::Error::into_compile_error));
+    quote!(#(#machines)*).into()
+}
@@ -0,0 +1,218 @@
+/// this file contains the dump of the expansions, which writes the code generated for each annotated item to a file
+/// when the crate is compiled with `STATE_SHIFT_DEBUG=1`, so that the expansion of a single struct or `impl` block
+/// can be read without running `cargo expand` on the whole crate
+use std::path::{Path, PathBuf};
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenTree;
+use syn::{ImplItem, Item, Lit};
+
+/// The environment variable that turns the dump on
+const DEBUG_VAR: &str = "STATE_SHIFT_DEBUG";
+
+fn dump_enabled() -> bool {
+    std::env::var(DEBUG_VAR).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+/// The directory of the dumps of the crate being compiled: `target/state-shift/<crate>/`,
+/// or `$OUT_DIR/state-shift/<crate>/` for the crates with a build script
+fn dump_dir() -> PathBuf {
+    let manifest_dir = PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let base = match std::env::var_os("OUT_DIR") {
+        Some(out_dir) => PathBuf::from(out_dir),
+        None => target_dir(
+            &manifest_dir,
+            std::env::var_os("CARGO_TARGET_DIR").map(PathBuf::from),
+        ),
+    };
+    let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "crate".to_string());
+    base.join("state-shift").join(crate_name)
+}
+
+/// The `target` directory of the crate: the one given by `CARGO_TARGET_DIR` (relative to the root of the workspace),
+/// or `target` in the root of the workspace, which is shared by its members
+fn target_dir(manifest_dir: &Path, target_dir: Option<PathBuf>) -> PathBuf {
+    let workspace_root = workspace_root(manifest_dir);
+    match target_dir {
+        Some(target_dir) => workspace_root.join(target_dir),
+        None => workspace_root.join("target"),
+    }
+}
+
+/// The root of the workspace of the crate, which is the closest directory (the crate's own included)
+/// whose manifest has a `[workspace]` table, like cargo finds it, or the crate itself outside of a workspace
+fn workspace_root(manifest_dir: &Path) -> &Path {
+    manifest_dir
+        .ancestors()
+        .find(|dir| {
+            std::fs::read_to_string(dir.join("Cargo.toml")).is_ok_and(|manifest| {
+                manifest.lines().any(|line| {
+                    line.trim() == "[workspace]" || line.trim_start().starts_with("[workspace.")
+                })
+            })
+        })
+        .unwrap_or(manifest_dir)
+}
+
+/// The name of the annotated item, which names its dump:
+///
+/// `Player` for a struct (or a module, a trait, the machine of `state_shift!`, the spec of `state_machine_from!`),
+/// `Player-new` for an `impl` block (after its first method), and `Player-Display` for a trait implementation
+fn item_name(input: proc_macro2::TokenStream) -> String {
+    let tokens: Vec<TokenTree> = input.into_iter().collect();
+    // the hidden macros get the item after the arguments they carry: `(<arguments>) (<generics>) impl Player { ... }`
+    let item = (0..tokens.len())
+        .find_map(|start| syn::parse2::<Item>(tokens[start..].iter().cloned().collect()).ok());
+    let name = match item {
+        Some(Item::Struct(item)) => Some(item.ident.to_string()),
+        Some(Item::Enum(item)) => Some(item.ident.to_string()),
+        Some(Item::Union(item)) => Some(item.ident.to_string()),
+        Some(Item::Mod(item)) => Some(item.ident.to_string()),
+        Some(Item::Trait(item)) => Some(item.ident.to_string()),
+        Some(Item::Impl(item)) => {
+            let self_ty = match *item.self_ty {
+                syn::Type::Path(ref type_path) => type_path
+                    .path
+                    .segments
+                    .last()
+                    .map(|segment| segment.ident.to_string()),
+                _ => None,
+            };
+            let part = match &item.trait_ {
+                Some((_, path, _)) => path
+                    .segments
+                    .last()
+                    .map(|segment| segment.ident.to_string()),
+                None => item.items.iter().find_map(|item| match item {
+                    ImplItem::Fn(method) => Some(method.sig.ident.to_string()),
+                    _ => None,
+                }),
+            };
+            self_ty.map(|self_ty| match part {
+                Some(part) => format!("{}-{}", self_ty, part),
+                None => self_ty,
+            })
+        }
+        _ => None,
+    };
+    // `state_shift! { machine Player { ... } }`, `state_machine_from!("specs/door.ron")`
+    name.or_else(|| {
+        tokens
+            .iter()
+            .enumerate()
+            .find_map(|(index, token)| match token {
+                TokenTree::Ident(ident) if ident == "machine" => match tokens.get(index + 1) {
+                    Some(TokenTree::Ident(name)) => Some(name.to_string()),
+                    _ => None,
+                },
+                TokenTree::Literal(literal) => match Lit::new(literal.clone()) {
+                    Lit::Str(path) => PathBuf::from(path.value())
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned()),
+                    _ => None,
+                },
+                _ => None,
+            })
+    })
+    .unwrap_or_else(|| "item".to_string())
+}
+
+/// Writes the expansion of the macro to `<macro>-<item>.rs` in the directory of the dumps, if `STATE_SHIFT_DEBUG` is set,
+/// and gives the expansion back as it is (along with the error, if the file cannot be written)
+///
+/// the file is left as it is if it is up to date, like the diagrams of `#[impl_state(diagram = ...)]`,
+/// and the blocks forwarded to the hidden macros are dumped again by them, with the same name
+pub fn dump_expansion(macro_name: &str, input: TokenStream, expanded: TokenStream) -> TokenStream {
+    if !dump_enabled() {
+        return expanded;
+    }
+
+    let item = item_name(input.into());
+    let file = dump_dir().join(format!("{}-{}.rs", macro_name, item));
+    let dump = format!(
+        "// the expansion of `{}` for `{}` (format it with `rustfmt` to read it)\n{}\n",
+        macro_name, item, expanded
+    );
+    if std::fs::read_to_string(&file).is_ok_and(|existing| existing == dump) {
+        return expanded;
+    }
+
+    let written = match file.parent() {
+        Some(dir) => std::fs::create_dir_all(dir),
+        None => Ok(()),
+    }
+    .and_then(|_| std::fs::write(&file, dump));
+    match written {
+        Ok(()) => expanded,
+        Err(error) => {
+            let error = syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!(
+                    "cannot write the expansion to `{}` (set by `{}`): {}",
+                    file.display(),
+                    DEBUG_VAR,
+                    error
+                ),
+            )
+            .to_compile_error();
+            let mut expanded = proc_macro2::TokenStream::from(expanded);
+            expanded.extend(error);
+            expanded.into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_are_named_after_their_types_and_first_methods() {
+        let name = |tokens: &str| item_name(tokens.parse().unwrap());
+
+        assert_eq!(name("struct Player { level: u8 }"), "Player");
+        assert_eq!(name("enum Message { Data {} }"), "Message");
+        assert_eq!(
+            name("impl Player { fn new() -> Self { todo!() } }"),
+            "Player-new"
+        );
+        assert_eq!(name("impl Display for crate::Player { }"), "Player-Display");
+        // the arguments carried by the hidden macros come before the item
+        assert_eq!(
+            name("(states = (Idle, Running)) () impl Player { fn start(self) {} }"),
+            "Player-start"
+        );
+        assert_eq!(name("machine Door { states: Closed, Open }"), "Door");
+        assert_eq!(name("\"specs/door.ron\""), "door");
+        assert_eq!(name("fn main() {}"), "item");
+    }
+
+    #[test]
+    fn dumps_are_in_the_target_of_the_workspace() {
+        let root = std::env::temp_dir().join(format!("state-shift-dump-{}", std::process::id()));
+        let member = root.join("crates/player");
+        std::fs::create_dir_all(&member).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        )
+        .unwrap();
+        std::fs::write(member.join("Cargo.toml"), "[package]\nname = \"player\"\n").unwrap();
+
+        assert_eq!(target_dir(&member, None), root.join("target"));
+        assert_eq!(
+            target_dir(&member, Some(PathBuf::from("build"))),
+            root.join("build")
+        );
+        assert_eq!(
+            target_dir(&member, Some(PathBuf::from("/tmp/build"))),
+            PathBuf::from("/tmp/build")
+        );
+
+        // outside of a workspace, the crate is its own root
+        std::fs::write(root.join("Cargo.toml"), "[package]\nname = \"root\"\n").unwrap();
+        assert_eq!(target_dir(&member, None), member.join("target"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,5 +1,104 @@
-use proc_macro::TokenTree;
-use syn::{punctuated::Punctuated, Attribute, Ident, Token};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit_mut::VisitMut,
+    AngleBracketedGenericArguments, Attribute, Expr, GenericArgument, GenericParam, Ident,
+    ItemImpl, Lifetime, LifetimeParam, PathArguments, PathSegment, Token, Type, TypePath,
+};
+
+use crate::type_state::StateSlot;
+
+/// A state given to a slot of `#[require]` or `#[switch_to]`: `Idle`,
+/// or a generic state along with its arguments: `Loaded<Config>`,
+/// or a substate along with its parents: `Connected::Active`
+#[derive(Clone)]
+pub struct StateArg {
+    /// the parents of the substate, outermost first: `Connected::Active` -> `Connected`
+    pub parents: Vec<Ident>,
+    pub ident: Ident,
+    pub args: Option<AngleBracketedGenericArguments>,
+}
+
+impl StateArg {
+    /// Whether this is a generic standing for any state of the slot: `A`
+    pub fn is_generic(&self) -> bool {
+        self.args.is_none() && is_single_letter(&self.ident)
+    }
+
+    /// The generics among the arguments of a generic state: `Loaded<C>` -> `C`
+    ///
+    /// like the generic states, they are single letters, and they are declared by the generated `impl` blocks
+    pub fn generic_args(&self) -> Vec<Ident> {
+        self.args
+            .iter()
+            .flat_map(|args| args.args.iter())
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(Type::Path(type_path)) => type_path.path.get_ident(),
+                _ => None,
+            })
+            .filter(|ident| is_single_letter(ident))
+            .cloned()
+            .collect()
+    }
+
+    /// The state as a generic argument of the struct: `Loaded<Config>`
+    /// (built from its parts, since parsing its tokens again for each generated method adds up for the large `impl` blocks)
+    pub fn to_generic_argument(&self) -> GenericArgument {
+        let arguments = match &self.args {
+            Some(args) => PathArguments::AngleBracketed(args.clone()),
+            None => PathArguments::None,
+        };
+        GenericArgument::Type(Type::Path(TypePath {
+            qself: None,
+            path: PathSegment {
+                ident: self.ident.clone(),
+                arguments,
+            }
+            .into(),
+        }))
+    }
+}
+
+impl From<Ident> for StateArg {
+    fn from(ident: Ident) -> Self {
+        StateArg {
+            parents: Vec::new(),
+            ident,
+            args: None,
+        }
+    }
+}
+
+impl Parse for StateArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut parents = Vec::new();
+        let mut ident = input.parse()?;
+        while input.peek(Token![::]) {
+            input.parse::<Token![::]>()?;
+            parents.push(std::mem::replace(&mut ident, input.parse()?));
+        }
+        let args = if input.peek(Token![<]) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(StateArg {
+            parents,
+            ident,
+            args,
+        })
+    }
+}
+
+impl ToTokens for StateArg {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.ident.to_tokens(tokens);
+        self.args.to_tokens(tokens);
+    }
+}
 
 /// Helper function to find and remove an attribute by name
 fn find_and_remove_attr(attrs: &mut Vec<Attribute>, attr_name: &str) -> Option<Attribute> {
@@ -9,34 +108,230 @@ fn find_and_remove_attr(attrs: &mut Vec<Attribute>, attr_name: &str) -> Option<A
     Some(attrs.remove(pos))
 }
 
-/// Extracts the arguments from a macro call
-pub fn extract_macro_args(
+/// The error of an `impl` block whose type is not a struct (or an enum) given by its path: `impl Trait for &Player`
+pub fn unsupported_self_type(self_ty: &Type) -> TokenStream {
+    syn::Error::new_spanned(
+        self_ty,
+        "`#[impl_state]` expects an `impl` block of a struct or an enum declared with `#[type_state]`",
+    )
+    .to_compile_error()
+}
+
+/// The error of `#[require]` or `#[switch_to]` used outside of `#[impl_state]` (which would consume them),
+/// spanned to the attribute, along with the item it is put on, so that the item is still there for the rest of the code
+pub fn unconsumed_attribute(macro_name: &str, item: TokenStream) -> TokenStream {
+    let error = syn::Error::new(
+        Span::call_site(),
+        format!(
+            "`#[{macro_name}]` must be used inside an `impl` block (or a trait) annotated with `#[impl_state]`"
+        ),
+    )
+    .to_compile_error();
+    quote! {
+        #error
+
+        #item
+    }
+}
+
+/// The `#[cfg]` attributes of a method, which are given to the items generated for it as well
+/// (like its `impl` blocks, and the enum of a branching transition), so that they are gated together
+pub fn cfg_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("cfg"))
+}
+
+/// Extracts the arguments from a macro call, along with the span of the macro call for error reporting
+///
+/// the malformed arguments are an error, instead of leaving the method as if it had no such macro
+pub fn extract_macro_args<T: Parse>(
     attrs: &mut Vec<Attribute>,
     macro_name: &str,
-) -> Option<Punctuated<Ident, Token![,]>> {
-    let attr = find_and_remove_attr(attrs, macro_name)?;
-    let args: Punctuated<Ident, Token![,]> =
-        attr.parse_args_with(Punctuated::parse_terminated).ok()?;
-    Some(args)
+) -> syn::Result<Option<(Span, Punctuated<T, Token![,]>)>> {
+    let Some(attr) = find_and_remove_attr(attrs, macro_name) else {
+        return Ok(None);
+    };
+    let args: Punctuated<T, Token![,]> = attr.parse_args_with(Punctuated::parse_terminated)?;
+    Ok(Some((attr.path().span(), args)))
 }
 
-pub fn is_single_letter(ident: &Ident) -> bool {
-    ident.to_string().len() == 1
+/// Puts the arguments of `#[require]` or `#[switch_to]` in the order of the slots
+///
+/// positional arguments are kept as they are,
+/// named arguments are placed into their slots, and the slots that are not mentioned are filled with `unmentioned`:
+/// `#[require(conn = Connected)]` -> `#[require(_, Connected)]` for `slots = (auth = LoggedOut, conn = Disconnected)`
+///
+/// the positional and named arguments cannot be mixed, and the names have to be the names of the slots
+pub fn order_slot_args<'a, T: Clone>(
+    args: impl IntoIterator<Item = (Option<&'a Ident>, T)>,
+    slots: &[StateSlot],
+    unmentioned: T,
+) -> syn::Result<Vec<T>> {
+    let args: Vec<_> = args.into_iter().collect();
+    let Some(first_name) = args.iter().find_map(|(slot_name, _)| *slot_name) else {
+        return Ok(args.into_iter().map(|(_, arg)| arg).collect());
+    };
+
+    let mut ordered = vec![unmentioned; slots.len()];
+    for (slot_name, arg) in args {
+        let Some(slot_name) = slot_name else {
+            return Err(syn::Error::new(
+                first_name.span(),
+                "positional and named arguments cannot be mixed for the slots",
+            ));
+        };
+        let Some(index) = slots
+            .iter()
+            .position(|slot| slot.name.as_ref() == Some(slot_name))
+        else {
+            let slot_names = slots
+                .iter()
+                .filter_map(|slot| slot.name.as_ref())
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>();
+            let expected = if slot_names.is_empty() {
+                "the slots declared in `#[type_state]` are not named".to_string()
+            } else {
+                format!("expected one of the slots: {}", slot_names.join(", "))
+            };
+            return Err(syn::Error::new(
+                slot_name.span(),
+                format!("there is no slot named `{}`, {}", slot_name, expected),
+            ));
+        };
+        ordered[index] = arg;
+    }
+
+    Ok(ordered)
 }
 
-pub fn extract_idents_from_group(token: &TokenTree, error_msg: &str) -> Vec<Ident> {
-    match token {
-        proc_macro::TokenTree::Group(group) => group
-            .stream()
-            .into_iter()
-            .filter_map(|tt| {
-                if let proc_macro::TokenTree::Ident(ident) = tt {
-                    Some(Ident::new(&format!("{}", ident), ident.span().into()))
-                } else {
-                    None
-                }
-            })
-            .collect(),
-        _ => panic!("{}", error_msg),
+/// Turns generic parameters into the arguments that refer to them: `<'a, T: Clone, const N: usize>` -> `'a, T, N`
+pub fn generic_args(params: &Punctuated<GenericParam, Token![,]>) -> Vec<TokenStream> {
+    params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Lifetime(lifetime) => {
+                let lifetime = &lifetime.lifetime;
+                quote!(#lifetime)
+            }
+            GenericParam::Type(type_param) => {
+                let ident = &type_param.ident;
+                quote!(#ident)
+            }
+            GenericParam::Const(const_param) => {
+                let ident = &const_param.ident;
+                quote!(#ident)
+            }
+        })
+        .collect()
+}
+
+/// Removes the defaults of the generic parameters, which are not allowed outside of the type definitions:
+/// `<T: Transport = Tcp, const N: usize = 4>` -> `<T: Transport, const N: usize>`
+pub fn strip_generic_defaults(
+    params: &Punctuated<GenericParam, Token![,]>,
+) -> Punctuated<GenericParam, Token![,]> {
+    let mut params = params.clone();
+    for param in params.iter_mut() {
+        match param {
+            GenericParam::Type(type_param) => {
+                type_param.eq_token = None;
+                type_param.default = None;
+            }
+            GenericParam::Const(const_param) => {
+                const_param.eq_token = None;
+                const_param.default = None;
+            }
+            GenericParam::Lifetime(_) => {}
+        }
+    }
+    params
+}
+
+/// Fills the omitted generic arguments of the struct with their defaults,
+/// since the generics of the state slots are appended after the arguments in the generated `impl` blocks
+///
+/// `impl Client` -> `impl Client<Tcp>` for `struct Client<T = Tcp>`
+pub fn fill_default_generic_args(
+    self_ty: &mut Type,
+    struct_params: &Punctuated<GenericParam, Token![,]>,
+) {
+    let Type::Path(type_path) = self_ty else {
+        return;
+    };
+    let Some(last_segment) = type_path.path.segments.last_mut() else {
+        return;
+    };
+
+    let given_args = match &last_segment.arguments {
+        PathArguments::AngleBracketed(angle_bracketed) => angle_bracketed
+            .args
+            .iter()
+            .filter(|arg| !matches!(arg, GenericArgument::Lifetime(_)))
+            .count(),
+        PathArguments::None => 0,
+        PathArguments::Parenthesized(_) => return,
+    };
+
+    let defaults: Vec<GenericArgument> = struct_params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(type_param) => {
+                Some(type_param.default.clone().map(GenericArgument::Type))
+            }
+            GenericParam::Const(const_param) => {
+                Some(const_param.default.clone().map(|default| match default {
+                    Expr::Lit(_) | Expr::Path(_) | Expr::Block(_) => {
+                        GenericArgument::Const(default)
+                    }
+                    _ => GenericArgument::Const(syn::parse_quote!({ #default })),
+                }))
+            }
+            GenericParam::Lifetime(_) => None,
+        })
+        .skip(given_args)
+        .map_while(|default| default)
+        .collect();
+    if defaults.is_empty() {
+        return;
+    }
+
+    if let PathArguments::None = last_segment.arguments {
+        last_segment.arguments = PathArguments::AngleBracketed(syn::parse_quote!(<>));
     }
+    if let PathArguments::AngleBracketed(angle_bracketed) = &mut last_segment.arguments {
+        angle_bracketed.args.extend(defaults);
+    }
+}
+
+/// Names the elided lifetimes of the type of an `impl` block: `impl Parser<'_>` -> `impl<'elided1> Parser<'elided1>`
+///
+/// the generated methods refer to the struct explicitly instead of `Self` (`-> Parser<'_, NewState>`),
+/// and `'_` cannot be resolved there like `Self` does
+pub fn name_elided_lifetimes(item: &mut ItemImpl) {
+    let mut visitor = ElidedLifetimeVisitor { named: Vec::new() };
+    visitor.visit_type_mut(&mut item.self_ty);
+
+    // lifetimes are declared before the other generic parameters
+    for (index, lifetime) in visitor.named.into_iter().enumerate() {
+        item.generics
+            .params
+            .insert(index, GenericParam::Lifetime(LifetimeParam::new(lifetime)));
+    }
+}
+
+struct ElidedLifetimeVisitor {
+    named: Vec<Lifetime>,
+}
+
+impl VisitMut for ElidedLifetimeVisitor {
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = Lifetime::new(&format!("'elided{}", self.named.len() + 1), lifetime.span());
+            self.named.push(lifetime.clone());
+        }
+    }
+}
+
+pub fn is_single_letter(ident: &Ident) -> bool {
+    ident.to_string().len() == 1
 }
@@ -0,0 +1,582 @@
+/// this file contains the hooks of the states, which are declared by the methods of an `impl` block:
+/// `#[on_enter(Connected)] fn log_connect(&self)`, `#[on_exit(Idle)] fn check_idle(&self)`,
+/// and called by the transitions of the same `impl` block that enter or leave their states
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{
+    punctuated::Punctuated, Attribute, Block, GenericArgument, Ident, ImplItem, ImplItemFn,
+    ItemImpl, PathArguments, ReturnType, Token, Type,
+};
+
+use crate::{
+    cfg_attrs, closest_name, mentions_struct, outcome_enum_name, switch_to_args_branching_slot,
+    RequireCombination, StateArg, TypeStateArgs,
+};
+
+/// A hook of a state: `#[on_enter(Connected)] fn log_connect(&self)` -> `(Connected, log_connect)`
+pub struct StateHook {
+    state: Ident,
+    method: Ident,
+    cfgs: Vec<Attribute>,
+}
+
+/// The hooks of the states declared in an `impl` block
+#[derive(Default)]
+pub struct StateHooks {
+    on_enter: Vec<StateHook>,
+    on_exit: Vec<StateHook>,
+}
+
+impl StateHooks {
+    pub fn is_empty(&self) -> bool {
+        self.on_enter.is_empty() && self.on_exit.is_empty()
+    }
+
+    /// Whether the block declares the hooks of the state entering it (or leaving it, with `on_enter = false`)
+    pub fn declares(&self, state: &Ident, on_enter: bool) -> bool {
+        let hooks = if on_enter {
+            &self.on_enter
+        } else {
+            &self.on_exit
+        };
+        hooks.iter().any(|hook| hook.state == *state)
+    }
+}
+
+/// The type that tells the states whose hooks are declared, by their position in the declared states: `__PlayerHooks<2>`
+pub fn hooks_type(struct_name: &Ident) -> Ident {
+    format_ident!("__{}Hooks", struct_name, span = struct_name.span())
+}
+
+/// The trait that gives the states without hooks `ON_ENTER` and `ON_EXIT` (see `declared_hooks`)
+pub fn no_hooks_trait(struct_name: &Ident) -> Ident {
+    format_ident!("__{}NoHooks", struct_name, span = struct_name.span())
+}
+
+/// The type and the trait generated by `#[type_state]`, through which the `impl` blocks tell each other
+/// which states have hooks, since each of them is expanded on its own (see `declared_hooks` and `foreign_hook_check`)
+pub fn hooks_registry(struct_name: &Ident, type_state_args: &TypeStateArgs) -> TokenStream {
+    let hooks_type = hooks_type(struct_name);
+    let no_hooks_trait = no_hooks_trait(struct_name);
+    let marker_vis = &type_state_args.marker_vis;
+    quote! {
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        #marker_vis struct #hooks_type<const STATE: usize>;
+
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        #marker_vis trait #no_hooks_trait {
+            const ON_ENTER: bool = false;
+            const ON_EXIT: bool = false;
+        }
+
+        impl<const STATE: usize> #no_hooks_trait for #hooks_type<STATE> {}
+    }
+}
+
+/// The consts of the states whose hooks are declared in the `impl` block: `impl __PlayerHooks<2> { pub const ON_ENTER: bool = true; }`
+///
+/// they are inherent, so they take precedence over the ones of `__PlayerNoHooks` in the other blocks
+/// (and a state whose hooks are declared in two blocks gets its const defined twice)
+pub fn declared_hooks(
+    hooks: &StateHooks,
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+) -> TokenStream {
+    let hooks_type = hooks_type(struct_name);
+    let mut declared: Vec<(&Ident, &str)> = Vec::new();
+    let consts = [("ON_ENTER", &hooks.on_enter), ("ON_EXIT", &hooks.on_exit)]
+        .into_iter()
+        .flat_map(|(name, hooks)| hooks.iter().map(move |hook| (name, hook)))
+        .filter_map(|(name, hook)| {
+            if declared.contains(&(&hook.state, name)) {
+                return None;
+            }
+            declared.push((&hook.state, name));
+            let index = type_state_args
+                .states
+                .iter()
+                .position(|state| *state == hook.state)?;
+            let name = Ident::new(name, hook.method.span());
+            let cfgs = &hook.cfgs;
+            Some(quote! {
+                #(#cfgs)*
+                impl #hooks_type<#index> {
+                    #[allow(dead_code)]
+                    pub const #name: bool = true;
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    quote!(#(#consts)*)
+}
+
+/// The check of a transition entering (or leaving) a state whose hooks are not declared in its `impl` block,
+/// which fails if they are declared in another one, since the transition cannot call them
+pub fn foreign_hook_check(
+    state: &Ident,
+    on_enter: bool,
+    method: &Ident,
+    cfgs: &[Attribute],
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+) -> TokenStream {
+    let Some(index) = type_state_args
+        .states
+        .iter()
+        .position(|other| other == state)
+    else {
+        return TokenStream::new();
+    };
+    let hooks_type = hooks_type(struct_name);
+    let no_hooks_trait = no_hooks_trait(struct_name);
+    let (name, kind, verb) = if on_enter {
+        ("ON_ENTER", "on_enter", "enters")
+    } else {
+        ("ON_EXIT", "on_exit", "leaves")
+    };
+    let name = Ident::new(name, method.span());
+    let message = format!(
+        "`{}` {} `{}`, whose `#[{}]` hooks are declared in another `impl` block, so it cannot call them \
+         (the hooks of a state are declared in the block of its transitions)",
+        method, verb, state, kind
+    );
+    quote_spanned! {method.span()=>
+        #(#cfgs)*
+        const _: () = {
+            #[allow(unused_imports)]
+            use #no_hooks_trait as _;
+            ::core::assert!(!#hooks_type::<#index>::#name, #message);
+        };
+    }
+}
+
+/// Takes the `#[on_enter]` and `#[on_exit]` attributes out of the methods of the `impl` block,
+/// and gives the hooks `#[require(_, ...)]` (unless they have their own), so they can be called in any state
+pub fn take_state_hooks(
+    input: &mut ItemImpl,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<StateHooks> {
+    let mut hooks = StateHooks::default();
+    for item in input.items.iter_mut() {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let mut is_hook = false;
+        for kind in ["on_enter", "on_exit"] {
+            let Some(position) = method
+                .attrs
+                .iter()
+                .position(|attr| attr.path().is_ident(kind))
+            else {
+                continue;
+            };
+            let attr = method.attrs.remove(position);
+            let states: Punctuated<Ident, Token![,]> =
+                attr.parse_args_with(Punctuated::parse_terminated)?;
+            for state in states {
+                if !type_state_args.states.contains(&state) {
+                    let message = match closest_name(&state, &type_state_args.states) {
+                        Some(closest) => {
+                            format!("unknown state `{}`; did you mean `{}`?", state, closest)
+                        }
+                        None => format!("unknown state `{}`", state),
+                    };
+                    return Err(syn::Error::new(state.span(), message));
+                }
+                let hook = StateHook {
+                    state,
+                    method: method.sig.ident.clone(),
+                    cfgs: cfg_attrs(&method.attrs).cloned().collect(),
+                };
+                match kind {
+                    "on_enter" => hooks.on_enter.push(hook),
+                    _ => hooks.on_exit.push(hook),
+                }
+            }
+            is_hook = true;
+        }
+        if !is_hook {
+            continue;
+        }
+
+        let takes_ref_self = method
+            .sig
+            .receiver()
+            .is_some_and(|receiver| receiver.reference.is_some() && receiver.mutability.is_none());
+        if !takes_ref_self
+            || method.sig.inputs.len() != 1
+            || method.sig.asyncness.is_some()
+            || !matches!(method.sig.output, ReturnType::Default)
+        {
+            return Err(syn::Error::new(
+                method.sig.ident.span(),
+                format!(
+                    "the hook `{}` is expected to be `fn {}(&self)`, since it is called by the transitions",
+                    method.sig.ident, method.sig.ident
+                ),
+            ));
+        }
+        if !method
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("require"))
+        {
+            let any_states = type_state_args.slots.iter().map(|_| quote!(_));
+            method
+                .attrs
+                .push(syn::parse_quote!(#[require(#(#any_states),*)]));
+        }
+    }
+    Ok(hooks)
+}
+
+/// The calls of the hooks of a state, by the value that is in the state:
+/// directly for the concrete states, and after checking the name of the state for the generic ones (`A`)
+fn state_hook_calls(
+    hooks: &[StateHook],
+    slot: usize,
+    state: &StateArg,
+    is_generic: bool,
+    value: &TokenStream,
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+) -> TokenStream {
+    let allowed = type_state_args.states_of_slot(slot);
+    let calls = hooks
+        .iter()
+        .filter(|hook| allowed.contains(&hook.state))
+        .filter_map(
+            |StateHook {
+                 state: hook_state,
+                 method,
+                 cfgs,
+             }| {
+                if !is_generic {
+                    return (state.ident == *hook_state)
+                        .then(|| quote!(#(#cfgs)* #value.#method();));
+                }
+                let sealer = type_state_args.sealer_of_slot(slot, struct_name);
+                let generic = &state.ident;
+                let name = type_state_args.unprefixed_name(hook_state).to_string();
+                Some(quote! {
+                    #(#cfgs)*
+                    if <#generic as #sealer>::STATE_NAME == #name {
+                        #value.#method();
+                    }
+                })
+            },
+        );
+    quote!(#(#calls)*)
+}
+
+/// Whether the type is the struct itself: `Self`, `Player`, `Player<T>`
+fn is_struct(ty: &Type, struct_name: &Ident) -> bool {
+    matches!(ty, Type::Path(type_path) if type_path.qself.is_none()
+        && (type_path.path.is_ident("Self")
+            || type_path.path.segments.last().is_some_and(|segment| segment.ident == *struct_name)))
+}
+
+/// The first and the second type arguments of `Option<T>` or `Result<T, E>`, along with the name of the type
+fn wrapped_types(ty: &Type) -> Option<(String, Option<&Type>, Option<&Type>)> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    let mut types = arguments.args.iter().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    Some((segment.ident.to_string(), types.next(), types.next()))
+}
+
+/// Reaches the struct in the value returned by a transition (by the reference `entered`), and runs `enter` on it:
+/// the struct itself, the element of a tuple that has it (like `#[switch_to]`, the first one), the `Some` of an `Option`,
+/// and the `Ok` arm of a `Result`, along with its `Err` arm for the guarded transitions (`err_enter`, only the outermost one)
+///
+/// `-> Result<Self, (Self, Error)>`:
+///
+/// ```ignore
+/// match entered {
+///     Ok(entered) => { <enter> }
+///     Err(entered) => { let entered = &entered.0; <err_enter> }
+/// }
+/// ```
+fn reach_struct(
+    ty: &Type,
+    struct_name: &Ident,
+    enter: &TokenStream,
+    err_enter: Option<&TokenStream>,
+) -> Option<TokenStream> {
+    if is_struct(ty, struct_name) {
+        return Some(enter.clone());
+    }
+    match ty {
+        Type::Paren(paren) => return reach_struct(&paren.elem, struct_name, enter, err_enter),
+        Type::Group(group) => return reach_struct(&group.elem, struct_name, enter, err_enter),
+        Type::Tuple(tuple) => {
+            let (index, elem) = tuple
+                .elems
+                .iter()
+                .enumerate()
+                .find(|(_, elem)| mentions_struct(quote!(#elem), struct_name))?;
+            let index = syn::Index::from(index);
+            let inner = reach_struct(elem, struct_name, enter, None)?;
+            return Some(quote! {
+                let entered = &entered.#index;
+                #inner
+            });
+        }
+        _ => {}
+    }
+    match wrapped_types(ty)? {
+        (wrapper, Some(some_type), _) if wrapper == "Option" => {
+            let inner = reach_struct(some_type, struct_name, enter, None)?;
+            Some(quote! {
+                if let ::core::option::Option::Some(entered) = entered {
+                    #inner
+                }
+            })
+        }
+        (wrapper, Some(ok_type), err_type) if wrapper == "Result" => {
+            let ok_arm = reach_struct(ok_type, struct_name, enter, None)?;
+            let err_arm = err_type
+                .zip(err_enter)
+                .and_then(|(err_type, err_enter)| {
+                    reach_struct(err_type, struct_name, err_enter, None)
+                })
+                .map(|err_arm| {
+                    quote! {
+                        ::core::result::Result::Err(entered) => {
+                            #err_arm
+                        }
+                    }
+                });
+            Some(quote! {
+                match entered {
+                    ::core::result::Result::Ok(entered) => {
+                        #ok_arm
+                    }
+                    #err_arm
+                    #[allow(unreachable_patterns)]
+                    _ => {}
+                }
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Wraps the body of a transition with the calls of the hooks of the states it leaves and enters:
+///
+/// ```ignore
+/// {
+///     self.check_idle();
+///     let value = (move || -> Player<Connected> { <body> })();
+///     let entered = &value;
+///     entered.log_connect();
+///     value
+/// }
+/// ```
+///
+/// (the body is run in a closure, so that its `return`s and `?`s still reach the hooks of the new states,
+/// and only the slots that are switched leave and enter their states)
+///
+/// the exit hooks are called before the body, as the transition starts, so they are called by the guarded transitions
+/// that fail too, which enter their `on_err` state then (even if it is the state they left, like a transition into itself);
+/// the branching transitions enter the state of the variant they return (`ConnectOutcome::Connected(entered)`),
+/// and the cross-type transitions (without `switch_to_args`) leave all of the states of the struct,
+/// while they enter the ones of the other struct, whose hooks are its own
+#[allow(clippy::too_many_arguments)]
+pub fn hooked_body(
+    hooks: &StateHooks,
+    method: &ImplItemFn,
+    body: Block,
+    combination: &RequireCombination,
+    switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    resolved: Option<&Punctuated<StateArg, Token![,]>>,
+    err_switch_to_args: Option<&Punctuated<StateArg, Token![,]>>,
+    new_output: &ReturnType,
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<Block> {
+    if hooks.is_empty() {
+        return Ok(body);
+    }
+    let switch_to = switch_to_args.zip(resolved);
+    let switched_slots: Vec<usize> = match switch_to {
+        Some((switch_to_args, _)) => switch_to_args
+            .iter()
+            .enumerate()
+            .filter(|(_, states)| states.is_some())
+            .map(|(index, _)| index)
+            .collect(),
+        None => (0..type_state_args.slots.len()).collect(),
+    };
+    let switched: Vec<usize> = switched_slots
+        .into_iter()
+        .filter(|slot| type_state_args.slot_component(*slot).is_none())
+        .collect();
+    if switched.is_empty() {
+        return Ok(body);
+    }
+    let is_generic = |state: &StateArg| {
+        state.is_generic()
+            || combination
+                .generics
+                .iter()
+                .any(|(generic, _)| *generic == state.ident)
+    };
+
+    // the hooks of the states it leaves, which are the required ones (the constructors don't leave any state)
+    // (the `self` of the receiver is used, since the one of the macro is not the same as the method's)
+    let exits: Vec<_> = match method.sig.receiver() {
+        Some(receiver) => switched
+            .iter()
+            .map(|slot| {
+                let state = &combination.args[*slot];
+                state_hook_calls(
+                    &hooks.on_exit,
+                    *slot,
+                    state,
+                    is_generic(state),
+                    &receiver.self_token.to_token_stream(),
+                    struct_name,
+                    type_state_args,
+                )
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    // the hooks of the states it enters, called by the value in the new states
+    let entered = quote!(entered);
+    let enter_calls = |args: &Punctuated<StateArg, Token![,]>| -> TokenStream {
+        let calls = switched.iter().map(|slot| {
+            let state = &args[*slot];
+            state_hook_calls(
+                &hooks.on_enter,
+                *slot,
+                state,
+                is_generic(state),
+                &entered,
+                struct_name,
+                type_state_args,
+            )
+        });
+        quote!(#(#calls)*)
+    };
+    let enters = match switch_to {
+        Some((switch_to_args, resolved)) => match switch_to_args_branching_slot(switch_to_args) {
+            // the branching transitions return the enum, whose variants are in the states of the branches
+            Some(branching_slot) => {
+                let enum_name = outcome_enum_name(&method.sig.ident);
+                let branches = switch_to_args[branching_slot].iter().flatten();
+                let arms: Vec<_> = branches
+                    .map(|branch| {
+                        let mut args = resolved.clone();
+                        args[branching_slot] = branch.clone();
+                        (branch, enter_calls(&args))
+                    })
+                    .collect();
+                if arms.iter().all(|(_, calls)| calls.is_empty()) {
+                    TokenStream::new()
+                } else {
+                    let arms = arms.iter().map(|(branch, calls)| {
+                        let variant = &branch.ident;
+                        let cfgs = type_state_args.cfgs_of_state(variant);
+                        quote! {
+                            #(#cfgs)*
+                            #enum_name::#variant(entered) => {
+                                #calls
+                            }
+                        }
+                    });
+                    quote! {
+                        match entered {
+                            #(#arms)*
+                        }
+                    }
+                }
+            }
+            None => enter_calls(resolved),
+        },
+        None => TokenStream::new(),
+    };
+    let err_enters = err_switch_to_args.map(enter_calls).unwrap_or_default();
+
+    // the hooks are not `const`, so they cannot be called by a `const fn`
+    if let Some(constness) = &method.sig.constness {
+        let hooked = if !enters.is_empty() || !err_enters.is_empty() {
+            Some("enters a state with `#[on_enter]` hooks")
+        } else if exits.iter().any(|exit| !exit.is_empty()) {
+            Some("leaves a state with `#[on_exit]` hooks")
+        } else {
+            None
+        };
+        if let Some(hooked) = hooked {
+            return Err(syn::Error::new(
+                constness.span,
+                format!(
+                    "`{}` {}, which cannot be called in a `const fn`",
+                    method.sig.ident, hooked
+                ),
+            ));
+        }
+    }
+    let stmts = &body.stmts;
+    if enters.is_empty() && err_enters.is_empty() {
+        return Ok(syn::parse_quote!({
+            #(#exits)*
+            #(#stmts)*
+        }));
+    }
+
+    let ReturnType::Type(_, original_output) = &method.sig.output else {
+        return Err(missing_struct(method));
+    };
+    let Some(enter) = reach_struct(
+        original_output,
+        struct_name,
+        &enters,
+        (!err_enters.is_empty()).then_some(&err_enters),
+    ) else {
+        return Err(missing_struct(method));
+    };
+    if method.sig.asyncness.is_some() {
+        return Err(syn::Error::new(
+            method.sig.ident.span(),
+            format!(
+                "`{}` enters a state with `#[on_enter]` hooks, which cannot be called after an `async` body",
+                method.sig.ident
+            ),
+        ));
+    }
+    let ReturnType::Type(_, output_type) = new_output else {
+        return Err(missing_struct(method));
+    };
+
+    Ok(syn::parse_quote!({
+        #(#exits)*
+        #[allow(clippy::redundant_closure_call)]
+        let value = (move || -> #output_type { #(#stmts)* })();
+        let entered = &value;
+        #enter
+        value
+    }))
+}
+
+/// The error of a transition entering a state with hooks, whose return type doesn't tell where the struct is
+fn missing_struct(method: &ImplItemFn) -> syn::Error {
+    syn::Error::new(
+        method.sig.ident.span(),
+        format!(
+            "`{}` enters a state with `#[on_enter]` hooks, so it has to return the struct, or a tuple, an `Option` or a `Result` of it",
+            method.sig.ident
+        ),
+    )
+}
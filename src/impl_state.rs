@@ -1,54 +1,787 @@
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, ImplItem, ItemImpl, Type};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    GenericParam, Generics, Ident, ImplItem, ImplItemFn, ItemImpl, ItemTrait, LitStr, Meta, Path,
+    PathArguments, Token, Type,
+};
 
-use crate::{extract_macro_args, generate_impl_block_for_method_based_on_require_args};
+use crate::{
+    callback_macro_name, check_arity, check_branching, check_slot_states, check_terminal_states,
+    constructor_require_args, declared_hooks, expand_require_alternatives, extend_states,
+    extract_macro_args, extract_other_type_target, fill_default_generic_args,
+    forward_impl_state_trait, generate_impl_block_for_method_based_on_require_args,
+    generate_outcome_enum, generate_trait_impl_block_based_on_require_args, merge_impl_blocks,
+    name_elided_lifetimes, on_err_switch_to_args, order_slot_args, other_type_target,
+    registry_import, state_trait_inner, switch_to_args_branching_slot, take_on_err,
+    take_state_hooks, take_state_invariants, unsupported_self_type, RequireArg, RequiredSlot,
+    StateHooks, SwitchToArg, TransitionGraph, TypeStateArgs,
+};
+
+/// Input of the internal `__impl_state` macro:
+/// `(<arguments of #[type_state]>) (<generic parameters of the struct>) impl ... { ... }`
+///
+/// the `impl` block is preceded by the arguments of `#[impl_state]` (if it is given any): `@args(transitions)`,
+/// and the whole input by the arguments of the targets of its cross-type transitions: `@target(Connection (...))`
+struct ImplStateInput {
+    targets: Vec<(Ident, TypeStateArgs)>,
+    type_state_args: TypeStateArgs,
+    raw_type_state_args: proc_macro2::TokenStream,
+    struct_params: Punctuated<GenericParam, Token![,]>,
+    args: ImplStateArgs,
+    item: ItemImpl,
+}
+
+impl Parse for ImplStateInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut targets = Vec::new();
+        while input.peek(Token![@]) {
+            input.parse::<Token![@]>()?;
+            input.parse::<Ident>()?;
+            let content;
+            parenthesized!(content in input);
+            let target: Ident = content.parse()?;
+            let target_args;
+            parenthesized!(target_args in content);
+            targets.push((target, target_args.parse()?));
+        }
+        let content;
+        parenthesized!(content in input);
+        let raw_type_state_args: proc_macro2::TokenStream = content.parse()?;
+        let struct_params;
+        parenthesized!(struct_params in input);
+        let mut args = ImplStateArgs::default();
+        if input.peek(Token![@]) {
+            input.parse::<Token![@]>()?;
+            input.parse::<Ident>()?;
+            let content;
+            parenthesized!(content in input);
+            args = content.parse()?;
+        }
+        Ok(ImplStateInput {
+            targets,
+            type_state_args: syn::parse2(raw_type_state_args.clone())?,
+            raw_type_state_args,
+            struct_params: Punctuated::parse_terminated(&struct_params)?,
+            args,
+            item: input.parse()?,
+        })
+    }
+}
+
+/// Arguments of the `#[impl_state]` macro: `#[impl_state(transitions, diagram = "docs/player.mmd")]`
+///
+/// `transitions` generates the table of the transitions of the `impl` block (see `TransitionGraph::table`),
+/// `diagram` writes them into a Mermaid diagram (see `TransitionGraph::diagram`),
+/// `dot` into a Graphviz graph (see `TransitionGraph::dot`),
+/// and `plantuml` into a PlantUML diagram (see `TransitionGraph::plantuml`),
+/// `tla` and `smv` write them into the modules of the model checkers (see `TransitionGraph::tla` and `TransitionGraph::smv`),
+/// while `proptest` generates the strategy of the call sequences (see `TransitionGraph::proptest`),
+/// `kani` the proof harnesses of the transitions (see `TransitionGraph::kani`),
+/// and `dual` the protocol of the other side of the transitions (see `TransitionGraph::dual`),
+/// while `part` leaves the block out of the lints of the state machine as a whole (see `TransitionGraph::lints`),
+/// for the methods that are split across several `impl` blocks,
+/// `extend` appends new states to the ones of the struct, for the methods of the block (see `extend_states`),
+/// and `debug` reports the code generated for the block as a warning (see `debug_expansion`)
+#[derive(Default)]
+struct ImplStateArgs {
+    transitions: bool,
+    part: bool,
+    debug: bool,
+    proptest: bool,
+    kani: bool,
+    diagram: Option<LitStr>,
+    dot: Option<LitStr>,
+    plantuml: Option<LitStr>,
+    tla: Option<LitStr>,
+    smv: Option<LitStr>,
+    dual: Option<Ident>,
+    extend: Option<Punctuated<Ident, Token![,]>>,
+}
+
+impl ImplStateArgs {
+    /// The first of the arguments that describe the transitions of the struct,
+    /// which only the inherent `impl` blocks can be given
+    fn describing_arg(&self) -> Option<&'static str> {
+        if self.transitions {
+            Some("transitions")
+        } else if self.diagram.is_some() {
+            Some("diagram")
+        } else if self.dot.is_some() {
+            Some("dot")
+        } else if self.plantuml.is_some() {
+            Some("plantuml")
+        } else if self.tla.is_some() {
+            Some("tla")
+        } else if self.smv.is_some() {
+            Some("smv")
+        } else if self.proptest {
+            Some("proptest")
+        } else if self.kani {
+            Some("kani")
+        } else if self.dual.is_some() {
+            Some("dual")
+        } else if self.part {
+            Some("part")
+        } else if self.extend.is_some() {
+            Some("extend")
+        } else {
+            None
+        }
+    }
+}
+
+impl Parse for ImplStateArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = ImplStateArgs::default();
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            match key.to_string().as_str() {
+                "transitions" => args.transitions = true,
+                "part" => args.part = true,
+                "debug" => args.debug = true,
+                "proptest" => args.proptest = true,
+                "kani" => args.kani = true,
+                "diagram" => {
+                    input.parse::<Token![=]>()?;
+                    args.diagram = Some(input.parse()?);
+                }
+                "dot" => {
+                    input.parse::<Token![=]>()?;
+                    args.dot = Some(input.parse()?);
+                }
+                "plantuml" => {
+                    input.parse::<Token![=]>()?;
+                    args.plantuml = Some(input.parse()?);
+                }
+                "dual" => {
+                    input.parse::<Token![=]>()?;
+                    args.dual = Some(input.parse()?);
+                }
+                "tla" => {
+                    input.parse::<Token![=]>()?;
+                    args.tla = Some(input.parse()?);
+                }
+                "smv" => {
+                    input.parse::<Token![=]>()?;
+                    args.smv = Some(input.parse()?);
+                }
+                "extend" => {
+                    input.parse::<Token![=]>()?;
+                    let content;
+                    parenthesized!(content in input);
+                    args.extend = Some(Punctuated::parse_terminated(&content)?);
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown argument, expected `transitions`, `diagram`, `dot`, `plantuml`, `tla`, \
+                         `smv`, `proptest`, `kani`, `dual`, `part`, `extend` or `debug`",
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Forwards the `impl` block to the hidden macro generated by `#[type_state]` for the struct,
+/// which carries the arguments of `#[type_state]` back to `#[impl_state]` (see `__impl_state`)
+///
+/// `impl path::to::PlayerBuilder` -> `path::to::__state_shift_player_builder! { impl ... }`
+pub fn forward_impl_state(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let raw_args = proc_macro2::TokenStream::from(attr.clone());
+    let args = parse_macro_input!(attr as ImplStateArgs);
+
+    // the type-state protocols defined as traits don't need the arguments of `#[type_state]`
+    if let Ok(input) = syn::parse::<ItemTrait>(item.clone()) {
+        if let Some(arg) = args.describing_arg() {
+            return inherent_only(arg, &input.ident).into();
+        }
+        let trait_name = input.ident.clone();
+        let expanded = state_trait_inner(input);
+        return debug_expansion(args.debug, &trait_name, expanded).into();
+    }
+
+    let input = parse_macro_input!(item as ItemImpl);
+    if let Some(arg) = args.describing_arg().filter(|_| input.trait_.is_some()) {
+        return inherent_only(arg, &input.self_ty).into();
+    }
+
+    let callback_path = match *input.self_ty {
+        Type::Path(ref type_path) => callback_path(type_path.path.clone()),
+        _ => return unsupported_self_type(&input.self_ty).into(),
+    };
+
+    let args = (!raw_args.is_empty()).then(|| quote!(@args(#raw_args)));
+    quote! {
+        #callback_path! { #args #input }
+    }
+    .into()
+}
+
+/// The path of the hidden macro generated by `#[type_state]` for the struct of the path (see `callback_macro_name`):
+/// `path::to::Connection<Established>` -> `path::to::__state_shift_connection`
+fn callback_path(mut path: Path) -> Path {
+    let last_segment = path.segments.last_mut().unwrap();
+    last_segment.ident = callback_macro_name(&last_segment.ident);
+    last_segment.arguments = PathArguments::None;
+    path
+}
+
+/// The error of `#[impl_state(transitions)]` (or `diagram`) on a trait or a trait implementation,
+/// since they describe the transitions of the struct, which are defined by its inherent `impl` blocks
+fn inherent_only(arg: &str, tokens: impl ToTokens) -> proc_macro2::TokenStream {
+    syn::Error::new_spanned(
+        tokens,
+        format!("`{arg}` can only be given to the inherent `impl` blocks of the structs"),
+    )
+    .to_compile_error()
+}
 
 pub fn impl_state_inner(item: TokenStream) -> TokenStream {
-    // Parse the impl block
-    let mut input = parse_macro_input!(item as ItemImpl);
+    let tokens = proc_macro2::TokenStream::from(item.clone());
+    // Parse the arguments of `#[type_state]` and the impl block
+    let ImplStateInput {
+        targets,
+        mut type_state_args,
+        raw_type_state_args,
+        struct_params,
+        args,
+        item: mut input,
+    } = parse_macro_input!(item as ImplStateInput);
+    // the states appended by the block: `#[impl_state(extend = (Maintenance))]`
+    let extended_states = match (&args.extend, &*input.self_ty) {
+        (Some(new_states), Type::Path(type_path)) => {
+            match extend_states(new_states, &type_path.path, &mut type_state_args) {
+                Ok(extended_states) => Some(extended_states),
+                Err(error) => return error.to_compile_error().into(),
+            }
+        }
+        _ => None,
+    };
+    name_elided_lifetimes(&mut input);
+    fill_default_generic_args(&mut input.self_ty, &struct_params);
+    resolve_state_names(&mut input, &type_state_args);
+    // the struct named by its path gets its states from the registry: `impl crate::player::Player`
+    let registry_import = registry_import(&input.self_ty, &type_state_args);
 
     // Extract the type name and generics of the struct being implemented
     let (struct_name, struct_generics) = match *input.self_ty {
         Type::Path(ref type_path) => {
             let last_segment = type_path.path.segments.last().unwrap();
             let struct_name = last_segment.ident.clone();
-            let struct_generics = &last_segment.arguments;
+            let struct_generics = last_segment.arguments.clone();
             (struct_name, struct_generics)
         }
-        _ => panic!("Unsupported type for impl block"),
+        _ => return unsupported_self_type(&input.self_ty).into(),
+    };
+    let struct_generics = &struct_generics;
+
+    if input.trait_.is_some() {
+        // the trait implementations that are gated on states: `#[require(Idle)] impl Display for Player`
+        let require_args = match extract_macro_args::<RequireArg>(&mut input.attrs, "require") {
+            Ok(require_args) => require_args,
+            Err(error) => return error.to_compile_error().into(),
+        };
+        if let Some((require_span, require_args)) = require_args {
+            let require_args = check_arity(
+                require_span,
+                "require",
+                require_args.iter().map(|arg| arg.slot_name.as_ref()),
+                &type_state_args,
+            )
+            .and_then(|_| {
+                order_slot_args(
+                    require_args
+                        .iter()
+                        .map(|arg| (arg.slot_name.as_ref(), arg.slot.clone())),
+                    &type_state_args.slots,
+                    RequiredSlot::Any(Default::default()),
+                )
+            });
+            let require_args = match require_args {
+                Ok(require_args) => require_args,
+                Err(error) => return error.to_compile_error().into(),
+            };
+            if let Err(error) = check_slot_states(&require_args, None, &type_state_args) {
+                return error.to_compile_error().into();
+            }
+
+            let impl_blocks =
+                expand_require_alternatives(&require_args, &struct_name, &type_state_args)
+                    .into_iter()
+                    .map(|combination| {
+                        generate_trait_impl_block_based_on_require_args(
+                            &input,
+                            &struct_name,
+                            &combination,
+                            struct_generics,
+                            &type_state_args,
+                        )
+                    });
+            let expanded = quote! { #registry_import #(#impl_blocks)* };
+            return debug_expansion(args.debug, &input.self_ty, expanded).into();
+        }
+
+        // the implementations of the type-state protocols defined as traits are handled with the states of the trait
+        return forward_impl_state_trait(input, raw_type_state_args).into();
+    }
+
+    // the targets of the cross-type transitions are initialized with their own field and shape,
+    // so their arguments are carried back by their hidden macros too, one after the other:
+    // `impl ConnectionBuilder` -> `__state_shift_connection! { @target ... }` -> `__impl_state! { @target(Connection (...)) ... }`
+    let missing_target = input
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Fn(method) => Some(&method.attrs),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|attr| other_type_target(attr, &type_state_args.states))
+        .find(|target| {
+            let target_name = &target.path.segments.last().unwrap().ident;
+            *target_name != struct_name && !targets.iter().any(|(name, _)| name == target_name)
+        });
+    if let Some(target) = missing_target {
+        let callback_path = callback_path(target.path);
+        return quote!(#callback_path! { @target #tokens }).into();
+    }
+
+    // the hooks of the states, which are called by the transitions: `#[on_enter(Running)] fn log(&self)`
+    let hooks = match take_state_hooks(&mut input, &type_state_args) {
+        Ok(hooks) => hooks,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    // the invariants of the states, which are checked by the proof harnesses: `#[invariant(Running)] fn valid(&self) -> bool`
+    let invariants = match take_state_invariants(&mut input, &type_state_args) {
+        Ok(invariants) => invariants,
+        Err(error) => return error.to_compile_error().into(),
     };
 
     // Extract the methods from the impl block
+    // (the methods with the same requirements are merged into a single `impl` block, see `merge_impl_blocks`)
     let mut methods = Vec::new();
+    let mut method_impls = Vec::new();
+    // the transitions of the methods, for the lints of the state machine as a whole
+    let mut transitions = TransitionGraph::new(&type_state_args);
+
+    // the methods with an error are still expanded along with it (see `partial_expansion`)
+    let fallback = |method: &ImplItemFn, error| {
+        partial_expansion(
+            method,
+            error,
+            &struct_name,
+            &input.generics,
+            struct_generics,
+            &type_state_args,
+        )
+    };
 
     for item in input.items.iter_mut() {
         if let ImplItem::Fn(ref mut method) = item {
             // Extract `#[require]` arguments if they exist
-            let require_args = extract_macro_args(&mut method.attrs, "require");
-
-            // Generate the impl block for the method based on the extracted #[require] arguments
-            let modified_method = if let Some(require_args) = require_args {
-                generate_impl_block_for_method_based_on_require_args(
-                    method,
-                    &struct_name,
-                    &require_args,
-                    &input.generics,
-                    struct_generics,
-                )
-            } else {
-                quote! { #method }
+            let require_args = match extract_macro_args::<RequireArg>(&mut method.attrs, "require")
+            {
+                Ok(None) => constructor_require_args(method, &type_state_args),
+                require_args => require_args,
             };
+            let require_args = match require_args {
+                Ok(require_args) => require_args,
+                Err(error) => {
+                    methods.push(fallback(method, error));
+                    continue;
+                }
+            };
+
+            // Generate the method for each combination of the extracted #[require] arguments
+            if let Some((require_span, require_args)) = require_args {
+                let other_type_target =
+                    extract_other_type_target(&mut method.attrs, &type_state_args.states);
+                // along with the arguments of its struct (carried back by its hidden macro, see above)
+                let other_type_target = other_type_target.as_ref().map(|target| {
+                    let target_name = &target.path.segments.last().unwrap().ident;
+                    let target_args = targets
+                        .iter()
+                        .find(|(name, _)| name == target_name)
+                        .map_or(&type_state_args, |(_, target_args)| target_args);
+                    (target, target_args)
+                });
+                let mut switch_to_args =
+                    match extract_macro_args::<SwitchToArg>(&mut method.attrs, "switch_to") {
+                        Ok(switch_to_args) => switch_to_args,
+                        Err(error) => {
+                            methods.push(fallback(method, error));
+                            continue;
+                        }
+                    };
+                // the state of the `Err` arm of a guarded transition: `on_err = Disconnected`
+                let on_err = match switch_to_args
+                    .as_mut()
+                    .map(|(_, switch_to_args)| take_on_err(switch_to_args))
+                    .transpose()
+                {
+                    Ok(on_err) => on_err.flatten(),
+                    Err(error) => {
+                        methods.push(fallback(method, error));
+                        continue;
+                    }
+                };
 
-            // Push the modified method to the list of methods
-            methods.push(modified_method);
+                let arity_check = check_arity(
+                    require_span,
+                    "require",
+                    require_args.iter().map(|arg| arg.slot_name.as_ref()),
+                    &type_state_args,
+                )
+                .and_then(|_| match &switch_to_args {
+                    Some((switch_to_span, switch_to_args)) => check_arity(
+                        *switch_to_span,
+                        "switch_to",
+                        switch_to_args.iter().map(|arg| arg.slot_name.as_ref()),
+                        &type_state_args,
+                    ),
+                    None => Ok(()),
+                });
+                if let Err(error) = arity_check {
+                    methods.push(fallback(method, error));
+                    continue;
+                }
+
+                let ordered_args = order_slot_args(
+                    require_args
+                        .iter()
+                        .map(|arg| (arg.slot_name.as_ref(), arg.slot.clone())),
+                    &type_state_args.slots,
+                    RequiredSlot::Any(Default::default()),
+                )
+                .and_then(|require_args| {
+                    let switch_to_args = switch_to_args
+                        .map(|(_, switch_to_args)| {
+                            order_slot_args(
+                                switch_to_args
+                                    .iter()
+                                    .map(|arg| (arg.slot_name.as_ref(), arg.states.clone())),
+                                &type_state_args.slots,
+                                None,
+                            )
+                        })
+                        .transpose()?;
+                    Ok((require_args, switch_to_args))
+                });
+                let (require_args, switch_to_args) = match ordered_args {
+                    Ok(ordered_args) => ordered_args,
+                    Err(error) => {
+                        methods.push(fallback(method, error));
+                        continue;
+                    }
+                };
+
+                let state_check =
+                    check_slot_states(&require_args, switch_to_args.as_deref(), &type_state_args)
+                        .and_then(|_| check_branching(switch_to_args.as_deref().unwrap_or(&[])))
+                        .and_then(|_| {
+                            check_terminal_states(
+                                &require_args,
+                                switch_to_args.as_deref(),
+                                &type_state_args,
+                            )
+                        });
+                if let Err(error) = state_check {
+                    methods.push(fallback(method, error));
+                    continue;
+                }
+                let err_switch_to_args = match on_err
+                    .map(|on_err| {
+                        on_err_switch_to_args(
+                            on_err,
+                            switch_to_args.as_deref().unwrap_or(&[]),
+                            &require_args,
+                            &type_state_args,
+                        )
+                    })
+                    .transpose()
+                {
+                    Ok(err_switch_to_args) => err_switch_to_args,
+                    Err(error) => {
+                        methods.push(fallback(method, error));
+                        continue;
+                    }
+                };
+                // (the cross-type transitions leave the struct, so they are only callable in the required states,
+                // and the guarded transitions go to the states of both of their arms)
+                let graph_switch_to_args = match &err_switch_to_args {
+                    Some(err_switch_to_args) => switch_to_args.as_ref().map(|switch_to_args| {
+                        switch_to_args
+                            .iter()
+                            .zip(err_switch_to_args)
+                            .map(|(states, err_states)| match (states, err_states) {
+                                (Some(states), Some(err_states)) => {
+                                    Some(states.iter().chain(err_states).cloned().collect())
+                                }
+                                (states, _) => states.clone(),
+                            })
+                            .collect::<Vec<_>>()
+                    }),
+                    None => switch_to_args.clone(),
+                };
+                transitions.add_method(method, &require_args, graph_switch_to_args.as_deref());
+
+                // a branching transition returns a generated enum, with a variant for each target state
+                if let Some(switch_to_args) = &switch_to_args {
+                    if let Some(branching_slot) = switch_to_args_branching_slot(switch_to_args) {
+                        methods.push(generate_outcome_enum(
+                            method,
+                            &struct_name,
+                            struct_generics,
+                            &input.generics,
+                            branching_slot,
+                            switch_to_args[branching_slot].as_ref().unwrap(),
+                            &type_state_args,
+                        ));
+                    }
+                }
+
+                for combination in
+                    expand_require_alternatives(&require_args, &struct_name, &type_state_args)
+                {
+                    match generate_impl_block_for_method_based_on_require_args(
+                        method,
+                        &struct_name,
+                        &combination,
+                        switch_to_args.as_deref(),
+                        err_switch_to_args.as_deref(),
+                        other_type_target,
+                        &input.generics,
+                        struct_generics,
+                        &type_state_args,
+                        &hooks,
+                    ) {
+                        Ok(method_impl) => method_impls.push(method_impl),
+                        Err(error) => methods.push(error.to_compile_error()),
+                    }
+                }
+            } else {
+                methods.push(quote! { #method });
+            }
         }
     }
 
+    let method_impls = merge_impl_blocks(method_impls);
+
+    // the parts of the methods only see some of the transitions, which the lints cannot tell from the whole machine,
+    // and so do the blocks that extend the states
+    let lints = (!args.part && args.extend.is_none()).then(|| transitions.lints());
+    let declared_checks = transitions.declared_checks(&struct_name);
+    // the hooks are only called by the transitions of their own block, which the other blocks are told about
+    let hook_consts = declared_hooks(&hooks, &struct_name, &type_state_args);
+    let hook_checks = transitions.hook_checks(&struct_name, &hooks);
+    let table = args.transitions.then(|| {
+        let table = transitions.table(&struct_name);
+        let state_tests = transitions.state_tests(&struct_name, !struct_params.is_empty());
+        quote!(#table #state_tests)
+    });
+    let proptest = args
+        .proptest
+        .then(|| transitions.proptest(&struct_name, !struct_params.is_empty()));
+    let kani = args
+        .kani
+        .then(|| transitions.kani(&struct_name, !struct_params.is_empty(), &invariants));
+    let dual = args
+        .dual
+        .map(|trait_name| transitions.dual(&trait_name, &struct_name, !struct_params.is_empty()));
+    let diagrams = [
+        args.diagram.map(|path| (path, transitions.diagram())),
+        args.dot.map(|path| (path, transitions.dot(&struct_name))),
+        args.plantuml.map(|path| (path, transitions.plantuml())),
+        args.tla.map(|path| (path, transitions.tla(&struct_name))),
+        args.smv.map(|path| (path, transitions.smv(&struct_name))),
+    ];
+    let diagram_errors = diagrams
+        .into_iter()
+        .flatten()
+        .filter_map(|(path, diagram)| {
+            write_diagram(&path, &diagram)
+                .err()
+                .map(|error| error.to_compile_error())
+        });
+
     // Generate the expanded code with unique modules and traits
     let expanded = quote! {
+        #registry_import
+
+        #extended_states
+
+        #method_impls
+
         #(#methods)*
+
+        #lints
+
+        #declared_checks
+
+        #hook_consts
+
+        #hook_checks
+
+        #table
+
+        #proptest
+
+        #kani
+
+        #dual
+
+        #(#diagram_errors)*
     };
 
-    expanded.into()
+    debug_expansion(args.debug, &input.self_ty, expanded).into()
+}
+
+/// Reports the code generated for the item as a warning, for `#[impl_state(debug)]`:
+/// like the `warn(...)` lints, it is the use of a deprecated item with the code as its note
+/// (since the procedural macros cannot emit warnings on stable Rust)
+fn debug_expansion(
+    debug: bool,
+    item: impl ToTokens,
+    expanded: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if !debug {
+        return expanded;
+    }
+    let span = item
+        .to_token_stream()
+        .into_iter()
+        .next()
+        .map_or_else(proc_macro2::Span::call_site, |token| token.span());
+    let note = format!(
+        "`debug` is given to `#[impl_state]`, which generated:\n{}",
+        expanded
+    );
+    quote_spanned! {span=>
+        #expanded
+
+        const _: () = {
+            #[deprecated(note = #note)]
+            #[allow(non_upper_case_globals)]
+            const generated_code: () = ();
+            generated_code
+        };
+    }
+}
+
+/// Writes the diagram of the transitions to the given path, relative to the crate being compiled (to `CARGO_MANIFEST_DIR`)
+///
+/// the file is left as it is if it is up to date, so that it is not touched on every build
+fn write_diagram(path: &LitStr, diagram: &str) -> syn::Result<()> {
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = std::path::Path::new(&manifest_dir).join(path.value());
+    if std::fs::read_to_string(&file).is_ok_and(|existing| existing == diagram) {
+        return Ok(());
+    }
+
+    let written = match file.parent() {
+        Some(dir) => std::fs::create_dir_all(dir),
+        None => Ok(()),
+    }
+    .and_then(|_| std::fs::write(&file, diagram));
+    written.map_err(|error| {
+        syn::Error::new(
+            path.span(),
+            format!(
+                "cannot write the diagram to `{}`: {}",
+                file.display(),
+                error
+            ),
+        )
+    })
+}
+
+/// Expands the method that has an error along with the error, as if it had no `#[require]` and `#[switch_to]`
+/// (callable in any state, and keeping its return type as it is),
+/// so that it stays resolvable in the IDE (for the completions and go-to-definition) while it is being edited
+fn partial_expansion(
+    method: &ImplItemFn,
+    error: syn::Error,
+    struct_name: &Ident,
+    impl_generics: &Generics,
+    struct_generics: &PathArguments,
+    type_state_args: &TypeStateArgs,
+) -> proc_macro2::TokenStream {
+    let mut method = method.clone();
+    method
+        .attrs
+        .retain(|attr| !attr.path().is_ident("require") && !attr.path().is_ident("switch_to"));
+
+    let any_states = vec![RequiredSlot::Any(Default::default()); type_state_args.slots.len()];
+    let impl_blocks = expand_require_alternatives(&any_states, struct_name, type_state_args)
+        .into_iter()
+        .map(|combination| {
+            generate_impl_block_for_method_based_on_require_args(
+                &method,
+                struct_name,
+                &combination,
+                None,
+                None,
+                None,
+                impl_generics,
+                struct_generics,
+                type_state_args,
+                &StateHooks::default(),
+            )
+            // the error of the method is enough, the fallback is only for the IDE
+            .map(|method_impl| method_impl.to_token_stream())
+            .unwrap_or_default()
+        });
+    let error = error.to_compile_error();
+    quote! {
+        #error
+
+        #(#impl_blocks)*
+    }
+}
+
+/// Resolves the unprefixed names of the states given to `#[require]`, `#[switch_to]`
+/// and to the type-state protocol (`impl Engine<Docked, Sailing> for Boat`), when the markers are prefixed:
+/// `prefix = "Player"` -> `#[require(Ready)]` -> `#[require(PlayerReady)]`
+fn resolve_state_names(input: &mut ItemImpl, type_state_args: &TypeStateArgs) {
+    if type_state_args.prefix.is_none() {
+        return;
+    }
+
+    let method_attrs = input.items.iter_mut().filter_map(|item| match item {
+        ImplItem::Fn(method) => Some(&mut method.attrs),
+        _ => None,
+    });
+    for attrs in std::iter::once(&mut input.attrs).chain(method_attrs) {
+        for attr in attrs.iter_mut() {
+            if ["require", "switch_to", "on_enter", "on_exit", "invariant"]
+                .iter()
+                .any(|name| attr.path().is_ident(name))
+            {
+                if let Meta::List(list) = &mut attr.meta {
+                    list.tokens = type_state_args.resolve_names(list.tokens.clone());
+                }
+            }
+        }
+    }
+
+    if let Some((_, trait_path, _)) = &mut input.trait_ {
+        let last_segment = trait_path.segments.last_mut().unwrap();
+        if let PathArguments::AngleBracketed(args) = &last_segment.arguments {
+            let resolved = type_state_args.resolve_names(args.to_token_stream());
+            if let Ok(resolved) = syn::parse2(resolved) {
+                last_segment.arguments = PathArguments::AngleBracketed(resolved);
+            }
+        }
+    }
 }
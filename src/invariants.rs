@@ -0,0 +1,85 @@
+/// this file contains the invariants of the states, which are declared by the methods of an `impl` block:
+/// `#[invariant(Running)] fn has_track(&self) -> bool`, and checked by the proof harnesses of `#[impl_state(kani)]`
+/// (see `TransitionGraph::kani`)
+use quote::quote;
+use syn::{punctuated::Punctuated, Attribute, Ident, ImplItem, ItemImpl, ReturnType, Token, Type};
+
+use crate::{cfg_attrs, closest_name, TypeStateArgs};
+
+/// An invariant of a state: `#[invariant(Running)] fn has_track(&self) -> bool` -> `(Running, has_track)`
+pub struct StateInvariant {
+    pub state: Ident,
+    pub method: Ident,
+    pub cfgs: Vec<Attribute>,
+}
+
+/// Takes the `#[invariant]` attributes out of the methods of the `impl` block,
+/// and gives the invariants `#[require(_, ...)]` (unless they have their own), so they can be checked in any state
+pub fn take_state_invariants(
+    input: &mut ItemImpl,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<Vec<StateInvariant>> {
+    let mut invariants = Vec::new();
+    for item in input.items.iter_mut() {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let Some(position) = method
+            .attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("invariant"))
+        else {
+            continue;
+        };
+        let attr = method.attrs.remove(position);
+        let states: Punctuated<Ident, Token![,]> =
+            attr.parse_args_with(Punctuated::parse_terminated)?;
+        for state in states {
+            if !type_state_args.states.contains(&state) {
+                let message = match closest_name(&state, &type_state_args.states) {
+                    Some(closest) => {
+                        format!("unknown state `{}`; did you mean `{}`?", state, closest)
+                    }
+                    None => format!("unknown state `{}`", state),
+                };
+                return Err(syn::Error::new(state.span(), message));
+            }
+            invariants.push(StateInvariant {
+                state,
+                method: method.sig.ident.clone(),
+                cfgs: cfg_attrs(&method.attrs).cloned().collect(),
+            });
+        }
+
+        let takes_ref_self = method
+            .sig
+            .receiver()
+            .is_some_and(|receiver| receiver.reference.is_some() && receiver.mutability.is_none());
+        let returns_bool = matches!(&method.sig.output, ReturnType::Type(_, ty)
+            if matches!(&**ty, Type::Path(type_path) if type_path.path.is_ident("bool")));
+        if !takes_ref_self
+            || method.sig.inputs.len() != 1
+            || method.sig.asyncness.is_some()
+            || !returns_bool
+        {
+            return Err(syn::Error::new(
+                method.sig.ident.span(),
+                format!(
+                    "the invariant `{}` is expected to be `fn {}(&self) -> bool`, since it is checked in its states",
+                    method.sig.ident, method.sig.ident
+                ),
+            ));
+        }
+        if !method
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("require"))
+        {
+            let any_states = type_state_args.slots.iter().map(|_| quote!(_));
+            method
+                .attrs
+                .push(syn::parse_quote!(#[require(#(#any_states),*)]));
+        }
+    }
+    Ok(invariants)
+}
@@ -11,22 +11,105 @@
 //!
 //! - `#[require]`: Enforces that a method can only be called when the provided state is active.
 //! - `#[switch_to]`: Modifies the return type of methods to switch between states.
+//! - `#[on_enter]` / `#[on_exit]`: Declares the hooks of a state, which are called by the transitions entering or leaving it.
 //! - `#[impl_state]`: Defines the valid states for a given type and generates corresponding marker structs and trait implementations.
 //! - `#[type_state]`: Transforms the struct into type-state compatible form, using state slots and default states.
+//! - `#[derive(TypeState)]`: The alternative to `#[type_state]` that doesn't rewrite the struct, which declares the states itself.
+//! - `transitions!`: Declares the transitions of a struct once, and the methods of `#[impl_state]` are checked against them.
+//! - `match_state!`: Matches the struct in any of its states, with an arm for each state.
+//! - `generate_state_tests!`: Generates the compile-fail tests of the methods called in the states they cannot be called in.
+//! - `#[state_machine]`: Annotates a module with the states once, for the struct and the `impl` blocks inside of it.
+//! - `state_shift!`: Declares a state machine up front, with its states and transitions, whose bodies are given separately.
+//! - `state_machine_from!`: Declares the state machine of `state_shift!` from a spec file (RON, TOML, Mermaid or DOT), read at compile time.
+//! - `#[type_state_builder]`: Generates the typed builder of a struct, which can only build it once all of its fields are set.
 
 extern crate proc_macro;
 
+mod accessors;
+mod aliases;
+mod analysis;
+mod any_state;
+mod builder;
+mod debug;
+mod declared_transitions;
+mod derive_type_state;
+mod derives;
+mod diagram_spec;
+mod dsl;
+mod expansion_dump;
 mod helper;
+mod hooks;
 mod impl_state;
+mod invariants;
+mod markers;
+mod match_state;
+mod only_in;
+mod rebuilders;
+mod registry;
 mod require;
+mod serde_support;
+mod spec;
+mod state_machine;
+mod state_tests;
+mod state_trait;
 mod switch_to;
+mod tracing_support;
 mod type_state;
+mod validation;
 
-use helper::{extract_idents_from_group, extract_macro_args, is_single_letter};
-use impl_state::impl_state_inner;
-use require::generate_impl_block_for_method_based_on_require_args;
-use switch_to::switch_to_inner;
-use type_state::type_state_inner;
+use accessors::{all_states, state_data_accessors, state_name_accessors};
+use aliases::{state_aliases, DEFAULT_ALIAS_NAMING};
+use analysis::TransitionGraph;
+use any_state::{any_state_enum, any_state_enum_name, state_enum, state_enum_name};
+use builder::type_state_builder_inner;
+use debug::debug_impl;
+use declared_transitions::{
+    declared_transitions_fn, forward_transitions, missing_transitions_fallback, transitions_inner,
+};
+use derive_type_state::derive_type_state_inner;
+use derives::{builder_derive, derive_impls, mentions_states, take_derives, REPLACED_DERIVES};
+use diagram_spec::{dot, mermaid, DiagramMachine};
+use dsl::state_shift_inner;
+use expansion_dump::dump_expansion;
+use helper::{
+    cfg_attrs, extract_macro_args, fill_default_generic_args, generic_args, name_elided_lifetimes,
+    order_slot_args, strip_generic_defaults, unconsumed_attribute, unsupported_self_type, StateArg,
+};
+use hooks::{
+    declared_hooks, foreign_hook_check, hooked_body, hooks_registry, hooks_type, no_hooks_trait,
+    take_state_hooks, StateHooks,
+};
+use impl_state::{forward_impl_state, impl_state_inner};
+use invariants::{take_state_invariants, StateInvariant};
+use markers::{extend_states, impl_for_state, marker_derives, state_set};
+use match_state::{forward_match_state, match_state_inner};
+use only_in::{only_in_fields, OnlyInFields};
+use rebuilders::{in_place_helper, state_rebuilders};
+use registry::{registry_import, state_registry};
+use require::{
+    add_state_field, constructor_require_args, expand_require_alternatives,
+    generate_impl_block_for_method_based_on_require_args,
+    generate_trait_impl_block_based_on_require_args, merge_impl_blocks, RequireArg,
+    RequireCombination, RequiredSlot,
+};
+use serde_support::{any_state_serde, serde_support};
+use spec::state_machine_from_inner;
+use state_machine::state_machine_inner;
+use state_tests::{forward_state_tests, state_tests_inner, state_tests_macro_name};
+use state_trait::{forward_impl_state_trait, impl_state_trait_inner, state_trait_inner};
+use switch_to::{
+    extract_other_type_target, generate_outcome_enum, mentions_struct, on_err_switch_to_args,
+    other_type_target, outcome_enum_name, outcome_enum_type, switch_to_args_branching_slot,
+    switch_to_inner, switch_to_other_type, take_on_err, SwitchToArg,
+};
+use tracing_support::{logged_body, traced_body};
+use type_state::{
+    callback_macro_name, reuse_states, type_state_inner, Lint, LintLevel, StateGroup, StructShape,
+    TypeStateArgs,
+};
+use validation::{
+    check_arity, check_branching, check_slot_states, check_terminal_states, closest_name,
+};
 
 use proc_macro::TokenStream;
 
@@ -36,16 +119,334 @@ use proc_macro::TokenStream;
 ///
 /// Arguments:
 /// - `states` -> A list of the states that the struct can transition through, which will be generated as marker structs and traits.
+///   States can be generic too: `states = (Empty, Loaded<T>)`, then they are given their arguments in `#[require]` and `#[switch_to]`:
+///   `#[switch_to(Loaded<Config>)]`, `#[require(Loaded<C>)]` (a single letter stands for any type, like the generic states).
+///   States can carry data as well: `states = (Disconnected, Connected { session_id: u64 })`.
+///   Then the states are stored in the `_state` field: the methods switching into `Connected` give it along with the other fields
+///   (`Connection { address, _state: Connected { session_id } }`), and `connection.state_data()` returns it in the `Connected` state
+///   (`<slot name>_state_data()` or `state_data_<slot number>()` with multiple slots).
+///   States can have substates too: `states = (Disconnected, Connected(Active, Idle))`.
+///   The parent state is a group of its substates (`#[require(Connected)]` accepts both `Active` and `Idle`),
+///   and the substates can be given with their parents: `#[require(Connected::Active)]`, `#[switch_to(Connected::Idle)]`.
+///   The markers that are defined by hand (with their own derives or docs) are declared as external: `states = (external(Idle), Running)`.
+///   Their markers are not generated then (the macro cannot see the existing definitions, so they have to be marked),
+///   but they are sealed like the other states.
+///   States can be gated with `#[cfg]` as well: `states = (Idle, #[cfg(feature = "tls")] Handshaking, Ready)`.
+///   Everything generated for a gated state (its marker, and the methods that require it or transition into it) is gated along with it,
+///   so the optional features can add states without another copy of the state machine. The default states cannot be gated.
+///   The generated markers are documented with what the declaration tells about them (the slots they are the default states of,
+///   their parents and groups, and the data they carry), and so are the groups and the sealer.
+///
+/// Fields can exist only in certain states (or groups) too: `#[only_in(Connected | Closing)] socket: TcpStream`.
+/// The field has its declared type only in those states, and `()` in the others (so it is initialized with `socket: ()` there),
+/// and the `socket()` and `socket_mut()` accessors are generated for those states.
 /// - `slots` -> Specifies the default states for the struct's state slots. Each slot corresponds to a tracked state.
+///   Slots can optionally be named: `slots = (auth = DefaultState1, conn = DefaultState2)`.
+/// - `slot_states` (optional) -> Restricts the states allowed in each slot: `slot_states = ((State1, State2), (State3, State4))`.
+///   Using a state in the wrong slot in `#[require]` or `#[switch_to]` is a compile error.
+/// - `groups` (optional) -> Named groups of states: `groups = (Group1 = (State1, State2), ...)`.
+///   A marker trait is generated for each group, and `#[require(Group1)]` accepts any state of the group.
+/// - `components` (optional) -> Composes other type-state structs (with a single slot) into the struct:
+///   `components = (auth: Auth<LoggedOut>, conn: Connection<Closed>)`.
+///   Each component becomes a field of the struct (`auth: Auth<..>`) and a named slot before the other slots
+///   (with the given default state), tracking the states of the component: `Session<LoggedIn, Open>`.
+///   Then `#[require(LoggedIn, _)]` and `#[switch_to(conn = Open)]` work with the states of the components,
+///   and the methods delegate to the components: `Self { conn: self.conn.open(), .. }`.
+///   `states` and `slots` can be left out, if the struct has no states of its own.
+/// - `reuse` (optional) -> Reuses the states of another struct: `#[type_state(reuse = Article, slots = (Draft))]`.
+///   The markers, the groups and the sealer of `Article` are shared instead of being generated again,
+///   so any number of structs can go through the same states (which have to be in scope, like for `Article`).
+///   `states` and `groups` are left out then, and `#[only_in]` fields are not supported.
+/// - `registry` (optional) -> Re-exports the struct and its states (with the groups and the sealer) from a module next to it:
+///   `registry = player_states`. The `impl` blocks in the other modules and files of the crate name the struct by its path,
+///   `#[impl_state] impl crate::player::Player { ... }`, and get the states from the registry, instead of importing them one by one.
+///   The hidden field of the states is `pub(crate)` then, since the methods of the other modules build the struct too.
+///   It cannot be used with `reuse`.
+/// - `extensible` (optional) -> `extensible = true` lets the other crates use `#[impl_state]` for the struct too.
+///   Since the inherent methods can only be defined in the crate of the struct, they add the methods through traits:
+///   `#[impl_state] #[require(Draft)] impl Preview for library::Article { ... }`
+///   (with the states in scope, and `SealerArticle` as well for `_` slots and generics).
+///   The hidden macro of the struct is exported then, so the structs with the same name in a crate cannot all be extensible.
+/// - `sealed` (optional) -> `sealed = false` opts out of sealing the states, so that `SealerStruct`
+///   can be implemented by other states as well (in other crates or in the tests): `impl SealerJob for Paused {}`.
+///   The states stay sealed by default.
+/// - `must_use` (optional) -> The transition methods (the ones with `#[switch_to]`) are `#[must_use]`,
+///   since they consume the struct and return it in its new state. `must_use = false` opts out of it,
+///   and a `#[must_use]` given to the method is kept instead.
+/// - `prefix` (optional) -> Prefixes the names of the generated markers and groups: `prefix = "Player"` -> `PlayerReady`,
+///   so that the structs in the same module can have the states with the same names.
+///   `#[require]`, `#[switch_to]` and `#[only_in]` still take the unprefixed names (`#[require(Ready)]`),
+///   and the prefixed ones are used in the types: `Player<PlayerReady>`.
+/// - `terminal` (optional) -> The states that cannot be left once they are reached: `terminal = (Closed)`.
+///   The methods that require them cannot transition out of them (`#[require(Closed)] #[switch_to(Open)]` is an error),
+///   which catches the protocol bugs like reusing a closed connection. A group stands for all of its states.
+/// - `warn(...)` / `deny(...)` (optional) -> Checks the transitions of the `#[impl_state]` blocks, and reports the problems
+///   as warnings or as errors: `deny(unreachable)`.
+///   `unreachable` reports the declared states that cannot be reached from the default states (or the constructors),
+///   and `dead_end` reports the states that the methods transition into, but none of the methods can be called in
+///   (the `terminal` states are not reported, since they are meant to be the end).
+///   The `impl` blocks are checked one at a time, so the checks are opt-in (the states may be reached in another `impl` block),
+///   and the blocks of `#[impl_state(part)]` (or `extend`) are left out of them.
+/// - `field` (optional) -> Renames the hidden field that holds the states: `field = "__ts_state"`,
+///   for the structs that have a `_state` field of their own. The states that carry data are given with the new name then:
+///   `Self { __ts_state: Connected { session_id }, .. }`.
+/// - `vis` (optional) -> The visibility of the generated markers and group traits: `vis = pub(crate)`,
+///   or `vis = pub(self)` for the private ones, so the states can be kept out of the public API. They are `pub` by default.
+/// - `derive(...)` (optional) -> The derives of the generated markers: `derive(Debug, Clone, Copy, PartialEq, Eq, Hash)`,
+///   so the states can be compared in the assertions or used as the keys of a map (`HashMap<Idle, u32>`).
+///   The markers that don't carry data derive them as unit structs, and the external states are left to their own derives.
+/// - `doc_hidden` (optional) -> `doc_hidden = true` tags the generated markers, the sealer and the group traits with `#[doc(hidden)]`,
+///   since the users of the struct rarely name them directly.
+/// - `phantom` (optional) -> Chooses the `PhantomData` of the hidden field, which decides how the states affect the struct:
+///   `"fn_pointer"` (the default) -> `PhantomData<fn() -> State>`, the struct is always `Send` and `Sync`, and covariant in its states,
+///   `"owned"` -> `PhantomData<State>`, the struct behaves as if it owned its states (the auto traits and the drop check),
+///   `"invariant"` -> `PhantomData<fn(State) -> State>`, the struct is invariant in its states.
+///   It has no effect on the states that carry data, since they are stored in the field instead.
+/// - `any_state` (optional) -> `any_state = true` generates the enum of the struct in any of its states:
+///   `enum AnyPlayer { Idle(Player<Idle>), Running(Player<Running>) }` (the variants are named after the unprefixed states),
+///   along with `From<Player<Idle>>` for each state, so that the values in different states can be stored together
+///   (`let players: Vec<AnyPlayer> = vec![idle.into(), running.into()]`) and recovered by matching.
+///   It needs a single slot of the struct's own states, which cannot be generic.
+/// - `aliases` (optional) -> `aliases = true` generates the type alias of the struct in each of its states:
+///   `pub type IdlePlayer = Player<Idle>` (named after the unprefixed states), so the signatures and the error messages
+///   of the code that uses the struct are easier to read. The naming is given with `{state}` and `{struct}`:
+///   `aliases = "{struct}In{state}"` -> `PlayerInIdle`. The aliases keep the generics of the struct
+///   (`type IdleClient<T = Tcp> = Client<T, Idle>`) and of the generic states, and need a single slot of the struct's own states.
+/// - `state_enum` (optional) -> `state_enum = true` generates the enum of the states: `enum PlayerState { Idle, Running }`
+///   (named after the unprefixed states), and `player.current_state()`, which tells the state at runtime, in any of its states
+///   (`<slot name>_current_state()` or `current_state_<slot number>()` with multiple slots, for the struct's own states).
+///   `AnyPlayer` gets `current_state()` as well, with `any_state = true`. The states implemented by hand (with `sealed = false`)
+///   give their variants as `const STATE: PlayerState`. It cannot be used with `reuse`.
+/// - `serde` (optional) -> `serde = true` derives `Serialize` and `Deserialize` for the struct (with the `serde` feature of `state-shift`,
+///   so the struct shouldn't derive them itself). The hidden `_state` field is skipped, since the state is told by the type:
+///   the struct is serialized in any of its states, and deserialized into its default states (`let player: Player = ..`).
+///   The other states that can be deserialized into are given instead: `serde = (Ready)` -> `let player: Player<Ready> = ..`
+///   (a group stands for all of its states). The states that carry data cannot be deserialized into, and the components cannot be used.
+///   With `any_state = true`, `AnyPlayer` is tagged with the unprefixed states: `{"state": "Running", "name": "Alice"}`,
+///   so the values round-trip with their states (in any of the states, since the state is given by the tag).
+///   It needs the named fields, without the states that carry data and the `#[only_in]` fields.
+/// - `into_state` (optional) -> `into_state = true` generates the escape hatch that forces the struct into any states,
+///   without a transition: `unsafe { player.into_state::<Running>() }` (for the recovery paths or the values that come from FFI),
+///   so that it is explicit and easy to audit. It needs the `into_state` feature of `state-shift`,
+///   and cannot be used when the states carry data, or the fields depend on the states (the `#[only_in]` fields and the components).
+/// - `reset` (optional) -> `reset = true` generates `player.reset()`, which moves the struct back into its default states
+///   from any of its states, to start over (`Player<Running>` -> `Player<Idle>`). The states that carry data are dropped,
+///   and it cannot be used when the default states carry data, or the fields depend on the states.
+/// - `log` (optional) -> `log = true` logs each transition of the struct with `log::trace!`, as a lighter alternative
+///   to the `tracing` feature: `transition struct=Player method=start from=Idle to=Running`
+///   (the generic states are logged with the state they are in at runtime).
+///   It needs the `log` feature of `state-shift`, and the crate that uses it depends on `log` itself.
+/// - `in_place` (optional) -> `in_place = true` generates `Struct::transition_in_place(&mut handle, placeholder, |handle| ...)`,
+///   which transitions an object stored in another struct through `&mut` access, by replacing it with `placeholder` meanwhile.
+///
+/// Enums can be type-state compatible too: `#[type_state(...)] enum Frame { Hello { version: u8 }, Data(Vec<u8>) }`.
+/// Each variant gets the hidden `_state` field (as the last element of the tuple variants), so the variants are matched
+/// with `..` (`Frame::Hello { version, .. }`, `Frame::Data(bytes, ..)`), and the unit variants are declared as `Close {}`.
+/// `#[impl_state]` appends the `_state` field to the initializations of the variants as well: `Self::Data(bytes)`.
+///
+/// So can the tuple structs: `#[type_state(...)] struct Meters(f64);` gets the state as its last element,
+/// which `#[impl_state]` appends to `Self(value)` (unless it is given explicitly: `Self(value, Connected { session_id })`).
+/// The components cannot be used with them, since they are named fields.
+/// The unit structs (`struct Token;`) become the structs with only the `_state` field,
+/// and `#[impl_state]` initializes it wherever the struct is initialized by its name: `Self`, `Token`.
+///
+/// The states can be told by their names too, for the logs and the error messages: `player.state_name()` returns the name
+/// of the current state (`<slot name>_state_name()` or `state_name_<slot number>()` with multiple slots),
+/// and the markers have their names as `Idle::STATE_NAME` (the declared names, without the `prefix`),
+/// which they are displayed with as well: `format!("entered {}", Idle)`.
+/// `#[derive(Debug)]` after `#[type_state]` shows the states too: `Player<Running> { level: 3, .. }`
+/// (`Pair<Full, Empty>` with multiple slots), since it is replaced with an impl for the struct in any of its states,
+/// which needs the generics of the struct to be `Debug` (like the derived impl), but not the markers.
+/// So are `Clone`, `Copy`, `PartialEq`, `Eq`, `Hash`, `PartialOrd` and `Ord`, so that the struct is cloned and compared
+/// in any of its states (in the methods that require `A` too), without the markers implementing them
+/// (unless the states carry data, then the markers derive them as well, since they are stored in the struct).
+/// The builder derives work after `#[type_state]` as well: `#[derive(Builder)]` of `derive_builder`
+/// and `#[derive(TypedBuilder)]` of `typed-builder` skip the hidden `_state` field, so the structs are built in their default states
+/// (the markers derive `Clone` for `derive_builder`, which needs it for the generics of the builder).
+/// They cannot be used with the states that carry data.
+/// `#[derive(Default)]` on the structs creates them in their default states only (`Player::default()` is a `Player<Idle>`),
+/// the other states are reached by the transitions.
+/// The code that is generic over any state of the struct is bounded by `PlayerStateMarker`, which all the states implement,
+/// and which gives their names as well: `fn describe<S: PlayerStateMarker>(player: &Player<S>) -> &'static str { S::NAME }`.
+/// The names of all the declared states are listed in `Player::ALL_STATES` (`Client::<Tcp>::ALL_STATES` for the generic structs),
+/// and in `AnyPlayer::VARIANTS` as well, with `any_state = true`.
 ///
 /// What it does:
 /// - Defines the valid states that a struct can transition between using the `states` attribute,
 /// - Configures multiple state slots if needed, allowing a struct to track multiple states concurrently,
+/// - Keeps the generics of the struct (lifetimes, types and const generics), and appends the generics of the state slots after them:
+///   `struct Buffer<const N: usize>` -> `Buffer<N, State1>`
+///   (the defaults of the generics are kept too, and `#[impl_state] impl Client` fills them in: `Client<Tcp, State1>`),
 /// - Protects against invalid struct initialization by sealing state transitions using traits and marker structs,
 /// - Seals the trait implementations for each state to ensure safety and prevent external modification.
 #[proc_macro_attribute]
 pub fn type_state(args: TokenStream, input: TokenStream) -> TokenStream {
-    type_state_inner(args, input)
+    dump_expansion("type_state", input.clone(), type_state_inner(args, input))
+}
+
+/// The alternative to `#[type_state]` that doesn't rewrite the struct, for the codebases that only allow the derives.
+///
+/// Usage:
+///
+/// ```ignore
+/// #[derive(TypeState)]
+/// #[type_state(states = (Idle, Running), slots = (Idle))]
+/// struct Player<S = Idle> {
+///     name: String,
+///     _state: PhantomData<fn() -> S>,
+/// }
+/// ```
+///
+/// The arguments are the ones of `#[type_state]`, given with the `#[type_state]` helper attribute
+/// (so the `type_state` attribute macro is not to be imported along with it), and the derive generates the same items,
+/// except for the struct itself, which declares what `#[type_state]` would add to it:
+/// - a generic parameter for each slot, after the other generics, defaulting to the default state of the slot: `S = Idle`,
+/// - the field of the states (or the last element of the tuple structs), with the type that `#[type_state]` would give it:
+///   `PhantomData<fn() -> S>`, or `(PhantomData<fn() -> S1>, PhantomData<fn() -> S2>)` for several slots
+///   (the declaration is checked at compile time, so a wrong type is reported on the field).
+///
+/// The `impl` blocks are written with `#[impl_state]`, or by hand with the markers: `impl Player<Idle>`.
+/// The derives of the struct are left as they are, and the options that add to the declaration of the struct
+/// (`serde`, `reuse` and the components) need `#[type_state]`.
+#[proc_macro_derive(TypeState, attributes(type_state))]
+pub fn derive_type_state(input: TokenStream) -> TokenStream {
+    dump_expansion(
+        "derive_type_state",
+        input.clone(),
+        derive_type_state_inner(input),
+    )
+}
+
+/// Declares a state machine with a single annotation, for the struct and its `impl` blocks.
+///
+/// Usage: `#[state_machine(states = (Idle, Running), slots = (Idle))] mod player { ... }`
+///
+/// The arguments are the ones of `#[type_state]`, which is put on the struct of the machine: the one named after the module
+/// (`mod player` -> `Player`), or the only struct (or enum) of the module. Its `impl` blocks get `#[impl_state]`
+/// (the trait implementations only if they are gated with `#[require]`), so the methods only need `#[require]` and `#[switch_to]`.
+/// The other items of the module are left as they are, and the module has to be declared inline.
+///
+/// On a struct or an enum, it is the same as `#[type_state]`, for the codebases that use `#[state_machine]` everywhere.
+#[proc_macro_attribute]
+pub fn state_machine(args: TokenStream, input: TokenStream) -> TokenStream {
+    dump_expansion(
+        "state_machine",
+        input.clone(),
+        state_machine_inner(args, input),
+    )
+}
+
+/// Declares state machines up front: the struct, its states and its transitions.
+///
+/// Usage:
+///
+/// ```ignore
+/// state_shift! {
+///     pub machine Player {
+///         fields { name: String, level: u8 }
+///         states { Idle, Running }
+///         transitions {
+///             start(boost: u8): Idle -> Running,
+///             stop: Running -> Idle,
+///         }
+///     }
+/// }
+/// ```
+///
+/// - the struct is a `#[type_state]` struct, with the first state as its default state, and `Player::new(name, level)`
+///   creates it in that state (the fields are optional, and so are the arguments of the transitions),
+/// - each transition is a method that can only be called in its state: `player.start(2)` is only defined for `Player<Idle>`,
+///   and returns `Player<Running>` (a transition can be taken in several states too: `stop: Running | Paused -> Idle`,
+///   and it is declared once, since its name is the name of the method),
+/// - the bodies of the transitions are given by implementing the `PlayerTransitions` trait, whose `on_start(&mut self, boost: u8)`
+///   runs before the switch, for the struct in any of its states:
+///   `impl<S: PlayerStateMarker> PlayerTransitions for Player<S> { ... }`.
+///
+/// The attributes of the machine are given to the struct, and the ones of the transitions to their methods.
+#[proc_macro]
+pub fn state_shift(input: TokenStream) -> TokenStream {
+    dump_expansion("state_shift", input.clone(), state_shift_inner(input))
+}
+
+/// Declares a state machine from a spec file, which is read at compile time.
+///
+/// Usage: `state_machine_from!("specs/door.ron")`, with the path relative to the crate.
+///
+/// The spec file describes the machine of `state_shift!` in RON or TOML (told by the extension),
+/// so that the spec shared with the other teams and languages stays the single source of truth.
+/// They are read by the `ron` and `toml` crates, with the `ron` and `toml` features of `state-shift`:
+///
+/// ```ron
+/// Machine(
+///     name: "Door",
+///     visibility: "pub",
+///     doc: "The door of the lobby",
+///     fields: {"code": "u32"},
+///     states: ["Closed", "Open", "Locked"],
+///     transitions: [
+///         (name: "open", from: "Closed", to: "Open"),
+///         (name: "lock", from: "Closed", to: "Locked", args: {"code": "u32"}, doc: "Locks the door"),
+///     ],
+/// )
+/// ```
+///
+/// ```toml
+/// name = "Door"
+/// states = ["Closed", "Open", "Locked"]
+///
+/// [fields]
+/// code = "u32"
+///
+/// [[transitions]]
+/// name = "lock"
+/// from = "Closed"
+/// to = "Locked"
+/// args = { code = "u32" }
+/// ```
+///
+/// Only `name` and `states` are required (the first state is the state of the machine when it is created),
+/// the names are strings (quoted in RON too, where a bare `Closed` is a unit value), the types of the fields and of the arguments
+/// are strings of Rust types, and the maps of both formats are read in the order they are written.
+/// The machine is generated like the one of `state_shift!`: the bodies of its transitions are given by implementing `DoorTransitions`,
+/// so a transition added to the spec is a compile error until it is implemented. The crate is rebuilt when the spec file changes.
+///
+/// The spec file can be an existing diagram as well, the inverse of `#[impl_state(diagram = ...)]` and `#[impl_state(dot = ...)]`:
+/// `state_machine_from!("docs/door.mmd")` reads a Mermaid state diagram, and `state_machine_from!("docs/door.dot")` a Graphviz graph.
+/// The states are the nodes, the start (`[*]` in Mermaid, the node drawn with `shape = point` in DOT) points to the first state,
+/// and each edge is labeled with the method of its transition (the edges labeled with the same method are taken in several states).
+/// The machine is named after the graph (`digraph Door`) or after the file, and the fields the diagram doesn't give
+/// are given along with the path: `state_machine_from!("docs/door.mmd", fields { code: u32 })`.
+#[proc_macro]
+pub fn state_machine_from(input: TokenStream) -> TokenStream {
+    dump_expansion(
+        "state_machine_from",
+        input.clone(),
+        state_machine_from_inner(input),
+    )
+}
+
+/// Generates the typed builder of a struct, whose fields are checked at compile time.
+///
+/// Usage: `#[type_state_builder] struct Player { name: String, level: u8 }`
+///
+/// Each field is a slot of the builder, which is `Unset` until the field is set:
+/// `Player::builder().name("Alice".to_string()).level(3).build()`, and `build()` can only be called once all the fields are set
+/// (forgetting one of them, or setting it twice, is a compile error instead of a panic at runtime).
+///
+/// The builder is a type-state struct itself (`PlayerBuilder`, with the `PlayerBuilderUnset` and `PlayerBuilderSet` states),
+/// so more methods can be added to it with `#[impl_state]`, and its slots are named after the fields:
+/// `#[require(name = Set)] fn greeting(&self) -> String`. The generics of the struct are kept on the builder as well.
+///
+/// The fields that don't have to be set are declared with `#[optional]` (filled with `Default::default()` if they are not set)
+/// or `#[default = expr]` (filled with the expression): they don't have a slot, so they can be set in any state of the builder,
+/// and `build()` only waits for the required fields.
+#[proc_macro_attribute]
+pub fn type_state_builder(args: TokenStream, input: TokenStream) -> TokenStream {
+    dump_expansion(
+        "type_state_builder",
+        input.clone(),
+        type_state_builder_inner(args, input),
+    )
 }
 
 /// Modifies the methods in an `impl` block to work with the type-state pattern.
@@ -60,10 +461,251 @@ pub fn type_state(args: TokenStream, input: TokenStream) -> TokenStream {
 ///
 /// Also:
 /// - Consumes the `#[require]` and `#[switch_to]` macros and handles the necessary transformations for those macros,
-/// - Ensures that the methods only execute in the correct state and can safely transition between valid states.
+/// - Ensures that the methods only execute in the correct state and can safely transition between valid states,
+/// - Keeps the methods with an error (like an unknown state in `#[require]`) next to the error, callable in any state,
+///   so that the IDE can still complete them and go to their definitions while they are being edited
+///   (and so does `#[type_state]` with the struct, if its arguments have an error).
+///
+/// The methods can be `async fn` or `const fn` as well, `#[require]` and `#[switch_to]` work the same way for them
+/// (so a type-state builder can be evaluated in `const` and `static` items),
+/// and they can take `self` through any receiver: `self: Box<Self>`, `self: Rc<Self>`, `self: Pin<&mut Self>`, ...
+/// (`-> Box<Self>` transitions into `Box<Player<NewState>>`, like any other return type).
+/// The generics and the `where` clauses of the methods (and of the `impl` block) are kept as they are,
+/// and the `impl` block can use the elided lifetimes as well: `impl Parser<'_>`.
+/// The other attributes of the methods (`#[inline]`, `#[deprecated]`, the doc comments, ...) are kept on them as they are,
+/// and their `#[cfg]` gates the items generated for them too (like the enum of a branching transition).
+///
+/// The methods can be split across several `impl` blocks of the struct, which share its markers (the states are declared once,
+/// by `#[type_state]`), in other modules too, with the struct and its states in scope (`use super::*`, or the `registry`).
+/// The blocks that only hold a part of the methods (the accessors of a feature, ...) are given `#[impl_state(part)]`,
+/// so the `warn(...)` / `deny(...)` checks of `#[type_state]` are left to the block with the transitions.
+///
+/// A feature can append its states to an existing machine, along with their transitions, with `extend`:
+/// `#[impl_state(extend = (Maintenance))] impl Player { #[require(Running)] #[switch_to(Maintenance)] fn service(self) -> Self }`
+/// generates the marker of `Maintenance` (and implements the sealer of `Player` for it), so `Player<Maintenance>` is a state
+/// of `Player` like the declared ones, and the methods that require any state (`#[require(A)]`) can be called in it.
+/// The new states cannot be declared by `#[type_state]` already, and they are only known by the methods of their block,
+/// which is in the module of the struct (or in a module inside it), and they are not in `ALL_STATES` either.
+/// The states of the struct cannot be extended with `state_enum`, `any_state`, `serde`, `prefix`, the states of the slots,
+/// or the states that carry data.
+///
+/// The methods with the same requirements share a single generated `impl` block, so the large `impl` blocks
+/// (with the alternatives of `#[require]` expanded for each of their states) stay cheap to expand and to type-check
+/// (`tests/large_impl_example.rs` has 80 of them). The bounds of the generated blocks are not shared through aliases,
+/// since each of them is the sealer trait of a slot already: `A: SealerPlayer`.
+/// Like the other procedural macros, the macros of the crate expand faster when they are compiled with optimizations:
+/// `[profile.dev.build-override] opt-level = 3` in the `Cargo.toml` of the crate using them.
+/// The names of the generated items (`SealerPlayer`, `PlayerStateMarker`, `__state_shift_player`, `StartOutcome`, ...)
+/// are derived from the names of the struct, its states and its methods, and never from a counter or the order
+/// the macros are expanded in, so the expansion of a struct stays the same (and cached) across the unrelated edits of the crate.
+///
+/// The code generated for each annotated item can be read without `cargo expand` on the whole crate:
+/// compiled with `STATE_SHIFT_DEBUG=1`, the macros write it to `target/state-shift/<crate>/` in the `target` directory
+/// of the workspace (or the one of `CARGO_TARGET_DIR`, and under `OUT_DIR` for the crates with a build script), in a file for each item: `type_state-Player.rs`, `impl_state-Player-new.rs` (named after its first method),
+/// `impl_state-Player-Display.rs`, ... Since cargo doesn't track the variable, the crate is rebuilt for it to take effect
+/// (`touch src/lib.rs`), and the files are unformatted (`rustfmt` makes them readable).
+/// The code generated for a single `impl` block is shown by `#[impl_state(debug)]` instead, as a warning on the block
+/// (which is removed along with the argument once the question is answered).
+///
+/// The transitions of the `impl` block can be inspected at runtime too, with `#[impl_state(transitions)]`:
+/// it generates the `player_transitions` module (after the struct), with the `Transition` struct
+/// (the name of a method, the slot, the states it can be called in, and the states it transitions into),
+/// and `TRANSITIONS: &[Transition]` with the transitions of each method, for the tests and the tools.
+/// The `impl` blocks are described one at a time, so only one `impl` block of the struct can generate it
+/// (along with the compile-fail tests of its methods, with `generate_state_tests!`).
+/// They can be drawn as well, with `#[impl_state(diagram = "docs/player.mmd")]`: it writes a Mermaid state diagram
+/// of the transitions (labeled with the methods) to the given path, relative to the crate, whenever the block is expanded,
+/// so that the drawings of the state machine are generated from the code instead of being maintained by hand.
+/// `#[impl_state(dot = "docs/player.dot")]` writes a Graphviz graph the same way, for the build pipelines using `dot`,
+/// and `#[impl_state(plantuml = "docs/player.puml")]` a PlantUML state diagram, for the design documents using PlantUML.
+/// The transition relation can be checked by the model checkers too: `#[impl_state(tla = "spec/player.tla")]` writes
+/// a TLA+ module (with `Init`, `Next`, `Spec`, and `TypeOK`) and `#[impl_state(smv = "spec/player.smv")]` a NuSMV module,
+/// in which each slot is a variable and each method an action (the input variable `method` in NuSMV),
+/// so that the safety and liveness properties of the protocol are checked against the machine encoded in the code.
+///
+/// The states can have hooks, which are the methods of the `impl` block declared with `#[on_enter]` and `#[on_exit]`:
+/// `#[on_enter(Connected)] fn log_connect(&self)` is called at the end of every transition of the block
+/// that enters `Connected` (the constructors included), and `#[on_exit(Connected)] fn log_leave(&self)`
+/// at the beginning of every transition that leaves it. The hooks take `&self` and can be called in any state,
+/// the branching transitions call the hooks of the state of the variant they return,
+/// the `Err` arm of a guarded transition calls the hooks of its `on_err` state (`Result<Self, Self>`, `Result<Self, (Self, Error)>`),
+/// the cross-type transitions call the exit hooks of the states they leave (the other struct has hooks of its own),
+/// and the generic states (`#[require(A)]`) call the hooks of the state they are in at runtime.
+/// Since the exit hooks are called before the body, a guarded transition that fails calls them too,
+/// and then the enter hooks of its `on_err` state, even if it is the state it left (like a transition into itself).
+/// The hooks of a state are declared in the block of its transitions, since each block is expanded on its own:
+/// a transition that enters or leaves a state whose hooks are declared in another block (like a `part` of the methods)
+/// fails to compile, instead of skipping them.
+///
+/// The method bodies can be property-tested along any legal path of the state machine with `#[impl_state(proptest)]`
+/// (with the `proptest` feature of `state-shift`, and `any_state = true` in `#[type_state]`): it generates the `player_proptest`
+/// module, with the `Step` enum (a variant for each method, with its arguments), the `steps(&player, 0..20)` strategy
+/// of the sequences of steps that can be taken one after another from the state of `player`,
+/// and `run_all(player, &steps)`, which takes them with the typed methods and returns the value in its final state.
+/// The steps are the methods that take `self` by value and return the struct (in a single state),
+/// and the ones that take a reference to it, whose results are dropped. Their arguments are generated with `Arbitrary`.
+///
+/// The transitions can be verified with the Kani model checker too, with `#[impl_state(kani)]`: it generates
+/// a `#[kani::proof]` harness for each method (the same ones as the steps of `proptest`) in each of the states it can be called in,
+/// which starts from any value in the state (`kani::any()`, so the struct implements `kani::Arbitrary` in any state),
+/// and checks the invariants of the states, which are the methods declared with `#[invariant]`:
+/// `#[invariant(Closed, Locked)] fn is_shut(&self) -> bool` is assumed to hold before a method called in `Closed` or `Locked`,
+/// and asserted after a method that ends up in them. The harnesses are only compiled with `#[cfg(kani)]` (by `cargo kani`),
+/// so the crate declares the `kani` cfg: `[lints.rust] unexpected_cfgs = { level = "warn", check-cfg = ['cfg(kani)'] }`.
+///
+/// Trait implementations can be gated on states as well, by putting `#[require]` on the `impl` block:
+/// `#[impl_state] #[require(Idle | Active)] impl Display for Player { ... }` implements `Display` for `Player<Idle>`
+/// and for `Player` in any state of the `Active` group (the struct initializations in its methods get the `_state` field too).
+///
+/// Type-state protocols can be defined as traits too, so that multiple structs (with a single slot) implement them:
+/// - `#[impl_state] trait Engine<Off, On> { #[require(Off)] #[switch_to(On)] fn start(self) -> Self; }`:
+///   the trait is generic over the states, and gets the `State` associated type (the current state of the implementor),
+///   plus an associated type for each target state (`Self::On`, the implementor in the `On` state),
+/// - `#[impl_state] impl Engine<Docked, Sailing> for Boat { ... }`: the implementor plugs its own states into the trait,
+///   and annotates the methods with `#[require]` and `#[switch_to]` just like the trait.
+///
+/// The other side of a client/server protocol can be generated as such a trait, with `#[impl_state(dual = ServerProtocol)]`:
+/// each transition of the `impl` block becomes its dual, in which the sends are receives and the receives are sends
+/// (`fn request(self, path: String) -> Self` -> `fn request(self) -> (Self, String)`, and
+/// `fn reply(self) -> (Self, u16)` -> `fn reply(self, payload: u16) -> Self`), so a struct that reuses the states
+/// implements `ServerProtocol<Idle, Waiting>`, and the two sides of the protocol cannot drift apart.
 #[proc_macro_attribute]
-pub fn impl_state(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    impl_state_inner(item)
+pub fn impl_state(attr: TokenStream, item: TokenStream) -> TokenStream {
+    dump_expansion("impl_state", item.clone(), forward_impl_state(attr, item))
+}
+
+/// Internal macro, not meant to be used directly.
+///
+/// `#[impl_state]` cannot see the arguments of `#[type_state]` on its own, so it expands into
+/// the hidden macro generated by `#[type_state]`, which in turn invokes this macro with:
+/// - the arguments of `#[type_state]` (in parentheses),
+/// - the generic parameters of the struct (in parentheses),
+/// - followed by the `impl` block.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __impl_state(input: TokenStream) -> TokenStream {
+    dump_expansion("impl_state", input.clone(), impl_state_inner(input))
+}
+
+/// Matches the enum of a struct in any of its states (generated with `any_state = true` in `#[type_state]`),
+/// with the arms given for the states instead of the variants.
+///
+/// Usage: `match_state!(value, Struct { State1(pattern) => ..., ... })`
+///
+/// - `Queued(job) => ...` matches a state, with `job` as `Job<Queued>`
+///   (the states are given by their unprefixed names, like the variants of the enum),
+/// - `Running(job) | Done(job) => job.payload()` matches several states (or the states of a group: `Active(job)`),
+///   and the body is expanded for each of them, so it works with each of the typed values,
+/// - `other => ...` matches the rest of the states, with `other` as the typed value of each of them,
+///   and `_ => ...` matches the rest of the states without their values.
+///
+/// It expands into a `match` of the variants, so the states that are not matched are reported like the variants:
+/// `match_state!(job, Job { Queued(job) => 0, Running(job) => 1 })`
+/// -> `match job { AnyJob::Queued(job) => 0, AnyJob::Running(job) => 1 }` (``pattern `AnyJob::Done(_)` not covered``)
+#[proc_macro]
+pub fn match_state(input: TokenStream) -> TokenStream {
+    forward_match_state(input)
+}
+
+/// Declares the transitions of a struct once, along with the methods that take them.
+///
+/// Usage: `transitions!(Player: Idle -> Running via start, Running -> Idle via stop)`
+///
+/// With `declared_transitions = true` in `#[type_state]`, each transition of the methods of `#[impl_state]`
+/// (from a state of `#[require]` to a state of `#[switch_to]`) has to be declared, or it fails to compile:
+/// ``the transition of `pause` from `Running` to `Paused` is not declared with `transitions!` ``.
+///
+/// - several states are given with `|`: `Idle | Paused -> Running via start`, and `_` is any state: `_ -> Idle via reset`,
+/// - the constructors and the methods that keep the states are not transitions, so they don't need to be declared,
+/// - it is given in the module of the `impl` blocks (like the markers), since they refer to it by the name of the struct,
+/// - the states are checked against the declared ones: ``unknown state `Runing`; did you mean `Running`?``,
+///   and the methods of `#[impl_state]` tell when it is missing:
+///   ```Player` has `declared_transitions = true`, so its transitions are declared with `transitions!(Player: ...)` ``.
+#[proc_macro]
+pub fn transitions(input: TokenStream) -> TokenStream {
+    forward_transitions(input)
+}
+
+/// Generates the compile-fail tests of the states of a struct, which call each method in the states it cannot be called in.
+///
+/// Usage: `generate_state_tests!(crate::player::Player)`, with `#[impl_state(transitions)]` on the `impl` block of the methods
+///
+/// The tests are the `compile_fail` doc tests of the generated `player_state_tests` module,
+/// so they are run by `cargo test --doc` (in a library crate), and they follow the `#[require]` of the methods as they change:
+///
+/// ```ignore
+/// fn start_in_running(mut value: my_crate::player::Player<my_crate::player::Running>) {
+///     let _ = value.start(todo!());
+/// }
+/// ```
+///
+/// - each method is called in one of the states it can be called in as well, which is expected to compile,
+///   so that the compile-fail tests don't pass because of a wrong path,
+/// - the tests are compiled as other crates, so the struct, its states and the methods are expected to be public,
+///   and only the public methods that take `self` without generic parameters are tested,
+/// - the sources of the compile-fail tests are given as `player_state_tests::COMPILE_FAIL` too, for `trybuild`.
+#[proc_macro]
+pub fn generate_state_tests(input: TokenStream) -> TokenStream {
+    forward_state_tests(input)
+}
+
+/// Internal macro, not meant to be used directly.
+///
+/// `generate_state_tests!` cannot see the methods of the struct on its own, so it expands into
+/// the hidden macro generated by `#[impl_state(transitions)]`, which in turn invokes this macro with:
+/// `[<the methods and their states>] <path of the struct>`
+#[doc(hidden)]
+#[proc_macro]
+pub fn __generate_state_tests(input: TokenStream) -> TokenStream {
+    state_tests_inner(input)
+}
+
+/// Internal macro, not meant to be used directly.
+///
+/// `transitions!` cannot see the states of the struct on its own, so it expands into
+/// the hidden macro generated by `#[type_state]`, which in turn invokes this macro with:
+/// `(<arguments of #[type_state]>) <input of transitions!>`
+#[doc(hidden)]
+#[proc_macro]
+pub fn __transitions(input: TokenStream) -> TokenStream {
+    transitions_inner(input)
+}
+
+/// Internal macro, not meant to be used directly.
+///
+/// `match_state!` cannot see the arguments of `#[type_state]` on its own, so it expands into
+/// the hidden macro generated by `#[type_state]`, which in turn invokes this macro with:
+/// `(<arguments of #[type_state]>) <input of match_state!>`
+#[doc(hidden)]
+#[proc_macro]
+pub fn __match_state(input: TokenStream) -> TokenStream {
+    match_state_inner(input)
+}
+
+/// Internal macro, not meant to be used directly.
+///
+/// `#[type_state(reuse = Article, ...)]` cannot see the states of `Article` on its own, so it expands into
+/// the hidden macro generated by `#[type_state]` for `Article`, which in turn invokes this macro with:
+/// `(<arguments of #[type_state] for Article>) (<arguments of #[type_state] for the struct>) struct ... { ... }`
+#[doc(hidden)]
+#[proc_macro]
+pub fn __type_state_reuse(input: TokenStream) -> TokenStream {
+    dump_expansion("type_state", input.clone(), reuse_states(input))
+}
+
+/// Internal macro, not meant to be used directly.
+///
+/// Like `__impl_state`, but for the implementations of the type-state protocols defined as traits:
+/// the hidden macro generated for the trait by `#[impl_state]` invokes this macro with:
+/// `(<states of the trait>) (<arguments of #[type_state]>) impl Trait<...> for Struct { ... }`
+#[doc(hidden)]
+#[proc_macro]
+pub fn __impl_state_trait(input: TokenStream) -> TokenStream {
+    dump_expansion(
+        "impl_state",
+        input.clone(),
+        impl_state_trait_inner(input).into(),
+    )
 }
 
 /// Denotes which state is required for this method to be called.
@@ -71,9 +713,16 @@ pub fn impl_state(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// Usage:
 /// - `#[require(State1)]`
 /// - or with multiple state slots: `#[require(State1, State2, ...)]`
+/// - or with alternative states for a slot: `#[require(State1 | State2, ...)]`
+/// - or with any state for a slot: `#[require(_, ...)]` (same as using a single letter generic like `A`)
+/// - or with any state except the given ones for a slot: `#[require(not(State1 | State2), ...)]`
+/// - or with any state of a group declared in `#[type_state]`: `#[require(Group1, ...)]`
+/// - or with any substate of a parent state: `#[require(Connected)]`, or a specific one: `#[require(Connected::Active)]`
+/// - or by addressing named slots: `#[require(auth = State1)]` (the slots that are not mentioned accept any state)
 ///
 /// This macro is consumed by the `#[impl_state]` macro, and it basically guides `#[impl_state]` macro to:
-/// - generate a specific `impl` block for each method,
+/// - generate a specific `impl` block for each combination of the required states (one for each alternative state),
+///   which is shared by the methods with the same requirements,
 /// - add the required types and generics to the generated `impl` blocks,
 /// - add the hidden `_state` field to the `Self { }` struct, so you don't have to worry about anything regarding type-state-pattern
 ///
@@ -83,10 +732,9 @@ pub fn impl_state(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// - generics
 /// - lifetimes
 #[proc_macro_attribute]
-pub fn require(_args: TokenStream, _input: TokenStream) -> TokenStream {
-    unreachable!(
-        "`#[require]` macro should not be imported. It is consumed by the `#[impl_state]` macro."
-    );
+pub fn require(_args: TokenStream, input: TokenStream) -> TokenStream {
+    // it is only expanded on its own if it is outside of an `impl` block with `#[impl_state]`
+    unconsumed_attribute("require", input.into()).into()
 }
 
 /// Denotes to which state will the object transition into after this method
@@ -94,16 +742,37 @@ pub fn require(_args: TokenStream, _input: TokenStream) -> TokenStream {
 /// Usage:
 /// - `#[switch_to(State1)]`
 /// - or with multiple state slots: `#[switch_to(State1, State2, ...)]`
+/// - or keeping the required state of a slot unchanged: `#[switch_to(_, State2, ...)]`
+/// - or by addressing named slots: `#[switch_to(conn = State2)]` (the slots that are not mentioned are kept unchanged)
+/// - or branching into one of several states at runtime: `#[switch_to(State1 | State2)]`.
+///   An enum named after the method is generated and returned instead of the struct
+///   (`fn connect` -> `ConnectOutcome { State1(Player<State1>), State2(Player<State2>) }`),
+///   and the method body constructs one of its variants.
+/// - or transitioning into another type-state struct: `#[switch_to(Connection<State1>)]`.
+///   `Connection` in the return type gets the given states (`-> Connection` -> `-> Connection<State1>`),
+///   and its initializations in the method body get the state field of `Connection` (whatever its `field` and its shape).
+/// - or on a constructor (an associated function without `self`) without `#[require]`: `#[switch_to(State1)] fn new() -> Self`.
+///   The constructor produces the struct in the given states (the slots that are not given get their default states).
+/// - or guarding a fallible transition: `#[switch_to(State2, on_err = State3)] fn connect(self) -> Result<Self, (Self, Error)>`.
+///   The `Ok` arm gets the new state, and the struct in the `Err` arm gets the state of `on_err`
+///   (`-> Result<Player<State2>, (Player<State3>, Error)>`), instead of keeping the original state.
+///
+/// With the `tracing` feature of `state-shift`, the transitions (the methods with `self`) run in a `transition` span
+/// of `tracing` at the `DEBUG` level, which records the struct, the method, and the states it switches from and to:
+/// `transition{struct="Player" method="start" from=Idle to=Running}` (the generic states are recorded at runtime).
+/// The crate that uses it depends on `tracing` itself.
 ///
 /// This macro is consumed by the `#[impl_state]` macro, and it basically guides `#[impl_state]` macro to:
 /// - overwrite the return type of the methods generated by the `#[impl_state]` macro
+///   (`Self` or the struct wherever it appears: `Option<Self>`, `Box<Self>`, `impl Future<Output = Self>`, ...,
+///   only the first element of the tuples that is the struct: `(Self, Report)`,
+///   and for fallible transitions, only the `Ok` variant of `Result<Self, Error>`, unless there is `on_err`)
 ///
 /// hence, it is empty, because it delegates its job to `#[impl_state]` macro
 /// the reason for that delegation is: `#[switch_to]` macro needs the below from the encapsulating `impl` block for the methods
 /// - name of the impl block (name of the struct)
 #[proc_macro_attribute]
-pub fn switch_to(_args: TokenStream, _input: TokenStream) -> TokenStream {
-    unreachable!(
-        "`#[switch_to]` macro should not be imported. It is consumed by the `#[impl_state]` macro."
-    );
+pub fn switch_to(_args: TokenStream, input: TokenStream) -> TokenStream {
+    // it is only expanded on its own if it is outside of an `impl` block with `#[impl_state]`
+    unconsumed_attribute("switch_to", input.into()).into()
 }
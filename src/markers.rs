@@ -0,0 +1,546 @@
+/// this file contains the set of the states of a struct, which is generated by the `#[type_state]` macro:
+/// the markers of the states, the sealer trait with their names, the public trait of the states, and the traits of the groups
+/// (the structs that reuse the states of another struct share its set instead)
+use proc_macro2::TokenStream;
+use quote::quote;
+use stringcase::snake_case;
+use syn::{punctuated::Punctuated, spanned::Spanned, GenericParam, Ident, Path, Token};
+
+use crate::{state_enum_name, OnlyInFields, StateGroup, TypeStateArgs};
+
+/// The name of the sealer trait of the states of the struct: `Player` -> `SealerPlayer`
+fn sealer_trait_name(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("Sealer{}", struct_name), struct_name.span())
+}
+
+/// The name of the private module of the sealed trait of the states: `Player` -> `sealed_player`
+fn sealed_mod_name(struct_name: &Ident) -> Ident {
+    Ident::new(
+        &format!("sealed_{}", snake_case(&struct_name.to_string())),
+        struct_name.span(),
+    )
+}
+
+/// `impl<T> Trait for Loaded<T>` for the generic states (gated along with the state)
+pub fn impl_for_state(
+    type_state_args: &TypeStateArgs,
+    trait_path: TokenStream,
+    state: &Ident,
+    items: TokenStream,
+) -> TokenStream {
+    let generics = type_state_args
+        .generics_of_state(state)
+        .cloned()
+        .unwrap_or_default();
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let cfgs = type_state_args.cfgs_of_state(state);
+    quote! {
+        #(#cfgs)*
+        impl #impl_generics #trait_path for #state #type_generics #where_clause {
+            #items
+        }
+    }
+}
+
+/// The derives of the markers: the ones given to the markers themselves (`derive(Clone, Hash)`),
+/// along with `Clone` for `derive_builder`, which bounds the generics of the builder with it, the states included
+///
+/// the states that are stored in the `_state` field are cloned and compared along with the struct,
+/// so the markers derive the replaced derives of the struct too (`Debug` shows the names of the states instead,
+/// and `Default` is implemented for the markers that don't carry data already)
+pub fn marker_derives(
+    type_state_args: &TypeStateArgs,
+    replaced_derives: &[Ident],
+    builder_derive: Option<&Ident>,
+) -> Vec<Path> {
+    let derive_name = |path: &Path| path.segments.last().map(|segment| segment.ident.clone());
+    let mut marker_derives: Vec<Path> = type_state_args.marker_derives.clone();
+    if builder_derive.is_some_and(|derive| derive == "Builder")
+        && !marker_derives
+            .iter()
+            .any(|path| derive_name(path).is_some_and(|name| name == "Clone"))
+    {
+        marker_derives.push(syn::parse_quote!(Clone));
+    }
+    if type_state_args.stores_states() {
+        for derive in replaced_derives
+            .iter()
+            .filter(|derive| *derive != "Debug" && *derive != "Default")
+        {
+            if !marker_derives
+                .iter()
+                .any(|path| derive_name(path).as_ref() == Some(derive))
+            {
+                marker_derives.push(derive.clone().into());
+            }
+        }
+    }
+    marker_derives
+}
+
+/// The doc comments of the marker of a state, with what is known about it in the declaration of the struct:
+/// the slots it is the default state of, its parent and groups, and where its methods are listed
+///
+/// (the methods themselves are not known here, since `#[type_state]` doesn't see the `impl` blocks)
+fn state_docs(state: &Ident, struct_name: &Ident, type_state_args: &TypeStateArgs) -> Vec<String> {
+    let mut docs = vec![format!(" The `{}` state of `{}`", state, struct_name)];
+
+    for (index, slot) in type_state_args.slots.iter().enumerate() {
+        if slot.default != *state {
+            continue;
+        }
+        docs.push(String::new());
+        docs.push(match &slot.name {
+            Some(name) => format!(" It is the default state of the `{}` slot.", name),
+            None if type_state_args.slots.len() == 1 => {
+                format!(" It is the default state of `{}`.", struct_name)
+            }
+            None => format!(" It is the default state of the slot {}.", index + 1),
+        });
+    }
+    if let Some(parent) = type_state_args.parent_of(state) {
+        docs.push(String::new());
+        docs.push(format!(" It is a substate of `{}`.", parent));
+    }
+    let groups: Vec<_> = type_state_args
+        .groups
+        .iter()
+        .filter(|group| group.states.contains(state))
+        .map(|group| format!("`{}`", group.name))
+        .collect();
+    if !groups.is_empty() {
+        docs.push(String::new());
+        docs.push(format!(" It is in the groups: {}.", groups.join(", ")));
+    }
+    if type_state_args.fields_of_state(state).is_some() {
+        docs.push(String::new());
+        docs.push(
+            " It carries data, which is accessible through the `state_data` accessor of its slot."
+                .to_string(),
+        );
+    }
+    docs.push(String::new());
+    docs.push(format!(
+        " The methods that require it, and the ones that transition into it, are in the `impl` blocks of `{}`.",
+        struct_name
+    ));
+    docs
+}
+
+/// The marker struct of a state (unless it is an external state, which is declared by the users):
+/// `pub struct Idle;`, `pub struct Loaded<T>(PhantomData<(fn() -> T,)>);`, or `pub struct Connected { session_id: u64 }`
+///
+/// the markers that don't carry data implement `Default` when the states are stored in the `_state` field,
+/// since the other states are initialized with it (see `state_field_expr`)
+fn marker(
+    state: &Ident,
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+    marker_derives: &[Path],
+) -> TokenStream {
+    let cfgs = type_state_args.cfgs_of_state(state);
+    let docs = state_docs(state, struct_name, type_state_args);
+    // the users don't have to name the generated items, so they can be hidden from the docs: `doc_hidden = true`
+    let doc_hidden = type_state_args.doc_hidden.then(|| quote!(#[doc(hidden)]));
+    let doc_hidden = quote!(#(#[doc = #docs])* #doc_hidden);
+    let marker_vis = &type_state_args.marker_vis;
+    let marker_name = Ident::new(&format!("{}", state), state.span());
+    let generics = type_state_args
+        .generics_of_state(state)
+        .cloned()
+        .unwrap_or_default();
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    // the markers that derive `Default` don't get the impl of the stored states
+    let derives_default = marker_derives.iter().any(|path| {
+        path.segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Default")
+    });
+    let marker_derives =
+        (!marker_derives.is_empty()).then(|| quote!(#[derive(#(#marker_derives),*)]));
+
+    if let Some(fields) = type_state_args.fields_of_state(state) {
+        return quote! {
+            #(#cfgs)*
+            #doc_hidden
+            #marker_derives
+            #marker_vis struct #marker_name #generics #where_clause #fields
+        };
+    }
+
+    let default_impl = |value| {
+        if type_state_args.stores_states() && !derives_default {
+            quote! {
+                #(#cfgs)*
+                impl #impl_generics ::core::default::Default for #marker_name #type_generics #where_clause {
+                    fn default() -> Self {
+                        #value
+                    }
+                }
+            }
+        } else {
+            quote! {}
+        }
+    };
+
+    if generics.params.is_empty() {
+        let default_impl = default_impl(quote!(Self));
+        return quote! {
+            #(#cfgs)*
+            #doc_hidden
+            #marker_derives
+            #marker_vis struct #marker_name;
+
+            #default_impl
+        };
+    }
+
+    // the generic states are still zero-sized, they only mark the type of their arguments
+    let phantom_types = generics.params.iter().filter_map(|param| match param {
+        GenericParam::Lifetime(lifetime) => {
+            let lifetime = &lifetime.lifetime;
+            Some(quote!(&#lifetime ()))
+        }
+        GenericParam::Type(type_param) => {
+            let ident = &type_param.ident;
+            Some(quote!(fn() -> #ident))
+        }
+        GenericParam::Const(_) => None,
+    });
+    let default_impl = default_impl(quote!(Self(::core::marker::PhantomData)));
+    quote! {
+        #(#cfgs)*
+        #doc_hidden
+        #marker_derives
+        #marker_vis struct #marker_name #generics (::core::marker::PhantomData<(#(#phantom_types,)*)>)
+        #where_clause;
+
+        #default_impl
+    }
+}
+
+/// The names of the states given by the markers themselves, for the markers that are generated:
+/// `Idle::STATE_NAME`, `format!("{}", Idle)`
+fn state_name_consts(state: &Ident, type_state_args: &TypeStateArgs) -> TokenStream {
+    let generics = type_state_args
+        .generics_of_state(state)
+        .cloned()
+        .unwrap_or_default();
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    let cfgs = type_state_args.cfgs_of_state(state);
+    let name = type_state_args.unprefixed_name(state).to_string();
+    quote! {
+        #(#cfgs)*
+        impl #impl_generics #state #type_generics #where_clause {
+            /// The name of the state
+            #[allow(dead_code)]
+            pub const STATE_NAME: &'static str = #name;
+        }
+
+        #(#cfgs)*
+        impl #impl_generics ::core::fmt::Display for #state #type_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(#name)
+            }
+        }
+    }
+}
+
+/// The marker trait of a group of states, implemented by each of its states:
+/// `pub trait Online: SealerConnection {}`, `impl Online for Connected {}`
+fn group_trait(
+    group: &StateGroup,
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+    only_in: &OnlyInFields,
+) -> TokenStream {
+    let StateGroup {
+        name,
+        states,
+        parent,
+    } = group;
+    let sealer_trait_name = sealer_trait_name(struct_name);
+    let marker_vis = &type_state_args.marker_vis;
+    let doc_hidden = type_state_args.doc_hidden.then(|| quote!(#[doc(hidden)]));
+    let group_impls = states
+        .iter()
+        .map(|state| impl_for_state(type_state_args, quote!(#name), state, quote!()));
+    let bounds = only_in
+        .group_bounds
+        .iter()
+        .filter(|(group, _)| group == name)
+        .map(|(_, bound)| bound);
+    // the group of a substate is also in the group of its parent: `Active: Connected`
+    let parent = parent.iter();
+    let cfgs = type_state_args.cfgs_of_state(name);
+    let doc = format!(
+        " The `{}` group of the states of `{}`: {}",
+        name,
+        struct_name,
+        states
+            .iter()
+            .map(|state| format!("`{}`", state))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    quote! {
+        #(#cfgs)*
+        #[doc = #doc]
+        #doc_hidden
+        #marker_vis trait #name: #sealer_trait_name #(+ #parent)* #(+ #bounds)* {}
+
+        #(#group_impls)*
+    }
+}
+
+/// Generates the set of the states of the struct, unless they are reused from another struct:
+///
+/// the markers, the private `Sealed` trait that only they implement (unless `sealed = false`,
+/// so the sealer trait can be implemented outside of the generated code as well),
+/// the sealer trait, which gives the names of the states (so that the struct can tell its state: `player.state_name()`),
+/// along with their variants of the enum of the states with `state_enum = true` (`player.current_state()`),
+/// the public trait of the states, for the code that is generic over any state of the struct:
+/// `fn describe<S: PlayerStateMarker>(player: &Player<S>) -> &'static str { S::NAME }`,
+/// and the traits of the groups
+pub fn state_set(
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+    marker_derives: &[Path],
+    only_in: &OnlyInFields,
+) -> syn::Result<TokenStream> {
+    if type_state_args.reuse.is_some() {
+        return match only_in.traits.first() {
+            Some(only_in_trait) => Err(syn::Error::new(
+                only_in_trait.span(),
+                "`#[only_in]` fields cannot be used with `reuse`, since the sealer of the states is shared",
+            )),
+            None => Ok(quote! {}),
+        };
+    }
+
+    let states = &type_state_args.states;
+    let marker_vis = &type_state_args.marker_vis;
+    let doc_hidden = type_state_args.doc_hidden.then(|| quote!(#[doc(hidden)]));
+    let sealer_trait_name = sealer_trait_name(struct_name);
+    let sealed_mod_name = sealed_mod_name(struct_name);
+    let state_enum_name = state_enum_name(struct_name);
+
+    let markers = states
+        .iter()
+        .filter(|state| !type_state_args.external_states.contains(state))
+        .map(|state| marker(state, struct_name, type_state_args, marker_derives));
+    let state_name_consts = states
+        .iter()
+        .filter(|state| !type_state_args.external_states.contains(state))
+        .map(|state| state_name_consts(state, type_state_args));
+    let trait_impls = states.iter().map(|state| {
+        let name = type_state_args.unprefixed_name(state).to_string();
+        let variant = type_state_args.state_enum.then(|| {
+            let variant = type_state_args.unprefixed_name(state);
+            quote!(const STATE: #state_enum_name = #state_enum_name::#variant;)
+        });
+        impl_for_state(
+            type_state_args,
+            quote!(#sealer_trait_name),
+            state,
+            quote! {
+                const STATE_NAME: &'static str = #name;
+                #variant
+            },
+        )
+    });
+    let group_traits = type_state_args
+        .groups
+        .iter()
+        .map(|group| group_trait(group, struct_name, type_state_args, only_in));
+
+    let sealing = if type_state_args.sealed {
+        let sealed_impls = states.iter().map(|state| {
+            impl_for_state(
+                type_state_args,
+                quote!(#sealed_mod_name::Sealed),
+                state,
+                quote!(),
+            )
+        });
+        quote! {
+            mod #sealed_mod_name {
+                pub trait Sealed {}
+            }
+
+            #(#sealed_impls)*
+        }
+    } else {
+        quote! {}
+    };
+    let sealer_bounds: Vec<_> = type_state_args
+        .sealed
+        .then(|| quote!(#sealed_mod_name::Sealed))
+        .into_iter()
+        .chain(
+            only_in
+                .traits
+                .iter()
+                .map(|only_in_trait| quote!(#only_in_trait)),
+        )
+        .collect();
+    let sealer_colon = (!sealer_bounds.is_empty()).then(<Token![:]>::default);
+
+    let sealer_doc = format!(" The trait of the states of `{}`", struct_name);
+    let state_marker_name = Ident::new(&format!("{}StateMarker", struct_name), struct_name.span());
+    let state_marker_doc = format!(
+        " Implemented by all the states of `{}`, so the code can be generic over any of its states",
+        struct_name
+    );
+    // the states implemented by hand (when they are not sealed) can give their names too, or they are unknown
+    let state_name_default = (!type_state_args.sealed).then(|| quote!(= "unknown"));
+    // the states implemented by hand give their variants too, since there is no variant for the unknown states
+    let state_enum_const = type_state_args.state_enum.then(|| {
+        quote! {
+            /// The variant of the state in the enum of the states
+            const STATE: #state_enum_name;
+        }
+    });
+
+    Ok(quote! {
+        #sealing
+
+        #[doc = #sealer_doc]
+        #doc_hidden
+        pub trait #sealer_trait_name #sealer_colon #(#sealer_bounds)+* {
+            /// The name of the state
+            const STATE_NAME: &'static str #state_name_default;
+
+            #state_enum_const
+        }
+
+        #[doc = #state_marker_doc]
+        #doc_hidden
+        #marker_vis trait #state_marker_name: #sealer_trait_name {
+            /// The name of the state
+            const NAME: &'static str;
+        }
+
+        impl<S: #sealer_trait_name> #state_marker_name for S {
+            const NAME: &'static str = <S as #sealer_trait_name>::STATE_NAME;
+        }
+
+        #(#markers)*
+
+        #(#state_name_consts)*
+
+        #(#trait_impls)*
+
+        #(#group_traits)*
+    })
+}
+
+/// Appends the states of `#[impl_state(extend = (Maintenance))]` to the states of the struct, for the methods of the block,
+/// and generates their markers: `pub struct Maintenance;`, `impl SealerPlayer for Maintenance`
+///
+/// the rest of the set is generated by `#[type_state]` already, so the new states are checked against it,
+/// and the sealer (along with the sealed trait) is named by the path of the struct in the `impl` block,
+/// which is in the module of the struct then (or in a module inside it, which sees its private sealed trait)
+pub fn extend_states(
+    new_states: &Punctuated<Ident, Token![,]>,
+    struct_path: &Path,
+    type_state_args: &mut TypeStateArgs,
+) -> syn::Result<TokenStream> {
+    let struct_name = &struct_path.segments.last().unwrap().ident;
+    let unsupported = if let Some(reuse) = &type_state_args.reuse {
+        Some(format!(
+            "the states of `{}` are reused from `{}`, so they are extended by an `impl` block of `{}`",
+            struct_name,
+            quote!(#reuse),
+            quote!(#reuse)
+        ))
+    } else if type_state_args.stores_states() {
+        Some("the states that carry data are stored by the struct, which cannot store the new states".to_string())
+    } else if type_state_args.state_enum {
+        Some(
+            "the enum of the states (`state_enum = true`) has no variants for the new states"
+                .to_string(),
+        )
+    } else if type_state_args.any_state {
+        Some("the enum of the struct in any state (`any_state = true`) has no variants for the new states".to_string())
+    } else if type_state_args.serde.is_some() {
+        Some("the struct is (de)serialized in its declared states only (`serde`)".to_string())
+    } else if type_state_args.prefix.is_some() {
+        Some(
+            "the names of the states are prefixed (`prefix`), which the new states are not"
+                .to_string(),
+        )
+    } else if type_state_args
+        .slots
+        .iter()
+        .any(|slot| slot.states.is_some() || slot.component.is_some())
+    {
+        Some(
+            "the slots are given their own states (or components), which the new states are not in"
+                .to_string(),
+        )
+    } else {
+        None
+    };
+    if let Some(unsupported) = unsupported {
+        return Err(syn::Error::new(
+            new_states.span(),
+            format!(
+                "the states of `{}` cannot be extended: {}",
+                struct_name, unsupported
+            ),
+        ));
+    }
+
+    let mut appended: Vec<Ident> = Vec::new();
+    for state in new_states {
+        let declared = type_state_args.states.contains(state)
+            || type_state_args
+                .groups
+                .iter()
+                .any(|group| group.name == *state);
+        if declared || appended.contains(state) {
+            return Err(syn::Error::new(
+                state.span(),
+                format!(
+                    "the state `{}` is declared by `{}` already, so it cannot be appended to its states",
+                    state, struct_name
+                ),
+            ));
+        }
+        appended.push(state.clone());
+    }
+    type_state_args.states.extend(appended.iter().cloned());
+
+    let mut module = struct_path.clone();
+    module.segments.pop();
+    let module = module.segments.iter().map(|segment| &segment.ident);
+    let module = quote!(#(#module::)*);
+    let sealer_trait_name = sealer_trait_name(struct_name);
+    let sealed_mod_name = sealed_mod_name(struct_name);
+    let markers = appended.iter().map(|state| {
+        let marker = marker(
+            state,
+            struct_name,
+            type_state_args,
+            &type_state_args.marker_derives,
+        );
+        let state_name_consts = state_name_consts(state, type_state_args);
+        let name = state.to_string();
+        let sealed = type_state_args
+            .sealed
+            .then(|| quote!(impl #module #sealed_mod_name::Sealed for #state {}));
+        quote! {
+            #marker
+
+            #state_name_consts
+
+            #sealed
+
+            impl #module #sealer_trait_name for #state {
+                const STATE_NAME: &'static str = #name;
+            }
+        }
+    });
+    Ok(quote!(#(#markers)*))
+}
@@ -0,0 +1,239 @@
+/// this file contains the logic of the `match_state!` macro, which matches the enum of a struct in any of its states
+/// (see `any_state_enum`), with the arms given for the states instead of the variants
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream},
+    parse_macro_input, Arm, Expr, Ident, Pat, Path, Token,
+};
+
+use crate::{any_state_enum_name, callback_macro_name, closest_name, TypeStateArgs};
+
+/// Input of the `match_state!` macro: `match_state!(job, Job { Queued(job) => ..., other => ... })`
+struct MatchStateInput {
+    value: Expr,
+    struct_path: Path,
+    arms: Vec<Arm>,
+}
+
+impl Parse for MatchStateInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let value = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let struct_path = input.parse()?;
+        let content;
+        braced!(content in input);
+        let mut arms = Vec::new();
+        while !content.is_empty() {
+            arms.push(content.parse()?);
+        }
+        Ok(MatchStateInput {
+            value,
+            struct_path,
+            arms,
+        })
+    }
+}
+
+/// Input of the internal `__match_state` macro:
+/// `(<arguments of #[type_state]>) <input of match_state!>`
+struct MatchStateCallbackInput {
+    type_state_args: TypeStateArgs,
+    input: MatchStateInput,
+}
+
+impl Parse for MatchStateCallbackInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        parenthesized!(content in input);
+        Ok(MatchStateCallbackInput {
+            type_state_args: content.parse()?,
+            input: input.parse()?,
+        })
+    }
+}
+
+/// Forwards `match_state!` to the hidden macro generated by `#[type_state]` for the struct,
+/// which carries the arguments of `#[type_state]` back to it (see `__match_state`), like `#[impl_state]`
+///
+/// `match_state!(job, path::to::Job { ... })` -> `path::to::__state_shift_job! { @match job, path::to::Job { ... } }`
+pub fn forward_match_state(input: TokenStream) -> TokenStream {
+    let tokens = proc_macro2::TokenStream::from(input.clone());
+    let MatchStateInput { struct_path, .. } = parse_macro_input!(input as MatchStateInput);
+
+    let mut callback_path = struct_path;
+    let last_segment = callback_path.segments.last_mut().unwrap();
+    last_segment.ident = callback_macro_name(&last_segment.ident);
+    last_segment.arguments = syn::PathArguments::None;
+
+    quote! {
+        #callback_path! { @match #tokens }
+    }
+    .into()
+}
+
+/// Expands `match_state!` into the `match` of the enum of the struct in any of its states:
+///
+/// `match_state!(job, Job { Queued(job) => ..., Running(job) | Done(job) => ..., })`
+/// -> `match job { AnyJob::Queued(job) => ..., AnyJob::Running(job) => ..., AnyJob::Done(job) => ..., }`
+///
+/// the arms of multiple states (or groups) are expanded into an arm for each of them,
+/// so that their bodies are checked with each of the typed values,
+/// and the arms with a binding (`other => ...`) stand for each of the states not matched before them,
+/// while `_ => ...` is kept as it is (the compiler checks the exhaustiveness of the match as usual)
+pub fn match_state_inner(input: TokenStream) -> TokenStream {
+    let MatchStateCallbackInput {
+        type_state_args,
+        input:
+            MatchStateInput {
+                value,
+                mut struct_path,
+                arms,
+            },
+    } = parse_macro_input!(input as MatchStateCallbackInput);
+
+    if !type_state_args.any_state {
+        return syn::Error::new_spanned(
+            &struct_path,
+            "`match_state!` matches the enum generated with `any_state = true` in `#[type_state]`",
+        )
+        .to_compile_error()
+        .into();
+    }
+    let last_segment = struct_path.segments.last_mut().unwrap();
+    last_segment.ident = any_state_enum_name(&last_segment.ident);
+    last_segment.arguments = syn::PathArguments::None;
+    let enum_path = struct_path;
+
+    let mut matched: Vec<Ident> = Vec::new();
+    let mut expanded_arms = Vec::new();
+    for arm in arms {
+        let states = match arm_states(&arm.pat, &type_state_args) {
+            Ok(states) => states,
+            Err(error) => return error.to_compile_error().into(),
+        };
+        let Arm {
+            attrs,
+            pat,
+            guard,
+            body,
+            ..
+        } = arm;
+        let guard = guard.map(|(if_token, guard)| quote!(#if_token #guard));
+
+        let Some(states) = states else {
+            // the binding stands for the states that are not matched before it, as their typed values
+            if let Pat::Ident(_) = pat {
+                for state in type_state_args.states_of_slot(0) {
+                    if matched.contains(state) {
+                        continue;
+                    }
+                    let cfgs = type_state_args.cfgs_of_state(state);
+                    let variant = type_state_args.unprefixed_name(state);
+                    expanded_arms.push(quote! {
+                        #(#attrs)* #(#cfgs)*
+                        #enum_path::#variant(#pat) #guard => #body,
+                    });
+                }
+            } else {
+                expanded_arms.push(quote!(#(#attrs)* #pat #guard => #body,));
+            }
+            continue;
+        };
+
+        for (state, elems) in states {
+            let cfgs = type_state_args.cfgs_of_state(&state);
+            let variant = type_state_args.unprefixed_name(&state);
+            expanded_arms.push(quote! {
+                #(#attrs)* #(#cfgs)*
+                #enum_path::#variant(#elems) #guard => #body,
+            });
+            // the guarded arms may not match, so the states are still matched by the bindings after them
+            if guard.is_none() {
+                matched.push(state);
+            }
+        }
+    }
+
+    quote! {
+        match #value {
+            #(#expanded_arms)*
+        }
+    }
+    .into()
+}
+
+/// The states of an arm, along with the patterns of their typed values: `Running(job) | Done(job)`,
+/// where the groups stand for each of their states: `Active(job)`
+///
+/// `None` for the arms of the other states: `other`, `_`
+fn arm_states(
+    pat: &Pat,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<Option<Vec<(Ident, proc_macro2::TokenStream)>>> {
+    let cases: Vec<&Pat> = match pat {
+        Pat::Ident(pat_ident) if pat_ident.subpat.is_none() => return Ok(None),
+        Pat::Wild(_) => return Ok(None),
+        Pat::Or(pat_or) => pat_or.cases.iter().collect(),
+        pat => vec![pat],
+    };
+
+    let allowed = type_state_args.states_of_slot(0);
+    let mut states = Vec::new();
+    for case in cases {
+        let Pat::TupleStruct(pat_tuple_struct) = case else {
+            return Err(syn::Error::new_spanned(
+                case,
+                "expected a state along with the pattern of its value: `Running(job)`, or a binding for the other states",
+            ));
+        };
+        let Some(given) = pat_tuple_struct.path.get_ident() else {
+            return Err(syn::Error::new_spanned(
+                &pat_tuple_struct.path,
+                "expected the name of a state",
+            ));
+        };
+        let elems = &pat_tuple_struct.elems;
+        let elems = quote!(#elems);
+
+        // the states are given by their unprefixed names, like the variants
+        let name = type_state_args.resolve_name(given);
+        if let Some(group) = type_state_args
+            .groups
+            .iter()
+            .find(|group| group.name == name)
+        {
+            states.extend(
+                group
+                    .states
+                    .iter()
+                    .map(|state| (state.clone(), elems.clone())),
+            );
+            continue;
+        }
+        if allowed.contains(&name) {
+            states.push((name, elems));
+            continue;
+        }
+
+        let variants: Vec<Ident> = allowed
+            .iter()
+            .map(|state| type_state_args.unprefixed_name(state))
+            .collect();
+        let message = match closest_name(given, &variants) {
+            Some(closest) => format!("unknown state `{}`; did you mean `{}`?", given, closest),
+            None => format!(
+                "unknown state `{}`, expected one of the declared states: {}",
+                given,
+                variants
+                    .iter()
+                    .map(|variant| format!("`{}`", variant))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        return Err(syn::Error::new(given.span(), message));
+    }
+    Ok(Some(states))
+}
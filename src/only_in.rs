@@ -0,0 +1,208 @@
+/// this file contains the logic of the fields that exist only in certain states: `#[only_in(Connected)] socket: TcpStream`,
+/// which is used by the `#[type_state]` macro
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use stringcase::pascal_case;
+use syn::{punctuated::Punctuated, spanned::Spanned, Field, GenericParam, Generics, Ident, Token};
+
+use crate::{closest_name, generic_args, strip_generic_defaults, TypeStateArgs};
+
+/// The fields of the struct that are declared with `#[only_in(...)]`
+pub struct OnlyInFields {
+    /// the hidden traits that pick the type of each field for the states, which are the supertraits of the sealer trait
+    pub traits: Vec<Ident>,
+    /// the bounds of the groups whose states all have the fields: `Online: __ConnectionAttemptsIn<Type<u32> = u32>`,
+    /// so that the fields have their declared types in the methods that require the groups as well
+    pub group_bounds: Vec<(Ident, TokenStream)>,
+    /// the hidden traits with their implementations for the states, and the accessors of the fields
+    pub items: TokenStream,
+}
+
+/// Name of the hidden trait that picks the type of a field for the states: `socket` of `Connection` -> `__ConnectionSocketIn`
+fn field_trait_name(struct_name: &Ident, field_name: &Ident) -> Ident {
+    Ident::new(
+        &format!(
+            "__{}{}In",
+            struct_name,
+            pascal_case(&field_name.to_string())
+        ),
+        field_name.span(),
+    )
+}
+
+/// Turns the fields that are declared with `#[only_in(State1 | State2)]` into fields whose type depends on the state:
+/// the declared type in the given states, and `()` in the other states
+///
+/// `#[only_in(Connected)] socket: TcpStream` -> `socket: <ConnectionState1 as __ConnectionSocketIn>::Type<TcpStream>`
+///
+/// so the field can only be used as `TcpStream` in the `Connected` state, and it is initialized with `()` in the others,
+/// the accessors `socket()` and `socket_mut()` are generated for the `Connected` state as well
+pub fn only_in_fields(
+    struct_name: &Ident,
+    generics: &Generics,
+    fields: &mut Punctuated<Field, Token![,]>,
+    state_idents: &[Ident],
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<OnlyInFields> {
+    let mut traits = Vec::new();
+    let mut group_bounds = Vec::new();
+    let mut items = Vec::new();
+
+    for field in fields.iter_mut() {
+        let Some(position) = field
+            .attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("only_in"))
+        else {
+            continue;
+        };
+        let attr = field.attrs.remove(position);
+        let Some(field_name) = field.ident.clone() else {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`#[only_in]` can only be used on the named fields",
+            ));
+        };
+
+        // the states can be given as the groups too (by their unprefixed names, if the markers are prefixed)
+        let given: Punctuated<Ident, Token![|]> =
+            attr.parse_args_with(Punctuated::parse_separated_nonempty)?;
+        let mut only_in = Vec::new();
+        for state in given
+            .iter()
+            .map(|state| type_state_args.resolve_name(state))
+        {
+            match type_state_args
+                .groups
+                .iter()
+                .find(|group| group.name == state)
+            {
+                Some(group) => only_in.extend(group.states.iter().cloned()),
+                None if type_state_args.states.contains(&state) => only_in.push(state),
+                None => {
+                    let suggestion = closest_name(&state, type_state_args.names())
+                        .map(|closest| format!("; did you mean `{}`?", closest))
+                        .unwrap_or_default();
+                    return Err(syn::Error::new(
+                        state.span(),
+                        format!(
+                            "`{}` is not one of the declared states or groups{}",
+                            state, suggestion
+                        ),
+                    ));
+                }
+            }
+        }
+
+        // the slot that tracks the given states
+        let slot = (0..state_idents.len())
+            .find(|&index| {
+                let allowed = type_state_args.states_of_slot(index);
+                only_in.iter().all(|state| allowed.contains(state))
+            })
+            .ok_or_else(|| {
+                syn::Error::new(
+                    attr.path().span(),
+                    "the states of `#[only_in]` have to be allowed in the same slot",
+                )
+            })?;
+
+        let field_trait = field_trait_name(struct_name, &field_name);
+        let field_type = field.ty.clone();
+        let slot_generic = &state_idents[slot];
+        field.ty = syn::parse_quote!(<#slot_generic as #field_trait>::Type<#field_type>);
+
+        // the group traits cannot refer to the generics of the struct
+        if !mentions_generics(quote!(#field_type), generics) {
+            for group in &type_state_args.groups {
+                if group.states.iter().all(|state| only_in.contains(state)) {
+                    group_bounds.push((
+                        group.name.clone(),
+                        quote!(#field_trait<Type<#field_type> = #field_type>),
+                    ));
+                }
+            }
+        }
+
+        let state_impls = type_state_args.states.iter().map(|state| {
+            let state_generics = type_state_args
+                .generics_of_state(state)
+                .cloned()
+                .unwrap_or_default();
+            let (impl_generics, type_generics, where_clause) = state_generics.split_for_impl();
+            let field_type = if only_in.contains(state) {
+                quote!(Field)
+            } else {
+                quote!(())
+            };
+            let cfgs = type_state_args.cfgs_of_state(state);
+            quote! {
+                #(#cfgs)*
+                impl #impl_generics #field_trait for #state #type_generics #where_clause {
+                    type Type<Field> = #field_type;
+                }
+            }
+        });
+
+        // the accessors are implemented for the states in which the field has its declared type
+        let accessor_mut = Ident::new(&format!("{}_mut", field_name), field_name.span());
+        let original_params = strip_generic_defaults(&generics.params);
+        let original_params = original_params.iter();
+        let original_args = generic_args(&generics.params);
+        let original_where_clause = &generics.where_clause;
+        let slot_bounds = state_idents.iter().enumerate().map(|(index, ident)| {
+            let sealer_trait_name = type_state_args.sealer_of_slot(index, struct_name);
+            if index == slot {
+                quote!(#ident: #sealer_trait_name + #field_trait<Type<#field_type> = #field_type>)
+            } else {
+                quote!(#ident: #sealer_trait_name)
+            }
+        });
+        let accessors = quote! {
+            impl<#(#original_params,)* #(#slot_bounds),*> #struct_name<#(#original_args,)* #(#state_idents),*>
+            #original_where_clause
+            {
+                #[allow(dead_code)]
+                pub fn #field_name(&self) -> &#field_type {
+                    &self.#field_name
+                }
+
+                #[allow(dead_code)]
+                pub fn #accessor_mut(&mut self) -> &mut #field_type {
+                    &mut self.#field_name
+                }
+            }
+        };
+
+        items.push(quote! {
+            #[doc(hidden)]
+            pub trait #field_trait {
+                type Type<Field>;
+            }
+
+            #(#state_impls)*
+
+            #accessors
+        });
+        traits.push(field_trait);
+    }
+
+    Ok(OnlyInFields {
+        traits,
+        group_bounds,
+        items: quote! { #(#items)* },
+    })
+}
+
+/// Whether the tokens of a type mention the generic parameters of the struct
+fn mentions_generics(tokens: TokenStream, generics: &Generics) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => generics.params.iter().any(|param| match param {
+            GenericParam::Type(type_param) => type_param.ident == ident,
+            GenericParam::Const(const_param) => const_param.ident == ident,
+            GenericParam::Lifetime(lifetime) => lifetime.lifetime.ident == ident,
+        }),
+        TokenTree::Group(group) => mentions_generics(group.stream(), generics),
+        _ => false,
+    })
+}
@@ -0,0 +1,258 @@
+/// this file contains the methods that move a struct into other states outside of its transitions,
+/// which are generated by the `#[type_state]` macro when they are opted into:
+/// the `into_state` escape hatch, the `reset` method, and the `transition_in_place` helper
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{punctuated::Punctuated, Field, Fields, Generics, Ident, Token, Variant};
+
+use crate::{cfg_attrs, generic_args, strip_generic_defaults, TypeStateArgs};
+
+/// Checks that `into_state` and `reset` can be generated for the struct:
+/// they move the fields into the struct in other states, and create the `_state` field with `Default`,
+/// so none of the other fields can depend on the states
+fn check_rebuilders(
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+    has_only_in: bool,
+) -> syn::Result<()> {
+    let checked_options = [
+        (type_state_args.into_state, "into_state"),
+        (type_state_args.reset, "reset"),
+    ];
+    for (_, option) in checked_options.iter().filter(|(enabled, _)| *enabled) {
+        let error = if *option == "into_state" && !cfg!(feature = "into_state") {
+            Some(
+                "`into_state` needs the `into_state` feature of `state-shift`: \
+                 `state-shift = { version = \"..\", features = [\"into_state\"] }`"
+                    .to_string(),
+            )
+        } else if *option == "into_state" && type_state_args.stores_states() {
+            Some(
+                "`into_state` cannot be used with the states that carry data, \
+                 since they cannot be created from the other states"
+                    .to_string(),
+            )
+        } else if *option == "reset"
+            && type_state_args
+                .slots
+                .iter()
+                .any(|slot| type_state_args.fields_of_state(&slot.default).is_some())
+        {
+            Some(
+                "`reset` cannot be used when the default states carry data, \
+                 since they cannot be created from the other states"
+                    .to_string(),
+            )
+        } else if has_only_in {
+            Some(format!(
+                "`{}` cannot be used with the `#[only_in]` fields, since they depend on the states",
+                option
+            ))
+        } else if type_state_args
+            .slots
+            .iter()
+            .any(|slot| slot.component.is_some())
+        {
+            Some(format!(
+                "`{}` cannot be used with the components, since they are in the states of their slots",
+                option
+            ))
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            return Err(syn::Error::new(struct_name.span(), error));
+        }
+    }
+    Ok(())
+}
+
+/// The body that moves the fields into the struct in other states, whose `_state` field is created with `Default`:
+/// `let Self { 0: field_0, name: field_1, _state: _ } = self; Player { 0: field_0, name: field_1, _state: Default::default() }`
+///
+/// the fields are moved by their members, which works for the tuple structs too: `Self { 0: field_0, .. }`,
+/// and the enums move the fields of each of their variants
+fn moved_into_states(
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+    struct_fields: &Punctuated<Field, Token![,]>,
+    tuple_fields: Option<&Punctuated<Field, Token![,]>>,
+    enum_variants: Option<&Punctuated<Variant, Token![,]>>,
+) -> TokenStream {
+    let state_field = &type_state_args.field;
+    let moved_fields = |fields: &Fields| {
+        let members: Vec<_> = fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let cfgs: Vec<_> = cfg_attrs(&field.attrs).collect();
+                let member = match &field.ident {
+                    Some(ident) => quote!(#ident),
+                    None => {
+                        let index = syn::Index::from(index);
+                        quote!(#index)
+                    }
+                };
+                let binding = Ident::new(&format!("field_{}", index), struct_name.span());
+                quote!(#(#cfgs)* #member: #binding)
+            })
+            .collect();
+        let state_member = match fields {
+            Fields::Unnamed(fields) => {
+                let index = syn::Index::from(fields.unnamed.len());
+                quote!(#index)
+            }
+            _ => quote!(#state_field),
+        };
+        (
+            quote!({ #(#members,)* #state_member: _ }),
+            quote!({ #(#members,)* #state_member: ::core::default::Default::default() }),
+        )
+    };
+    match (enum_variants, tuple_fields) {
+        (Some(variants), _) => {
+            let arms = variants.iter().map(|variant| {
+                let cfgs = cfg_attrs(&variant.attrs);
+                let name = &variant.ident;
+                let (pattern, rebuilt) = moved_fields(&variant.fields);
+                quote!(#(#cfgs)* Self::#name #pattern => #struct_name::#name #rebuilt,)
+            });
+            quote!(match self { #(#arms)* })
+        }
+        (None, Some(fields)) => {
+            let (pattern, rebuilt) = moved_fields(&Fields::Unnamed(syn::FieldsUnnamed {
+                paren_token: Default::default(),
+                unnamed: fields.clone(),
+            }));
+            quote! {
+                let Self #pattern = self;
+                #struct_name #rebuilt
+            }
+        }
+        (None, None) => {
+            let (pattern, rebuilt) = moved_fields(&Fields::Named(syn::FieldsNamed {
+                brace_token: Default::default(),
+                named: struct_fields.clone(),
+            }));
+            quote! {
+                let Self #pattern = self;
+                #struct_name #rebuilt
+            }
+        }
+    }
+}
+
+/// Generates the `into_state` escape hatch, if it is opted into (with the `into_state` feature of `state-shift`),
+/// which moves the fields into the struct in any other states: `unsafe { player.into_state::<Running>() }`
+///
+/// and the `reset` method, which moves them into the struct in its default states: `player.reset()`
+#[allow(clippy::too_many_arguments)]
+pub fn state_rebuilders(
+    struct_name: &Ident,
+    generics: &Generics,
+    state_idents: &[Ident],
+    type_state_args: &TypeStateArgs,
+    struct_fields: &Punctuated<Field, Token![,]>,
+    tuple_fields: Option<&Punctuated<Field, Token![,]>>,
+    enum_variants: Option<&Punctuated<Variant, Token![,]>>,
+    has_only_in: bool,
+) -> syn::Result<Option<TokenStream>> {
+    check_rebuilders(struct_name, type_state_args, has_only_in)?;
+    if !type_state_args.into_state && !type_state_args.reset {
+        return Ok(None);
+    }
+
+    let moved_into_states = moved_into_states(
+        struct_name,
+        type_state_args,
+        struct_fields,
+        tuple_fields,
+        enum_variants,
+    );
+    let original_params = strip_generic_defaults(&generics.params);
+    let original_params = original_params.iter();
+    let original_args = generic_args(&generics.params);
+    let original_where_clause = &generics.where_clause;
+    let default_slots = type_state_args.slots.iter().map(|slot| &slot.default);
+    let sealers: Vec<_> = (0..type_state_args.slots.len())
+        .map(|index| type_state_args.sealer_of_slot(index, struct_name))
+        .collect();
+    let target_idents: Vec<_> = state_idents
+        .iter()
+        .map(|state_ident| Ident::new(&format!("{}Into", state_ident), state_ident.span()))
+        .collect();
+    let into_state = type_state_args.into_state.then(|| {
+        quote! {
+            /// Moves the struct into the given states, without a transition
+            ///
+            /// # Safety
+            ///
+            /// it skips the checks of the transitions, so the caller makes sure that the struct is valid
+            /// in the given states (for the recovery paths, or the values that come from FFI)
+            #[allow(dead_code)]
+            pub unsafe fn into_state<#(#target_idents: #sealers),*>(
+                self,
+            ) -> #struct_name<#(#original_args,)* #(#target_idents),*> {
+                #moved_into_states
+            }
+        }
+    });
+    let reset = type_state_args.reset.then(|| {
+        quote! {
+            /// Moves the struct back into its default states, from any of its states
+            #[allow(dead_code)]
+            pub fn reset(self) -> #struct_name<#(#original_args,)* #(#default_slots),*> {
+                #moved_into_states
+            }
+        }
+    });
+    Ok(Some(quote! {
+        impl<#(#original_params,)* #(#state_idents: #sealers),*>
+            #struct_name<#(#original_args,)* #(#state_idents),*>
+        #original_where_clause
+        {
+            #into_state
+
+            #reset
+        }
+    }))
+}
+
+/// Generates the `transition_in_place` helper, if it is opted into with `in_place = true`
+///
+/// it is implemented for the default states only, so that it can be called without specifying the states
+pub fn in_place_helper(
+    struct_name: &Ident,
+    generics: &Generics,
+    type_state_args: &TypeStateArgs,
+) -> TokenStream {
+    if !type_state_args.in_place {
+        return quote! {};
+    }
+
+    let original_params = strip_generic_defaults(&generics.params);
+    let original_params = original_params.iter();
+    let original_args = generic_args(&generics.params);
+    let original_where_clause = &generics.where_clause;
+    let default_slots = type_state_args.slots.iter().map(|slot| &slot.default);
+    quote! {
+        impl<#(#original_params),*> #struct_name<#(#original_args,)* #(#default_slots),*>
+        #original_where_clause
+        {
+            /// Transitions the object stored in `handle` (which is usually a field of another struct), in place
+            ///
+            /// since the type of the object changes with its state, `handle` is anything that can hold the object
+            /// in all of its states (like an enum of them), and it holds `placeholder` while `transition` consumes
+            /// its content by value, then the result of `transition` is stored back in it
+            #[allow(dead_code)]
+            pub fn transition_in_place<Handle>(
+                handle: &mut Handle,
+                placeholder: Handle,
+                transition: impl FnOnce(Handle) -> Handle,
+            ) {
+                let current = ::core::mem::replace(handle, placeholder);
+                *handle = transition(current);
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+/// this file contains the registry of the states, which is the module that re-exports the struct and its states
+/// for the `impl` blocks in the other modules (and files) of the crate: `registry = player_states`,
+/// so that they name the struct by its path instead of importing its states (and its hidden sealer) one by one
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Ident, Path, Type, Visibility};
+
+use crate::{hooks_type, no_hooks_trait, TypeStateArgs};
+
+/// The visibility of an item re-exported from the registry, which is one module deeper than the item itself:
+/// the private items are visible in the module of the struct, so they are re-exported with `pub(super)`
+fn reexported_vis(vis: &Visibility) -> TokenStream {
+    match vis {
+        Visibility::Inherited => quote!(pub(super)),
+        Visibility::Restricted(restricted) => {
+            let path = &restricted.path;
+            match path.segments.first() {
+                Some(first) if path.leading_colon.is_none() && first.ident == "self" => {
+                    let rest = path.segments.iter().skip(1);
+                    quote!(pub(in super #(::#rest)*))
+                }
+                Some(first) if path.leading_colon.is_none() && first.ident == "super" => {
+                    quote!(pub(in super::#path))
+                }
+                _ => vis.to_token_stream(),
+            }
+        }
+        Visibility::Public(_) => vis.to_token_stream(),
+    }
+}
+
+/// Generates the registry of the states, next to the struct:
+///
+/// `mod player_states { pub use super::{Player, Idle, Running, PlayerStateMarker, SealerPlayer}; }`
+/// (with the hidden items through which the `impl` blocks tell each other about their hooks, see `hooks_registry`)
+///
+/// along with the groups of the states (the markers defined by hand are imported along with the struct instead)
+pub fn state_registry(
+    struct_name: &Ident,
+    visibility: &Visibility,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<TokenStream> {
+    let Some(registry) = &type_state_args.registry else {
+        return Ok(quote!());
+    };
+    if let Some(reuse) = &type_state_args.reuse {
+        return Err(syn::Error::new_spanned(
+            reuse,
+            "`registry` cannot be used with `reuse`, since the states are declared by the reused struct",
+        ));
+    }
+
+    let marker_vis = reexported_vis(&type_state_args.marker_vis);
+    let states = type_state_args
+        .states
+        .iter()
+        .filter(|state| !type_state_args.external_states.contains(state))
+        .chain(type_state_args.groups.iter().map(|group| &group.name))
+        .map(|state| {
+            let cfgs = type_state_args.cfgs_of_state(state);
+            quote!(#(#cfgs)* #marker_vis use super::#state;)
+        });
+    let struct_vis = reexported_vis(visibility);
+    let sealer_trait_name = Ident::new(&format!("Sealer{}", struct_name), struct_name.span());
+    let state_marker_name = Ident::new(&format!("{}StateMarker", struct_name), struct_name.span());
+    let hooks_type = hooks_type(struct_name);
+    let no_hooks_trait = no_hooks_trait(struct_name);
+    let doc = format!(
+        " The struct `{}` and its states, for the `impl` blocks of the other modules",
+        struct_name
+    );
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        #visibility mod #registry {
+            #struct_vis use super::#struct_name;
+            #marker_vis use super::#state_marker_name;
+            pub use super::#sealer_trait_name;
+            #marker_vis use super::{#hooks_type, #no_hooks_trait};
+            #(#states)*
+        }
+    })
+}
+
+/// Imports the registry of the states into the `impl` block of the struct named by its path:
+/// `impl crate::player::Player` -> `use crate::player::player_states::*;`
+/// (the `impl` blocks of the struct itself, named without a path, have the states in scope already)
+pub fn registry_import(self_ty: &Type, type_state_args: &TypeStateArgs) -> Option<TokenStream> {
+    let registry = type_state_args.registry.as_ref()?;
+    let Type::Path(type_path) = self_ty else {
+        return None;
+    };
+    if type_path.path.segments.len() < 2 {
+        return None;
+    }
+    let mut module = Path {
+        leading_colon: type_path.path.leading_colon,
+        segments: type_path.path.segments.clone(),
+    };
+    module.segments.pop();
+    module.segments.pop_punct();
+    Some(quote! {
+        #[allow(unused_imports)]
+        use #module::#registry::*;
+    })
+}
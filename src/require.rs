@@ -1,186 +1,877 @@
 /// this file contains the logic that modifies the methods that are annotated with `#[require]` macro,
 /// however, all the functions inside this file will be used by `#[impl_state]` macro due to delegation needs
-use proc_macro2::TokenStream;
-use quote::quote;
+use std::collections::{hash_map::Entry, HashMap};
+
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
 use syn::{
-    punctuated::Punctuated, Expr, ExprStruct, GenericParam, Ident, ImplItemFn, Member, Stmt, Token,
-    TypeParam,
+    parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    token,
+    visit_mut::VisitMut,
+    Block, Expr, ExprCall, ExprStruct, GenericParam, Ident, ImplItem, ImplItemFn, Item, ItemImpl,
+    Member, PredicateType, Token, TraitBound, TraitBoundModifier, Type, TypeParam, TypeParamBound,
+    TypePath, WhereClause, WherePredicate,
+};
+
+use crate::{
+    cfg_attrs, hooked_body, logged_body, order_slot_args, outcome_enum_type,
+    switch_to_args_branching_slot, switch_to_inner, switch_to_other_type, take_on_err, traced_body,
+    StateArg, StateHooks, StructShape, SwitchToArg, TypeStateArgs,
 };
 
-use crate::{extract_macro_args, is_single_letter, switch_to_inner};
+/// A single argument of the `#[require]` macro, optionally addressing a named slot: `auth = LoggedIn`
+pub struct RequireArg {
+    pub slot_name: Option<Ident>,
+    pub slot: RequiredSlot,
+}
+
+impl Parse for RequireArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let slot_name = if input.peek(Ident) && input.peek2(Token![=]) {
+            let slot_name = input.parse()?;
+            input.parse::<Token![=]>()?;
+            Some(slot_name)
+        } else {
+            None
+        };
+
+        Ok(RequireArg {
+            slot_name,
+            slot: input.parse()?,
+        })
+    }
+}
+
+/// A single slot of the `#[require]` macro
+#[derive(Clone)]
+pub enum RequiredSlot {
+    /// `_`: any state is accepted for this slot
+    Any(Token![_]),
+    /// `State1 | State2 | ...`: one of the listed states is required for this slot
+    OneOf(Punctuated<StateArg, Token![|]>),
+    /// `not(State1 | State2 | ...)`: any of the declared states except the listed ones is required for this slot
+    Not(Punctuated<StateArg, Token![|]>),
+}
+
+impl Parse for RequiredSlot {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![_]) {
+            Ok(RequiredSlot::Any(input.parse()?))
+        } else if input.peek(Ident) && input.peek2(token::Paren) {
+            let keyword: Ident = input.parse()?;
+            if keyword != "not" {
+                return Err(syn::Error::new(keyword.span(), "expected `not(...)`"));
+            }
+            let content;
+            parenthesized!(content in input);
+            Ok(RequiredSlot::Not(Punctuated::parse_separated_nonempty(
+                &content,
+            )?))
+        } else {
+            Ok(RequiredSlot::OneOf(Punctuated::parse_separated_nonempty(
+                input,
+            )?))
+        }
+    }
+}
+
+/// A generic of a generated `impl` block, along with its trait bound (if it has one): `A: SealerPlayerBuilder`
+pub type RequiredGeneric = (Ident, Option<syn::Path>);
+
+/// The arguments of a single generated `impl` block:
+/// - `args`: the state for each slot, e.g. `(Initial, A, PlayerBuilderState3, Loaded<C>)`
+/// - `generics`: the ones among `args` that are generic, with their trait bounds,
+///   e.g. `(A: SealerPlayerBuilder, PlayerBuilderState3: Alive, C)`
+pub struct RequireCombination {
+    pub args: Punctuated<StateArg, Token![,]>,
+    pub generics: Vec<RequiredGeneric>,
+}
+
+impl RequireCombination {
+    /// The generics of the combination that are not already declared by the `impl` block:
+    /// `#[require(Loaded<T>)]` in `impl<T> Store<T>` refers to the `T` of the `impl` block
+    pub fn new_generics<'a>(
+        &'a self,
+        impl_generics: &'a syn::Generics,
+    ) -> impl Iterator<Item = &'a RequiredGeneric> {
+        self.generics.iter().filter(move |(ident, _)| {
+            !impl_generics.params.iter().any(|param| match param {
+                GenericParam::Type(type_param) => type_param.ident == *ident,
+                _ => false,
+            })
+        })
+    }
+}
+
+/// Expands the alternatives of `#[require]` into every combination of concrete arguments
+///
+/// `#[require(Idle | Paused, B)]` -> `[(Idle, B), (Paused, B)]`
+/// each combination gets its own generated `impl` block (shared with the other methods of the same combination),
+/// so the method body only has to be written once
+///
+/// `_` slots are turned into generics named after the slot (`PlayerBuilderState2` for the 2nd slot),
+/// mirroring the generics of the struct definition generated by `#[type_state]`
+///
+/// `not(...)` slots are expanded into the alternatives of all the other declared `states`
+/// (or the states declared for the slot in `slot_states`):
+/// `#[require(not(Banned))]` -> `#[require(Idle | Active)]`
+///
+/// groups are turned into generics (named the same way as `_` slots) bounded by the marker trait of the group:
+/// `#[require(Alive)]` -> `impl<PlayerState1: Alive> Player<PlayerState1>`
+pub fn expand_require_alternatives(
+    parsed_args: &[RequiredSlot],
+    struct_name: &Ident,
+    type_state_args: &TypeStateArgs,
+) -> Vec<RequireCombination> {
+    let find_group = |ident: &Ident| {
+        type_state_args
+            .groups
+            .iter()
+            .find(|group| group.name == *ident)
+    };
+
+    let initial = RequireCombination {
+        args: Punctuated::new(),
+        generics: Vec::new(),
+    };
+
+    parsed_args
+        .iter()
+        .enumerate()
+        .fold(vec![initial], |combinations, (index, slot)| {
+            let slot_generic =
+                |span| Ident::new(&format!("{}State{}", struct_name, index + 1), span);
+            // the sealer of the struct, or the one of the component that the slot belongs to
+            let sealer_trait_name = type_state_args.sealer_of_slot(index, struct_name);
+
+            // each alternative is a state for the slot, along with the generics it introduces (with their trait bounds)
+            let alternatives: Vec<(StateArg, Vec<RequiredGeneric>)> = match slot {
+                RequiredSlot::Any(underscore) => {
+                    let generic = slot_generic(underscore.span);
+                    vec![(
+                        generic.clone().into(),
+                        vec![(generic, Some(sealer_trait_name.clone()))],
+                    )]
+                }
+                RequiredSlot::OneOf(alternatives) => alternatives
+                    .iter()
+                    .map(|state| match find_group(&state.ident) {
+                        Some(group) => {
+                            let generic = slot_generic(state.ident.span());
+                            (
+                                generic.clone().into(),
+                                vec![(generic, Some(group.name.clone().into()))],
+                            )
+                        }
+                        None if state.is_generic() => (
+                            state.clone(),
+                            vec![(state.ident.clone(), Some(sealer_trait_name.clone()))],
+                        ),
+                        // the generics among the arguments of a generic state: `Loaded<C>`
+                        None => (
+                            state.clone(),
+                            state
+                                .generic_args()
+                                .into_iter()
+                                .map(|generic| (generic, None))
+                                .collect(),
+                        ),
+                    })
+                    .collect(),
+                RequiredSlot::Not(excluded) => {
+                    let is_excluded = |state: &Ident| {
+                        excluded.iter().any(|excluded| {
+                            excluded.ident == *state
+                                || find_group(&excluded.ident).is_some_and(|group| {
+                                    group.states.iter().any(|member| member == state)
+                                })
+                        })
+                    };
+                    type_state_args
+                        .states_of_slot(index)
+                        .iter()
+                        .filter(|state| !is_excluded(state))
+                        .map(|state| {
+                            any_args_of_state(state, &slot_generic(state.span()), type_state_args)
+                        })
+                        .collect()
+                }
+            };
+
+            combinations
+                .iter()
+                .flat_map(|combination| {
+                    alternatives
+                        .iter()
+                        .map(|(alternative, alternative_generics)| {
+                            let mut args = combination.args.clone();
+                            let mut generics = combination.generics.clone();
+                            args.push(alternative.clone());
+                            generics.extend(alternative_generics.iter().cloned());
+                            RequireCombination { args, generics }
+                        })
+                })
+                .collect()
+        })
+}
+
+/// A declared state with generics for its parameters (if it is generic), named after the slot generic:
+/// `Loaded<T>` -> `Loaded<PlayerState1T>`
+fn any_args_of_state(
+    state: &Ident,
+    slot_generic: &Ident,
+    type_state_args: &TypeStateArgs,
+) -> (StateArg, Vec<RequiredGeneric>) {
+    let Some(generics) = type_state_args.generics_of_state(state) else {
+        return (state.clone().into(), Vec::new());
+    };
 
+    let params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| Ident::new(&format!("{}{}", slot_generic, param.ident), state.span()))
+        .collect();
+    let state_arg = StateArg {
+        parents: Vec::new(),
+        ident: state.clone(),
+        args: Some(syn::parse_quote!(<#(#params),*>)),
+    };
+
+    (
+        state_arg,
+        params.into_iter().map(|param| (param, None)).collect(),
+    )
+}
+
+/// The bound of a generic state of an `impl` block: `A: SealerPlayer`
+fn bound_predicate(ident: &Ident, bound: &syn::Path) -> WherePredicate {
+    WherePredicate::Type(PredicateType {
+        lifetimes: None,
+        bounded_ty: Type::Path(TypePath {
+            qself: None,
+            path: ident.clone().into(),
+        }),
+        colon_token: Default::default(),
+        bounds: std::iter::once(TypeParamBound::Trait(TraitBound {
+            paren_token: None,
+            modifier: TraitBoundModifier::None,
+            lifetimes: None,
+            path: bound.clone(),
+        }))
+        .collect(),
+    })
+}
+
+/// Generates the method for a combination of the required states, along with the header of its `impl` block
+/// (the methods with the same header are merged into a single `impl` block then, see `merge_impl_blocks`)
+#[allow(clippy::too_many_arguments)]
 pub fn generate_impl_block_for_method_based_on_require_args(
-    input_fn: &mut ImplItemFn,
+    input_fn: &ImplItemFn,
     struct_name: &Ident,
-    parsed_args: &Punctuated<Ident, Token![,]>,
+    combination: &RequireCombination,
+    switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    err_switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    other_type_target: Option<(&TypePath, &TypeStateArgs)>,
     impl_generics: &syn::Generics,
     struct_generics: &syn::PathArguments,
-) -> proc_macro2::TokenStream {
+    type_state_args: &TypeStateArgs,
+    hooks: &StateHooks,
+) -> syn::Result<MethodImpl> {
+    let parsed_args = &combination.args;
+
     // Convert the struct's generics into a Punctuated collection
-    let mut combined_generics = match struct_generics {
-        syn::PathArguments::AngleBracketed(angle_bracketed) => angle_bracketed.args.clone(),
-        syn::PathArguments::None => Punctuated::new(),
-        _ => panic!("Unsupported generics format for struct"),
-    };
+    let mut combined_generics =
+        match struct_generics {
+            syn::PathArguments::AngleBracketed(angle_bracketed) => angle_bracketed.args.clone(),
+            syn::PathArguments::None => Punctuated::new(),
+            syn::PathArguments::Parenthesized(arguments) => return Err(syn::Error::new_spanned(
+                arguments,
+                "the struct of the `impl` block can only be given its generics in angle brackets",
+            )),
+        };
 
     // Append the full list of arguments from `#[require]` macro: (A, B, State1, ...)
-    combined_generics.extend(parsed_args.iter().map(|ident| {
-        // Convert each parsed argument into a GenericArgument (which is a TypeParam)
-        syn::GenericArgument::Type(syn::Type::Path(syn::TypePath {
-            qself: None,
-            path: syn::Path::from(ident.clone()), // Use the ident for the type path
-        }))
-    }));
+    combined_generics.extend(parsed_args.iter().map(StateArg::to_generic_argument));
 
-    // put the sealed trait boundary for the generics:
+    // put the sealed trait (or group trait) boundary for the generics:
     /*
     ``` where
     A: Sealer,
     B: Sealer,
+    PlayerBuilderState3: Alive,
      */
-    let sealer_trait_name = Ident::new(&format!("Sealer{}", struct_name), struct_name.span());
-    let new_where_clauses: Vec<proc_macro2::TokenStream> = parsed_args
-        .iter()
-        .filter(|ident| is_single_letter(ident))
-        .map(|ident| quote!(#ident: #sealer_trait_name))
-        .collect();
-
-    // Merge with the existing where clause, if any.
-    let merged_where_clause = if let Some(existing_where) = &impl_generics.where_clause {
-        quote! {
-            #existing_where #(#new_where_clauses),*
+    let mut merged_where_clause = impl_generics.where_clause.clone();
+    for (ident, bound) in &combination.generics {
+        if let Some(bound) = bound {
+            // Merge with the existing where clause, if any.
+            merged_where_clause
+                .get_or_insert_with(|| WhereClause {
+                    where_token: Default::default(),
+                    predicates: Punctuated::new(),
+                })
+                .predicates
+                .push(bound_predicate(ident, bound));
         }
-    } else if !new_where_clauses.is_empty() {
-        quote! {
-            where #(#new_where_clauses),*
-        }
-    } else {
-        quote! {}
-    };
+    }
 
-    // Merge the original generics with the new single-letter generics.
+    // Merge the original generics with the new state generics.
     let mut all_generics = impl_generics.params.clone();
-    for ident in parsed_args.iter().filter(|i| is_single_letter(i)) {
+    for (ident, _) in combination.new_generics(impl_generics) {
         all_generics.push(GenericParam::Type(TypeParam::from(ident.clone())));
     }
 
-    // Generate PhantomData for the required number of states
-    let phantom_data: Vec<_> = (0..parsed_args.len())
-        .map(|_| quote!(::core::marker::PhantomData))
-        .collect();
-
-    let phantom_expr = if phantom_data.len() == 1 {
-        quote! { ::core::marker::PhantomData }
+    // Generate PhantomData for the required number of states (or the states themselves, if they are stored)
+    let phantom_expr = if type_state_args.stores_states() {
+        stored_state_expr(
+            parsed_args,
+            switch_to_args,
+            self_by_value(input_fn).zip(type_state_args.state_member()),
+        )
     } else {
-        quote! { ( #(#phantom_data),* ) }
+        phantom_state_expr(parsed_args.len())
     };
 
     // Modify the function body to append `_state: (PhantomData, ...)` to struct fields.
-    let new_fn_body: Vec<_> = input_fn
-        .block
-        .stmts
-        .iter()
-        .map(|stmt| {
-            if let Stmt::Expr(expr, maybe_semi) = stmt {
-                if let Some(modified_expr) =
-                    modify_struct_in_expr(expr, struct_name, phantom_expr.clone())
-                {
-                    // Return the modified expression as a statement
-                    return Stmt::Expr(modified_expr, *maybe_semi);
-                }
-            }
-            stmt.clone()
-        })
-        .collect();
+    let mut new_fn_body = input_fn.block.clone();
+    add_state_field(
+        &mut new_fn_body,
+        struct_name,
+        phantom_expr,
+        &type_state_args.shape,
+        &type_state_args.field,
+    );
+
+    // the target of a cross-type transition is initialized in the method body too, with the field and the shape of its struct,
+    // and its states are left to `Default`, since they are inferred from the return type
+    if let Some((target, target_args)) = other_type_target {
+        add_state_field(
+            &mut new_fn_body,
+            &target.path.segments.last().unwrap().ident,
+            quote!(::core::default::Default::default()),
+            &target_args.shape,
+            &target_args.field,
+        );
+    }
 
-    // Collect other function attributes (excluding `#[require]`).
+    // Collect other function attributes (excluding `#[require]` and `#[switch_to]`).
     let mut other_attrs: Vec<_> = input_fn
         .attrs
         .iter()
-        .filter(|attr| !attr.path().is_ident("require"))
+        .filter(|attr| !attr.path().is_ident("require") && !attr.path().is_ident("switch_to"))
         .cloned()
         .collect();
 
+    // the transitions are useless if their result is dropped, so they are `#[must_use]` (unless they have their own)
+    // (with a message, since the bare one is redundant for the types that are `#[must_use]` already, like `Result`)
+    let is_transition = switch_to_args.is_some() || other_type_target.is_some();
+    if type_state_args.must_use
+        && is_transition
+        && !matches!(input_fn.sig.output, syn::ReturnType::Default)
+        && !other_attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("must_use"))
+    {
+        other_attrs.push(syn::parse_quote!(
+            #[must_use = "the transition returns the struct in its new state"]
+        ));
+    }
+
     let fn_output = &input_fn.sig.output;
-    let switch_to_args = extract_macro_args(&mut other_attrs, "switch_to");
 
     // Generate the impl block for the method based on the extracted #[switch_to] arguments
-    let new_output = if let Some(switch_to_args) = switch_to_args {
-        switch_to_inner(fn_output, &switch_to_args, struct_name, &input_fn.sig.ident)
+    // `_` keeps the state of the slot as it is required
+    // (for the branching slot, the first state is only a placeholder, since the enum is returned instead)
+    let resolve = |switch_to_args: &[Option<Punctuated<StateArg, Token![|]>>]| {
+        switch_to_args
+            .iter()
+            .zip(parsed_args)
+            .map(|(switch_to_arg, require_arg)| match switch_to_arg {
+                Some(states) => states[0].clone(),
+                None => require_arg.clone(),
+            })
+            .collect::<Punctuated<StateArg, Token![,]>>()
+    };
+    let resolved = switch_to_args.map(resolve);
+    // the states of the `Err` arm of a guarded transition: `on_err = Disconnected`
+    let err_switch_to_args = err_switch_to_args.map(resolve);
+
+    let new_output = if let Some(target) = other_type_target {
+        // cross-type transition: `#[switch_to(Connection<Established>)]`
+        switch_to_other_type(fn_output, target.0, &input_fn.sig.ident)?
+    } else if let (Some(switch_to_args), Some(resolved)) = (switch_to_args, &resolved) {
+        let branching_slot = switch_to_args_branching_slot(switch_to_args);
+
+        // a branching transition returns the generated enum, which is given the states of the other slots
+        let outcome = branching_slot.map(|branching_slot| {
+            outcome_enum_type(
+                &input_fn.sig.ident,
+                impl_generics,
+                resolved
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| *index != branching_slot)
+                    .map(|(_, state)| state.clone()),
+            )
+        });
+
+        switch_to_inner(
+            fn_output,
+            resolved,
+            struct_name,
+            struct_generics,
+            outcome.as_ref(),
+            err_switch_to_args.as_ref(),
+            &input_fn.sig.ident,
+        )?
+    } else if let syn::ReturnType::Default = fn_output {
+        // there is nothing to rewrite for the methods that don't return anything
+        fn_output.clone()
     } else {
         // there is no `#[switch_to]` macro, so we use the `#[require]` macro's arguments instead
         // to keep the type same for the input and the output
-        switch_to_inner(fn_output, parsed_args, struct_name, &input_fn.sig.ident)
+        switch_to_inner(
+            fn_output,
+            parsed_args,
+            struct_name,
+            struct_generics,
+            None,
+            None,
+            &input_fn.sig.ident,
+        )?
     };
 
+    // the hooks of the states that the transition leaves and enters: `#[on_exit(Idle)]`, `#[on_enter(Running)]`
+    // (the cross-type transitions leave all of the states of the struct)
+    if other_type_target.is_some() || switch_to_args.is_some() {
+        new_fn_body = hooked_body(
+            hooks,
+            input_fn,
+            new_fn_body,
+            combination,
+            switch_to_args,
+            resolved.as_ref(),
+            err_switch_to_args.as_ref(),
+            &new_output,
+            struct_name,
+            type_state_args,
+        )?;
+    }
+    // the transitions are instrumented with `tracing` (with the `tracing` feature),
+    // and logged with `log` for the structs with `log = true` (with the `log` feature),
+    // except for the constructors, which don't switch from any state, and the `const fn`s
+    if cfg!(feature = "tracing")
+        && input_fn.sig.receiver().is_some()
+        && input_fn.sig.constness.is_none()
+    {
+        new_fn_body = traced_body(
+            input_fn,
+            new_fn_body,
+            combination,
+            switch_to_args,
+            other_type_target.map(|(target, _)| target),
+            struct_name,
+            type_state_args,
+        );
+    }
+    if type_state_args.log && input_fn.sig.receiver().is_some() && input_fn.sig.constness.is_none()
+    {
+        new_fn_body = logged_body(
+            input_fn,
+            new_fn_body,
+            combination,
+            switch_to_args,
+            other_type_target.map(|(target, _)| target),
+            struct_name,
+            type_state_args,
+        );
+    }
+    let new_fn_body = &new_fn_body.stmts;
+
     // construct the signature again
-    let fn_sig = &mut input_fn.sig;
+    let mut fn_sig = input_fn.sig.clone();
     fn_sig.output = new_output;
 
     // extract visibility
     let fn_vis = &input_fn.vis;
 
     // Generate the final output `impl` block.
-    let output = quote! {
-        impl<#all_generics> #struct_name<#combined_generics>
-        #merged_where_clause
-        {
+    // (the attributes are kept on the method as they are, and its `#[cfg]` gates the `impl` block too,
+    // along with the ones of the gated states that it requires or transitions into,
+    // except for the branches of a branching transition, which gate the variants of its enum instead)
+    let targets = switch_to_args
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|states| states.len() == 1)
+        .flatten();
+    let cfg_attrs = cfg_attrs(&other_attrs)
+        .chain(type_state_args.cfgs_of_states(parsed_args.iter().chain(targets)));
+    Ok(MethodImpl {
+        header: quote! {
+            #(#cfg_attrs)*
+            impl<#all_generics> #struct_name<#combined_generics>
+            #merged_where_clause
+        },
+        method: quote! {
             #(#other_attrs)*
             #fn_vis #fn_sig {
                 #(#new_fn_body)*
             }
+        },
+    })
+}
+
+/// A method generated for a combination of the required states, along with the header of its `impl` block:
+/// `impl<A> Player<A> where A: SealerPlayer`
+pub struct MethodImpl {
+    pub header: proc_macro2::TokenStream,
+    pub method: proc_macro2::TokenStream,
+}
+
+impl ToTokens for MethodImpl {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let MethodImpl { header, method } = self;
+        tokens.extend(quote!(#header { #method }));
+    }
+}
+
+/// Merges the methods with the same requirements (the same header) into a single `impl` block,
+/// in the order of their first methods, instead of generating an `impl` block for each of them
+/// (the map only finds the block of a header, so the order of the expansion doesn't depend on its hashing)
+pub fn merge_impl_blocks(method_impls: Vec<MethodImpl>) -> proc_macro2::TokenStream {
+    let mut blocks: Vec<MethodImpl> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for method_impl in method_impls {
+        match positions.entry(method_impl.header.to_string()) {
+            Entry::Occupied(position) => blocks[*position.get()].method.extend(method_impl.method),
+            Entry::Vacant(position) => {
+                position.insert(blocks.len());
+                blocks.push(method_impl);
+            }
         }
+    }
+    quote!(#(#blocks)*)
+}
+
+/// The `#[require]` arguments of a constructor (an associated function without `self`) that has `#[switch_to]` only:
+/// the constructor is placed into the `impl` block of the states it produces
+///
+/// `#[switch_to(Initial)] fn new() -> Self` -> `#[require(Initial)]`,
+/// and the slots that are kept unchanged (`_`) or branch into several states require their default states
+pub fn constructor_require_args(
+    method: &ImplItemFn,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<Option<(Span, Punctuated<RequireArg, Token![,]>)>> {
+    if method.sig.receiver().is_some() {
+        return Ok(None);
+    }
+
+    let Some(attr) = method
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("switch_to"))
+    else {
+        return Ok(None);
     };
+    let mut switch_to_args: Punctuated<SwitchToArg, Token![,]> =
+        attr.parse_args_with(Punctuated::parse_terminated)?;
+    // (the state of the `Err` arm doesn't change the states the constructor is placed for)
+    take_on_err(&mut switch_to_args)?;
+    let switch_to_args = order_slot_args(
+        switch_to_args
+            .iter()
+            .map(|arg| (arg.slot_name.as_ref(), arg.states.clone())),
+        &type_state_args.slots,
+        None,
+    )?;
+
+    let require_args = type_state_args
+        .slots
+        .iter()
+        .enumerate()
+        .map(|(index, slot)| {
+            let states = match switch_to_args.get(index) {
+                Some(Some(states)) if states.len() == 1 => states.clone(),
+                _ => Punctuated::from_iter([StateArg::from(slot.default.clone())]),
+            };
+            RequireArg {
+                slot_name: None,
+                slot: RequiredSlot::OneOf(states),
+            }
+        })
+        .collect();
 
-    output
+    Ok(Some((attr.path().span(), require_args)))
 }
 
-fn modify_struct_in_expr(
-    expr: &Expr,
-    struct_name: &syn::Ident,
-    phantom_expr: TokenStream,
-) -> Option<Expr> {
-    match expr {
-        Expr::Struct(expr_struct) if expr_struct.path.is_ident(struct_name) => {
-            // Clone the struct fields and add the `_state` field
-            let mut new_fields = expr_struct.fields.clone();
-            new_fields.push(syn::FieldValue {
-                attrs: Vec::new(),
-                member: Member::Named(syn::Ident::new("_state", struct_name.span())),
-                colon_token: Some(<Token![:]>::default()),
-                expr: Expr::Verbatim(phantom_expr.clone()),
-            });
+/// Generates an `impl` block of a trait for a combination of the `#[require]` arguments of the `impl` block:
+/// `#[require(Idle)] impl Display for Player` -> `impl Display for Player<Idle>`
+pub fn generate_trait_impl_block_based_on_require_args(
+    input: &ItemImpl,
+    struct_name: &Ident,
+    combination: &RequireCombination,
+    struct_generics: &syn::PathArguments,
+    type_state_args: &TypeStateArgs,
+) -> proc_macro2::TokenStream {
+    let mut input = input.clone();
+    // the implementations for the gated states are gated along with them
+    input.attrs.extend(
+        type_state_args
+            .cfgs_of_states(&combination.args)
+            .into_iter()
+            .cloned(),
+    );
+
+    // the generics of the state slots, along with their trait boundaries
+    let new_generics: Vec<_> = combination
+        .new_generics(&input.generics)
+        .map(|(ident, _)| ident.clone())
+        .collect();
+    for ident in new_generics {
+        input
+            .generics
+            .params
+            .push(GenericParam::Type(TypeParam::from(ident)));
+    }
+    for (ident, bound) in &combination.generics {
+        if let Some(bound) = bound {
+            input
+                .generics
+                .make_where_clause()
+                .predicates
+                .push(bound_predicate(ident, bound));
+        }
+    }
+
+    // the struct with the required states: `Player<Idle>`
+    let struct_args = match struct_generics {
+        syn::PathArguments::AngleBracketed(angle_bracketed) => {
+            angle_bracketed.args.iter().cloned().collect()
+        }
+        _ => Vec::new(),
+    };
+    let states = combination.args.iter();
+    // the path of the struct is kept, since it may be defined in another module (or crate): `library::Article`
+    if let syn::Type::Path(type_path) = &mut *input.self_ty {
+        type_path.path.segments.last_mut().unwrap().arguments =
+            syn::PathArguments::AngleBracketed(syn::parse_quote!(<#(#struct_args,)* #(#states),*>));
+    }
+
+    // the struct may be initialized in the methods as well
+    let phantom_expr = if type_state_args.stores_states() {
+        stored_state_expr(&combination.args, None, None)
+    } else {
+        phantom_state_expr(combination.args.len())
+    };
+    for item in input.items.iter_mut() {
+        if let ImplItem::Fn(method) = item {
+            add_state_field(
+                &mut method.block,
+                struct_name,
+                phantom_expr.clone(),
+                &type_state_args.shape,
+                &type_state_args.field,
+            );
+        }
+    }
+
+    quote! { #input }
+}
+
+/// The initialization of the `_state` field for the given number of slots: `PhantomData` or `(PhantomData, ...)`
+pub fn phantom_state_expr(slot_count: usize) -> TokenStream {
+    let phantom_data = (0..slot_count).map(|_| quote!(::core::marker::PhantomData));
+
+    if slot_count == 1 {
+        quote! { ::core::marker::PhantomData }
+    } else {
+        quote! { ( #(#phantom_data),* ) }
+    }
+}
 
-            // Return a modified struct expression with the new fields
-            Some(Expr::Struct(ExprStruct {
-                fields: new_fields,
-                ..expr_struct.clone()
-            }))
+/// The initialization of the `_state` field, when the states are stored in it (see `TypeStateArgs::stores_states`)
+///
+/// the slots that keep their states take them from `self` (if the method takes `self` by value): `self._state.1`,
+/// or the last element of the tuple structs: `self.2.1` (the enums have no single member for them),
+/// and the states of the other slots are initialized with `Default`, which is inferred from the return type
+/// (the states that carry data have to be given by the method body instead: `_state: Connected { session_id }`)
+fn stored_state_expr(
+    required: &Punctuated<StateArg, Token![,]>,
+    switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    self_member: Option<(Token![self], Member)>,
+) -> TokenStream {
+    let keeps_state = |index: usize| match switch_to_args.map(|args| &args[index]) {
+        None | Some(None) => true,
+        Some(Some(states)) => {
+            let required = &required[index];
+            states.len() == 1
+                && quote!(#required).to_string() == states[0].to_token_stream().to_string()
         }
-        // If it's an expression like `Some(Player { ... })` or `Ok(Player { ... })`
-        Expr::Call(call_expr) => {
-            let mut new_args = vec![];
-            let mut modified = false;
-
-            for arg in &call_expr.args {
-                let phantom = phantom_expr.clone();
-                if let Some(modified_arg) = modify_struct_in_expr(arg, struct_name, phantom) {
-                    new_args.push(modified_arg);
-                    modified = true;
+    };
+
+    let states: Vec<_> = (0..required.len())
+        .map(|index| match &self_member {
+            Some((self_token, member)) if keeps_state(index) => {
+                if required.len() == 1 {
+                    quote!(#self_token.#member)
                 } else {
-                    new_args.push(arg.clone());
+                    let index = syn::Index::from(index);
+                    quote!(#self_token.#member.#index)
                 }
             }
+            _ => quote!(::core::default::Default::default()),
+        })
+        .collect();
+
+    if states.len() == 1 {
+        states.into_iter().next().unwrap()
+    } else {
+        quote! { ( #(#states),* ) }
+    }
+}
+
+/// The `self` of the method, if it is taken by value (`self` or `mut self`), so that the states can be moved out of it
+///
+/// (the token of the method is reused, since a `self` generated by the macro would not refer to the receiver)
+fn self_by_value(method: &ImplItemFn) -> Option<Token![self]> {
+    method
+        .sig
+        .receiver()
+        .filter(|receiver| receiver.reference.is_none() && receiver.colon_token.is_none())
+        .map(|receiver| receiver.self_token)
+}
+
+/// Appends the `_state` field to every initialization of the struct in the method body (see `StructExprVisitor`)
+pub fn add_state_field(
+    block: &mut Block,
+    struct_name: &Ident,
+    phantom_expr: TokenStream,
+    shape: &StructShape,
+    field: &Ident,
+) {
+    StructExprVisitor {
+        struct_name,
+        phantom_expr,
+        shape,
+        field,
+    }
+    .visit_block_mut(block);
+}
 
-            if modified {
-                Some(Expr::Call(syn::ExprCall {
-                    args: new_args.into_iter().collect(),
-                    ..call_expr.clone()
-                }))
-            } else {
-                None
+/// Appends the `_state` field to every initialization of the struct in the method body:
+/// `Player { .. }` -> `Player { .., _state: PhantomData }`
+///
+/// `Self` refers to the struct with the required states, so `Self { ... }` is replaced with the struct name,
+/// and its states are inferred from the return type instead
+///
+/// the variants of the enums are initialized the same way: `Self::Ping { id }` -> `Frame::Ping { id, _state: PhantomData }`,
+/// and `Self::Data(bytes)` -> `Frame::Data(bytes, PhantomData)` for the tuple variants
+///
+/// the tuple structs get the state as their last element: `Self(value)` -> `Meters(value, PhantomData)`,
+/// and the unit structs are initialized with their only field: `Self` -> `Token { _state: PhantomData }`
+struct StructExprVisitor<'a> {
+    struct_name: &'a Ident,
+    phantom_expr: TokenStream,
+    shape: &'a StructShape,
+    /// the name of the `_state` field (see `TypeStateArgs::field`)
+    field: &'a Ident,
+}
+
+impl StructExprVisitor<'_> {
+    /// Whether the path is a variant of the struct, then `Self` in the path is replaced with the struct name:
+    /// `Self::Ping` -> `Frame::Ping`
+    fn is_variant_path(&self, path: &mut syn::Path) -> bool {
+        if path.segments.len() != 2 {
+            return false;
+        }
+        let first = &mut path.segments[0];
+        if first.ident != *self.struct_name && first.ident != "Self" {
+            return false;
+        }
+        first.ident = self.struct_name.clone();
+        true
+    }
+}
+
+impl VisitMut for StructExprVisitor<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        syn::visit_mut::visit_expr_mut(self, expr);
+
+        if let (StructShape::Unit, Expr::Path(path)) = (self.shape, &*expr) {
+            if path.qself.is_none()
+                && (path.path.is_ident(self.struct_name) || path.path.is_ident("Self"))
+            {
+                let struct_name = self.struct_name;
+                let phantom_expr = &self.phantom_expr;
+                let field = self.field;
+                *expr = syn::parse_quote!(#struct_name { #field: #phantom_expr });
             }
         }
-        _ => None,
     }
+
+    fn visit_expr_call_mut(&mut self, expr_call: &mut ExprCall) {
+        syn::visit_mut::visit_expr_call_mut(self, expr_call);
+
+        if let Expr::Path(func) = &mut *expr_call.func {
+            let is_constructor = match self.shape {
+                StructShape::Named | StructShape::Unit => false,
+                StructShape::Tuple(len) => {
+                    let is_struct =
+                        func.path.is_ident(self.struct_name) || func.path.is_ident("Self");
+                    if is_struct {
+                        func.path = self.struct_name.clone().into();
+                    }
+                    // the state can be given explicitly as the last element, like for the states that carry data
+                    is_struct && expr_call.args.len() == *len
+                }
+                StructShape::Enum { tuple_variants } => {
+                    let is_tuple_variant = func
+                        .path
+                        .segments
+                        .last()
+                        .is_some_and(|variant| tuple_variants.contains(&variant.ident));
+                    is_tuple_variant && self.is_variant_path(&mut func.path)
+                }
+            };
+            if is_constructor {
+                expr_call
+                    .args
+                    .push(Expr::Verbatim(self.phantom_expr.clone()));
+            }
+        }
+    }
+
+    fn visit_expr_struct_mut(&mut self, expr_struct: &mut ExprStruct) {
+        // handle the nested expressions first, like the fields
+        syn::visit_mut::visit_expr_struct_mut(self, expr_struct);
+
+        let is_struct =
+            expr_struct.path.is_ident(self.struct_name) || expr_struct.path.is_ident("Self");
+        if is_struct || self.is_variant_path(&mut expr_struct.path) {
+            if is_struct {
+                expr_struct.path = self.struct_name.clone().into();
+            }
+
+            // the `_state` field can be given explicitly, like for the states that carry data
+            if expr_struct
+                .fields
+                .iter()
+                .any(|field| matches!(&field.member, Member::Named(member) if member == self.field))
+            {
+                return;
+            }
+
+            expr_struct.fields.push(syn::FieldValue {
+                attrs: Vec::new(),
+                member: Member::Named(self.field.clone()),
+                colon_token: Some(<Token![:]>::default()),
+                expr: Expr::Verbatim(self.phantom_expr.clone()),
+            });
+        }
+    }
+
+    // items nested in the method body (functions, `impl` blocks, ...) have their own `Self`
+    fn visit_item_mut(&mut self, _item: &mut Item) {}
 }
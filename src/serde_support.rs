@@ -0,0 +1,201 @@
+/// this file contains the `Serialize` and `Deserialize` impls of the type-state structs,
+/// which are generated by the `#[type_state]` macro, when they are opted into with `serde = true` (with the `serde` feature),
+/// and the ones of their enums in any of their states, which are tagged with the states: `{"state": "Running", ...}`
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use syn::{Generics, Ident, LitStr};
+
+use crate::{generic_args, impl_for_state, strip_generic_defaults, StructShape, TypeStateArgs};
+
+/// Name of the trait of the states that the struct can be deserialized into: `Player` -> `DeserializablePlayer`
+fn deserializable_trait_name(struct_name: &Ident) -> Ident {
+    Ident::new(
+        &format!("Deserializable{}", struct_name),
+        struct_name.span(),
+    )
+}
+
+/// The attributes of the enum of the struct in any of its states, which is tagged with the unprefixed names of the states:
+/// `{"state": "Running", "name": "Alice"}`, so that the values round-trip with their states
+///
+/// each variant is deserialized with `Player::__deserialize_in_state`, since the struct itself is deserialized
+/// in its default states only (see `serde_support`)
+pub fn any_state_serde(
+    struct_name: &Ident,
+    generics: &Generics,
+    type_state_args: &TypeStateArgs,
+) -> (TokenStream, TokenStream) {
+    if type_state_args.serde.is_none() {
+        return Default::default();
+    }
+    let args = generic_args(&generics.params);
+    let default_state = &type_state_args.slots[0].default;
+    let bound = quote!(#struct_name<#(#args,)* #default_state>: ::serde::Deserialize<'de>);
+    let bound = LitStr::new(&bound.to_string(), struct_name.span());
+    let deserialize_with = LitStr::new(
+        &format!("{}::__deserialize_in_state", struct_name),
+        struct_name.span(),
+    );
+
+    (
+        quote! {
+            #[derive(::serde::Serialize, ::serde::Deserialize)]
+            #[serde(tag = "state", bound(deserialize = #bound))]
+        },
+        quote!(#[serde(deserialize_with = #deserialize_with)]),
+    )
+}
+
+/// Generates the `Serialize` and `Deserialize` impls of the struct, by deriving them from the serde's own derives,
+/// with the hidden `_state` field skipped, since the state is told by the type and not by the data:
+///
+/// `#[serde(skip, default = "::core::default::Default::default", bound(deserialize = "PlayerState1: DeserializablePlayer<0>"))]`
+///
+/// so that the struct is serialized in any of its states, and deserialized into its default states,
+/// or into the states that are given explicitly: `serde = (Ready)` -> `let player: Player<Ready> = serde_json::from_str(..)?`
+/// (each slot is deserialized into the states of the slot only, which implement `DeserializablePlayer<SLOT>`)
+///
+/// with `any_state = true`, the enum of the struct in any of its states is deserialized in any of the states, since the state
+/// is given by the tag (see `any_state_serde`): the struct is deserialized in its default state, and `rebuilt` in the tagged one
+///
+/// returns the derive of the struct, the attribute of the `_state` field, and the impls of the deserializable states
+pub fn serde_support(
+    struct_name: &Ident,
+    generics: &Generics,
+    state_idents: &[Ident],
+    type_state_args: &TypeStateArgs,
+    has_only_in: bool,
+    rebuilt: TokenStream,
+) -> syn::Result<(TokenStream, TokenStream, TokenStream)> {
+    let Some(listed) = &type_state_args.serde else {
+        return Ok(Default::default());
+    };
+    if !cfg!(feature = "serde") {
+        return Err(syn::Error::new(
+            struct_name.span(),
+            "`serde` needs the `serde` feature of `state-shift`: `state-shift = { version = \"..\", features = [\"serde\"] }`",
+        ));
+    }
+    if let Some(component) = type_state_args
+        .slots
+        .iter()
+        .find_map(|slot| slot.name.as_ref().filter(|_| slot.component.is_some()))
+    {
+        return Err(syn::Error::new(
+            component.span(),
+            "`serde` cannot be used with the components, since the states of the components are not deserialized",
+        ));
+    }
+
+    // the default states of the slots, and the states that are given explicitly (in the slots that allow them)
+    let trait_name = deserializable_trait_name(struct_name);
+    let mut impls = Vec::new();
+    for (index, slot) in type_state_args.slots.iter().enumerate() {
+        let slot_states = type_state_args.states_of_slot(index);
+        let given = listed
+            .iter()
+            .filter(|state| **state != slot.default && slot_states.contains(state));
+        for state in std::iter::once(&slot.default).chain(given) {
+            // the states that carry data cannot be created from nothing
+            if type_state_args.fields_of_state(state).is_some() {
+                return Err(syn::Error::new(
+                    state.span(),
+                    format!(
+                        "`{}` cannot be deserialized into, since the data of the state is not serialized",
+                        type_state_args.unprefixed_name(state)
+                    ),
+                ));
+            }
+            let index = Literal::usize_unsuffixed(index);
+            impls.push(impl_for_state(
+                type_state_args,
+                quote!(#trait_name<#index>),
+                state,
+                quote!(),
+            ));
+        }
+    }
+
+    let bounds = state_idents.iter().enumerate().map(|(index, state)| {
+        let index = Literal::usize_unsuffixed(index);
+        quote!(#state: #trait_name<#index>)
+    });
+    let bounds = LitStr::new(&quote!(#(#bounds),*).to_string(), struct_name.span());
+    let doc = format!(
+        " The states that `{}` can be deserialized into, in the slot `SLOT` (starting from `0`)",
+        struct_name
+    );
+
+    // the struct in any of the states, for the tagged enum of the struct in any of its states
+    let deserialize_in_state = if type_state_args.any_state {
+        if !matches!(
+            type_state_args.shape,
+            StructShape::Named | StructShape::Unit
+        ) {
+            return Err(syn::Error::new(
+                struct_name.span(),
+                "`serde` with `any_state` needs a struct with named fields, since the state is tagged next to the fields",
+            ));
+        }
+        if let Some((state, _)) = type_state_args.state_fields.first() {
+            return Err(syn::Error::new(
+                state.span(),
+                format!(
+                    "`serde` with `any_state` cannot be used with the states that carry data, since the data of `{}` is not serialized",
+                    type_state_args.unprefixed_name(state)
+                ),
+            ));
+        }
+        if has_only_in {
+            return Err(syn::Error::new(
+                struct_name.span(),
+                "`serde` with `any_state` cannot be used with the `#[only_in]` fields, since their types depend on the state",
+            ));
+        }
+
+        let params = strip_generic_defaults(&generics.params);
+        let params = params.iter();
+        let args = generic_args(&generics.params);
+        let where_clause = &generics.where_clause;
+        let default_state = &type_state_args.slots[0].default;
+        let sealer = type_state_args.sealer_of_slot(0, struct_name);
+        quote! {
+            impl<#(#params),*> #struct_name<#(#args,)* #default_state>
+            #where_clause
+            {
+                /// The struct deserialized in its default state, and rebuilt in the state given by the tag of `AnyPlayer`
+                #[doc(hidden)]
+                #[allow(dead_code)]
+                fn __deserialize_in_state<'de, D, S>(
+                    deserializer: D,
+                ) -> ::core::result::Result<#struct_name<#(#args,)* S>, D::Error>
+                where
+                    D: ::serde::Deserializer<'de>,
+                    S: #sealer,
+                    Self: ::serde::Deserialize<'de>,
+                {
+                    let value = <Self as ::serde::Deserialize<'de>>::deserialize(deserializer)?;
+                    ::core::result::Result::Ok(#rebuilt)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok((
+        quote!(#[derive(::serde::Serialize, ::serde::Deserialize)]),
+        quote! {
+            #[serde(skip, default = "::core::default::Default::default", bound(deserialize = #bounds))]
+        },
+        quote! {
+            #[doc = #doc]
+            #[doc(hidden)]
+            pub trait #trait_name<const SLOT: usize> {}
+
+            #(#impls)*
+
+            #deserialize_in_state
+        },
+    ))
+}
@@ -0,0 +1,420 @@
+/// this file contains the `state_machine_from!` macro, which reads the description of a state machine from a spec file
+/// (RON or TOML, read by `ron` and `toml` with the features of the same names, or a Mermaid or Graphviz diagram,
+/// see `diagram_spec.rs`) at compile time, and declares it with `state_shift!` (see `dsl.rs`),
+/// so that the spec shared with the other teams and languages stays the single source of truth
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use stringcase::pascal_case;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, FieldsNamed, Ident, LitStr, Token, Type, Visibility,
+};
+
+use crate::{dot, mermaid, DiagramMachine};
+
+mod keyword {
+    syn::custom_keyword!(fields);
+}
+
+/// Input of the `state_machine_from!` macro: `"specs/door.ron"`, or `"docs/door.mmd", fields { code: u32 }`
+/// with the fields that the spec doesn't give (like the ones of a diagram)
+struct SpecInput {
+    path: LitStr,
+    fields: Option<FieldsNamed>,
+}
+
+impl Parse for SpecInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let fields = if input.is_empty() {
+            None
+        } else {
+            input.parse::<Token![,]>()?;
+            input.parse::<keyword::fields>()?;
+            Some(input.parse()?)
+        };
+        Ok(SpecInput { path, fields })
+    }
+}
+
+/// A value of the spec file, which is either a string, a list, or a map (a struct or a map in RON, a table in TOML),
+/// and the other values (numbers, ...), which the spec doesn't expect, by their kind
+/// (it is only read from the RON and TOML files, with the `ron` and `toml` features)
+#[cfg_attr(not(any(feature = "ron", feature = "toml")), allow(dead_code))]
+enum Value {
+    Str(String),
+    List(Vec<Value>),
+    Map(Vec<(String, Value)>),
+    Other(&'static str),
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Str(_) => "a string",
+            Value::List(_) => "a list",
+            Value::Map(_) => "a map",
+            Value::Other(kind) => kind,
+        }
+    }
+
+    /// The value of RON, whose maps are read in the order they are written (with the `indexmap` feature of `ron`)
+    ///
+    /// the names are strings in RON (`"Closed"`), since a bare name (`Closed`) is a unit value that doesn't keep it
+    #[cfg(feature = "ron")]
+    fn from_ron(value: ron::Value) -> Value {
+        match value {
+            ron::Value::String(string) => Value::Str(string),
+            ron::Value::Seq(values) => Value::List(values.into_iter().map(Value::from_ron).collect()),
+            ron::Value::Map(map) => map
+                .into_iter()
+                .map(|(key, value)| match key {
+                    ron::Value::String(key) => Some((key, Value::from_ron(value))),
+                    _ => None,
+                })
+                .collect::<Option<_>>()
+                .map_or(Value::Other("a map whose keys are not strings"), Value::Map),
+            ron::Value::Option(Some(value)) => Value::from_ron(*value),
+            ron::Value::Option(None) => Value::Other("`None`"),
+            ron::Value::Unit => {
+                Value::Other("a bare name, which is a unit value in RON (the names are written as strings: `\"Closed\"`)")
+            }
+            ron::Value::Bool(_) => Value::Other("a boolean"),
+            ron::Value::Char(_) => Value::Other("a character"),
+            ron::Value::Number(_) => Value::Other("a number"),
+            ron::Value::Bytes(_) => Value::Other("bytes"),
+        }
+    }
+
+    /// The value of TOML, whose tables are read in the order they are written (with the `preserve_order` feature of `toml`)
+    #[cfg(feature = "toml")]
+    fn from_toml(value: toml::Value) -> Value {
+        match value {
+            toml::Value::String(string) => Value::Str(string),
+            toml::Value::Array(values) => {
+                Value::List(values.into_iter().map(Value::from_toml).collect())
+            }
+            toml::Value::Table(table) => Value::Map(
+                table
+                    .into_iter()
+                    .map(|(key, value)| (key, Value::from_toml(value)))
+                    .collect(),
+            ),
+            toml::Value::Integer(_) | toml::Value::Float(_) => Value::Other("a number"),
+            toml::Value::Boolean(_) => Value::Other("a boolean"),
+            toml::Value::Datetime(_) => Value::Other("a date"),
+        }
+    }
+}
+
+/// Parses a RON spec with `ron` (with the `ron` feature)
+#[cfg(feature = "ron")]
+fn ron_spec(source: &str) -> Result<Value, String> {
+    ron::from_str::<ron::Value>(source)
+        .map(Value::from_ron)
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(not(feature = "ron"))]
+fn ron_spec(_source: &str) -> Result<Value, String> {
+    Err("the RON spec files are read with the `ron` feature of `state-shift`".to_string())
+}
+
+/// Parses a TOML spec with `toml` (with the `toml` feature)
+#[cfg(feature = "toml")]
+fn toml_spec(source: &str) -> Result<Value, String> {
+    source
+        .parse::<toml::Table>()
+        .map(|table| Value::from_toml(toml::Value::Table(table)))
+        .map_err(|error| error.to_string())
+}
+
+#[cfg(not(feature = "toml"))]
+fn toml_spec(_source: &str) -> Result<Value, String> {
+    Err("the TOML spec files are read with the `toml` feature of `state-shift`".to_string())
+}
+
+/// Reads the spec file into the tokens of `state_shift!`, with the errors of the spec reported on its path
+struct SpecReader<'a> {
+    path: &'a LitStr,
+    /// the fields given along with the path, after the ones of the spec
+    fields: Vec<TokenStream>,
+}
+
+impl SpecReader<'_> {
+    fn error(&self, message: String) -> syn::Error {
+        syn::Error::new(
+            self.path.span(),
+            format!("{}: {}", self.path.value(), message),
+        )
+    }
+
+    /// The entry of a map of the spec, if it is given
+    fn entry<'v>(&self, entries: &'v [(String, Value)], key: &str) -> Option<&'v Value> {
+        entries
+            .iter()
+            .find(|(entry, _)| entry == key)
+            .map(|(_, value)| value)
+    }
+
+    fn string(&self, value: &Value, what: &str) -> syn::Result<String> {
+        match value {
+            Value::Str(string) => Ok(string.clone()),
+            value => Err(self.error(format!(
+                "expected a string for {}, found {}",
+                what,
+                value.kind()
+            ))),
+        }
+    }
+
+    fn required_string(
+        &self,
+        entries: &[(String, Value)],
+        key: &str,
+        what: &str,
+    ) -> syn::Result<String> {
+        match self.entry(entries, key) {
+            Some(value) => self.string(value, &format!("`{}` of {}", key, what)),
+            None => Err(self.error(format!("expected `{}` in {}", key, what))),
+        }
+    }
+
+    fn list<'v>(&self, value: &'v Value, what: &str) -> syn::Result<&'v [Value]> {
+        match value {
+            Value::List(values) => Ok(values),
+            value => Err(self.error(format!(
+                "expected a list for {}, found {}",
+                what,
+                value.kind()
+            ))),
+        }
+    }
+
+    fn map<'v>(&self, value: &'v Value, what: &str) -> syn::Result<&'v [(String, Value)]> {
+        match value {
+            Value::Map(entries) => Ok(entries),
+            value => Err(self.error(format!(
+                "expected a map for {}, found {}",
+                what,
+                value.kind()
+            ))),
+        }
+    }
+
+    fn ident(&self, name: &str, what: &str) -> syn::Result<Ident> {
+        syn::parse_str::<Ident>(name)
+            .map(|ident| Ident::new(&ident.to_string(), self.path.span()))
+            .map_err(|_| self.error(format!("`{}` is not a valid name of {}", name, what)))
+    }
+
+    /// The named and typed entries of a map: `{ level = "u8" }` -> `level: u8`
+    fn typed_entries(&self, value: Option<&Value>, what: &str) -> syn::Result<Vec<TokenStream>> {
+        let Some(value) = value else {
+            return Ok(Vec::new());
+        };
+        self.map(value, what)?
+            .iter()
+            .map(|(name, ty)| {
+                let ident = self.ident(name, what)?;
+                let ty = self.string(ty, &format!("the type of `{}`", name))?;
+                let ty = syn::parse_str::<Type>(&ty).map_err(|_| {
+                    self.error(format!("`{}` is not a valid type of `{}`", ty, name))
+                })?;
+                Ok(quote!(#ident: #ty))
+            })
+            .collect()
+    }
+
+    fn doc(&self, entries: &[(String, Value)]) -> syn::Result<Option<TokenStream>> {
+        self.entry(entries, "doc")
+            .map(|doc| {
+                let doc = format!(" {}", self.string(doc, "`doc`")?);
+                Ok(quote!(#[doc = #doc]))
+            })
+            .transpose()
+    }
+
+    /// The machine of the spec, in the syntax of `state_shift!`:
+    ///
+    /// ```ignore
+    /// (
+    ///     name: "Door",
+    ///     visibility: "pub",
+    ///     fields: {"code": "u32"},
+    ///     states: ["Closed", "Open", "Locked"],
+    ///     transitions: [
+    ///         (name: "open", from: "Closed", to: "Open"),
+    ///         (name: "lock", from: "Closed", to: "Locked", args: {"code": "u32"}),
+    ///     ],
+    /// )
+    /// // ->
+    /// pub machine Door {
+    ///     fields { code: u32 }
+    ///     states { Closed, Open, Locked }
+    ///     transitions { open: Closed -> Open, lock(code: u32): Closed -> Locked }
+    /// }
+    /// ```
+    fn machine(&self, spec: &Value) -> syn::Result<TokenStream> {
+        let spec = self.map(spec, "the machine")?;
+        let name = self.required_string(spec, "name", "the machine")?;
+        let name = self.ident(&name, "the machine")?;
+        let doc = self.doc(spec)?;
+        let visibility = match self.entry(spec, "visibility") {
+            Some(visibility) => {
+                let visibility = self.string(visibility, "`visibility`")?;
+                syn::parse_str::<Visibility>(&visibility).map_err(|_| {
+                    self.error(format!("`{}` is not a valid visibility", visibility))
+                })?
+            }
+            None => Visibility::Inherited,
+        };
+        let mut fields = self.typed_entries(self.entry(spec, "fields"), "a field")?;
+        fields.extend(self.fields.iter().cloned());
+
+        let Some(states) = self.entry(spec, "states") else {
+            return Err(self.error("expected `states` in the machine".to_string()));
+        };
+        let states = self
+            .list(states, "`states`")?
+            .iter()
+            .map(|state| self.ident(&self.string(state, "a state")?, "a state"))
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        let transitions = match self.entry(spec, "transitions") {
+            Some(transitions) => self.list(transitions, "`transitions`")?,
+            None => &[],
+        };
+        let transitions = transitions
+            .iter()
+            .map(|transition| {
+                let transition = self.map(transition, "a transition")?;
+                let method = self.required_string(transition, "name", "a transition")?;
+                let what = format!("the transition `{}`", method);
+                let method = self.ident(&method, "a transition")?;
+                let doc = self.doc(transition)?;
+                let from = self.required_string(transition, "from", &what)?;
+                let from = self.ident(&from, "a state")?;
+                let to = self.required_string(transition, "to", &what)?;
+                let to = self.ident(&to, "a state")?;
+                let args = match self.entry(transition, "args") {
+                    Some(args) => {
+                        let args = self.typed_entries(Some(args), "an argument")?;
+                        quote!((#(#args),*))
+                    }
+                    None => TokenStream::new(),
+                };
+                Ok(quote!(#doc #method #args: #from -> #to))
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        Ok(quote! {
+            #doc
+            #visibility machine #name {
+                fields { #(#fields),* }
+                states { #(#states),* }
+                transitions { #(#transitions),* }
+            }
+        })
+    }
+
+    /// The machine of a diagram (see `diagram_spec.rs`), whose transitions take no arguments:
+    ///
+    /// ```ignore
+    /// stateDiagram-v2
+    ///     [*] --> Closed
+    ///     Closed --> Open: open
+    ///     Open --> Closed: close
+    /// // ->
+    /// machine Door {
+    ///     states { Closed, Open }
+    ///     transitions { open: Closed -> Open, close: Open -> Closed }
+    /// }
+    /// ```
+    fn diagram_machine(&self, diagram: DiagramMachine, stem: &str) -> syn::Result<TokenStream> {
+        let name = diagram.name.unwrap_or_else(|| pascal_case(stem));
+        let name = self.ident(&name, "the machine")?;
+        let fields = &self.fields;
+        let states = diagram
+            .states
+            .iter()
+            .map(|state| self.ident(state, "a state"))
+            .collect::<syn::Result<Vec<_>>>()?;
+        let transitions = diagram
+            .transitions
+            .iter()
+            .map(|transition| {
+                let method = self.ident(&transition.method, "a transition")?;
+                let from = transition
+                    .from
+                    .iter()
+                    .map(|from| self.ident(from, "a state"))
+                    .collect::<syn::Result<Vec<_>>>()?;
+                let to = self.ident(&transition.to, "a state")?;
+                Ok(quote!(#method: #(#from)|* -> #to))
+            })
+            .collect::<syn::Result<Vec<_>>>()?;
+
+        Ok(quote! {
+            machine #name {
+                fields { #(#fields),* }
+                states { #(#states),* }
+                transitions { #(#transitions),* }
+            }
+        })
+    }
+}
+
+/// Expands `state_machine_from!("protocol.ron")` into the machine of the spec file (see `SpecReader::machine`
+/// and `SpecReader::diagram_machine`), with the path relative to the crate
+/// (the format is told by the extension: `.ron`, `.toml`, `.mmd` or `.dot`)
+///
+/// the spec file is included in the crate as well, so that the crate is rebuilt when it changes
+pub fn state_machine_from_inner(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let SpecInput { path, fields } = parse_macro_input!(input as SpecInput);
+    let reader = SpecReader {
+        path: &path,
+        fields: fields
+            .iter()
+            .flat_map(|fields| &fields.named)
+            .map(ToTokens::to_token_stream)
+            .collect(),
+    };
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let file = std::path::Path::new(&manifest_dir).join(path.value());
+
+    let spec = std::fs::read_to_string(&file)
+        .map_err(|error| reader.error(format!("cannot read `{}`: {}", file.display(), error)))
+        .and_then(|source| {
+            // the machines of the diagrams are named after the graph, or after the file
+            let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+            match file.extension().and_then(|extension| extension.to_str()) {
+                Some("ron") => {
+                    reader.machine(&ron_spec(&source).map_err(|error| reader.error(error))?)
+                }
+                Some("toml") => {
+                    reader.machine(&toml_spec(&source).map_err(|error| reader.error(error))?)
+                }
+                Some("mmd" | "mermaid") => reader.diagram_machine(
+                    mermaid(&source).map_err(|error| reader.error(error))?,
+                    &stem,
+                ),
+                Some("dot" | "gv") => reader
+                    .diagram_machine(dot(&source).map_err(|error| reader.error(error))?, &stem),
+                _ => Err(reader
+                    .error("expected a `.ron`, a `.toml`, a `.mmd` or a `.dot` file".to_string())),
+            }
+        });
+    let machine = match spec {
+        Ok(machine) => machine,
+        Err(error) => return error.into_compile_error().into(),
+    };
+
+    let include = LitStr::new(&file.to_string_lossy(), Span::call_site());
+    quote! {
+        const _: &[u8] = ::core::include_bytes!(#include);
+
+        ::state_shift::state_shift! { #machine }
+    }
+    .into()
+}
@@ -0,0 +1,142 @@
+/// this file contains the `#[state_machine]` attribute, which is a single annotation point for the type-state structs:
+/// on a module, it puts `#[type_state]` on the struct of the machine and `#[impl_state]` on its `impl` blocks,
+/// so that the `#[require]` and `#[switch_to]` methods are all that is left to write
+use proc_macro2::TokenStream;
+use quote::quote;
+use stringcase::pascal_case;
+use syn::{parse_macro_input, spanned::Spanned, Ident, Item, ItemMod, Type};
+
+/// Whether the attributes already have the given attribute: `#[type_state(...)]`, `#[state_shift::impl_state]`
+fn has_attribute(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name)
+    })
+}
+
+/// The name of the type of an `impl` block: `Player` for `impl<T> Player<T>` and `impl Display for Player`
+fn self_type_name(self_ty: &Type) -> Option<&Ident> {
+    match self_ty {
+        Type::Path(type_path) => type_path.path.segments.last().map(|segment| &segment.ident),
+        _ => None,
+    }
+}
+
+/// Picks the struct (or the enum) of the machine in the module: the one named after the module (`mod player` -> `Player`),
+/// or the only one in the module
+fn machine_name(module: &ItemMod, items: &[Item]) -> syn::Result<Ident> {
+    let names: Vec<&Ident> = items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Struct(item_struct) => Some(&item_struct.ident),
+            Item::Enum(item_enum) => Some(&item_enum.ident),
+            _ => None,
+        })
+        .collect();
+    let module_name = pascal_case(&module.ident.to_string());
+    if let Some(name) = names.iter().find(|name| **name == module_name.as_str()) {
+        return Ok((*name).clone());
+    }
+    match names.as_slice() {
+        [name] => Ok((*name).clone()),
+        [] => Err(syn::Error::new(
+            module.ident.span(),
+            "`#[state_machine]` expects a struct or an enum in the module",
+        )),
+        _ => Err(syn::Error::new(
+            module.ident.span(),
+            format!(
+                "`#[state_machine]` cannot tell which of the structs is the machine, name it after the module: `{}`",
+                module_name
+            ),
+        )),
+    }
+}
+
+/// Expands `#[state_machine(...)]`, whose arguments are the ones of `#[type_state]`:
+///
+/// - on a module, the struct named after the module (or the only struct of the module) gets `#[type_state(...)]`,
+///   and its `impl` blocks get `#[impl_state]` (the trait implementations only if they have `#[require]`),
+/// - on a struct or an enum, it is `#[type_state(...)]` itself
+pub fn state_machine_inner(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = TokenStream::from(args);
+    let item = parse_macro_input!(input as Item);
+    match item {
+        Item::Struct(_) | Item::Enum(_) => quote! {
+            #[::state_shift::type_state(#args)]
+            #item
+        }
+        .into(),
+        Item::Mod(module) => match state_machine_module(args, module.clone()) {
+            Ok(module) => module.into(),
+            Err(error) => {
+                let error = error.to_compile_error();
+                quote!(#error #module).into()
+            }
+        },
+        item => syn::Error::new(
+            item.span(),
+            "`#[state_machine]` expects a module, a struct or an enum",
+        )
+        .to_compile_error()
+        .into(),
+    }
+}
+
+fn state_machine_module(args: TokenStream, mut module: ItemMod) -> syn::Result<TokenStream> {
+    let Some((_, items)) = &module.content else {
+        return Err(syn::Error::new(
+            module.ident.span(),
+            "`#[state_machine]` expects the module to be declared inline: `mod player { ... }`",
+        ));
+    };
+    let machine = machine_name(&module, items)?;
+    let Some((_, items)) = &mut module.content else {
+        unreachable!("the content of the module is checked above");
+    };
+
+    for item in items.iter_mut() {
+        match item {
+            Item::Struct(item_struct) if item_struct.ident == machine => {
+                if has_attribute(&item_struct.attrs, "type_state") {
+                    return Err(syn::Error::new(
+                        item_struct.ident.span(),
+                        "the struct of `#[state_machine]` is given its `#[type_state]` by the module",
+                    ));
+                }
+                item_struct
+                    .attrs
+                    .insert(0, syn::parse_quote!(#[::state_shift::type_state(#args)]));
+            }
+            Item::Enum(item_enum) if item_enum.ident == machine => {
+                if has_attribute(&item_enum.attrs, "type_state") {
+                    return Err(syn::Error::new(
+                        item_enum.ident.span(),
+                        "the enum of `#[state_machine]` is given its `#[type_state]` by the module",
+                    ));
+                }
+                item_enum
+                    .attrs
+                    .insert(0, syn::parse_quote!(#[::state_shift::type_state(#args)]));
+            }
+            Item::Impl(item_impl)
+                if self_type_name(&item_impl.self_ty) == Some(&machine)
+                    && !has_attribute(&item_impl.attrs, "impl_state")
+                    && (item_impl.trait_.is_none()
+                        || has_attribute(&item_impl.attrs, "require")) =>
+            {
+                item_impl
+                    .attrs
+                    .insert(0, syn::parse_quote!(#[::state_shift::impl_state]));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(quote!(#module))
+}
@@ -0,0 +1,206 @@
+/// this file contains the `generate_state_tests!` macro, which generates the compile-fail tests of the states of a struct:
+/// each method of the struct is called in each of the states it cannot be called in, as a `compile_fail` doc test,
+/// so that the tests are kept in sync with the `#[require]` of the methods instead of being written by hand
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use stringcase::snake_case;
+use syn::{
+    bracketed,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    Ident, LitBool, LitInt, Path, Token,
+};
+
+/// Name of the hidden macro of the tests of the states, generated by `#[impl_state(transitions)]`:
+/// `Player` -> `__state_shift_player_state_tests`
+pub fn state_tests_macro_name(struct_name: &Ident) -> Ident {
+    format_ident!(
+        "__state_shift_{}_state_tests",
+        snake_case(&struct_name.to_string()),
+        span = struct_name.span()
+    )
+}
+
+/// Forwards `generate_state_tests!` to the hidden macro generated by `#[impl_state(transitions)]` for the struct,
+/// which carries the states of the methods back to it (see `TransitionGraph::state_tests`)
+///
+/// `generate_state_tests!(crate::player::Player)` -> `crate::player::__state_shift_player_state_tests! { crate::player::Player }`
+pub fn forward_state_tests(input: TokenStream) -> TokenStream {
+    let struct_path = parse_macro_input!(input as Path);
+
+    let mut callback_path = struct_path.clone();
+    let last_segment = callback_path.segments.last_mut().unwrap();
+    last_segment.ident = state_tests_macro_name(&last_segment.ident);
+    last_segment.arguments = syn::PathArguments::None;
+
+    quote! {
+        #callback_path! { #struct_path }
+    }
+    .into()
+}
+
+/// A test of a method in some states: `start(1) [Running, Locked] true`,
+/// with the number of the arguments of the method, and whether it is expected to fail to compile
+struct StateTestCase {
+    method: Ident,
+    args: usize,
+    states: Vec<Ident>,
+    fails: bool,
+}
+
+impl Parse for StateTestCase {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let method = input.parse()?;
+        let args;
+        syn::parenthesized!(args in input);
+        let states;
+        bracketed!(states in input);
+        Ok(StateTestCase {
+            method,
+            args: args.parse::<LitInt>()?.base10_parse()?,
+            states: Punctuated::<Ident, Token![,]>::parse_terminated(&states)?
+                .into_iter()
+                .collect(),
+            fails: input.parse::<LitBool>()?.value,
+        })
+    }
+}
+
+/// Input of the internal `__generate_state_tests` macro: `[<cases>;*] <path of the struct>`
+struct StateTestsInput {
+    cases: Vec<StateTestCase>,
+    struct_path: Path,
+}
+
+impl Parse for StateTestsInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let cases;
+        bracketed!(cases in input);
+        Ok(StateTestsInput {
+            cases: Punctuated::<StateTestCase, Token![;]>::parse_terminated(&cases)?
+                .into_iter()
+                .collect(),
+            struct_path: input.parse()?,
+        })
+    }
+}
+
+/// The path of the struct as the tests see it, since they are compiled as other crates:
+/// `crate::player::Player` and `Player` -> `my_crate::player::Player`, `my_crate::Player`
+fn external_path(struct_path: &Path) -> Vec<String> {
+    let crate_name = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "crate".to_string());
+    let mut segments: Vec<String> = struct_path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    if segments[0] == "crate" {
+        segments[0] = crate_name;
+    } else if segments.len() == 1 {
+        segments.insert(0, crate_name);
+    }
+    segments
+}
+
+/// Expands `generate_state_tests!` into a module whose documentation has the tests as doc tests:
+///
+/// ```ignore
+/// /// ```compile_fail
+/// /// fn start_in_running(mut value: my_crate::Player<my_crate::Running>) {
+/// ///     let _ = value.start(todo!());
+/// /// }
+/// /// ```
+/// mod player_state_tests {
+///     pub const COMPILE_FAIL: &[&str] = &["fn start_in_running(..) { .. }"];
+/// }
+/// ```
+///
+/// (each method is called in a state it can be called in as well, which is expected to compile,
+/// so that the compile-fail tests don't pass because of a wrong path)
+pub fn state_tests_inner(input: TokenStream) -> TokenStream {
+    let StateTestsInput { cases, struct_path } = parse_macro_input!(input as StateTestsInput);
+
+    let mut segments = external_path(&struct_path);
+    let struct_name = segments.pop().unwrap();
+    let module = segments.join("::");
+    let mod_name = format_ident!("{}_state_tests", snake_case(&struct_name));
+
+    let sources: Vec<(&StateTestCase, String)> = cases
+        .iter()
+        .map(|case| {
+            let states = case
+                .states
+                .iter()
+                .map(|state| format!("{}::{}", module, state))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let fn_name = format!(
+                "{}_in_{}",
+                case.method,
+                case.states
+                    .iter()
+                    .map(|state| snake_case(&state.to_string()))
+                    .collect::<Vec<_>>()
+                    .join("_")
+            );
+            let args = vec!["todo!()"; case.args].join(", ");
+            let source = format!(
+                "fn {}(mut value: {}::{}<{}>) {{\n    let _ = value.{}({});\n}}",
+                fn_name, module, struct_name, states, case.method, args
+            );
+            (case, source)
+        })
+        .collect();
+
+    let docs = sources.iter().map(|(case, source)| {
+        let states = case
+            .states
+            .iter()
+            .map(|state| format!("`{}`", state))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (summary, fence) = if case.fails {
+            (
+                format!(" `{}` cannot be called in {}:", case.method, states),
+                "compile_fail",
+            )
+        } else {
+            (
+                format!(" `{}` can be called in {}:", case.method, states),
+                "",
+            )
+        };
+        let code = format!(
+            " ```{}\n # #![allow(unused, unreachable_code)]\n {}\n ```",
+            fence,
+            source.replace('\n', "\n ")
+        );
+        quote! {
+            #[doc = #summary]
+            #[doc = ""]
+            #[doc = #code]
+            #[doc = ""]
+        }
+    });
+    let compile_fail = sources
+        .iter()
+        .filter(|(case, _)| case.fails)
+        .map(|(_, source)| source);
+    let mod_doc = format!(
+        " The tests of the states of `{}`, generated by `generate_state_tests!`: each method is called in each of the states it cannot be called in",
+        struct_name
+    );
+
+    quote! {
+        #[doc = #mod_doc]
+        #[doc = ""]
+        #(#docs)*
+        #[allow(dead_code)]
+        mod #mod_name {
+            /// The sources of the compile-fail tests, which can be given to `trybuild` as well
+            pub const COMPILE_FAIL: &[&str] = &[#(#compile_fail),*];
+        }
+    }
+    .into()
+}
@@ -0,0 +1,412 @@
+/// this file contains the logic for the type-state protocols defined as traits:
+/// the methods of a trait can be annotated with `#[require]` and `#[switch_to]` as well,
+/// so that the same protocol can be implemented by multiple type-state structs
+///
+/// the trait is generic over the states (`trait Engine<Off, On>`), so that each implementor can plug in its own markers,
+/// and the state of the implementor is exposed through the `State` associated type, which gates the methods:
+/// `#[require(Off)] #[switch_to(On)] fn start(self) -> Self` -> `fn start(self) -> Self::On where Self::State: __EngineStateIs<Off>`
+use std::collections::BTreeMap;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use stringcase::snake_case;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    Attribute, GenericArgument, Ident, ImplItem, ItemImpl, ItemTrait, Path, PathArguments,
+    Signature, Token, TraitItem, Type, TypePath,
+};
+
+use crate::{
+    add_state_field, extract_macro_args, switch_to_inner, unsupported_self_type, RequireArg,
+    RequiredSlot, SwitchToArg, TypeStateArgs,
+};
+
+/// Name of the hidden macro generated for a type-state protocol: `__state_shift_trait_engine`
+///
+/// like the one generated by `#[type_state]` (see `callback_macro_name`), it carries the states of the trait
+/// to the implementations of the trait
+fn trait_callback_macro_name(trait_name: &Ident) -> Ident {
+    Ident::new(
+        &format!(
+            "__state_shift_trait_{}",
+            snake_case(&trait_name.to_string())
+        ),
+        trait_name.span(),
+    )
+}
+
+/// Name of the hidden helper trait that gates the methods of a type-state protocol: `__EngineStateIs`
+///
+/// it is implemented for every type with itself only, so `Self::State: __EngineStateIs<Off>` means `Self::State == Off`
+fn state_is_trait_name(trait_name: &Ident) -> Ident {
+    Ident::new(&format!("__{}StateIs", trait_name), trait_name.span())
+}
+
+/// Replaces the last segment of the path of the trait: `super::Engine<Off, On>` -> `super::__EngineStateIs<Off>`
+fn sibling_path(trait_path: &Path, ident: Ident, arguments: PathArguments) -> Path {
+    let mut path = trait_path.clone();
+    let last_segment = path.segments.last_mut().unwrap();
+    last_segment.ident = ident;
+    last_segment.arguments = arguments;
+    path
+}
+
+/// The required and the target states of a method of a type-state protocol
+struct ProtocolMethod {
+    required: Option<Ident>,
+    target: Option<Ident>,
+}
+
+impl ProtocolMethod {
+    /// Extracts the `#[require]` and `#[switch_to]` arguments of a method of a type-state protocol,
+    /// which can only be a single state, since the protocol tracks a single state slot
+    fn extract(attrs: &mut Vec<Attribute>) -> syn::Result<Self> {
+        let required = match extract_macro_args::<RequireArg>(attrs, "require")? {
+            Some((span, args)) => match args.iter().collect::<Vec<_>>().as_slice() {
+                [RequireArg {
+                    slot_name: None,
+                    slot: RequiredSlot::OneOf(states),
+                }] if states.len() == 1 && states[0].args.is_none() => {
+                    Some(states[0].ident.clone())
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        span,
+                        "`#[require]` of a trait method expects a single state",
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        let target = match extract_macro_args::<SwitchToArg>(attrs, "switch_to")? {
+            Some((span, args)) => match args.iter().collect::<Vec<_>>().as_slice() {
+                [SwitchToArg {
+                    slot_name: None,
+                    states: Some(states),
+                }] if states.len() == 1 && states[0].args.is_none() && required.is_some() => {
+                    Some(states[0].ident.clone())
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        span,
+                        "`#[switch_to]` of a trait method expects a single state, along with `#[require]`",
+                    ))
+                }
+            },
+            None => None,
+        };
+
+        Ok(ProtocolMethod { required, target })
+    }
+
+    /// Gates the signature on the required state, and switches the implementor in the return type to the target state:
+    /// `fn start(self) -> Self` -> `fn start(self) -> Self::On where Self::State: __EngineStateIs<Off>`
+    ///
+    /// `switched` is the associated type of the target state (`Self::On`),
+    /// and `self_name` is the name of the implementor, which is also switched if it is mentioned instead of `Self`
+    fn gate(
+        &self,
+        sig: &mut Signature,
+        state_is_trait: &Path,
+        switched: Option<&TypePath>,
+        self_name: &Ident,
+    ) -> syn::Result<()> {
+        let Some(required) = &self.required else {
+            return Ok(());
+        };
+
+        if let Some(switched) = switched {
+            sig.output = switch_to_inner(
+                &sig.output,
+                &Punctuated::new(),
+                self_name,
+                &PathArguments::None,
+                Some(switched),
+                None,
+                &sig.ident,
+            )?;
+        }
+
+        let mut state_is_trait = state_is_trait.clone();
+        state_is_trait.segments.last_mut().unwrap().arguments =
+            PathArguments::AngleBracketed(parse_quote!(<#required>));
+        sig.generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(Self::State: #state_is_trait));
+
+        Ok(())
+    }
+}
+
+/// Generates the type-state protocol from a trait definition annotated with `#[impl_state]`
+pub fn state_trait_inner(mut input: ItemTrait) -> TokenStream {
+    let trait_name = input.ident.clone();
+    let visibility = &input.vis;
+    let state_is_trait_name = state_is_trait_name(&trait_name);
+    let state_is_trait: Path = parse_quote!(#state_is_trait_name);
+    let trait_params: Vec<_> = input
+        .generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+
+    // the target states of the transitions, each of them gets an associated type: `type On;`
+    let mut targets = BTreeMap::new();
+    let mut errors = Vec::new();
+
+    for item in input.items.iter_mut() {
+        if let TraitItem::Fn(method) = item {
+            match ProtocolMethod::extract(&mut method.attrs) {
+                Ok(protocol_method) => {
+                    let switched = protocol_method
+                        .target
+                        .as_ref()
+                        .map(|target| parse_quote!(Self::#target));
+                    if let Err(error) = protocol_method.gate(
+                        &mut method.sig,
+                        &state_is_trait,
+                        switched.as_ref(),
+                        &trait_name,
+                    ) {
+                        errors.push(error.to_compile_error());
+                    }
+                    if let Some(target) = protocol_method.target {
+                        targets.insert(target.to_string(), target);
+                    }
+                }
+                Err(error) => errors.push(error.to_compile_error()),
+            }
+        }
+    }
+
+    input.items.insert(
+        0,
+        parse_quote! {
+            /// The current state of the implementor
+            type State;
+        },
+    );
+    input
+        .items
+        .extend(targets.into_values().map(|target| -> TraitItem {
+            let doc = format!(" The implementor in the `{}` state", target);
+            parse_quote! {
+                #[doc = #doc]
+                type #target;
+            }
+        }));
+
+    let callback_macro_name = trait_callback_macro_name(&trait_name);
+
+    quote! {
+        #(#errors)*
+
+        #input
+
+        #[doc(hidden)]
+        #visibility trait #state_is_trait_name<S> {}
+
+        impl<S> #state_is_trait_name<S> for S {}
+
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #callback_macro_name {
+            ($($tokens:tt)*) => {
+                ::state_shift::__impl_state_trait! { (#(#trait_params),*) $($tokens)* }
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #callback_macro_name;
+    }
+}
+
+/// Forwards the implementation of a type-state protocol to the hidden macro generated for the trait,
+/// which carries the states of the trait back (see `__impl_state_trait`)
+///
+/// `impl path::to::Engine<Off, On> for Car` -> `path::to::__state_shift_trait_engine! { (...) impl ... }`
+pub fn forward_impl_state_trait(input: ItemImpl, type_state_args: TokenStream) -> TokenStream {
+    let trait_path = &input.trait_.as_ref().unwrap().1;
+    let trait_name = &trait_path.segments.last().unwrap().ident;
+    let callback_path = sibling_path(
+        trait_path,
+        trait_callback_macro_name(trait_name),
+        PathArguments::None,
+    );
+
+    quote! {
+        #callback_path! { (#type_state_args) #input }
+    }
+}
+
+/// Input of the internal `__impl_state_trait` macro:
+/// `(<states of the trait>) (<arguments of #[type_state]>) impl ... for ... { ... }`
+struct ImplStateTraitInput {
+    trait_params: Vec<Ident>,
+    type_state_args: TypeStateArgs,
+    item: ItemImpl,
+}
+
+impl Parse for ImplStateTraitInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let trait_params;
+        parenthesized!(trait_params in input);
+        let type_state_args;
+        parenthesized!(type_state_args in input);
+        Ok(ImplStateTraitInput {
+            trait_params: Punctuated::<Ident, Token![,]>::parse_terminated(&trait_params)?
+                .into_iter()
+                .collect(),
+            type_state_args: type_state_args.parse()?,
+            item: input.parse()?,
+        })
+    }
+}
+
+/// Generates the implementation of a type-state protocol (a trait annotated with `#[impl_state]`) for a type-state struct:
+///
+/// `impl Engine<Docked, Sailing> for Boat { ... }` -> `impl<BoatState1: SealerBoat> Engine<Docked, Sailing> for Boat<BoatState1> { ... }`,
+/// with the `State` and the target state associated types filled in: `type On = Boat<Sailing>;`
+pub fn impl_state_trait_inner(input: proc_macro::TokenStream) -> TokenStream {
+    let ImplStateTraitInput {
+        trait_params,
+        type_state_args,
+        item: mut input,
+    } = match syn::parse(input) {
+        Ok(input) => input,
+        Err(error) => return error.to_compile_error(),
+    };
+
+    let trait_path = input.trait_.as_ref().unwrap().1.clone();
+    let (struct_name, struct_generics) = match *input.self_ty {
+        Type::Path(ref type_path) => {
+            let last_segment = type_path.path.segments.last().unwrap();
+            (last_segment.ident.clone(), last_segment.arguments.clone())
+        }
+        _ => return unsupported_self_type(&input.self_ty),
+    };
+
+    if type_state_args.slots.len() != 1 {
+        return syn::Error::new_spanned(
+            &trait_path,
+            "type-state protocols (traits with `#[require]`) can only be implemented by structs with a single slot",
+        )
+        .to_compile_error();
+    }
+
+    // the states of the implementor, in the order of the states of the trait: `Engine<Docked, Sailing>`
+    let trait_args: Vec<_> = match &trait_path.segments.last().unwrap().arguments {
+        PathArguments::AngleBracketed(arguments) => arguments
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                GenericArgument::Type(Type::Path(type_path)) => type_path.path.get_ident().cloned(),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let trait_param_of = |state: &Ident| {
+        trait_args
+            .iter()
+            .position(|arg| arg == state)
+            .and_then(|index| trait_params.get(index))
+            .ok_or_else(|| {
+                syn::Error::new(
+                    state.span(),
+                    format!(
+                        "state `{}` is not one of the states given to the trait",
+                        state
+                    ),
+                )
+            })
+    };
+
+    let state_generic = Ident::new(&format!("{}State1", struct_name), struct_name.span());
+    let sealer_trait_name = type_state_args.sealer_of_slot(0, &struct_name);
+    let state_is_trait = sibling_path(
+        &trait_path,
+        state_is_trait_name(&trait_path.segments.last().unwrap().ident),
+        PathArguments::None,
+    );
+    let struct_args = match &struct_generics {
+        PathArguments::AngleBracketed(angle_bracketed) => {
+            angle_bracketed.args.iter().cloned().collect()
+        }
+        _ => Vec::new(),
+    };
+
+    // the target states of the transitions, along with the states of the trait they correspond to
+    let mut targets = BTreeMap::new();
+    let mut errors = Vec::new();
+
+    for item in input.items.iter_mut() {
+        if let ImplItem::Fn(method) = item {
+            let protocol_method = ProtocolMethod::extract(&mut method.attrs).and_then(|method| {
+                let trait_param = method.target.as_ref().map(trait_param_of).transpose()?;
+                Ok((method, trait_param.cloned()))
+            });
+
+            match protocol_method {
+                Ok((protocol_method, trait_param)) => {
+                    let switched = trait_param
+                        .as_ref()
+                        .map(|trait_param| parse_quote!(Self::#trait_param));
+                    if let Err(error) = protocol_method.gate(
+                        &mut method.sig,
+                        &state_is_trait,
+                        switched.as_ref(),
+                        &struct_name,
+                    ) {
+                        errors.push(error.to_compile_error());
+                    }
+                    if let (Some(target), Some(trait_param)) = (protocol_method.target, trait_param)
+                    {
+                        targets.insert(trait_param.to_string(), (trait_param, target));
+                    }
+                }
+                Err(error) => errors.push(error.to_compile_error()),
+            }
+
+            // the struct is initialized in the body for the transitions
+            add_state_field(
+                &mut method.block,
+                &struct_name,
+                if type_state_args.stores_states() {
+                    quote!(::core::default::Default::default())
+                } else {
+                    quote!(::core::marker::PhantomData)
+                },
+                &type_state_args.shape,
+                &type_state_args.field,
+            );
+        }
+    }
+
+    let target_types = targets.into_values().map(|(trait_param, target)| {
+        quote!(type #trait_param = #struct_name<#(#struct_args,)* #target>;)
+    });
+    let impl_params = input.generics.params.iter();
+    let where_clause = &input.generics.where_clause;
+    let items = &input.items;
+
+    quote! {
+        #(#errors)*
+
+        impl<#(#impl_params,)* #state_generic: #sealer_trait_name> #trait_path
+            for #struct_name<#(#struct_args,)* #state_generic>
+        #where_clause
+        {
+            type State = #state_generic;
+            #(#target_types)*
+
+            #(#items)*
+        }
+    }
+}
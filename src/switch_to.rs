@@ -1,78 +1,516 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::quote;
+use stringcase::pascal_case;
 use syn::{
-    punctuated::Punctuated, visit_mut::VisitMut, Ident, PathArguments, ReturnType, Token, Type,
-    TypePath,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    visit_mut::VisitMut,
+    Attribute, Generics, Ident, ImplItemFn, PathArguments, ReturnType, Token, Type, TypePath,
 };
 
+use crate::{cfg_attrs, check_slot_states, generic_args, RequiredSlot, StateArg, TypeStateArgs};
+
+/// A single argument of the `#[switch_to]` macro, optionally addressing a named slot: `conn = Connected`
+///
+/// `states` is `None` for `_`, which keeps the state of the slot unchanged,
+/// and has more than one state for branching transitions: `Connected | Failed`
+pub struct SwitchToArg {
+    pub slot_name: Option<Ident>,
+    pub states: Option<Punctuated<StateArg, Token![|]>>,
+}
+
+impl Parse for SwitchToArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let slot_name = if input.peek(Ident) && input.peek2(Token![=]) {
+            let slot_name = input.parse()?;
+            input.parse::<Token![=]>()?;
+            Some(slot_name)
+        } else {
+            None
+        };
+
+        let states = if input.peek(Token![_]) {
+            input.parse::<Token![_]>()?;
+            None
+        } else {
+            Some(Punctuated::parse_separated_nonempty(input)?)
+        };
+
+        Ok(SwitchToArg { slot_name, states })
+    }
+}
+
+/// Takes the state of the guarded transitions out of the arguments of `#[switch_to]`:
+/// `#[switch_to(Connected, on_err = Disconnected)]` -> `Disconnected`,
+/// which is the state of the struct in the `Err` arm of the returned `Result`
+pub fn take_on_err(
+    switch_to_args: &mut Punctuated<SwitchToArg, Token![,]>,
+) -> syn::Result<Option<StateArg>> {
+    let is_on_err = |arg: &SwitchToArg| arg.slot_name.as_ref().is_some_and(|name| name == "on_err");
+    let Some(on_err) = switch_to_args.iter().find(|arg| is_on_err(arg)) else {
+        return Ok(None);
+    };
+    let slot_name = on_err.slot_name.as_ref().unwrap();
+    let state = match &on_err.states {
+        Some(states) if states.len() == 1 => states[0].clone(),
+        _ => {
+            return Err(syn::Error::new(
+                slot_name.span(),
+                "`on_err` expects a single state, which the struct is given in the `Err` arm: `on_err = Disconnected`",
+            ))
+        }
+    };
+    *switch_to_args = std::mem::take(switch_to_args)
+        .into_iter()
+        .filter(|arg| !is_on_err(arg))
+        .collect();
+    Ok(Some(state))
+}
+
+/// The arguments of `#[switch_to]` for the `Err` arm of a guarded transition:
+/// the slot switched by the transition gets the state of `on_err` instead, and the other slots are kept
+///
+/// `#[switch_to(_, Connected, on_err = Disconnected)]` -> `(_, Disconnected)`
+pub fn on_err_switch_to_args(
+    on_err: StateArg,
+    switch_to_args: &[Option<Punctuated<StateArg, Token![|]>>],
+    require_args: &[RequiredSlot],
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<Vec<Option<Punctuated<StateArg, Token![|]>>>> {
+    if type_state_args.stores_states() {
+        return Err(syn::Error::new(
+            on_err.ident.span(),
+            "`on_err` cannot be used with the states that carry data, since the struct is created with the same states in both arms",
+        ));
+    }
+    if switch_to_args_branching_slot(switch_to_args).is_some() {
+        return Err(syn::Error::new(
+            on_err.ident.span(),
+            "`on_err` cannot be used with the branching transitions, their enum has a variant for the failure already",
+        ));
+    }
+    let mut switched = switch_to_args
+        .iter()
+        .enumerate()
+        .filter(|(_, states)| states.is_some());
+    let (Some((slot, _)), None) = (switched.next(), switched.next()) else {
+        return Err(syn::Error::new(
+            on_err.ident.span(),
+            "`on_err` can only be used with the transitions of a single slot, which is given its state in the `Err` arm",
+        ));
+    };
+
+    let mut err_switch_to_args = switch_to_args.to_vec();
+    err_switch_to_args[slot] = Some(std::iter::once(on_err).collect());
+    check_slot_states(require_args, Some(&err_switch_to_args), type_state_args)?;
+    Ok(err_switch_to_args)
+}
+
+/// Returns the index of the slot that has more than one target state (`Connected | Failed`), if there is one
+pub fn switch_to_args_branching_slot(
+    switch_to_args: &[Option<Punctuated<StateArg, Token![|]>>],
+) -> Option<usize> {
+    switch_to_args
+        .iter()
+        .position(|states| states.as_ref().is_some_and(|states| states.len() > 1))
+}
+
+/// Name of the enum generated for a branching transition: `connect` -> `ConnectOutcome`
+pub fn outcome_enum_name(fn_name: &Ident) -> Ident {
+    Ident::new(
+        &format!("{}Outcome", pascal_case(&fn_name.to_string())),
+        fn_name.span(),
+    )
+}
+
+/// Generates the enum for a branching transition, with a variant for each of the target states of the branching slot:
+///
+/// `#[switch_to(Connected | Failed)]` on `fn connect` ->
+/// `enum ConnectOutcome { Connected(Player<Connected>), Failed(Player<Failed>) }`
+///
+/// the other slots become the generics of the enum (named after the slot, like the generics of the struct),
+/// so a single enum serves every combination of the `#[require]` alternatives
+pub fn generate_outcome_enum(
+    method: &ImplItemFn,
+    struct_name: &Ident,
+    struct_generics: &PathArguments,
+    impl_generics: &Generics,
+    branching_slot: usize,
+    branches: &Punctuated<StateArg, Token![|]>,
+    type_state_args: &TypeStateArgs,
+) -> TokenStream {
+    let fn_vis = &method.vis;
+    let enum_name = outcome_enum_name(&method.sig.ident);
+    let slot_count = type_state_args.slots.len();
+
+    let struct_generic_args = match struct_generics {
+        PathArguments::AngleBracketed(angle_bracketed) => {
+            angle_bracketed.args.iter().cloned().collect()
+        }
+        _ => Vec::new(),
+    };
+
+    let slot_generics: Vec<_> = (0..slot_count)
+        .filter(|&index| index != branching_slot)
+        .map(|index| {
+            (
+                Ident::new(
+                    &format!("{}State{}", struct_name, index + 1),
+                    struct_name.span(),
+                ),
+                type_state_args.sealer_of_slot(index, struct_name),
+            )
+        })
+        .collect();
+
+    let variants = branches.iter().map(|branch| {
+        let slot_args = (0..slot_count).map(|index| {
+            if index == branching_slot {
+                branch.clone()
+            } else {
+                Ident::new(
+                    &format!("{}State{}", struct_name, index + 1),
+                    struct_name.span(),
+                )
+                .into()
+            }
+        });
+        // the variants are named after the states: `Loaded<Config>` -> `Loaded(Store<Loaded<Config>>)`
+        let variant = &branch.ident;
+        let cfgs = type_state_args.cfgs_of_state(variant);
+        quote! {
+            #(#cfgs)*
+            #variant(#struct_name<#(#struct_generic_args,)* #(#slot_args),*>)
+        }
+    });
+
+    let impl_params = impl_generics.params.iter();
+    let where_clauses = impl_generics
+        .where_clause
+        .iter()
+        .flat_map(|where_clause| where_clause.predicates.iter())
+        .map(|predicate| quote!(#predicate))
+        .chain(
+            slot_generics
+                .iter()
+                .map(|(generic, sealer)| quote!(#generic: #sealer)),
+        );
+    let slot_generics = slot_generics.iter().map(|(generic, _)| generic);
+
+    let cfg_attrs = cfg_attrs(&method.attrs);
+
+    quote! {
+        #(#cfg_attrs)*
+        #fn_vis enum #enum_name<#(#impl_params,)* #(#slot_generics),*>
+        where #(#where_clauses),*
+        {
+            #(#variants),*
+        }
+    }
+}
+
+/// The generic arguments of the enum of a branching transition for a combination of `#[require]` arguments
+///
+/// the generics of the `impl` block, followed by the states of the slots other than the branching one:
+/// `ConnectOutcome<'a, T, Locked>`
+pub fn outcome_enum_type(
+    fn_name: &Ident,
+    impl_generics: &Generics,
+    other_slot_args: impl Iterator<Item = StateArg>,
+) -> TypePath {
+    let enum_name = outcome_enum_name(fn_name);
+    let impl_args = generic_args(&impl_generics.params);
+
+    syn::parse_quote!(#enum_name<#(#impl_args,)* #(#other_slot_args),*>)
+}
+
+/// Rewrites the return type of a method for `#[switch_to]`
+///
+/// the struct (or `Self`) in the return type gets `parsed_args` as its states,
+/// or it is replaced with the enum of a branching transition, if there is an `outcome`
+///
+/// for the guarded transitions, the struct in the `Err` arm of the `Result` gets the states of `on_err`:
+/// `#[switch_to(Connected, on_err = Disconnected)]`: `-> Result<Self, (Self, Error)>`
+/// -> `-> Result<Player<Connected>, (Player<Disconnected>, Error)>`
 pub fn switch_to_inner(
     fn_output: &ReturnType,
-    parsed_args: &Punctuated<Ident, Token![,]>,
+    parsed_args: &Punctuated<StateArg, Token![,]>,
     struct_name: &Ident,
+    struct_generics: &PathArguments,
+    outcome: Option<&TypePath>,
+    on_err: Option<&Punctuated<StateArg, Token![,]>>,
     fn_name: &Ident,
-) -> ReturnType {
-    let generic_idents: Vec<syn::GenericArgument> = parsed_args
-        .iter()
-        .map(|i| {
-            syn::GenericArgument::Type(Type::Path(TypePath {
-                qself: None,
-                path: i.clone().into(),
-            }))
-        })
-        .collect();
+) -> syn::Result<ReturnType> {
+    let to_generic_idents = |args: &Punctuated<StateArg, Token![,]>| -> Vec<syn::GenericArgument> {
+        args.iter().map(StateArg::to_generic_argument).collect()
+    };
+    let generic_idents = to_generic_idents(parsed_args);
 
     let original_return_type = match &fn_output {
         ReturnType::Type(_, ty) => &**ty,
-        _ => panic!(
-            "Function `{}`: Expected a return type with explicit type annotation (e.g., '-> Type'), but found none.",
-            fn_name
-        ),
+        ReturnType::Default => return Err(missing_return_type(fn_name)),
     };
 
     let mut modified_return_type = original_return_type.clone();
 
+    // the `Err` arm of a guarded transition has to carry the struct, so that it is given its states
+    if on_err.is_some() {
+        let err_type = result_types(&mut modified_return_type).and_then(|(_, err_type)| err_type);
+        if !err_type.is_some_and(|err_type| mentions_struct(quote!(#err_type), struct_name)) {
+            return Err(syn::Error::new(
+                fn_name.span(),
+                format!(
+                    "`{}` has `on_err`, so it has to return a `Result` whose `Err` arm carries the struct (e.g. `-> Result<Self, Self>`)",
+                    fn_name
+                ),
+            ));
+        }
+    }
+
     // Recursively modify the return type, using the struct_name to match
     recursively_modify_return_type(
         &mut modified_return_type,
         generic_idents,
+        on_err.map(to_generic_idents),
         struct_name,
+        struct_generics,
+        outcome,
         fn_name,
-    );
+    )?;
 
-    ReturnType::Type(Default::default(), Box::new(modified_return_type))
+    Ok(ReturnType::Type(
+        Default::default(),
+        Box::new(modified_return_type),
+    ))
+}
+
+/// The error of a transition that doesn't return anything, so there is no struct to give the new states to
+fn missing_return_type(fn_name: &Ident) -> syn::Error {
+    syn::Error::new(
+        fn_name.span(),
+        format!(
+            "`{}` is a transition, so it has to return the struct in its new state (e.g. `-> Self`)",
+            fn_name
+        ),
+    )
 }
 
 // utilize `visit_type_mut` to handle all the variants of the return type in `syn`
 // otherwise, we would have to write a lot of match arms
-fn visit_type(ty: &mut Type, visitor: impl Fn(&mut TypePath)) {
-    struct TypeVisitor<F>(F);
-    impl<F: Fn(&mut TypePath)> VisitMut for TypeVisitor<F> {
-        fn visit_type_path_mut(&mut self, type_path: &mut TypePath) {
-            (self.0)(type_path);
+struct ReturnTypeVisitor<'a> {
+    generic_idents: Vec<syn::GenericArgument>,
+    /// the states of the struct in the `Err` arm of the returned `Result`, for the guarded transitions (see `on_err`)
+    err_generic_idents: Option<Vec<syn::GenericArgument>>,
+    struct_name: &'a Ident,
+    struct_generics: &'a PathArguments,
+    outcome: Option<&'a TypePath>,
+    /// `false` for cross-type transitions, where `Self` is still the struct with the required states
+    rewrite_self: bool,
+    fn_name: &'a Ident,
+    /// the first segment of the struct that could not be given the states
+    error: Option<syn::Error>,
+}
+
+impl VisitMut for ReturnTypeVisitor<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        // for fallible transitions, only the `Ok` variant is switched: `Result<Self, Error>` -> `Result<Player<B>, Error>`,
+        // so that the `Err` variant can still give back the object in its original state: `Result<Self, (Self, Error)>`,
+        // or in the state of `on_err`, for the guarded transitions (only the outermost `Result` is guarded)
+        let err_generic_idents = self.err_generic_idents.take();
+        if let Some((ok_type, err_type)) = result_types(ty) {
+            if let (Some(err_type), Some(err_generic_idents)) = (err_type, err_generic_idents) {
+                let ok_generic_idents =
+                    std::mem::replace(&mut self.generic_idents, err_generic_idents);
+                self.visit_type_mut(err_type);
+                self.generic_idents = ok_generic_idents;
+            }
+            return self.visit_type_mut(ok_type);
+        }
+
+        // for the tuples, only the first element that is the struct is switched, and the rest are passed through:
+        // `(Self, Report)` -> `(Parser<Parsed>, Report)`, `(Self, Self)` -> `(Parser<Parsed>, Self)`
+        if let Type::Tuple(tuple) = ty {
+            let struct_name = self.struct_name.clone();
+            if let Some(transitioned) = tuple
+                .elems
+                .iter_mut()
+                .find(|elem| mentions_struct(quote!(#elem), &struct_name))
+            {
+                self.visit_type_mut(transitioned);
+            }
+            return;
+        }
+
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_type_path_mut(&mut self, type_path: &mut TypePath) {
+        // handle the nested types first: `Option<Self>`, `Box<Self>`, ...
+        syn::visit_mut::visit_type_path_mut(self, type_path);
+
+        // the return type of a branching transition is the generated enum instead of the struct itself
+        if let Some(outcome) = self.outcome {
+            let is_struct = (self.rewrite_self && type_path.path.is_ident("Self"))
+                || type_path
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == *self.struct_name);
+            if type_path.qself.is_none() && is_struct {
+                *type_path = outcome.clone();
+            }
+            return;
+        }
+
+        // `Self` refers to the struct with the required states, so it is replaced with the struct itself
+        if self.rewrite_self && type_path.qself.is_none() && type_path.path.is_ident("Self") {
+            let segment = &mut type_path.path.segments[0];
+            segment.ident = self.struct_name.clone();
+            segment.arguments = self.struct_generics.clone();
+        }
+
+        // Check each segment in the path
+        for segment in type_path.path.segments.iter_mut() {
+            if segment.ident == *self.struct_name {
+                if let Err(error) =
+                    modify_segment(segment, self.generic_idents.clone(), self.fn_name)
+                {
+                    self.error.get_or_insert(error);
+                }
+            }
         }
     }
-    TypeVisitor(visitor).visit_type_mut(ty);
 }
 
+/// Rewrites the struct (or `Self`) wherever it appears in the return type:
+/// `Self`, `Option<Self>`, `Box<Self>`, `(Self, Stats)`, `Result<(Self, T), E>`, ...
 fn recursively_modify_return_type(
     ty: &mut Type,
     generic_idents: Vec<syn::GenericArgument>,
+    err_generic_idents: Option<Vec<syn::GenericArgument>>,
     struct_name: &Ident,
+    struct_generics: &PathArguments,
+    outcome: Option<&TypePath>,
     fn_name: &Ident,
-) {
-    visit_type(ty, |type_path| {
-        // Check each segment in the path
-        for segment in type_path.path.segments.iter_mut() {
-            if segment.ident == *struct_name {
-                modify_segment(segment, generic_idents.clone(), fn_name);
-            }
-        }
+) -> syn::Result<()> {
+    let mut visitor = ReturnTypeVisitor {
+        generic_idents,
+        err_generic_idents,
+        struct_name,
+        struct_generics,
+        outcome,
+        rewrite_self: true,
+        fn_name,
+        error: None,
+    };
+    visitor.visit_type_mut(ty);
+
+    match visitor.error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Extracts the target of a cross-type transition, which is another type-state struct with its states:
+/// `#[switch_to(Connection<Established>)]`
+///
+/// the generic states of the struct itself (`#[switch_to(Loaded<Config>)]`) are not cross-type transitions
+pub fn extract_other_type_target(attrs: &mut Vec<Attribute>, states: &[Ident]) -> Option<TypePath> {
+    let position = attrs
+        .iter()
+        .position(|attr| other_type_target(attr, states).is_some())?;
+    attrs.remove(position).parse_args().ok()
+}
+
+/// The target of a cross-type transition, if the attribute is the `#[switch_to]` of one (see `extract_other_type_target`)
+pub fn other_type_target(attr: &Attribute, states: &[Ident]) -> Option<TypePath> {
+    if !attr.path().is_ident("switch_to") {
+        return None;
+    }
+    attr.parse_args::<TypePath>().ok().filter(|target| {
+        target.path.segments.last().is_some_and(|segment| {
+            matches!(segment.arguments, PathArguments::AngleBracketed(_))
+                && !states.contains(&segment.ident)
+        })
+    })
+}
+
+/// Rewrites the return type of a method for a cross-type transition:
+/// the other struct in the return type gets the states of the target
+///
+/// `#[switch_to(Connection<Established>)]`: `-> Result<Connection, Error>` -> `-> Result<Connection<Established>, Error>`
+pub fn switch_to_other_type(
+    fn_output: &ReturnType,
+    target: &TypePath,
+    fn_name: &Ident,
+) -> syn::Result<ReturnType> {
+    let target_segment = target.path.segments.last().unwrap();
+    let generic_idents = match &target_segment.arguments {
+        PathArguments::AngleBracketed(arguments) => arguments.args.iter().cloned().collect(),
+        _ => Vec::new(),
+    };
+
+    let mut modified_return_type = match &fn_output {
+        ReturnType::Type(_, ty) => (**ty).clone(),
+        ReturnType::Default => return Err(missing_return_type(fn_name)),
+    };
+
+    let mut visitor = ReturnTypeVisitor {
+        generic_idents,
+        err_generic_idents: None,
+        struct_name: &target_segment.ident,
+        struct_generics: &PathArguments::None,
+        outcome: None,
+        rewrite_self: false,
+        fn_name,
+        error: None,
+    };
+    visitor.visit_type_mut(&mut modified_return_type);
+    if let Some(error) = visitor.error {
+        return Err(error);
+    }
+
+    Ok(ReturnType::Type(
+        Default::default(),
+        Box::new(modified_return_type),
+    ))
+}
+
+/// Whether the tokens of a type mention the struct or `Self`
+pub fn mentions_struct(tokens: TokenStream, struct_name: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == *struct_name || ident == "Self",
+        TokenTree::Group(group) => mentions_struct(group.stream(), struct_name),
+        _ => false,
+    })
+}
+
+/// Returns the `Ok` type of a `Result`, along with its `Err` type (unless it is left to an alias: `io::Result<Self>`),
+/// if the given type is a `Result`: `Result<Self, Error>` -> `(Self, Some(Error))`
+fn result_types(ty: &mut Type) -> Option<(&mut Type, Option<&mut Type>)> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let last_segment = type_path.path.segments.last_mut()?;
+    if last_segment.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &mut last_segment.arguments else {
+        return None;
+    };
+    let mut types = arguments.args.iter_mut().filter_map(|arg| match arg {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
     });
+    let ok_type = types.next()?;
+    Some((ok_type, types.next()))
 }
 
 fn modify_segment(
     segment: &mut syn::PathSegment,
     generic_idents: Vec<syn::GenericArgument>,
     fn_name: &Ident,
-) {
+) -> syn::Result<()> {
     match &mut segment.arguments {
         PathArguments::AngleBracketed(arguments) => {
             arguments.args.extend(generic_idents);
@@ -86,9 +524,16 @@ fn modify_segment(
                     gt_token: Default::default(),
                 });
         }
-        _ => panic!(
-            "Function `{}`: Unsupported arguments in return type of the function.",
-            fn_name
-        ),
+        PathArguments::Parenthesized(arguments) => {
+            return Err(syn::Error::new_spanned(
+                arguments,
+                format!(
+                    "`{}`: the struct in the return type can only be given its generics in angle brackets",
+                    fn_name
+                ),
+            ))
+        }
     }
+
+    Ok(())
 }
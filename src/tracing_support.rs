@@ -0,0 +1,182 @@
+/// this file contains the `tracing` instrumentation of the transitions (with the `tracing` feature):
+/// each `#[switch_to]` method runs in a `transition` span, which records the struct, the method,
+/// and the states it switches from and to, so that the state machines can be observed without hand-written logging,
+/// and the lighter `log::trace!` lines with the same metadata, for the structs with `log = true` (with the `log` feature)
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{punctuated::Punctuated, Block, ImplItemFn, Token, TypePath};
+
+use crate::{RequireCombination, StateArg, TypeStateArgs};
+
+/// The name of the state of a slot, as an expression: `"Idle"` for the concrete states,
+/// and `<A as SealerPlayer>::STATE_NAME` for the generic ones, which are only known at runtime
+fn state_name(
+    slot: usize,
+    state: &StateArg,
+    combination: &RequireCombination,
+    struct_name: &syn::Ident,
+    type_state_args: &TypeStateArgs,
+) -> TokenStream {
+    let is_generic = state.is_generic()
+        || combination
+            .generics
+            .iter()
+            .any(|(generic, _)| *generic == state.ident);
+    if is_generic {
+        let sealer = type_state_args.sealer_of_slot(slot, struct_name);
+        let generic = &state.ident;
+        quote!(<#generic as #sealer>::STATE_NAME)
+    } else {
+        let name = type_state_args.unprefixed_name(&state.ident).to_string();
+        quote!(#name)
+    }
+}
+
+/// The names of the states of all the slots, as a single value to display:
+/// the name itself for a single slot, and `(Idle, Locked)` for several slots
+fn states_field(names: Vec<TokenStream>) -> TokenStream {
+    match names.as_slice() {
+        [name] => quote!(#name),
+        _ => {
+            let format = format!(
+                "({})",
+                names.iter().map(|_| "{}").collect::<Vec<_>>().join(", ")
+            );
+            quote!(::core::format_args!(#format, #(#names),*))
+        }
+    }
+}
+
+/// The states that a transition switches from and to, as values to display (see `states_field`):
+/// the states of the slots that are kept (`_`) are the required ones,
+/// the branching slots give all of their states: `Running | Crashed`,
+/// and the cross-type transitions give their target: `Connection<Established>`
+fn transition_states(
+    combination: &RequireCombination,
+    switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    other_type_target: Option<&TypePath>,
+    struct_name: &syn::Ident,
+    type_state_args: &TypeStateArgs,
+) -> Option<(TokenStream, TokenStream)> {
+    let from = states_field(
+        combination
+            .args
+            .iter()
+            .enumerate()
+            .map(|(slot, state)| state_name(slot, state, combination, struct_name, type_state_args))
+            .collect(),
+    );
+    let to = match (other_type_target, switch_to_args) {
+        (Some(target), _) => {
+            let target = target.to_token_stream().to_string().replace(' ', "");
+            quote!(#target)
+        }
+        (None, Some(switch_to_args)) => states_field(
+            switch_to_args
+                .iter()
+                .zip(&combination.args)
+                .enumerate()
+                .map(|(slot, (states, required))| match states {
+                    Some(states) if states.len() > 1 => {
+                        let names = states
+                            .iter()
+                            .map(|state| type_state_args.unprefixed_name(&state.ident).to_string())
+                            .collect::<Vec<_>>()
+                            .join(" | ");
+                        quote!(#names)
+                    }
+                    Some(states) => {
+                        state_name(slot, &states[0], combination, struct_name, type_state_args)
+                    }
+                    None => state_name(slot, required, combination, struct_name, type_state_args),
+                })
+                .collect(),
+        ),
+        (None, None) => return None,
+    };
+    Some((from, to))
+}
+
+/// Wraps the body of a transition in its `transition` span, along with a `debug` event:
+///
+/// ```ignore
+/// {
+///     let span = ::tracing::debug_span!("transition", r#struct = "Player", method = "start", from = "Idle", to = "Running");
+///     let _entered = span.enter();
+///     ::tracing::debug!("switching from Idle to Running");
+///     <body>
+/// }
+/// ```
+///
+/// (the `async` transitions only record the event, since the span cannot be entered across the `await`s)
+pub fn traced_body(
+    method: &ImplItemFn,
+    body: Block,
+    combination: &RequireCombination,
+    switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    other_type_target: Option<&TypePath>,
+    struct_name: &syn::Ident,
+    type_state_args: &TypeStateArgs,
+) -> Block {
+    let Some((from, to)) = transition_states(
+        combination,
+        switch_to_args,
+        other_type_target,
+        struct_name,
+        type_state_args,
+    ) else {
+        return body;
+    };
+    let struct_field = struct_name.to_string();
+    let method_field = method.sig.ident.to_string();
+
+    let enter = method
+        .sig
+        .asyncness
+        .is_none()
+        .then(|| quote!(let __state_shift_entered = __state_shift_span.enter();));
+    let stmts = &body.stmts;
+    syn::parse_quote!({
+        let __state_shift_span = ::tracing::debug_span!(
+            "transition",
+            r#struct = #struct_field,
+            method = #method_field,
+            from = %#from,
+            to = %#to,
+        );
+        #enter
+        ::tracing::debug!(parent: &__state_shift_span, "switching from {} to {}", #from, #to);
+        #(#stmts)*
+    })
+}
+
+/// Starts the body of a transition with its `log::trace!` line:
+/// `transition struct=Player method=start from=Idle to=Running`
+pub fn logged_body(
+    method: &ImplItemFn,
+    body: Block,
+    combination: &RequireCombination,
+    switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    other_type_target: Option<&TypePath>,
+    struct_name: &syn::Ident,
+    type_state_args: &TypeStateArgs,
+) -> Block {
+    let Some((from, to)) = transition_states(
+        combination,
+        switch_to_args,
+        other_type_target,
+        struct_name,
+        type_state_args,
+    ) else {
+        return body;
+    };
+    let format = format!(
+        "transition struct={} method={} from={{}} to={{}}",
+        struct_name, method.sig.ident
+    );
+    let stmts = &body.stmts;
+    syn::parse_quote!({
+        ::log::trace!(#format, #from, #to);
+        #(#stmts)*
+    })
+}
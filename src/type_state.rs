@@ -1,85 +1,1415 @@
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, ToTokens};
 use stringcase::snake_case;
-use syn::{parse_macro_input, Fields, Ident, ItemStruct};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream, Parser},
+    parse_macro_input,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    token, Attribute, Fields, FieldsNamed, GenericArgument, Generics, Ident, Item, ItemEnum,
+    ItemStruct, LitBool, LitInt, LitStr, Member, Path, PathArguments, Token, Type, Variant,
+    Visibility,
+};
 
-use crate::extract_idents_from_group;
+use crate::{
+    all_states, any_state_enum, builder_derive, cfg_attrs, closest_name, debug_impl, derive_impls,
+    hooks_registry, in_place_helper, marker_derives, missing_transitions_fallback, only_in_fields,
+    serde_support, state_aliases, state_data_accessors, state_enum, state_name_accessors,
+    state_rebuilders, state_registry, state_set, take_derives, StateArg, DEFAULT_ALIAS_NAMING,
+    REPLACED_DERIVES,
+};
 
-pub fn type_state_inner(args: TokenStream, input: TokenStream) -> TokenStream {
-    // Parse the input struct
-    let input_struct = parse_macro_input!(input as ItemStruct);
-    let struct_name = &input_struct.ident;
-    let generics = &input_struct.generics;
-    let visibility = &input_struct.vis;
+/// Arguments of the `#[type_state]` macro:
+/// `#[type_state(states = (State1, State2, State3), slots = (State1, State1), groups = (Group1 = (State1, State2)))]`
+///
+/// optionally, the states allowed in each slot can be restricted with:
+/// `slot_states = ((State1, State2), (State1, State3))`
+///
+/// and the `transition_in_place` helper can be opted into with: `in_place = true`
+///
+/// and the `into_state` escape hatch, with the `into_state` feature: `into_state = true`
+///
+/// and the `reset` method, which moves the struct back into its default states: `reset = true`
+///
+/// and the `log` lines of the transitions, with the `log` feature: `log = true`
+///
+/// and the enum of the struct in any of its states can be opted into with: `any_state = true` (see `any_state_enum`)
+///
+/// and the enum of the states themselves, which tells the state at runtime, with: `state_enum = true` (see `state_enum`)
+///
+/// and the type aliases of the struct in each state, with: `aliases = true` (see `state_aliases`)
+///
+/// and the hidden macro of the struct can be exported for the other crates with: `extensible = true`
+///
+/// and the sealing of the states can be opted out of with: `sealed = false`
+///
+/// and the `#[must_use]` of the transition methods can be opted out of with: `must_use = false`
+///
+/// and the `Serialize` and `Deserialize` impls can be opted into with: `serde = true`, or `serde = (Ready)`
+/// for the states that can be deserialized into, besides the default ones (with the `serde` feature)
+///
+/// the states that cannot be left once they are reached can be declared as: `terminal = (Closed)`
+/// (see `check_terminal_states`)
+///
+/// the transitions of the `impl` blocks can be checked with: `warn(unreachable)` or `deny(unreachable)`
+/// (see `Lint`), and against the ones declared with `transitions!`, with: `declared_transitions = true`
+/// (see `TransitionGraph::declared_checks`)
+///
+/// the hidden field of the states can be renamed with: `field = "__ts_state"`, if the struct has a `_state` field already
+///
+/// and the type of the hidden field can be chosen with: `phantom = "owned"` (see `PhantomKind`)
+///
+/// and the visibility of the markers and the groups can be chosen with: `vis = pub(crate)` (`pub` by default),
+/// and they can be hidden from the docs along with the sealer with: `doc_hidden = true`
+///
+/// the markers of the states can be defined by hand as well: `states = (external(Idle), Running)`
+///
+/// and the markers and the groups can be prefixed with: `prefix = "Player"`,
+/// then the names of the states and the groups in `TypeStateArgs` are the prefixed ones (`PlayerReady`),
+/// and the unprefixed ones given to `#[require]`, `#[switch_to]` and `#[only_in]` are resolved to them (see `resolve_names`)
+///
+/// the states can be generic as well: `states = (Empty, Loaded<T>)`,
+/// and they can carry data: `states = (Disconnected, Connected { session_id: u64 })`
+///
+/// other type-state structs (with a single slot) can be composed into the struct as well:
+/// `components = (auth: Auth<LoggedOut>, conn: Connection<Closed>)`,
+/// then each component is a field of the struct, and it gets a slot for its states (see `StateSlot`)
+///
+/// the states of another struct can be reused as well: `reuse = Article`, with the struct's own `slots`,
+/// then the markers and the sealer of `Article` are shared instead of being generated again
+/// (see `reuse_states`)
+///
+/// and the struct and its states can be re-exported for the `impl` blocks of the other modules with: `registry = player_states`
+/// (see `state_registry`)
+///
+/// the states can have substates too: `states = (Disconnected, Connected(Active, Idle))`,
+/// then the parent state is a group of its substates (see `StateGroup`), and only the substates are the actual states
+///
+/// these are also carried over to the `#[impl_state]` macro (see `callback_macro_name`),
+/// so that the `impl` blocks know about the declared states
+pub struct TypeStateArgs {
+    pub states: Vec<Ident>,
+    /// the generic parameters of the generic states: `Loaded<T>` -> `(Loaded, <T>)`
+    pub state_generics: Vec<(Ident, Generics)>,
+    /// the fields of the states that carry data: `Connected { session_id: u64 }` -> `(Connected, { session_id: u64 })`
+    pub state_fields: Vec<(Ident, FieldsNamed)>,
+    pub slots: Vec<StateSlot>,
+    pub groups: Vec<StateGroup>,
+    /// the parents of the substates: `Connected(Active, Idle)` -> `(Active, Connected)`, `(Idle, Connected)`
+    pub parents: Vec<(Ident, Ident)>,
+    /// the struct whose states (along with their markers and sealer) are reused: `reuse = Article`
+    pub reuse: Option<Path>,
+    /// the module that re-exports the struct and its states for the `impl` blocks of the other modules: `registry = player_states`
+    pub registry: Option<Ident>,
+    pub in_place: bool,
+    /// whether the `unsafe fn into_state` escape hatch is generated: `into_state = true`
+    pub into_state: bool,
+    /// whether the `reset` method is generated: `reset = true`
+    pub reset: bool,
+    /// whether the transitions of the methods are checked against the ones of `transitions!`: `declared_transitions = true`
+    pub declared_transitions: bool,
+    /// whether the transitions are logged with `log::trace!` (with the `log` feature): `log = true`
+    pub log: bool,
+    /// whether the enum of the struct in any of its states is generated: `any_state = true`
+    pub any_state: bool,
+    /// the naming of the type aliases of the struct in each state, if they are generated:
+    /// `aliases = true` -> `Some("{state}{struct}")`, `aliases = "{struct}In{state}"`
+    pub aliases: Option<String>,
+    /// whether the enum of the states is generated, along with `current_state()`: `state_enum = true`
+    pub state_enum: bool,
+    /// whether other crates can use `#[impl_state]` for the struct too: `extensible = true`
+    pub extensible: bool,
+    /// whether the sealer trait is sealed, so that only the declared states implement it (opted out with `sealed = false`)
+    pub sealed: bool,
+    /// whether the transition methods are `#[must_use]`, since they consume the struct (opted out with `must_use = false`)
+    pub must_use: bool,
+    /// the prefix of the names of the markers and the groups: `prefix = "Player"` -> `PlayerReady`
+    pub prefix: Option<String>,
+    /// the states whose markers are defined by hand, instead of being generated: `external(Idle)`
+    pub external_states: Vec<Ident>,
+    /// whether `#[type_state]` is used on a struct with named fields, a tuple struct or an enum
+    pub shape: StructShape,
+    /// the name of the hidden field that holds the states: `_state` by default, or `field = "__ts_state"`
+    pub field: Ident,
+    /// the `PhantomData` of the hidden field: `phantom = "fn_pointer"`, `"owned"` or `"invariant"`
+    pub phantom: PhantomKind,
+    /// the `#[cfg]` attributes of the gated states (and parent states): `#[cfg(feature = "tls")] Handshaking`
+    pub state_cfgs: Vec<(Ident, Vec<Attribute>)>,
+    /// the visibility of the markers and the group traits: `vis = pub(crate)`, or `vis = pub(self)` for the private ones
+    pub marker_vis: Visibility,
+    /// whether the markers, the sealer and the group traits are `#[doc(hidden)]`: `doc_hidden = true`
+    pub doc_hidden: bool,
+    /// the derives of the generated markers: `derive(Clone, PartialEq, Hash)`
+    pub marker_derives: Vec<Path>,
+    /// the states that the methods cannot transition out of: `terminal = (Closed)`
+    /// (the groups given to it are replaced with their states)
+    pub terminal_states: Vec<Ident>,
+    /// the checks of the transitions, along with whether they are warnings or errors: `warn(unreachable)`
+    pub lints: Vec<(Lint, LintLevel)>,
+    /// the states that can be deserialized into besides the default ones, when serde is opted into:
+    /// `serde = true` -> `Some([])`, `serde = (Ready)` -> `Some([Ready])`
+    /// (the groups given to it are replaced with their states)
+    pub serde: Option<Vec<Ident>>,
+}
 
-    // Parse arguments (states and slots)
-    /*
-    Indices:
-    ---
-    0. `states`
-    1. `=`
-    2. `(State1, State2, State3)`
-    3. `,`
-    4. `slots`
-    5. `=`
-    6. `(State1, State1)`
-     */
-    let input_args: Vec<_> = args.into_iter().collect();
-    let states: Vec<Ident> = extract_idents_from_group(&input_args[2], "expected a list of states");
-
-    let default_slots: Vec<Ident> =
-        extract_idents_from_group(&input_args[6], "expected a list of default slots");
-
-    // Generate the marker structs and sealing traits
-    let sealer_trait_name = Ident::new(&format!("Sealer{}", struct_name), struct_name.span());
-    let sealed_mod_name = Ident::new(
-        &format!("sealed_{}", snake_case(&struct_name.to_string())),
-        struct_name.span(),
+/// A check of the transitions of an `impl` block (see `TransitionGraph`), which is opted into with `warn(...)` or `deny(...)`:
+/// - `unreachable`: the declared states that cannot be reached from the default states (or the constructors),
+/// - `dead_end`: the states that the methods transition into, but no method can be called in
+///   (except for the terminal states, which are meant to be the end)
+///
+/// (they are opt-in, since the `impl` blocks are checked one at a time,
+/// and a struct whose methods are split into several `impl` blocks would have false positives)
+#[derive(Clone, Copy, PartialEq)]
+pub enum Lint {
+    Unreachable,
+    DeadEnd,
+}
+
+impl Lint {
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::Unreachable => "unreachable",
+            Lint::DeadEnd => "dead_end",
+        }
+    }
+}
+
+impl Parse for Lint {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lint: Ident = input.parse()?;
+        match lint.to_string().as_str() {
+            "unreachable" => Ok(Lint::Unreachable),
+            "dead_end" => Ok(Lint::DeadEnd),
+            _ => Err(syn::Error::new(
+                lint.span(),
+                "unknown lint, expected `unreachable` or `dead_end`",
+            )),
+        }
+    }
+}
+
+/// Whether a lint is reported as a warning (`warn(...)`) or as an error (`deny(...)`)
+#[derive(Clone, Copy, PartialEq)]
+pub enum LintLevel {
+    Warn,
+    Deny,
+}
+
+/// The type of the hidden field of the states, which decides the variance and the auto traits of the struct
+/// with respect to its states:
+/// - `FnPointer`: `PhantomData<fn() -> State1>`, the states never affect `Send`, `Sync` or the drop check,
+///   and the struct is covariant in them (the default),
+/// - `Owned`: `PhantomData<State1>`, the struct behaves as if it owned its states,
+/// - `Invariant`: `PhantomData<fn(State1) -> State1>`, the struct is invariant in its states
+///
+/// (the states that are stored in the field are not affected, see `TypeStateArgs::stores_states`)
+#[derive(Clone, Copy, Default)]
+pub enum PhantomKind {
+    #[default]
+    FnPointer,
+    Owned,
+    Invariant,
+}
+
+impl Parse for PhantomKind {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let kind: LitStr = input.parse()?;
+        match kind.value().as_str() {
+            "fn_pointer" => Ok(PhantomKind::FnPointer),
+            "owned" => Ok(PhantomKind::Owned),
+            "invariant" => Ok(PhantomKind::Invariant),
+            _ => Err(syn::Error::new(
+                kind.span(),
+                "expected `\"fn_pointer\"`, `\"owned\"` or `\"invariant\"`",
+            )),
+        }
+    }
+}
+
+impl PhantomKind {
+    /// The `PhantomData` of the slot with the given generic: `PhantomData<fn() -> State1>`
+    pub fn phantom_type(self, state: &Ident) -> proc_macro2::TokenStream {
+        match self {
+            PhantomKind::FnPointer => quote!(::core::marker::PhantomData<fn() -> #state>),
+            PhantomKind::Owned => quote!(::core::marker::PhantomData<#state>),
+            PhantomKind::Invariant => quote!(::core::marker::PhantomData<fn(#state) -> #state>),
+        }
+    }
+}
+
+/// The shape of the type-state struct, which is given by `#[type_state]` itself (not by the users) to `#[impl_state]`,
+/// after the arguments of the users: `@shape(tuple(2))` (see `TypeStateArgs::parse`):
+/// - `Named`: a struct with named fields, which gets the `_state` field,
+/// - `Tuple`: a tuple struct with the given number of elements, which gets the `_state` element at the end:
+///   `@shape(tuple(2))`,
+/// - `Unit`: a unit struct, which becomes a struct with only the `_state` field: `@shape(unit)`,
+///   so the struct is initialized with its name alone (`Self`, `Token`), which gets the `_state` too
+/// - `Enum`: an enum, whose variants get the `_state` field (as the last element, for the tuple variants):
+///   `@shape(variants(Data, Ack))`, along with the tuple variants, since their initializations get the `_state` too
+#[derive(Default)]
+pub enum StructShape {
+    #[default]
+    Named,
+    Tuple(usize),
+    Unit,
+    Enum {
+        tuple_variants: Vec<Ident>,
+    },
+}
+
+impl Parse for StructShape {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let shape: Ident = input.parse()?;
+        match shape.to_string().as_str() {
+            "tuple" => {
+                let content;
+                parenthesized!(content in input);
+                Ok(StructShape::Tuple(
+                    content.parse::<LitInt>()?.base10_parse()?,
+                ))
+            }
+            "unit" => Ok(StructShape::Unit),
+            "variants" => Ok(StructShape::Enum {
+                tuple_variants: parse_ident_list(input)?,
+            }),
+            _ => Err(syn::Error::new(
+                shape.span(),
+                "expected `tuple`, `unit` or `variants`",
+            )),
+        }
+    }
+}
+
+impl ToTokens for StructShape {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        tokens.extend(match self {
+            StructShape::Named => quote!(named),
+            StructShape::Tuple(len) => {
+                let len = LitInt::new(&len.to_string(), proc_macro2::Span::call_site());
+                quote!(tuple(#len))
+            }
+            StructShape::Unit => quote!(unit),
+            StructShape::Enum { tuple_variants } => quote!(variants(#(#tuple_variants),*)),
+        });
+    }
+}
+
+impl TypeStateArgs {
+    /// The hidden field that holds the states: `_state` (see `field`), or the last element of the tuple structs: `1`
+    /// (none for the enums, since each of their variants has its own field)
+    pub fn state_member(&self) -> Option<Member> {
+        match &self.shape {
+            StructShape::Named | StructShape::Unit => Some(Member::Named(self.field.clone())),
+            StructShape::Tuple(len) => Some(Member::Unnamed((*len).into())),
+            StructShape::Enum { .. } => None,
+        }
+    }
+
+    /// The states that are allowed in the slot at `index`:
+    /// the ones declared for the slot in `slot_states`, or else all the declared `states`
+    ///
+    /// the states of the components are not known by the struct, so there are none for their slots
+    pub fn states_of_slot(&self, index: usize) -> &[Ident] {
+        match self.slots.get(index) {
+            Some(slot) if slot.component.is_some() => &[],
+            slot => slot
+                .and_then(|slot| slot.states.as_deref())
+                .unwrap_or(&self.states),
+        }
+    }
+
+    /// The generic parameters of a declared state, if it is generic: `Loaded<T>` -> `<T>`
+    pub fn generics_of_state(&self, state: &Ident) -> Option<&Generics> {
+        self.state_generics
+            .iter()
+            .find(|(name, _)| name == state)
+            .map(|(_, generics)| generics)
+    }
+
+    /// The fields of a declared state, if it carries data: `Connected { session_id: u64 }` -> `{ session_id: u64 }`
+    pub fn fields_of_state(&self, state: &Ident) -> Option<&FieldsNamed> {
+        self.state_fields
+            .iter()
+            .find(|(name, _)| name == state)
+            .map(|(_, fields)| fields)
+    }
+
+    /// Whether the states are stored in the `_state` field, instead of being only marked with `PhantomData`,
+    /// which is the case when some of the states carry data
+    pub fn stores_states(&self) -> bool {
+        !self.state_fields.is_empty()
+    }
+
+    /// The path of the component that the slot at `index` belongs to, if it tracks the states of a component
+    pub fn slot_component(&self, index: usize) -> Option<&Path> {
+        self.slots
+            .get(index)
+            .and_then(|slot| slot.component.as_ref())
+    }
+
+    /// The trait that bounds the states of the slot at `index`: `SealerPlayer` of the struct itself,
+    /// or the one of the component that the slot belongs to: `auth::Auth<LoggedOut>` -> `auth::SealerAuth`,
+    /// or the one of the struct whose states are reused: `reuse = Article` -> `SealerArticle`
+    pub fn sealer_of_slot(&self, index: usize, struct_name: &Ident) -> Path {
+        match (self.slot_component(index), &self.reuse) {
+            (Some(component), _) => sealer_path(component),
+            (None, Some(reuse)) => sealer_path(reuse),
+            (None, None) => {
+                Ident::new(&format!("Sealer{}", struct_name), struct_name.span()).into()
+            }
+        }
+    }
+
+    /// Prefixes the names of the declared states and groups
+    /// (the external states and the states of the components are left as they are)
+    fn apply_prefix(&mut self, prefix: String) {
+        let external_states = self.external_states.clone();
+        let prefixed = |ident: &mut Ident| {
+            if !external_states.contains(ident) {
+                *ident = Ident::new(&format!("{}{}", prefix, ident), ident.span());
+            }
+        };
+
+        self.states.iter_mut().for_each(prefixed);
+        self.state_generics
+            .iter_mut()
+            .for_each(|(state, _)| prefixed(state));
+        self.state_fields
+            .iter_mut()
+            .for_each(|(state, _)| prefixed(state));
+        for slot in self
+            .slots
+            .iter_mut()
+            .filter(|slot| slot.component.is_none())
+        {
+            prefixed(&mut slot.default);
+            slot.states.iter_mut().flatten().for_each(prefixed);
+        }
+        for group in &mut self.groups {
+            prefixed(&mut group.name);
+            group.states.iter_mut().for_each(prefixed);
+            group.parent.iter_mut().for_each(prefixed);
+        }
+        for (substate, parent) in &mut self.parents {
+            prefixed(substate);
+            prefixed(parent);
+        }
+        self.state_cfgs
+            .iter_mut()
+            .for_each(|(state, _)| prefixed(state));
+        self.terminal_states.iter_mut().for_each(prefixed);
+        self.serde.iter_mut().flatten().for_each(prefixed);
+
+        self.prefix = Some(prefix);
+    }
+
+    /// The declared state or group that is given by its unprefixed name: `Ready` -> `PlayerReady`,
+    /// with `prefix = "Player"` (the other names are left as they are)
+    pub fn resolve_name(&self, ident: &Ident) -> Ident {
+        if let Some(prefix) = &self.prefix {
+            let prefixed = Ident::new(&format!("{}{}", prefix, ident), ident.span());
+            if self.states.contains(&prefixed)
+                || self.groups.iter().any(|group| group.name == prefixed)
+            {
+                return prefixed;
+            }
+        }
+        ident.clone()
+    }
+
+    /// The declared name of a state, without the prefix: `PlayerReady` -> `Ready`, with `prefix = "Player"`
+    /// (for the names that are shown to the users, like the variants of `AnyPlayer` and `STATE_NAME`)
+    pub fn unprefixed_name(&self, state: &Ident) -> Ident {
+        let name = state.to_string();
+        match &self.prefix {
+            Some(prefix) if name.len() > prefix.len() && name.starts_with(prefix.as_str()) => {
+                Ident::new(&name[prefix.len()..], state.span())
+            }
+            _ => state.clone(),
+        }
+    }
+
+    /// Resolves the unprefixed names of the states and groups in the arguments of an attribute:
+    /// `#[require(Ready | Done)]` -> `#[require(PlayerReady | PlayerDone)]`
+    pub fn resolve_names(&self, tokens: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        if self.prefix.is_none() {
+            return tokens;
+        }
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                proc_macro2::TokenTree::Ident(ident) => self.resolve_name(&ident).into(),
+                proc_macro2::TokenTree::Group(group) => {
+                    let mut resolved = proc_macro2::Group::new(
+                        group.delimiter(),
+                        self.resolve_names(group.stream()),
+                    );
+                    resolved.set_span(group.span());
+                    resolved.into()
+                }
+                token => token,
+            })
+            .collect()
+    }
+
+    /// The `#[cfg]` attributes of a state (or a parent state), if it is gated: `#[cfg(feature = "tls")] Handshaking`
+    pub fn cfgs_of_state(&self, state: &Ident) -> &[Attribute] {
+        self.state_cfgs
+            .iter()
+            .find(|(gated, _)| gated == state)
+            .map_or(&[], |(_, cfgs)| cfgs)
+    }
+
+    /// The `#[cfg]` attributes of the `impl` blocks (or the other items) that refer to the given states,
+    /// so that they are gated along with the states: `#[require(Handshaking)]` -> `#[cfg(feature = "tls")]`
+    pub fn cfgs_of_states<'a>(
+        &'a self,
+        states: impl IntoIterator<Item = &'a StateArg>,
+    ) -> Vec<&'a Attribute> {
+        states
+            .into_iter()
+            .flat_map(|state| self.cfgs_of_state(&state.ident))
+            .collect()
+    }
+
+    /// The names of the declared states and groups, which the arguments of the macros can refer to
+    pub fn names(&self) -> impl Iterator<Item = &Ident> {
+        self.states
+            .iter()
+            .chain(self.groups.iter().map(|group| &group.name))
+    }
+
+    /// Whether the lint is opted into, and whether it is a warning or an error
+    pub fn lint_level(&self, lint: Lint) -> Option<LintLevel> {
+        self.lints
+            .iter()
+            .find(|(other, _)| *other == lint)
+            .map(|(_, level)| *level)
+    }
+
+    /// The parent of a substate, if it is declared as one: `Connected(Active, Idle)` -> `Active` -> `Connected`
+    pub fn parent_of(&self, state: &Ident) -> Option<&Ident> {
+        self.parents
+            .iter()
+            .find(|(substate, _)| substate == state)
+            .map(|(_, parent)| parent)
+    }
+}
+
+/// A declared state, optionally generic, and optionally carrying data:
+/// `State1`, `Loaded<T>` or `Connected { session_id: u64 }`
+///
+/// or a parent state with its substates: `Connected(Active, Idle)`
+///
+/// or a state whose marker is defined by hand: `external(Idle)`
+///
+/// and any of them can be gated with `#[cfg]`: `#[cfg(feature = "tls")] Handshaking`
+struct StateDecl {
+    cfgs: Vec<Attribute>,
+    name: Ident,
+    generics: Generics,
+    fields: Option<FieldsNamed>,
+    substates: Vec<StateDecl>,
+    external: bool,
+}
+
+impl Parse for StateDecl {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let cfgs = Attribute::parse_outer(input)?;
+        if let Some(attr) = cfgs.iter().find(|attr| !attr.path().is_ident("cfg")) {
+            return Err(syn::Error::new(
+                attr.span(),
+                "only `#[cfg]` attributes are supported on the states",
+            ));
+        }
+        let mut state = StateDecl::parse_ungated(input)?;
+        state.cfgs.splice(0..0, cfgs);
+        Ok(state)
+    }
+}
+
+impl StateDecl {
+    /// Parses the state after its `#[cfg]` attributes
+    fn parse_ungated(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        // the markers that are defined by hand: `external(Idle)`
+        if name == "external" && input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let state = StateDecl {
+                external: true,
+                ..content.parse()?
+            };
+            if !state.substates.is_empty() || state.fields.is_some() {
+                return Err(syn::Error::new(
+                    state.name.span(),
+                    "the external states cannot have substates or fields, since their markers are defined by hand",
+                ));
+            }
+            return Ok(state);
+        }
+        if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            return Ok(StateDecl {
+                cfgs: Vec::new(),
+                name,
+                generics: Generics::default(),
+                fields: None,
+                substates: Punctuated::<StateDecl, Token![,]>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect(),
+                external: false,
+            });
+        }
+
+        Ok(StateDecl {
+            cfgs: Vec::new(),
+            name,
+            generics: input.parse()?,
+            fields: if input.peek(token::Brace) {
+                Some(input.parse()?)
+            } else {
+                None
+            },
+            substates: Vec::new(),
+            external: false,
+        })
+    }
+
+    /// Collects the actual states (the ones without substates) into `states`,
+    /// the parent states into `groups` (each with all the states below it),
+    /// and the parents of the substates into `parents`
+    ///
+    /// the `#[cfg]` attributes of the states and the parent states are collected into `cfgs`,
+    /// and the substates are gated by the ones of their parents too
+    fn flatten(
+        self,
+        parent: Option<&Ident>,
+        states: &mut Vec<StateDecl>,
+        groups: &mut Vec<StateGroup>,
+        parents: &mut Vec<(Ident, Ident)>,
+        cfgs: &mut Vec<(Ident, Vec<Attribute>)>,
+    ) {
+        if let Some(parent) = parent {
+            parents.push((self.name.clone(), parent.clone()));
+        }
+        if !self.cfgs.is_empty() {
+            cfgs.push((self.name.clone(), self.cfgs.clone()));
+        }
+        if self.substates.is_empty() {
+            states.push(self);
+            return;
+        }
+
+        let first_state = states.len();
+        let group_index = groups.len();
+        groups.push(StateGroup {
+            name: self.name.clone(),
+            states: Vec::new(),
+            parent: parent.cloned(),
+        });
+        for mut substate in self.substates {
+            substate.cfgs.splice(0..0, self.cfgs.iter().cloned());
+            substate.flatten(Some(&self.name), states, groups, parents, cfgs);
+        }
+        groups[group_index].states = states[first_state..]
+            .iter()
+            .map(|state| state.name.clone())
+            .collect();
+    }
+}
+
+/// A state slot with its default state, optionally named: `State1` or `auth = State1`
+///
+/// named slots can be addressed by their names in `#[require]`: `#[require(auth = State2)]`
+///
+/// `states` are the states allowed in this slot, if they are restricted by `slot_states`
+///
+/// `component` is the path of the component that the slot belongs to, if the slot tracks the states of a component:
+/// `components = (auth: Auth<LoggedOut>)` -> the slot `auth = LoggedOut` with the component `Auth`
+pub struct StateSlot {
+    pub name: Option<Ident>,
+    pub default: Ident,
+    pub states: Option<Vec<Ident>>,
+    pub component: Option<Path>,
+}
+
+impl Parse for StateSlot {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Ok(StateSlot {
+                name: Some(ident),
+                default: input.parse()?,
+                states: None,
+                component: None,
+            })
+        } else {
+            Ok(StateSlot {
+                name: None,
+                default: ident,
+                states: None,
+                component: None,
+            })
+        }
+    }
+}
+
+/// A component of the struct, along with its default state: `auth: Auth<LoggedOut>`
+///
+/// it becomes a named slot (`auth = LoggedOut`) whose states are the ones of the component,
+/// and the `auth: Auth<SessionState1>` field of the struct
+struct ComponentDecl {
+    field: Ident,
+    path: Path,
+    default: Ident,
+}
+
+impl Parse for ComponentDecl {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let field = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let mut path: Path = input.parse()?;
+
+        let last_segment = path.segments.last_mut().unwrap();
+        let arguments = std::mem::replace(&mut last_segment.arguments, PathArguments::None);
+        let default = match &arguments {
+            PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+                GenericArgument::Type(Type::Path(type_path)) => type_path.path.get_ident().cloned(),
+                _ => None,
+            },
+            _ => None,
+        };
+        let default = default.ok_or_else(|| {
+            syn::Error::new(
+                last_segment.span(),
+                "expected a type-state struct with a single slot, along with its default state: `Auth<LoggedOut>`",
+            )
+        })?;
+
+        Ok(ComponentDecl {
+            field,
+            path,
+            default,
+        })
+    }
+}
+
+/// Path of the sealer trait of a type-state struct: `auth::Auth` -> `auth::SealerAuth`
+fn sealer_path(struct_path: &Path) -> Path {
+    let mut path = struct_path.clone();
+    let last_segment = path.segments.last_mut().unwrap();
+    last_segment.ident = Ident::new(
+        &format!("Sealer{}", last_segment.ident),
+        last_segment.ident.span(),
     );
+    path
+}
 
-    let markers: Vec<_> = states
-        .iter()
-        .map(|state| {
-            let marker_name = Ident::new(&format!("{}", state), state.span());
-            quote! {
-                pub struct #marker_name;
+/// A named group of states: `Alive = (Idle, Running)`
+///
+/// a marker trait is generated for each group, and implemented by the states of the group
+///
+/// the parent states are groups too, of the states below them: `Connected(Active(Sending, Receiving), Idle)`
+/// -> `Connected = (Sending, Receiving, Idle)`, and `Active = (Sending, Receiving)` whose `parent` is `Connected`
+pub struct StateGroup {
+    pub name: Ident,
+    pub states: Vec<Ident>,
+    pub parent: Option<Ident>,
+}
+
+impl Parse for StateGroup {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        Ok(StateGroup {
+            name,
+            states: parse_ident_list(input)?,
+            parent: None,
+        })
+    }
+}
+
+/// Parses a parenthesized list of identifiers: `(State1, State2, State3)`
+fn parse_ident_list(input: ParseStream) -> syn::Result<Vec<Ident>> {
+    let ParenthesizedIdents(idents) = input.parse()?;
+    Ok(idents)
+}
+
+struct ParenthesizedIdents(Vec<Ident>);
+
+impl Parse for ParenthesizedIdents {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        parenthesized!(content in input);
+        let idents = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+        Ok(ParenthesizedIdents(idents.into_iter().collect()))
+    }
+}
+
+impl Parse for TypeStateArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut states = None;
+        let mut slots = None;
+        let mut groups = Vec::new();
+        let mut slot_states = None;
+        let mut in_place = false;
+        let mut into_state = false;
+        let mut reset = false;
+        let mut declared_transitions = false;
+        let mut log = false;
+        let mut components = Vec::new();
+        let mut reuse = None;
+        let mut registry = None;
+        let mut any_state = false;
+        let mut state_enum = false;
+        let mut aliases = None;
+        let mut extensible = false;
+        let mut sealed = true;
+        let mut must_use = true;
+        let mut prefix = None;
+        let mut shape = StructShape::Named;
+        let mut field = Ident::new("_state", proc_macro2::Span::call_site());
+        let mut phantom = PhantomKind::default();
+        let mut marker_vis: Visibility = syn::parse_quote!(pub);
+        let mut doc_hidden = false;
+        let mut marker_derives: Vec<Path> = Vec::new();
+        let mut terminal = Vec::new();
+        let mut serde = None;
+        let mut lints = Vec::new();
+
+        while !input.is_empty() {
+            // the arguments given by `#[type_state]` itself to the other macros (see `StructShape`),
+            // which are not arguments of the users
+            if input.peek(Token![@]) {
+                input.parse::<Token![@]>()?;
+                let key: Ident = input.parse()?;
+                let content;
+                parenthesized!(content in input);
+                match key.to_string().as_str() {
+                    "shape" => shape = content.parse()?,
+                    _ => return Err(syn::Error::new(key.span(), "unknown argument")),
+                }
+                if !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+                continue;
+            }
+
+            let key: Ident = input.parse()?;
+
+            // the lints are given like the lint attributes: `warn(unreachable)`
+            if (key == "warn" || key == "deny") && input.peek(token::Paren) {
+                let level = if key == "warn" {
+                    LintLevel::Warn
+                } else {
+                    LintLevel::Deny
+                };
+                let content;
+                parenthesized!(content in input);
+                for lint in Punctuated::<Lint, Token![,]>::parse_terminated(&content)? {
+                    lints.retain(|(other, _)| *other != lint);
+                    lints.push((lint, level));
+                }
+                if !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+                continue;
+            }
+
+            // the derives of the markers are given like the derive attribute: `derive(Clone, PartialEq)`
+            if key == "derive" && input.peek(token::Paren) {
+                let content;
+                parenthesized!(content in input);
+                marker_derives.extend(Punctuated::<Path, Token![,]>::parse_terminated(&content)?);
+                if !input.is_empty() {
+                    input.parse::<Token![,]>()?;
+                }
+                continue;
             }
+
+            input.parse::<Token![=]>()?;
+
+            match key.to_string().as_str() {
+                "states" => {
+                    let content;
+                    parenthesized!(content in input);
+                    states = Some(Punctuated::<StateDecl, Token![,]>::parse_terminated(&content)?);
+                }
+                "slots" => {
+                    let content;
+                    parenthesized!(content in input);
+                    slots = Some(
+                        Punctuated::<StateSlot, Token![,]>::parse_terminated(&content)?
+                            .into_iter()
+                            .collect(),
+                    );
+                }
+                "slot_states" => {
+                    let content;
+                    parenthesized!(content in input);
+                    let lists =
+                        Punctuated::<ParenthesizedIdents, Token![,]>::parse_terminated(&content)?;
+                    slot_states = Some((key, lists));
+                }
+                "groups" => {
+                    let content;
+                    parenthesized!(content in input);
+                    groups = Punctuated::<StateGroup, Token![,]>::parse_terminated(&content)?
+                        .into_iter()
+                        .collect();
+                }
+                "components" => {
+                    let content;
+                    parenthesized!(content in input);
+                    components = Punctuated::<ComponentDecl, Token![,]>::parse_terminated(&content)?
+                        .into_iter()
+                        .collect();
+                }
+                "reuse" => reuse = Some(input.parse()?),
+                "registry" => registry = Some(input.parse()?),
+                "in_place" => in_place = input.parse::<LitBool>()?.value,
+                "into_state" => into_state = input.parse::<LitBool>()?.value,
+                "reset" => reset = input.parse::<LitBool>()?.value,
+                "declared_transitions" => {
+                    declared_transitions = input.parse::<LitBool>()?.value
+                }
+                "log" => log = input.parse::<LitBool>()?.value,
+                "any_state" => any_state = input.parse::<LitBool>()?.value,
+                "state_enum" => state_enum = input.parse::<LitBool>()?.value,
+                "aliases" => {
+                    aliases = if input.peek(LitStr) {
+                        Some(input.parse::<LitStr>()?.value())
+                    } else {
+                        input
+                            .parse::<LitBool>()?
+                            .value
+                            .then(|| DEFAULT_ALIAS_NAMING.to_string())
+                    }
+                }
+                "extensible" => extensible = input.parse::<LitBool>()?.value,
+                "sealed" => sealed = input.parse::<LitBool>()?.value,
+                "must_use" => must_use = input.parse::<LitBool>()?.value,
+                "prefix" => prefix = Some(input.parse::<LitStr>()?.value()),
+                "field" => field = input.parse::<LitStr>()?.parse()?,
+                "phantom" => phantom = input.parse()?,
+                "vis" => marker_vis = input.parse()?,
+                "doc_hidden" => doc_hidden = input.parse::<LitBool>()?.value,
+                "terminal" => terminal = parse_ident_list(input)?,
+                "serde" => {
+                    serde = if input.peek(token::Paren) {
+                        Some(parse_ident_list(input)?)
+                    } else {
+                        input.parse::<LitBool>()?.value.then(Vec::new)
+                    }
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "unknown argument, expected `states`, `slots`, `slot_states`, `groups`, `components`, `reuse`, \
+                         `registry`, `in_place`, `into_state`, `reset`, `declared_transitions`, `log`, `any_state`, \
+                         `state_enum`, `aliases`, `extensible`, `sealed`, `must_use`, `prefix`, `field`, `phantom`, \
+                         `vis`, `doc_hidden`, `terminal`, `serde`, `derive`, `warn` or `deny`",
+                    ))
+                }
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        // the states and the slots of the struct itself are optional, if it is composed of components
+        let mut slots: Vec<StateSlot> = match slots {
+            Some(slots) => slots,
+            None if !components.is_empty() => Vec::new(),
+            None => return Err(input.error("expected a list of default slots")),
+        };
+
+        if let Some((key, lists)) = slot_states {
+            if lists.len() != slots.len() {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!(
+                        "expected a list of states for each of the {} slots, found {}",
+                        slots.len(),
+                        lists.len()
+                    ),
+                ));
+            }
+
+            for (slot, ParenthesizedIdents(states)) in slots.iter_mut().zip(lists) {
+                if !states.contains(&slot.default) {
+                    return Err(syn::Error::new(
+                        slot.default.span(),
+                        format!(
+                            "default state `{}` is not one of the states of its slot",
+                            slot.default
+                        ),
+                    ));
+                }
+                slot.states = Some(states);
+            }
+        }
+
+        // the slots of the components come first
+        slots.splice(
+            0..0,
+            components.into_iter().map(|component| StateSlot {
+                name: Some(component.field),
+                default: component.default,
+                states: None,
+                component: Some(component.path),
+            }),
+        );
+
+        let declared_states = match states {
+            Some(states) => states,
+            None if slots.iter().any(|slot| slot.component.is_some()) => Punctuated::new(),
+            None => return Err(input.error("expected a list of states")),
+        };
+        let mut states = Vec::new();
+        let mut parents = Vec::new();
+        let mut state_cfgs = Vec::new();
+        for state in declared_states {
+            state.flatten(
+                None,
+                &mut states,
+                &mut groups,
+                &mut parents,
+                &mut state_cfgs,
+            );
+        }
+
+        // the groups stand for all of their states: `terminal = (Closed)`, `serde = (Ready)`
+        let resolve_states = |given: Vec<Ident>, description: &str| {
+            let mut resolved = Vec::new();
+            for state in given {
+                if let Some(group) = groups.iter().find(|group| group.name == state) {
+                    resolved.extend(group.states.iter().cloned());
+                } else if states.iter().any(|declared| declared.name == state) {
+                    resolved.push(state);
+                } else {
+                    let declared = states
+                        .iter()
+                        .map(|state| &state.name)
+                        .chain(groups.iter().map(|group| &group.name));
+                    let suggestion = closest_name(&state, declared)
+                        .map(|closest| format!("; did you mean `{}`?", closest))
+                        .unwrap_or_default();
+                    return Err(syn::Error::new(
+                        state.span(),
+                        format!(
+                            "the {} `{}` is not one of the declared states{}",
+                            description, state, suggestion
+                        ),
+                    ));
+                }
+            }
+            Ok(resolved)
+        };
+        let terminal_states = resolve_states(terminal, "terminal state")?;
+        let serde = serde
+            .map(|serde| resolve_states(serde, "deserialized state"))
+            .transpose()?;
+
+        // the default states are always there, since the struct refers to them
+        if let Some((state, cfgs)) = state_cfgs
+            .iter()
+            .find(|(state, _)| slots.iter().any(|slot| slot.default == *state))
+        {
+            return Err(syn::Error::new(
+                cfgs[0].span(),
+                format!(
+                    "the default state `{}` cannot be gated with `#[cfg]`",
+                    state
+                ),
+            ));
+        }
+
+        let mut type_state_args = TypeStateArgs {
+            state_fields: states
+                .iter()
+                .filter_map(|state| Some((state.name.clone(), state.fields.clone()?)))
+                .collect(),
+            state_generics: states
+                .iter()
+                .filter(|state| !state.generics.params.is_empty())
+                .map(|state| (state.name.clone(), state.generics.clone()))
+                .collect(),
+            external_states: states
+                .iter()
+                .filter(|state| state.external)
+                .map(|state| state.name.clone())
+                .collect(),
+            states: states.into_iter().map(|state| state.name).collect(),
+            slots,
+            groups,
+            parents,
+            reuse,
+            registry,
+            in_place,
+            into_state,
+            reset,
+            declared_transitions,
+            log,
+            any_state,
+            state_enum,
+            aliases,
+            extensible,
+            sealed,
+            must_use,
+            prefix: None,
+            shape,
+            field,
+            phantom,
+            state_cfgs,
+            marker_vis,
+            doc_hidden,
+            marker_derives,
+            terminal_states,
+            lints,
+            serde,
+        };
+        if let Some(prefix) = prefix {
+            type_state_args.apply_prefix(prefix);
+        }
+
+        Ok(type_state_args)
+    }
+}
+
+/// A raw argument of `#[type_state]`, whose value is kept as it is: `states = (State1, State2)`,
+/// or `deny(unreachable)` for the arguments given like attributes, and `@shape(unit)` for the internal ones
+struct RawArg {
+    at_token: Option<Token![@]>,
+    key: Ident,
+    eq_token: Option<Token![=]>,
+    value: proc_macro2::TokenStream,
+}
+
+impl Parse for RawArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let at_token = input.parse()?;
+        let key = input.parse()?;
+        let eq_token = if input.peek(token::Paren) {
+            None
+        } else {
+            Some(input.parse()?)
+        };
+        let mut value = proc_macro2::TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![,]) {
+            value.extend([input.parse::<proc_macro2::TokenTree>()?]);
+        }
+        Ok(RawArg {
+            at_token,
+            key,
+            eq_token,
+            value,
         })
-        .collect();
+    }
+}
+
+impl ToTokens for RawArg {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let RawArg {
+            at_token,
+            key,
+            eq_token,
+            value,
+        } = self;
+        tokens.extend(quote!(#at_token #key #eq_token #value));
+    }
+}
+
+fn parse_raw_args(args: proc_macro2::TokenStream) -> syn::Result<Vec<RawArg>> {
+    Ok(Punctuated::<RawArg, Token![,]>::parse_terminated
+        .parse2(args)?
+        .into_iter()
+        .collect())
+}
+
+/// The struct whose states are reused, if the states are not given yet: `reuse = Article`
+fn reused_struct(args: &TokenStream) -> Option<Path> {
+    let args = parse_raw_args(args.clone().into()).ok()?;
+    if args.iter().any(|arg| arg.key == "states") {
+        return None;
+    }
+    let reuse = args.into_iter().find(|arg| arg.key == "reuse")?;
+    syn::parse2(reuse.value).ok()
+}
+
+/// Input of the internal `__type_state_reuse` macro:
+/// `(<arguments of the reused struct>) (<arguments of the struct>) struct ... { ... }`
+struct ReuseStatesInput {
+    reused_args: proc_macro2::TokenStream,
+    args: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+}
+
+impl Parse for ReuseStatesInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let reused_args;
+        parenthesized!(reused_args in input);
+        let args;
+        parenthesized!(args in input);
+        Ok(ReuseStatesInput {
+            reused_args: reused_args.parse()?,
+            args: args.parse()?,
+            item: input.parse()?,
+        })
+    }
+}
 
-    let sealed_impls: Vec<_> = states
+/// Expands `#[type_state(reuse = Article, slots = (Draft))]` with the states of `Article`:
+/// `#[type_state(states = (Draft, Review, Published), reuse = Article, slots = (Draft))]`
+///
+/// the `states`, the `groups` and the `prefix` are taken from `Article`, and the rest of the arguments are the struct's own,
+/// the markers, the groups and the sealer of `Article` are not generated again, since they are shared
+///
+/// if `Article` reuses the states of another struct too, that one is reused instead,
+/// since it is the one that generated the markers and the sealer
+pub fn reuse_states(input: TokenStream) -> TokenStream {
+    let ReuseStatesInput {
+        reused_args,
+        args,
+        item,
+    } = parse_macro_input!(input as ReuseStatesInput);
+    let (reused_args, args) = match (parse_raw_args(reused_args), parse_raw_args(args)) {
+        (Ok(reused_args), Ok(args)) => (reused_args, args),
+        (Err(error), _) | (_, Err(error)) => {
+            return match syn::parse2(item) {
+                Ok(item) => partial_expansion(&item, error),
+                Err(_) => error.to_compile_error().into(),
+            }
+        }
+    };
+
+    let shared_keys = ["states", "groups", "reuse", "prefix"];
+    let shared_args: Vec<_> = reused_args
         .iter()
-        .map(|state| {
-            let marker_name = Ident::new(&format!("{}", state), state.span());
+        .filter(|arg| shared_keys.contains(&arg.key.to_string().as_str()))
+        .collect();
+    // the shared arguments take the place of the struct's own ones
+    let own_args = args
+        .iter()
+        .filter(|arg| !shared_args.iter().any(|shared| shared.key == arg.key));
+
+    type_state_inner(quote!(#(#shared_args,)* #(#own_args),*).into(), item.into())
+}
+
+/// Splits an enum into a struct without fields (with the same name, generics and attributes), and the variants of the enum,
+/// so that the enums are handled like the structs, and the `_state` field is put into each of the variants at the end
+fn enum_as_struct(input_enum: ItemEnum) -> (ItemStruct, Option<Punctuated<Variant, Token![,]>>) {
+    let ItemEnum {
+        attrs,
+        vis,
+        enum_token,
+        ident,
+        generics,
+        variants,
+        ..
+    } = input_enum;
+    let input_struct = ItemStruct {
+        attrs,
+        vis,
+        struct_token: Token![struct](enum_token.span),
+        ident,
+        generics,
+        fields: Fields::Named(syn::parse_quote!({})),
+        semi_token: None,
+    };
+    (input_struct, Some(variants))
+}
+
+/// Expands the struct (or enum) as it is, along with the error, when it cannot be made type-state compatible,
+/// so that it stays resolvable in the IDE (for the completions and go-to-definition) while it is being edited
+///
+/// its `#[only_in]` attributes are removed, and its hidden macro swallows the `impl` blocks with `#[impl_state]`,
+/// since they expect the states of the struct (which would only bury the error under the errors of the `impl` blocks)
+fn partial_expansion(item: &Item, error: syn::Error) -> TokenStream {
+    let mut item = item.clone();
+    let fields: Vec<&mut Fields> = match &mut item {
+        Item::Struct(item_struct) => vec![&mut item_struct.fields],
+        Item::Enum(item_enum) => item_enum
+            .variants
+            .iter_mut()
+            .map(|variant| &mut variant.fields)
+            .collect(),
+        _ => Vec::new(),
+    };
+    for field in fields.into_iter().flat_map(|fields| fields.iter_mut()) {
+        field.attrs.retain(|attr| !attr.path().is_ident("only_in"));
+    }
+
+    let error = error.to_compile_error();
+    let callback_macro = match &item {
+        Item::Struct(ItemStruct { ident, .. }) | Item::Enum(ItemEnum { ident, .. }) => {
+            let callback_macro_name = callback_macro_name(ident);
             quote! {
-                impl #sealed_mod_name::Sealed for #marker_name {}
+                #[doc(hidden)]
+                #[allow(unused_macros)]
+                macro_rules! #callback_macro_name {
+                    ($($tokens:tt)*) => {};
+                }
+
+                #[doc(hidden)]
+                #[allow(unused_imports)]
+                pub(crate) use #callback_macro_name;
             }
-        })
+        }
+        _ => quote! {},
+    };
+    quote! {
+        #error
+
+        #item
+
+        #callback_macro
+    }
+    .into()
+}
+
+/// Name of the hidden macro generated by `#[type_state]` for the struct: `__state_shift_player_builder`
+///
+/// `#[impl_state]` expands into an invocation of this macro, which forwards the `impl` block
+/// together with the arguments of `#[type_state]` to the internal `__impl_state` macro
+pub fn callback_macro_name(struct_name: &Ident) -> Ident {
+    Ident::new(
+        &format!("__state_shift_{}", snake_case(&struct_name.to_string())),
+        struct_name.span(),
+    )
+}
+
+pub fn type_state_inner(args: TokenStream, input: TokenStream) -> TokenStream {
+    // the struct reusing the states of another struct gets them from the hidden macro of that struct first
+    if let Some(reuse) = reused_struct(&args) {
+        let mut callback_path = reuse;
+        let last_segment = callback_path.segments.last_mut().unwrap();
+        last_segment.ident = callback_macro_name(&last_segment.ident);
+        let args = proc_macro2::TokenStream::from(args);
+        let input = proc_macro2::TokenStream::from(input);
+        return quote! {
+            #callback_path! { @reuse (#args) #input }
+        }
+        .into();
+    }
+
+    // Parse the input struct (or enum)
+    let item = parse_macro_input!(input as Item);
+    let original = item.clone();
+    let (input_struct, enum_variants) = match item {
+        Item::Struct(input_struct) => (input_struct, None),
+        Item::Enum(input_enum) => enum_as_struct(input_enum),
+        item => {
+            return syn::Error::new(item.span(), "`#[type_state]` expects a struct or an enum")
+                .to_compile_error()
+                .into()
+        }
+    };
+    let struct_name = &input_struct.ident;
+    let generics = &input_struct.generics;
+    let visibility = &input_struct.vis;
+
+    // Parse arguments (states and slots)
+    let mut raw_args = proc_macro2::TokenStream::from(args);
+    // the tuple structs, the unit structs and the enums get the `_state` field in other places,
+    // so `#[impl_state]` is told where to initialize it as well: `Self(meters)`, `Self`, `Self::Data(bytes)`
+    let shape = match (&enum_variants, &input_struct.fields) {
+        (Some(variants), _) => StructShape::Enum {
+            tuple_variants: variants
+                .iter()
+                .filter(|variant| matches!(variant.fields, Fields::Unnamed(_)))
+                .map(|variant| variant.ident.clone())
+                .collect(),
+        },
+        (None, Fields::Unnamed(fields)) => StructShape::Tuple(fields.unnamed.len()),
+        (None, Fields::Unit) => StructShape::Unit,
+        (None, _) => StructShape::Named,
+    };
+    if !matches!(shape, StructShape::Named) {
+        let separator = (!raw_args.is_empty()).then(<Token![,]>::default);
+        raw_args.extend(quote!(#separator @shape(#shape)));
+    }
+    let type_state_args: TypeStateArgs = match syn::parse2(raw_args.clone()) {
+        Ok(type_state_args) => type_state_args,
+        Err(error) => return partial_expansion(&original, error),
+    };
+    let TypeStateArgs {
+        states,
+        slots,
+        field: state_field,
+        marker_vis,
+        ..
+    } = &type_state_args;
+
+    // when some of the states carry data, the states are stored in the `_state` field,
+    // and the other states are initialized with `Default` (see `state_field_expr`)
+    let stores_states = type_state_args.stores_states();
+
+    // Get the struct's attributes (other macros) excluding the #[type_state] macro
+    let mut attrs: Vec<_> = input_struct
+        .attrs
+        .iter()
+        .filter(|attr| !attr.path().is_ident("type_state"))
+        .cloned()
         .collect();
 
-    let trait_impls: Vec<_> = states
+    // the derives that would bound the states are replaced with the impls of the macro (see `derive_impls`)
+    // (`Default` of the enums is left to the derive, since it is given with the `#[default]` variant)
+    let replaceable: Vec<_> = REPLACED_DERIVES
         .iter()
-        .map(|state| {
-            let marker_name = Ident::new(&format!("{}", state), state.span());
-            quote! {
-                impl #sealer_trait_name for #marker_name {}
-            }
-        })
+        .copied()
+        .chain(enum_variants.is_none().then_some("Default"))
         .collect();
+    let replaced_derives = match take_derives(&mut attrs, &replaceable) {
+        Ok(replaced_derives) => replaced_derives,
+        Err(error) => return partial_expansion(&original, error),
+    };
+
+    // the builder derives skip the `_state` field, which is created with `Default` by the builder,
+    // and `derive_builder` bounds the generics of the builder with `Clone`, the states included,
+    // so the markers derive `Clone` for it (the states that carry data cannot be created by the builder)
+    let builder_derive = match builder_derive(&attrs) {
+        Ok(builder_derive) => builder_derive,
+        Err(error) => return partial_expansion(&original, error),
+    };
+    if let Some((derive, _)) = builder_derive.as_ref().filter(|_| stores_states) {
+        return partial_expansion(
+            &original,
+            syn::Error::new(
+                derive.span(),
+                format!(
+                    "`{}` cannot be used with the states that carry data, \
+                     since the builder creates the struct in its default states",
+                    derive
+                ),
+            ),
+        );
+    }
+    let marker_derives = marker_derives(
+        &type_state_args,
+        &replaced_derives,
+        builder_derive.as_ref().map(|(derive, _)| derive),
+    );
 
     // Extract fields from the struct
     // we cannot use `input_struct.fields` directly because
     // quote! treats the Fields reference as a block expression,
     // leading to the generated fields being wrapped inside
     // an extra set of braces ({ ... }).
-    let struct_fields = match input_struct.fields {
-        Fields::Named(ref fields) => &fields.named,
-        Fields::Unnamed(_) => panic!("Expected named fields in struct."),
-        Fields::Unit => panic!("Expected a struct with fields."),
+    let (mut struct_fields, tuple_fields) = match input_struct.fields {
+        Fields::Named(ref fields) => (fields.named.clone(), None),
+        Fields::Unnamed(ref fields) => (Punctuated::new(), Some(fields.unnamed.clone())),
+        // the unit structs have only the `_state` field: `struct Token<State1> { _state: PhantomData<State1> }`
+        Fields::Unit => (Punctuated::new(), None),
     };
 
     // Generate state generics: `struct StructName<PlayerState1, PlayerState2, ...>`
-    let state_idents: Vec<_> = (0..default_slots.len())
+    let state_idents: Vec<_> = (0..slots.len())
         .map(|i| {
             Ident::new(
                 &format!("{}State{}", struct_name, i + 1),
@@ -88,68 +1418,430 @@ pub fn type_state_inner(args: TokenStream, input: TokenStream) -> TokenStream {
         })
         .collect();
 
-    // Construct the new generics by merging original generics with default states
-    let default_generics = default_slots.iter().collect::<Vec<_>>();
-    let combined_generics = if generics.params.is_empty() {
-        quote! { #(#state_idents = #default_generics),* }
-    } else {
-        let original_generics = generics.params.iter();
-        quote! { #(#original_generics),*, #(#state_idents = #default_generics),* }
+    // the fields that exist only in certain states: `#[only_in(Connected)] socket: TcpStream`
+    let only_in = match only_in_fields(
+        struct_name,
+        generics,
+        &mut struct_fields,
+        &state_idents,
+        &type_state_args,
+    ) {
+        Ok(only_in) => only_in,
+        Err(error) => return partial_expansion(&original, error),
     };
+    let has_only_in = !only_in.traits.is_empty();
 
-    // create a new where clause for the new generics (states)
-    let new_where_clause: Vec<_> = state_idents
-        .iter()
-        .map(|state| quote!(#state: #sealer_trait_name))
-        .collect();
-
-    // Merge the where clauses if there is an existing one
-    let merged_where_clause = if let Some(existing_where) = &generics.where_clause {
-        quote! { #existing_where #(#new_where_clause),* }
-    } else if !new_where_clause.is_empty() {
-        quote! { where #(#new_where_clause),* }
+    // Generate the enum of the struct in any of its states, if it is opted into
+    let any_state_enum = if type_state_args.any_state {
+        match any_state_enum(struct_name, visibility, generics, &type_state_args) {
+            Ok(any_state_enum) => any_state_enum,
+            Err(error) => return partial_expansion(&original, error),
+        }
     } else {
         quote! {}
     };
 
+    // Generate the type aliases of the struct in each state, if they are opted into: `type IdlePlayer = Player<Idle>`
+    let state_aliases = match state_aliases(struct_name, visibility, generics, &type_state_args) {
+        Ok(state_aliases) => state_aliases,
+        Err(error) => return partial_expansion(&original, error),
+    };
+    let state_registry = match state_registry(struct_name, visibility, &type_state_args) {
+        Ok(state_registry) => state_registry,
+        Err(error) => return partial_expansion(&original, error),
+    };
+
+    // Generate the enum of the states, if it is opted into
+    // (the states that are reused are given their variants by the struct that declares them)
+    let state_enum = match (type_state_args.state_enum, &type_state_args.reuse) {
+        (false, _) => quote! {},
+        (true, None) => state_enum(struct_name, marker_vis, states, &type_state_args),
+        (true, Some(reuse)) => {
+            return partial_expansion(
+                &original,
+                syn::Error::new(
+                    reuse.span(),
+                    "`state_enum` cannot be used with `reuse`, \
+                     since the states of another struct are not in the enum of this one",
+                ),
+            )
+        }
+    };
+
+    if type_state_args.log && !cfg!(feature = "log") {
+        return partial_expansion(
+            &original,
+            syn::Error::new(
+                struct_name.span(),
+                "`log` needs the `log` feature of `state-shift`: \
+                 `state-shift = { version = \"..\", features = [\"log\"] }`",
+            ),
+        );
+    }
+
+    // the accessors of the states (see `accessors.rs`)
+    let state_data_accessors = state_data_accessors(
+        struct_name,
+        generics,
+        &state_idents,
+        &type_state_args,
+        enum_variants.as_ref(),
+    );
+    let state_name_accessors =
+        state_name_accessors(struct_name, generics, &state_idents, &type_state_args);
+    let all_states = all_states(struct_name, generics, &type_state_args);
+
+    // the methods that move the struct into other states outside of its transitions (see `rebuilders.rs`)
+    let state_rebuilders = match state_rebuilders(
+        struct_name,
+        generics,
+        &state_idents,
+        &type_state_args,
+        &struct_fields,
+        tuple_fields.as_ref(),
+        enum_variants.as_ref(),
+        has_only_in,
+    ) {
+        Ok(state_rebuilders) => state_rebuilders,
+        Err(error) => return partial_expansion(&original, error),
+    };
+    let in_place_helper = in_place_helper(struct_name, generics, &type_state_args);
+
+    // the markers, the groups and the sealer of the states, unless they are reused from another struct
+    // (see `markers.rs`)
+    let state_set = match state_set(struct_name, &type_state_args, &marker_derives, &only_in) {
+        Ok(state_set) => state_set,
+        Err(error) => return partial_expansion(&original, error),
+    };
+
+    // the fields of the struct moved into the struct in another state, for the tagged `AnyPlayer` (see `serde_support`)
+    let field_names = struct_fields.iter().map(|field| {
+        let cfgs = cfg_attrs(&field.attrs);
+        let name = &field.ident;
+        quote!(#(#cfgs)* #name: value.#name)
+    });
+    let rebuilt = quote! {
+        #struct_name {
+            #(#field_names,)*
+            #state_field: ::core::default::Default::default(),
+        }
+    };
+
+    // the `Serialize` and `Deserialize` impls, if they are opted into (see `serde_support`)
+    let (serde_derive, serde_field, serde_states) = match serde_support(
+        struct_name,
+        generics,
+        &state_idents,
+        &type_state_args,
+        has_only_in,
+        rebuilt,
+    ) {
+        Ok(serde_support) => serde_support,
+        Err(error) => return partial_expansion(&original, error),
+    };
+
     // Construct the `_state` field with PhantomData
     // `_state: PhantomData<fn() -> T>`
     // the reason for using `fn() -> T` is to: https://github.com/ozgunozerk/state-shift/issues/1
-    let phantom_fields = state_idents
-        .iter()
-        .map(|ident| quote!(::core::marker::PhantomData<fn() -> #ident>))
-        .collect::<Vec<_>>();
+    // (unless another one is chosen with `phantom`, or the states are stored: `_state: T`)
+    let phantom_fields = state_idents.iter().map(|ident| {
+        if stores_states {
+            quote!(#ident)
+        } else {
+            type_state_args.phantom.phantom_type(ident)
+        }
+    });
+    let state_type = quote!((#(#phantom_fields),*));
 
-    // Get the struct's attributes (other macros) excluding the #[type_state] macro
-    let attrs: Vec<_> = input_struct
-        .attrs
+    // `#[derive(Debug)]` is replaced with the `Debug` impl that shows the states (see `debug_impl`)
+    let debug_impl = replaced_derives
         .iter()
-        .filter(|attr| !attr.path().is_ident("type_state"))
-        .collect();
+        .any(|derive| derive == "Debug")
+        .then(|| {
+            debug_impl(
+                struct_name,
+                generics,
+                &state_idents,
+                &type_state_args,
+                &struct_fields,
+                tuple_fields.as_ref(),
+                enum_variants.as_ref(),
+            )
+        });
+    let derive_impls = match derive_impls(
+        &replaced_derives,
+        struct_name,
+        generics,
+        &state_idents,
+        &type_state_args,
+        &struct_fields,
+        tuple_fields.as_ref(),
+        enum_variants.as_ref(),
+        &state_type,
+    ) {
+        Ok(derive_impls) => derive_impls,
+        Err(error) => return partial_expansion(&original, error),
+    };
+
+    // the struct, or the enum with the `_state` field in each of its variants
+    let definition = match struct_definition(
+        &input_struct,
+        attrs,
+        &state_idents,
+        &type_state_args,
+        struct_fields,
+        tuple_fields,
+        enum_variants,
+        &state_type,
+        (serde_derive, serde_field),
+        builder_derive.map(|(_, skipped)| skipped),
+    ) {
+        Ok(definition) => definition,
+        Err(error) => return partial_expansion(&original, error),
+    };
+
+    let callback_macro = callback_macro(struct_name, generics, &type_state_args, &raw_args);
+    let only_in_items = &only_in.items;
+    let missing_transitions = type_state_args
+        .declared_transitions
+        .then(|| missing_transitions_fallback(struct_name));
+    let hooks_registry = hooks_registry(struct_name, &type_state_args);
 
     // Generate the final output
     let output = quote! {
-        mod #sealed_mod_name {
-            pub trait Sealed {}
-        }
+        #callback_macro
 
-        pub trait #sealer_trait_name: #sealed_mod_name::Sealed {}
+        #state_set
 
-        #(#markers)*
+        #definition
 
-        #(#sealed_impls)*
+        #in_place_helper
 
-        #(#trait_impls)*
+        #any_state_enum
 
-        #(#attrs)*
-        #[allow(clippy::type_complexity)]
-        #visibility struct #struct_name<#combined_generics>
-        #merged_where_clause
-        {
-            #struct_fields
-            _state: (#(#phantom_fields),*),
-        }
+        #state_aliases
+
+        #state_registry
+
+        #state_enum
+
+        #serde_states
+
+        #debug_impl
+
+        #derive_impls
+
+        #(#state_data_accessors)*
+
+        #state_name_accessors
+
+        #all_states
+
+        #state_rebuilders
+
+        #only_in_items
+
+        #missing_transitions
+
+        #hooks_registry
     };
 
     output.into()
 }
+
+/// The struct given to `#[type_state]` with the generics of its states, and the `_state` field that holds them:
+/// `struct Player<PlayerState1 = Idle> where PlayerState1: SealerPlayer { name: String, _state: (PhantomData<..>,) }`
+///
+/// (the tuple structs get the `_state` as their last element, and the enums get it in each of their variants)
+#[allow(clippy::too_many_arguments)]
+fn struct_definition(
+    input_struct: &ItemStruct,
+    attrs: Vec<Attribute>,
+    state_idents: &[Ident],
+    type_state_args: &TypeStateArgs,
+    struct_fields: Punctuated<syn::Field, Token![,]>,
+    tuple_fields: Option<Punctuated<syn::Field, Token![,]>>,
+    enum_variants: Option<Punctuated<Variant, Token![,]>>,
+    state_type: &proc_macro2::TokenStream,
+    (serde_derive, serde_field): (proc_macro2::TokenStream, proc_macro2::TokenStream),
+    // the attribute that skips the `_state` field in the builder of the struct (see `builder_derive`)
+    builder_field: Option<proc_macro2::TokenStream>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let struct_name = &input_struct.ident;
+    let generics = &input_struct.generics;
+    let visibility = &input_struct.vis;
+    let slots = &type_state_args.slots;
+    let state_field = &type_state_args.field;
+    let default_slots = slots.iter().map(|slot| &slot.default);
+
+    // Construct the new generics by merging original generics with default states
+    let combined_generics = if generics.params.is_empty() {
+        quote! { #(#state_idents = #default_slots),* }
+    } else {
+        let original_generics = generics.params.iter();
+        quote! { #(#original_generics),*, #(#state_idents = #default_slots),* }
+    };
+
+    // Merge the where clauses (the existing one, if any, and the one for the new generics (states))
+    let mut merged_where_clause = generics.where_clause.clone();
+    if !state_idents.is_empty() {
+        let where_clause = merged_where_clause.get_or_insert_with(|| syn::parse_quote!(where));
+        for (index, state) in state_idents.iter().enumerate() {
+            let sealer = type_state_args.sealer_of_slot(index, struct_name);
+            where_clause
+                .predicates
+                .push(syn::parse_quote!(#state: #sealer));
+        }
+    }
+
+    // the methods of the other modules build the struct as well, when its states are registered for them
+    // (see `state_registry`)
+    let state_field_vis = type_state_args
+        .registry
+        .is_some()
+        .then(|| quote!(pub(crate)));
+
+    Ok(match (enum_variants, tuple_fields) {
+        (None, Some(tuple_fields)) => {
+            if let Some(component) = slots
+                .iter()
+                .find_map(|slot| slot.name.as_ref().filter(|_| slot.component.is_some()))
+            {
+                return Err(syn::Error::new(
+                    component.span(),
+                    "the components cannot be used with the tuple structs, since they are named fields",
+                ));
+            }
+            let separator = (!tuple_fields.empty_or_trailing()).then(<Token![,]>::default);
+            quote! {
+                #serde_derive
+                #(#attrs)*
+                #[allow(clippy::type_complexity)]
+                #visibility struct #struct_name<#combined_generics>(
+                    #tuple_fields #separator
+                    #[doc(hidden)] #serde_field #state_field_vis #state_type,
+                )
+                #merged_where_clause;
+            }
+        }
+        (None, None) => {
+            // the `_state` field is appended after the last field, which may not have a trailing comma
+            let fields_separator = if struct_fields.empty_or_trailing() {
+                quote! {}
+            } else {
+                quote! { , }
+            };
+            // the fields of the components, in the states of their slots: `auth: Auth<SessionState1>`
+            let component_fields =
+                slots
+                    .iter()
+                    .zip(state_idents)
+                    .filter_map(|(slot, state_ident)| {
+                        let component = slot.component.as_ref()?;
+                        let field = slot.name.as_ref()?;
+                        Some(quote!(#field: #component<#state_ident>,))
+                    });
+            quote! {
+                #serde_derive
+                #(#attrs)*
+                #[allow(clippy::type_complexity)]
+                #visibility struct #struct_name<#combined_generics>
+                #merged_where_clause
+                {
+                    #struct_fields #fields_separator
+                    #(#component_fields)*
+                    #serde_field
+                    #builder_field
+                    #state_field_vis #state_field: #state_type,
+                }
+            }
+        }
+        (Some(mut variants), _) => {
+            for variant in variants.iter_mut() {
+                match &mut variant.fields {
+                    Fields::Named(fields) => fields.named.push(syn::parse_quote!(
+                        #[doc(hidden)] #serde_field #state_field: #state_type
+                    )),
+                    Fields::Unnamed(fields) => fields.unnamed.push(
+                        syn::Field::parse_unnamed
+                            .parse2(quote!(#[doc(hidden)] #serde_field #state_type))
+                            .unwrap(),
+                    ),
+                    Fields::Unit => {
+                        return Err(syn::Error::new(
+                            variant.ident.span(),
+                            format!(
+                                "the unit variants cannot carry the state, \
+                                 declare it as `{} {{}}` instead",
+                                variant.ident
+                            ),
+                        ))
+                    }
+                }
+            }
+            quote! {
+                #serde_derive
+                #(#attrs)*
+                #[allow(clippy::type_complexity)]
+                #visibility enum #struct_name<#combined_generics>
+                #merged_where_clause
+                {
+                    #variants
+                }
+            }
+        }
+    })
+}
+
+/// The hidden macro of the struct (see `callback_macro_name`), which carries the arguments of `#[type_state]`
+/// to the other macros, along with its `use`, so that it can be named by its path like the struct
+fn callback_macro(
+    struct_name: &Ident,
+    generics: &Generics,
+    type_state_args: &TypeStateArgs,
+    raw_args: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let callback_macro_name = callback_macro_name(struct_name);
+    // the generic parameters of the struct are passed to `#[impl_state]` as well, for their defaults
+    let struct_params = &generics.params;
+
+    // the hidden macro is exported for the other crates, if the struct is extensible:
+    // `#[impl_state] #[require(Draft)] impl Review for library::Article` in another crate
+    // expands into `library::__state_shift_article!`, which is re-exported next to the struct
+    let (macro_export, macro_visibility) = if type_state_args.extensible {
+        (quote!(#[macro_export]), quote!(pub))
+    } else {
+        (quote!(), quote!(pub(crate)))
+    };
+
+    quote! {
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        #macro_export
+        macro_rules! #callback_macro_name {
+            // the structs reusing the states of this struct: `reuse = Player` (see `reuse_states`)
+            (@reuse $($tokens:tt)*) => {
+                ::state_shift::__type_state_reuse! { (#raw_args) $($tokens)* }
+            };
+            // the `impl` blocks with cross-type transitions into this struct (see `impl_state_inner`)
+            (@target $($tokens:tt)*) => {
+                ::state_shift::__impl_state! { @target(#struct_name (#raw_args)) $($tokens)* }
+            };
+            // the `match_state!` of the struct (see `forward_match_state`)
+            (@match $($tokens:tt)*) => {
+                ::state_shift::__match_state! { (#raw_args) $($tokens)* }
+            };
+            // the `transitions!` of the struct (see `forward_transitions`)
+            (@transitions $($tokens:tt)*) => {
+                ::state_shift::__transitions! { (#raw_args) $($tokens)* }
+            };
+            ($($tokens:tt)*) => {
+                ::state_shift::__impl_state! { (#raw_args) (#struct_params) $($tokens)* }
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        #macro_visibility use #callback_macro_name;
+    }
+}
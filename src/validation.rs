@@ -0,0 +1,238 @@
+/// this file contains the checks of the `#[require]` and `#[switch_to]` arguments
+/// against the declaration of the `#[type_state]` macro, so that the mistakes are reported
+/// on the arguments themselves, instead of somewhere deep in the generated code
+use proc_macro2::Span;
+use syn::{punctuated::Punctuated, Ident, Token};
+
+use crate::{RequiredSlot, StateArg, TypeStateArgs};
+
+/// Checks that there is an argument for each slot, if the arguments are positional
+///
+/// named arguments are placed into their slots instead (see `order_slot_args`)
+pub fn check_arity<'a>(
+    span: Span,
+    macro_name: &str,
+    mut slot_names: impl ExactSizeIterator<Item = Option<&'a Ident>>,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<()> {
+    let arg_count = slot_names.len();
+    let slot_count = type_state_args.slots.len();
+
+    if arg_count != slot_count && slot_names.all(|slot_name| slot_name.is_none()) {
+        return Err(syn::Error::new(
+            span,
+            format!(
+                "`#[{}]` expects {} argument(s), one for each slot declared in `#[type_state]`, but found {}",
+                macro_name, slot_count, arg_count
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that at most one slot of `#[switch_to]` branches into several states (`Connected | Failed`),
+/// since the variants of the generated enum are named after the states of that slot
+pub fn check_branching(
+    switch_to_args: &[Option<Punctuated<StateArg, Token![|]>>],
+) -> syn::Result<()> {
+    let mut branching_slots = switch_to_args
+        .iter()
+        .flatten()
+        .filter(|states| states.len() > 1);
+
+    if let (Some(_), Some(second)) = (branching_slots.next(), branching_slots.next()) {
+        return Err(syn::Error::new(
+            second[0].ident.span(),
+            "only one slot of `#[switch_to]` can branch into several states",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that the methods that require a terminal state (see `TypeStateArgs::terminal_states`) don't transition out of it:
+/// `#[require(Closed)] #[switch_to(Open)]` is an error for `terminal = (Closed)`
+///
+/// (the methods that require any state of the slot, like `A` or a group, can still transition,
+/// since they don't require the terminal state in particular)
+pub fn check_terminal_states(
+    require_args: &[RequiredSlot],
+    switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<()> {
+    let Some(switch_to_args) = switch_to_args else {
+        return Ok(());
+    };
+
+    for (slot, target) in require_args.iter().zip(switch_to_args) {
+        let (RequiredSlot::OneOf(required), Some(target)) = (slot, target) else {
+            continue;
+        };
+        let terminal = required
+            .iter()
+            .find(|state| type_state_args.terminal_states.contains(&state.ident));
+        let leaving = target
+            .iter()
+            .find(|target| terminal.is_some_and(|terminal| target.ident != terminal.ident));
+        if let (Some(terminal), Some(leaving)) = (terminal, leaving) {
+            return Err(syn::Error::new(
+                leaving.ident.span(),
+                format!(
+                    "`{}` is a terminal state, so the methods that require it cannot transition out of it",
+                    terminal.ident
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the concrete states given to each slot are declared, and allowed in that slot (see `slot_states`),
+/// and that the substates are given with their own parents: `Connected::Active`
+pub fn check_slot_states(
+    require_args: &[RequiredSlot],
+    switch_to_args: Option<&[Option<Punctuated<StateArg, Token![|]>>]>,
+    type_state_args: &TypeStateArgs,
+) -> syn::Result<()> {
+    let is_group = |ident: &Ident| type_state_args.groups.iter().any(|g| g.name == *ident);
+
+    // the states of the components are checked by the compiler against the sealers of the components,
+    // since the struct doesn't know them
+    for (index, slot) in require_args.iter().enumerate() {
+        if let (RequiredSlot::Not(states), Some(_)) = (slot, type_state_args.slot_component(index))
+        {
+            return Err(syn::Error::new(
+                states[0].ident.span(),
+                "`not(...)` cannot be used for the slots of the components, since their states are not known here",
+            ));
+        }
+    }
+    let is_component_slot = |index: usize| type_state_args.slot_component(index).is_some();
+
+    let required_states = require_args.iter().map(|slot| match slot {
+        RequiredSlot::Any(_) => Vec::new(),
+        RequiredSlot::OneOf(states) | RequiredSlot::Not(states) => states.iter().collect(),
+    });
+    let switched_states = switch_to_args
+        .into_iter()
+        .flatten()
+        .map(|states| states.iter().flatten().collect());
+
+    for (index, states) in required_states
+        .enumerate()
+        .chain(switched_states.enumerate())
+    {
+        if is_component_slot(index) {
+            continue;
+        }
+        let allowed = type_state_args.states_of_slot(index);
+        for state in states {
+            check_substate_path(state, type_state_args)?;
+            if state.is_generic() || is_group(&state.ident) {
+                continue;
+            }
+            // the typos are reported here, instead of as the missing types in the generated code
+            if !type_state_args.states.contains(&state.ident) {
+                let message = match closest_name(&state.ident, type_state_args.names()) {
+                    Some(closest) => {
+                        format!(
+                            "unknown state `{}`; did you mean `{}`?",
+                            state.ident, closest
+                        )
+                    }
+                    None => format!(
+                        "unknown state `{}`, expected one of the declared states: {}",
+                        state.ident,
+                        type_state_args
+                            .states
+                            .iter()
+                            .map(|state| format!("`{}`", state))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                };
+                return Err(syn::Error::new(state.ident.span(), message));
+            }
+            if !allowed.contains(&state.ident) {
+                return Err(syn::Error::new(
+                    state.ident.span(),
+                    format!(
+                        "state `{}` is not allowed in slot {}, expected one of: {}",
+                        state.ident,
+                        index + 1,
+                        allowed
+                            .iter()
+                            .map(|state| format!("`{}`", state))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that each state of the path is a substate of the one before it:
+/// `Connected::Active` requires `Active` to be declared as `Connected(Active, ...)`
+fn check_substate_path(state: &StateArg, type_state_args: &TypeStateArgs) -> syn::Result<()> {
+    let path: Vec<&Ident> = state.parents.iter().chain([&state.ident]).collect();
+    for pair in path.windows(2) {
+        let (parent, substate) = (pair[0], pair[1]);
+        if type_state_args.parent_of(substate) != Some(parent) {
+            return Err(syn::Error::new(
+                substate.span(),
+                format!("`{}` is not a substate of `{}`", substate, parent),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The declared name (of a state or a group) that is the closest to a misspelled one, if it is close enough,
+/// for the suggestions of the errors: "unknown state `Connceted`; did you mean `Connected`?"
+///
+/// the names are compared by their edit distance, which can be a third of the length of the name at most
+pub fn closest_name<'a>(
+    name: &Ident,
+    candidates: impl IntoIterator<Item = &'a Ident>,
+) -> Option<&'a Ident> {
+    let name = name.to_string();
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&name, &candidate.to_string()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The optimal string alignment distance of two names: the number of the characters to insert, remove or replace,
+/// and of the adjacent characters to swap, so that `Opne` is as close to `Open` as `Opn` is
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // the rows of the characters of `a` before the current one, and of the one before them (for the swaps)
+    let mut before_previous: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 0..a.len() {
+        let mut current = vec![i + 1];
+        for j in 0..b.len() {
+            let replaced = previous[j] + usize::from(a[i] != b[j]);
+            let mut distance = replaced.min(previous[j + 1] + 1).min(current[j] + 1);
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j] {
+                distance = distance.min(before_previous[j - 1] + 1);
+            }
+            current.push(distance);
+        }
+        before_previous = std::mem::replace(&mut previous, current);
+    }
+
+    previous[b.len()]
+}
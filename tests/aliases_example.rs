@@ -0,0 +1,73 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running), slots = (Idle), aliases = true)]
+struct Player {
+    level: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new(level: u8) -> Self {
+        Self { level }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { level: self.level }
+    }
+}
+
+// the signatures outside of `#[impl_state]` name the states through the aliases
+fn start_all(players: Vec<IdlePlayer>) -> Vec<RunningPlayer> {
+    players.into_iter().map(Player::start).collect()
+}
+
+// the naming is configurable, and the states are named without the prefix
+#[type_state(
+    states = (Empty, Loaded<T>),
+    slots = (Empty),
+    prefix = "Cache",
+    aliases = "{struct}In{state}"
+)]
+struct Cache<K = String> {
+    keys: Vec<K>,
+}
+
+#[impl_state]
+impl<K> Cache<K> {
+    #[switch_to(Empty)]
+    fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    #[require(Empty)]
+    #[switch_to(Loaded<T>)]
+    fn load<T>(self) -> Self {
+        Self { keys: self.keys }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aliases_name_the_states() {
+        let players: Vec<IdlePlayer> = vec![Player::new(1), Player::new(2)];
+        let players = start_all(players);
+        assert_eq!(players[1].level, 2);
+        assert_eq!(players[0].state_name(), "Running");
+    }
+
+    #[test]
+    fn aliases_keep_the_generics() {
+        // the default of the generics of the struct is kept
+        let cache: CacheInEmpty = Cache::new();
+        assert!(cache.keys.is_empty());
+
+        let cache: CacheInLoaded<u8, u32> = Cache::new().load();
+        assert_eq!(cache.state_name(), "Loaded");
+    }
+}
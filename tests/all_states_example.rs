@@ -0,0 +1,61 @@
+use state_shift::{impl_state, type_state};
+
+// the declared states can be enumerated, for the CLIs, the debug UIs and the test matrices
+#[type_state(
+    states = (
+        Disconnected,
+        Connecting,
+        #[cfg(target_os = "none")] Offline,
+        Connected(Active, Idle),
+    ),
+    slots = (Disconnected),
+    prefix = "Link"
+)]
+struct Link {
+    peer: String,
+}
+
+#[impl_state]
+impl Link {
+    #[switch_to(Disconnected)]
+    fn new(peer: &str) -> Self {
+        Self {
+            peer: peer.to_string(),
+        }
+    }
+
+    #[require(A)]
+    fn peer(&self) -> &str {
+        &self.peer
+    }
+}
+
+#[type_state(states = (Empty, Loaded), slots = (Empty))]
+struct Store<T> {
+    items: Vec<T>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_states_are_listed() {
+        // the substates are listed instead of their parents, and the gated states along with them
+        assert_eq!(
+            Link::ALL_STATES,
+            ["Disconnected", "Connecting", "Active", "Idle"]
+        );
+        assert_eq!(Link::new("peer").peer(), "peer");
+    }
+
+    #[test]
+    fn all_states_of_generic_structs_are_listed() {
+        assert_eq!(Store::<u8>::ALL_STATES, ["Empty", "Loaded"]);
+        let store: Store<u8> = Store {
+            items: Vec::new(),
+            _state: ::core::marker::PhantomData,
+        };
+        assert!(store.items.is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Locked, Unlocked), slots = (Locked, Locked))]
+struct Vault {
+    gold: u32,
+}
+
+#[impl_state]
+impl Vault {
+    #[require(Locked, Locked)]
+    fn new(gold: u32) -> Vault {
+        Vault { gold }
+    }
+
+    #[require(Locked, _)]
+    #[switch_to(Unlocked, _)] // keeps the 2nd slot as it is
+    fn unlock_outer(self) -> Vault {
+        Vault { gold: self.gold }
+    }
+
+    #[require(Unlocked, Locked)]
+    #[switch_to(_, Unlocked)]
+    fn unlock_inner(self) -> Vault {
+        Vault { gold: self.gold }
+    }
+
+    #[require(Unlocked, _)]
+    #[switch_to(Locked, _)]
+    fn lock_outer(self) -> Vault {
+        Vault { gold: self.gold }
+    }
+
+    #[require(Locked, Unlocked)]
+    #[switch_to(Unlocked, _)]
+    fn unlock_outer_again(self) -> Vault {
+        Vault { gold: self.gold }
+    }
+
+    #[require(_, _)] // available at any state
+    fn deposit(self, gold: u32) -> Vault {
+        Vault {
+            gold: self.gold + gold,
+        }
+    }
+
+    #[require(_, Unlocked)]
+    fn gold(&self) -> u32 {
+        self.gold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn any_state_is_kept() {
+        let vault = Vault::new(10)
+            .unlock_outer()
+            .unlock_inner()
+            .lock_outer()
+            .unlock_outer_again();
+        assert_eq!(vault.gold(), 10);
+    }
+
+    #[test]
+    fn any_state_works() {
+        let vault = Vault::new(10)
+            .deposit(5)
+            .unlock_outer()
+            .deposit(5)
+            .unlock_inner()
+            .deposit(5);
+
+        assert_eq!(vault.gold(), 25);
+    }
+}
@@ -0,0 +1,74 @@
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use state_shift::{impl_state, type_state};
+
+// a minimal executor, since the futures below never wait on anything
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+async fn handshake(attempt: u8) -> Result<u8, String> {
+    match attempt {
+        0 => Err("refused".to_string()),
+        _ => Ok(attempt),
+    }
+}
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Socket {
+    session: u8,
+}
+
+#[impl_state]
+impl Socket {
+    #[require(Closed)]
+    fn new() -> Socket {
+        Socket { session: 0 }
+    }
+
+    #[require(Closed)]
+    #[switch_to(Open)]
+    async fn open(self, attempt: u8) -> Result<Self, String> {
+        let session = handshake(attempt).await?;
+        Ok(Socket { session })
+    }
+
+    #[require(Open)]
+    #[switch_to(Closed)]
+    async fn close(self) -> Socket {
+        async {}.await;
+        Socket {
+            session: self.session,
+        }
+    }
+
+    #[require(Open)]
+    async fn session(&self) -> u8 {
+        self.session
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn async_methods_work() {
+        block_on(async {
+            assert!(Socket::new().open(0).await.is_err());
+
+            let socket = Socket::new().open(7).await.unwrap();
+            assert_eq!(socket.session().await, 7);
+
+            let _socket: Socket<Closed> = socket.close().await;
+        });
+    }
+}
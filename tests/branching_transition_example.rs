@@ -0,0 +1,95 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Connected, Failed), slots = (Idle))]
+struct Player {
+    retries: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[require(Idle)]
+    fn new() -> Player {
+        Player { retries: 0 }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Connected | Failed)] // returns `ConnectOutcome { Connected(Player<Connected>), Failed(Player<Failed>) }`
+    fn connect(self, online: bool) -> Self {
+        if online {
+            ConnectOutcome::Connected(Player {
+                retries: self.retries,
+            })
+        } else {
+            ConnectOutcome::Failed(Player {
+                retries: self.retries,
+            })
+        }
+    }
+
+    #[require(Failed)]
+    #[switch_to(Idle)]
+    fn retry(self) -> Player {
+        Player {
+            retries: self.retries + 1,
+        }
+    }
+
+    #[require(Connected)]
+    fn retries(&self) -> u8 {
+        self.retries
+    }
+}
+
+#[type_state(states = (Closed, Open, Jammed, Red, Green), slots = (Closed, Red))]
+struct Gate {}
+
+#[impl_state]
+impl Gate {
+    #[require(Closed, Red)]
+    fn new() -> Gate {
+        Gate {}
+    }
+
+    #[require(Closed, _)]
+    #[switch_to(Open | Jammed, _)] // the other slots are kept as the generics of the enum
+    fn open(self, stuck: bool) -> Result<Gate, ()> {
+        match stuck {
+            true => Ok(OpenOutcome::Jammed(Gate {})),
+            false => Ok(OpenOutcome::Open(Gate {})),
+        }
+    }
+
+    #[require(Open, Red)]
+    #[switch_to(Open, Green)]
+    fn turn_green(self) -> Gate {
+        Gate {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branching_transition_works() {
+        let player = match Player::new().connect(false) {
+            ConnectOutcome::Connected(_) => panic!("should not be connected"),
+            ConnectOutcome::Failed(player) => player.retry(),
+        };
+
+        match player.connect(true) {
+            ConnectOutcome::Connected(player) => assert_eq!(player.retries(), 1),
+            ConnectOutcome::Failed(_) => panic!("should be connected"),
+        }
+    }
+
+    #[test]
+    fn branching_transition_with_multiple_slots_works() {
+        assert!(matches!(Gate::new().open(true), Ok(OpenOutcome::Jammed(_))));
+
+        let _gate: Gate<Open, Green> = match Gate::new().open(false) {
+            Ok(OpenOutcome::Open(gate)) => gate.turn_green(),
+            _ => panic!("should be open"),
+        };
+    }
+}
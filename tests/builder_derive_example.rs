@@ -0,0 +1,70 @@
+use derive_builder::Builder;
+use state_shift::{impl_state, type_state};
+use typed_builder::TypedBuilder;
+
+// the builder derives go after `#[type_state]`, so that they see the hidden `_state` field, which they skip
+#[type_state(states = (Idle, Running), slots = (Idle))]
+#[derive(Builder)]
+struct Player {
+    name: String,
+    level: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self {
+            name: self.name,
+            level: self.level + 1,
+        }
+    }
+}
+
+#[type_state(states = (Off, On), slots = (Off))]
+#[derive(TypedBuilder)]
+struct Lamp<T> {
+    watts: T,
+    #[builder(default = 3)]
+    bulbs: u8,
+}
+
+#[impl_state]
+impl<T> Lamp<T> {
+    #[require(Off)]
+    #[switch_to(On)]
+    fn turn_on(self) -> Self {
+        Self {
+            watts: self.watts,
+            bulbs: self.bulbs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_builder_builds_in_the_default_state() {
+        let player: Player = PlayerBuilder::default()
+            .name("Alice".to_string())
+            .level(1)
+            .build()
+            .unwrap();
+        assert_eq!(player.state_name(), "Idle");
+
+        let player = player.start();
+        assert_eq!((player.name.as_str(), player.level), ("Alice", 2));
+    }
+
+    #[test]
+    fn typed_builder_builds_in_the_default_state() {
+        let lamp: Lamp<u32> = Lamp::builder().watts(60).build();
+        assert_eq!(lamp.state_name(), "Off");
+
+        let lamp = lamp.turn_on();
+        assert_eq!((lamp.watts, lamp.bulbs), (60, 3));
+    }
+}
@@ -0,0 +1,86 @@
+use state_shift::{impl_state, type_state_builder};
+
+#[type_state_builder]
+#[derive(Debug, PartialEq)]
+struct Player {
+    name: String,
+    level: u8,
+}
+
+// the builder is a type-state struct, so it can get more methods, whose slots are named after the fields
+#[impl_state]
+impl PlayerBuilder {
+    #[require(name = Set)]
+    fn greeting(&self) -> String {
+        format!("Hello, {}!", self.name.as_deref().unwrap_or_default())
+    }
+}
+
+// the generics of the struct are kept on the builder
+#[type_state_builder]
+struct Pair<T: Clone, const N: usize> {
+    items: [T; N],
+    label: &'static str,
+}
+
+// the optional fields don't have a slot, `build()` fills them with their defaults
+#[type_state_builder]
+struct Server {
+    host: String,
+    #[optional]
+    tags: Vec<String>,
+    #[default = 8080]
+    port: u16,
+    #[default = format!("{}-backup", "db")]
+    backup: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn struct_is_built_once_all_fields_are_set() {
+        let player = Player::builder().level(3).name("Alice".to_string()).build();
+        assert_eq!(
+            player,
+            Player {
+                name: "Alice".to_string(),
+                level: 3
+            }
+        );
+    }
+
+    #[test]
+    fn builder_tracks_each_field() {
+        let builder = PlayerBuilder::new().name("Bob".to_string());
+        assert_eq!(builder.name_state_name(), "Set");
+        assert_eq!(builder.level_state_name(), "Unset");
+        assert_eq!(builder.greeting(), "Hello, Bob!");
+        assert_eq!(builder.level(7).build().level, 7);
+    }
+
+    #[test]
+    fn generic_structs_are_built() {
+        let pair: Pair<char, 2> = Pair::builder().items(['a', 'b']).label("ab").build();
+        assert_eq!(pair.items, ['a', 'b']);
+        assert_eq!(pair.label, "ab");
+    }
+
+    #[test]
+    fn optional_fields_are_defaulted() {
+        let server = Server::builder().host("localhost".to_string()).build();
+        assert!(server.tags.is_empty());
+        assert_eq!(server.port, 8080);
+        assert_eq!(server.backup, "db-backup");
+
+        // and they can be set in any state, before or after the required fields
+        let server = Server::builder()
+            .port(443)
+            .host("example.com".to_string())
+            .tags(vec!["web".to_string()])
+            .build();
+        assert_eq!((server.host.as_str(), server.port), ("example.com", 443));
+        assert_eq!(server.tags, ["web"]);
+    }
+}
@@ -0,0 +1,79 @@
+use state_shift::{impl_state, type_state};
+
+// `Handshaking` only exists on the targets with an OS (like a state of an optional feature would),
+// and `Offline` on the others
+#[type_state(
+    states = (
+        Idle,
+        #[cfg(not(target_os = "none"))] Handshaking,
+        #[cfg(target_os = "none")] Offline,
+        Ready,
+    ),
+    slots = (Idle)
+)]
+struct Link {
+    peer: String,
+}
+
+#[impl_state]
+impl Link {
+    #[switch_to(Idle)]
+    fn new(peer: &str) -> Self {
+        Self {
+            peer: peer.to_string(),
+        }
+    }
+
+    // the transitions of the gated states are gated along with them
+    #[require(Idle)]
+    #[switch_to(Handshaking)]
+    fn handshake(self) -> Self {
+        Self { peer: self.peer }
+    }
+
+    #[require(Handshaking)]
+    #[switch_to(Ready)]
+    fn finish(self) -> Self {
+        Self { peer: self.peer }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Offline)]
+    fn go_offline(self) -> Self {
+        Self { peer: self.peer }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Ready)]
+    fn connect(self) -> Self {
+        Self { peer: self.peer }
+    }
+
+    // `not(...)` covers the gated states only when they exist
+    #[require(not(Ready))]
+    fn is_ready(&self) -> bool {
+        false
+    }
+
+    #[require(Ready)]
+    fn peer(&self) -> &str {
+        &self.peer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_gated_states_work() {
+        let link: Link<Handshaking> = Link::new("alpha").handshake();
+        assert!(!link.is_ready());
+
+        let link: Link<Ready> = link.finish();
+        assert_eq!(link.peer(), "alpha");
+
+        let link: Link<Ready> = Link::new("beta").connect();
+        assert_eq!(link.peer(), "beta");
+    }
+}
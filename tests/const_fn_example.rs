@@ -0,0 +1,57 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Unset, PortSet, Ready), slots = (Unset))]
+struct Config {
+    port: u16,
+    verbose: bool,
+}
+
+#[impl_state]
+impl Config {
+    #[require(Unset)]
+    const fn new() -> Config {
+        Config {
+            port: 0,
+            verbose: false,
+        }
+    }
+
+    #[require(Unset)]
+    #[switch_to(PortSet)]
+    const fn port(self, port: u16) -> Config {
+        Config {
+            port,
+            verbose: self.verbose,
+        }
+    }
+
+    #[require(PortSet)]
+    #[switch_to(Ready)]
+    const fn finish(self, verbose: bool) -> Self {
+        Self {
+            port: self.port,
+            verbose,
+        }
+    }
+
+    #[require(Ready)]
+    const fn port_number(&self) -> u16 {
+        self.port
+    }
+}
+
+// the whole configuration is built at compile time
+const CONFIG: Config<Ready> = Config::new().port(8080).finish(true);
+static PORT: u16 = CONFIG.port_number();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn const_fn_transitions_work() {
+        let config = CONFIG;
+        assert_eq!(PORT, 8080);
+        assert!(config.verbose);
+    }
+}
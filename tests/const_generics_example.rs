@@ -0,0 +1,66 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Empty, Partial, Full), slots = (Empty))]
+struct Buffer<const N: usize> {
+    data: [u8; N],
+    len: usize,
+}
+
+#[impl_state]
+impl<const N: usize> Buffer<N> {
+    #[switch_to(Empty)]
+    fn new() -> Self {
+        Self {
+            data: [0; N],
+            len: 0,
+        }
+    }
+
+    #[require(Empty | Partial)]
+    #[switch_to(Partial | Full)]
+    fn push(mut self, byte: u8) -> Self {
+        self.data[self.len] = byte;
+        self.len += 1;
+
+        if self.len == N {
+            PushOutcome::Full(Buffer {
+                data: self.data,
+                len: self.len,
+            })
+        } else {
+            PushOutcome::Partial(Buffer {
+                data: self.data,
+                len: self.len,
+            })
+        }
+    }
+
+    #[require(Full)]
+    fn sum(&self) -> u32 {
+        self.data.iter().map(|byte| *byte as u32).sum()
+    }
+
+    #[require(A)]
+    fn capacity(&self) -> usize {
+        N
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn const_generics_work() {
+        let buffer = Buffer::<2>::new();
+        assert_eq!(buffer.capacity(), 2);
+
+        let PushOutcome::Partial(buffer) = buffer.push(3) else {
+            panic!("the buffer cannot be full after a single push");
+        };
+        let PushOutcome::Full(buffer) = buffer.push(4) else {
+            panic!("the buffer must be full after two pushes");
+        };
+        assert_eq!(buffer.sum(), 7);
+    }
+}
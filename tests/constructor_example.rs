@@ -0,0 +1,59 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Draft, Published, Private, Public),
+    slots = (status = Draft, access = Private)
+)]
+struct Article {
+    title: String,
+}
+
+#[impl_state]
+impl Article {
+    // the constructor produces an `Article<Draft, Private>`
+    #[switch_to(Draft, Private)]
+    fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+        }
+    }
+
+    // the `access` slot is not mentioned, it gets its default state: `Article<Published, Private>`
+    #[switch_to(status = Published)]
+    fn imported(title: &str) -> Article {
+        Article {
+            title: title.to_string(),
+        }
+    }
+
+    #[require(Draft, _)]
+    #[switch_to(Published, _)]
+    fn publish(self) -> Article {
+        Article { title: self.title }
+    }
+
+    #[require(Published, Private)]
+    #[switch_to(_, Public)]
+    fn share(self) -> Article {
+        Article { title: self.title }
+    }
+
+    #[require(Published, Public)]
+    fn link(&self) -> String {
+        format!("/articles/{}", self.title)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constructors_produce_the_target_states() {
+        let article: Article<Draft, Private> = Article::new("hello");
+        assert_eq!(article.publish().share().link(), "/articles/hello");
+
+        let article: Article<Published, Private> = Article::imported("world");
+        assert_eq!(article.share().link(), "/articles/world");
+    }
+}
@@ -0,0 +1,115 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Established, Closed), slots = (Established))]
+struct Connection {
+    host: String,
+}
+
+#[impl_state]
+impl Connection {
+    #[require(Established)]
+    #[switch_to(Closed)]
+    fn close(self) -> Connection {
+        Connection { host: self.host }
+    }
+
+    #[require(_)]
+    fn host(&self) -> &str {
+        &self.host
+    }
+}
+
+// the targets initialize their states in their own field, and at the end of the tuple structs
+#[type_state(states = (Pending, Granted), slots = (Pending), field = "status")]
+struct Lease {
+    host: String,
+}
+
+#[impl_state]
+impl Lease {
+    #[require(_)]
+    fn host(&self) -> &str {
+        &self.host
+    }
+}
+
+#[type_state(states = (Issued, Revoked), slots = (Issued))]
+struct Ticket(u32);
+
+#[impl_state]
+impl Ticket {
+    #[require(_)]
+    fn number(&self) -> u32 {
+        self.0
+    }
+}
+
+#[type_state(states = (Empty, HostSet), slots = (Empty))]
+struct ConnectionBuilder {
+    host: Option<String>,
+}
+
+#[impl_state]
+impl ConnectionBuilder {
+    #[require(Empty)]
+    fn new() -> ConnectionBuilder {
+        ConnectionBuilder { host: None }
+    }
+
+    #[require(Empty)]
+    #[switch_to(HostSet)]
+    fn host(self, host: &str) -> ConnectionBuilder {
+        ConnectionBuilder {
+            host: Some(host.to_string()),
+        }
+    }
+
+    #[require(HostSet)]
+    #[switch_to(Connection<Established>)] // finishes the builder by producing another type-state struct
+    fn connect(self) -> Result<Connection, String> {
+        match self.host {
+            Some(host) => Ok(Connection { host }),
+            None => Err("no host".to_string()),
+        }
+    }
+
+    #[require(HostSet)]
+    #[switch_to(Lease<Granted>)]
+    fn lease(self) -> Lease {
+        Lease {
+            host: self.host.unwrap_or_default(),
+        }
+    }
+
+    #[require(_)]
+    #[switch_to(Ticket<Issued>)]
+    fn ticket(self, number: u32) -> Ticket {
+        Ticket(number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cross_type_transition_works() {
+        let connection: Connection<Established> = ConnectionBuilder::new()
+            .host("localhost")
+            .connect()
+            .unwrap();
+        assert_eq!(connection.host(), "localhost");
+
+        let connection: Connection<Closed> = connection.close();
+        assert_eq!(connection.host(), "localhost");
+    }
+
+    #[test]
+    fn cross_type_transition_into_other_shapes_works() {
+        let lease: Lease<Granted> = ConnectionBuilder::new().host("localhost").lease();
+        assert_eq!(lease.host(), "localhost");
+
+        let ticket: Ticket<Issued> = ConnectionBuilder::new().ticket(7);
+        assert_eq!(ticket.number(), 7);
+    }
+}
@@ -0,0 +1,112 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Disconnected, Connected { session_id: u64 }, Closed),
+    slots = (Disconnected)
+)]
+struct Connection {
+    address: String,
+}
+
+#[impl_state]
+impl Connection {
+    #[require(Disconnected)]
+    fn new(address: &str) -> Connection {
+        Connection {
+            address: address.to_string(),
+        }
+    }
+
+    // the data of the state is given along with the rest of the fields
+    #[require(Disconnected)]
+    #[switch_to(Connected)]
+    fn connect(self, session_id: u64) -> Connection {
+        Connection {
+            address: self.address,
+            _state: Connected { session_id },
+        }
+    }
+
+    // the state is kept as it is
+    #[require(Connected)]
+    fn redirect(self, address: &str) -> Connection {
+        Connection {
+            address: address.to_string(),
+        }
+    }
+
+    #[require(Connected)]
+    fn session(&self) -> u64 {
+        self.state_data().session_id
+    }
+
+    #[require(Connected)]
+    #[switch_to(Closed)]
+    fn close(self) -> Connection {
+        Connection {
+            address: self.address,
+        }
+    }
+}
+
+#[type_state(
+    states = (Guest, Member { name: String }, Idle, Busy),
+    slots = (auth = Guest, work = Idle),
+    slot_states = ((Guest, Member), (Idle, Busy))
+)]
+struct Worker {
+    jobs: u32,
+}
+
+#[impl_state]
+impl Worker {
+    #[switch_to(Guest, Idle)]
+    fn new() -> Self {
+        Self { jobs: 0 }
+    }
+
+    #[require(Guest, A)]
+    #[switch_to(Member, _)]
+    fn log_in(self, name: &str) -> Self {
+        Self {
+            jobs: self.jobs,
+            _state: (
+                Member {
+                    name: name.to_string(),
+                },
+                self._state.1,
+            ),
+        }
+    }
+
+    #[require(A, Idle)]
+    #[switch_to(_, Busy)]
+    fn start(self) -> Self {
+        Self {
+            jobs: self.jobs + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_state_data_is_accessible_in_its_state() {
+        let connection = Connection::new("10.0.0.1").connect(7).redirect("10.0.0.2");
+        assert_eq!(connection.session(), 7);
+        assert_eq!(connection.state_data().session_id, 7);
+        assert_eq!(connection.address, "10.0.0.2");
+
+        let connection = connection.close();
+        assert_eq!(connection.address, "10.0.0.2");
+    }
+
+    #[test]
+    fn the_state_data_is_kept_in_the_other_slots_transitions() {
+        let worker = Worker::new().log_in("ada").start();
+        assert_eq!(worker.auth_state_data().name, "ada");
+        assert_eq!(worker.jobs, 1);
+    }
+}
@@ -0,0 +1,69 @@
+use state_shift::{impl_state, type_state};
+
+// each state that the methods transition into has a method that can be called in it,
+// except for the terminal states, which are meant to be the end
+#[type_state(
+    states = (Queued, Running, Failed, Done),
+    slots = (Queued),
+    terminal = (Done),
+    deny(dead_end)
+)]
+struct Build {
+    attempts: u32,
+}
+
+#[impl_state]
+impl Build {
+    #[switch_to(Queued)]
+    fn new() -> Self {
+        Self { attempts: 0 }
+    }
+
+    #[require(Queued)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self {
+            attempts: self.attempts + 1,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Done | Failed)]
+    fn finish(self, success: bool) -> FinishOutcome {
+        if success {
+            FinishOutcome::Done(Build {
+                attempts: self.attempts,
+            })
+        } else {
+            FinishOutcome::Failed(Build {
+                attempts: self.attempts,
+            })
+        }
+    }
+
+    #[require(Failed)]
+    #[switch_to(Queued)]
+    fn retry(self) -> Self {
+        Self {
+            attempts: self.attempts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_state_is_a_dead_end() {
+        let build = match Build::new().start().finish(false) {
+            FinishOutcome::Failed(build) => build,
+            FinishOutcome::Done(_) => panic!("the build should have failed"),
+        };
+        let build = match build.retry().start().finish(true) {
+            FinishOutcome::Done(build) => build,
+            FinishOutcome::Failed(_) => panic!("the build should be done"),
+        };
+        assert_eq!(build.attempts, 2);
+    }
+}
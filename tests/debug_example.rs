@@ -0,0 +1,105 @@
+use state_shift::{impl_state, type_state};
+
+// `#[derive(Debug)]` shows the state next to the name of the struct, instead of the hidden `_state` field
+#[type_state(states = (Idle, Running), slots = (Idle), prefix = "Player")]
+#[derive(Debug)]
+struct Player {
+    level: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new(level: u8) -> Self {
+        Self { level }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { level: self.level }
+    }
+}
+
+// the generics of the struct need to be `Debug`, like the derived impl
+#[type_state(states = (Empty, Full), slots = (Empty, Empty))]
+#[derive(Debug)]
+struct Pair<T> {
+    items: Vec<T>,
+}
+
+#[impl_state]
+impl<T> Pair<T> {
+    #[switch_to(_, _)]
+    fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    #[require(Empty, A)]
+    #[switch_to(Full, A)]
+    fn fill(self, item: T) -> Self {
+        Self { items: vec![item] }
+    }
+}
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+#[derive(Debug)]
+struct Meters(f64);
+
+#[type_state(states = (Handshake, Established), slots = (Handshake))]
+#[derive(Debug)]
+enum Frame {
+    Hello { version: u8 },
+    Data(Vec<u8>),
+}
+
+#[impl_state]
+impl Frame {
+    #[switch_to(Handshake)]
+    fn hello(version: u8) -> Self {
+        Self::Hello { version }
+    }
+
+    #[require(Handshake)]
+    #[switch_to(Established)]
+    fn data(self, bytes: Vec<u8>) -> Self {
+        Self::Data(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_is_shown() {
+        let player = Player::new(3);
+        assert_eq!(format!("{:?}", player), "Player<Idle> { level: 3, .. }");
+        let player = player.start();
+        assert_eq!(format!("{:?}", player), "Player<Running> { level: 3, .. }");
+    }
+
+    #[test]
+    fn states_of_the_slots_are_shown() {
+        let pair: Pair<u8> = Pair::new();
+        let pair = pair.fill(7);
+        assert_eq!(
+            format!("{:?}", pair),
+            "Pair<Full, Empty> { items: [7], .. }"
+        );
+    }
+
+    #[test]
+    fn tuple_structs_and_enums_show_the_state() {
+        let meters: Meters = Meters(1.5, Default::default());
+        assert_eq!(format!("{:?}", meters), "Meters<Closed>(1.5)");
+
+        let frame = Frame::hello(2);
+        assert_eq!(
+            format!("{:?}", frame),
+            "Frame<Handshake>::Hello { version: 2, .. }"
+        );
+        let frame = frame.data(vec![1, 2]);
+        assert_eq!(format!("{:?}", frame), "Frame<Established>::Data([1, 2])");
+    }
+}
@@ -0,0 +1,47 @@
+use state_shift::{impl_state, type_state};
+
+// `#[impl_state(debug)]` shows the code generated for the block as a warning on it,
+// which is allowed here so that the example compiles without warnings
+#[allow(deprecated)]
+mod lamp {
+    use super::*;
+
+    #[type_state(states = (Off, On), slots = (Off))]
+    pub struct Lamp {
+        pub brightness: u8,
+    }
+
+    #[impl_state(debug)]
+    impl Lamp {
+        #[switch_to(Off)]
+        pub fn new() -> Self {
+            Self { brightness: 0 }
+        }
+
+        #[require(Off)]
+        #[switch_to(On)]
+        pub fn turn_on(self) -> Self {
+            Self { brightness: 100 }
+        }
+    }
+}
+
+use lamp::Lamp;
+
+fn main() {
+    let lamp = Lamp::new().turn_on();
+    println!("{}", lamp.brightness);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_keeps_the_expansion() {
+        let lamp = Lamp::new();
+        assert_eq!(lamp.brightness, 0);
+        let lamp = lamp.turn_on();
+        assert_eq!(lamp.brightness, 100);
+    }
+}
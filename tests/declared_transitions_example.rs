@@ -0,0 +1,70 @@
+use state_shift::{impl_state, transitions, type_state};
+
+#[type_state(
+    states = (Idle, Running, Paused),
+    slots = (Idle),
+    declared_transitions = true
+)]
+struct Player {
+    level: u8,
+}
+
+// each transition of the methods has to be declared, or it fails to compile
+transitions!(
+    Player:
+    Idle | Paused -> Running via start,
+    Running -> Paused via pause,
+    _ -> Idle via reset,
+);
+
+#[impl_state]
+impl Player {
+    // the constructors are not transitions
+    #[require(Idle)]
+    fn new(level: u8) -> Player {
+        Player { level }
+    }
+
+    #[require(Idle | Paused)]
+    #[switch_to(Running)]
+    fn start(self) -> Player {
+        Player {
+            level: self.level + 1,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Paused)]
+    fn pause(self) -> Player {
+        Player { level: self.level }
+    }
+
+    #[require(A)]
+    #[switch_to(Idle)]
+    fn reset(self) -> Player {
+        Player { level: 0 }
+    }
+
+    // the methods that keep the states are not transitions either
+    #[require(A)]
+    fn level(&self) -> u8 {
+        self.level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared_transitions_are_taken() {
+        let player: Player<Running> = Player::new(1).start();
+        assert_eq!(player.level(), 2);
+
+        let player: Player<Running> = player.pause().start();
+        assert_eq!(player.level(), 3);
+
+        let player: Player<Idle> = player.reset();
+        assert_eq!(player.level(), 0);
+    }
+}
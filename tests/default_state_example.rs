@@ -0,0 +1,89 @@
+use state_shift::{impl_state, type_state};
+
+// `#[derive(Default)]` creates the struct in its default state only,
+// the other states are reached by the transitions: `Player<Running>` is not `Default`
+#[type_state(states = (Idle, Running), slots = (Idle))]
+#[derive(Default)]
+struct Player {
+    name: String,
+    level: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self {
+            name: self.name,
+            level: self.level + 1,
+        }
+    }
+
+    #[require(A)]
+    fn level(&self) -> u8 {
+        self.level
+    }
+}
+
+// the generics of the struct need to be `Default`, like the derived impl,
+// and the states that carry data are fine, as long as the default state doesn't
+#[type_state(
+    states = (Empty, Sealed { label: String }),
+    slots = (Empty)
+)]
+#[derive(Default)]
+struct Crate<T> {
+    items: Vec<T>,
+}
+
+#[impl_state]
+impl<T> Crate<T> {
+    #[require(A)]
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[require(Empty)]
+    #[switch_to(Sealed)]
+    fn seal(self, label: &str) -> Self {
+        Self {
+            items: self.items,
+            _state: Sealed {
+                label: label.to_string(),
+            },
+        }
+    }
+}
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+#[derive(Default)]
+struct Meters(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn struct_starts_in_the_default_state() {
+        let player = Player::default();
+        assert_eq!(player.name, "");
+        assert_eq!(player.state_name(), "Idle");
+
+        let player = player.start();
+        assert_eq!(player.level(), 1);
+    }
+
+    #[test]
+    fn generics_are_defaulted() {
+        let items: Crate<u8> = Crate::default();
+        assert_eq!(items.len(), 0);
+        assert_eq!(items.state_name(), "Empty");
+
+        let items = items.seal("fruit");
+        assert_eq!(items.state_data().label, "fruit");
+
+        let meters: Meters = Default::default();
+        assert_eq!(meters.0, 0.0);
+    }
+}
@@ -0,0 +1,74 @@
+use state_shift::{impl_state, type_state};
+
+pub trait Transport {
+    fn name(&self) -> &'static str;
+}
+
+#[derive(Default)]
+pub struct Tcp;
+
+impl Transport for Tcp {
+    fn name(&self) -> &'static str {
+        "tcp"
+    }
+}
+
+#[derive(Default)]
+pub struct Udp;
+
+impl Transport for Udp {
+    fn name(&self) -> &'static str {
+        "udp"
+    }
+}
+
+#[type_state(states = (Idle, Connected), slots = (Idle), in_place = true)]
+struct Client<T: Transport = Tcp, const RETRIES: u8 = 3> {
+    transport: T,
+}
+
+// `Client` is `Client<Tcp, 3>` here
+#[impl_state]
+impl Client {
+    #[switch_to(Idle)]
+    fn new() -> Self {
+        Self { transport: Tcp }
+    }
+}
+
+#[impl_state]
+impl<T: Transport + Default, const RETRIES: u8> Client<T, RETRIES> {
+    #[switch_to(Idle)]
+    fn with_transport() -> Self {
+        Self {
+            transport: T::default(),
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Connected)]
+    fn connect(self) -> Self {
+        Self {
+            transport: self.transport,
+        }
+    }
+
+    #[require(Connected)]
+    fn describe(&self) -> String {
+        format!("{} (retries: {})", self.transport.name(), RETRIES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_type_params_work() {
+        let client = Client::new().connect();
+        assert_eq!(client.describe(), "tcp (retries: 3)");
+
+        let client = Client::<Udp, 5>::with_transport().connect();
+        assert_eq!(client.describe(), "udp (retries: 5)");
+    }
+}
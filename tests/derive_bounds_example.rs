@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use state_shift::{impl_state, type_state};
+
+// the derives don't bound the states, so the struct is cloned and compared in any of its states,
+// without the markers implementing the traits
+#[type_state(states = (Idle, Running), slots = (Idle), prefix = "Player")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Player {
+    level: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new(level: u8) -> Self {
+        Self { level }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { level: self.level }
+    }
+
+    // the methods that accept any state can clone the struct too
+    #[require(A)]
+    fn twin(&self) -> Self {
+        self.clone()
+    }
+}
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+#[derive(Clone, Copy)]
+struct Meters(f64);
+
+// the generics of the struct are bounded like the derived impls
+#[type_state(states = (Empty, Full), slots = (Empty))]
+#[derive(Clone, PartialEq)]
+struct Crate<T> {
+    items: Vec<T>,
+}
+
+#[impl_state]
+impl<T> Crate<T> {
+    #[switch_to(Empty)]
+    fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    #[require(Empty)]
+    #[switch_to(Full)]
+    fn fill(self, item: T) -> Self {
+        Self { items: vec![item] }
+    }
+}
+
+// the enums are compared by their variants first, like the derived impls
+#[type_state(states = (Handshake, Established), slots = (Handshake))]
+#[derive(Clone, PartialEq, PartialOrd)]
+enum Frame {
+    Hello { version: u8 },
+    Data(Vec<u8>),
+}
+
+#[impl_state]
+impl Frame {
+    #[switch_to(Handshake)]
+    fn hello(version: u8) -> Self {
+        Self::Hello { version }
+    }
+
+    #[switch_to(Handshake)]
+    fn data(bytes: Vec<u8>) -> Self {
+        Self::Data(bytes)
+    }
+}
+
+// the states that carry data are stored in the struct, so their markers get the derives as well
+#[type_state(
+    states = (Disconnected, Connected { session_id: u64 }),
+    slots = (Disconnected)
+)]
+#[derive(Clone, PartialEq)]
+struct Connection {
+    address: String,
+}
+
+#[impl_state]
+impl Connection {
+    #[switch_to(Disconnected)]
+    fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+        }
+    }
+
+    #[require(Disconnected)]
+    #[switch_to(Connected)]
+    fn connect(self, session_id: u64) -> Self {
+        Self {
+            address: self.address,
+            _state: Connected { session_id },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn struct_is_cloned_in_any_state() {
+        let player = Player::new(3).start();
+        assert_eq!(player.twin(), player);
+        assert!(Player::new(2) < Player::new(3));
+    }
+
+    #[test]
+    fn struct_is_copied() {
+        let meters: Meters = Meters(1.5, Default::default());
+        let copied = meters;
+        assert_eq!(meters.0 + copied.0, 3.0);
+    }
+
+    #[test]
+    fn struct_is_hashed() {
+        let players: HashSet<Player> = [Player::new(1), Player::new(1), Player::new(2)].into();
+        assert_eq!(players.len(), 2);
+    }
+
+    #[test]
+    fn generics_are_bounded() {
+        let items: Crate<String> = Crate::new();
+        let items = items.fill("apple".to_string());
+        assert!(items.clone() == items);
+    }
+
+    #[test]
+    fn enums_are_compared_by_their_variants() {
+        assert!(Frame::hello(1) == Frame::hello(1).clone());
+        assert!(Frame::hello(1) != Frame::hello(2));
+        assert!(Frame::hello(9) < Frame::data(vec![0]));
+        assert!(Frame::data(vec![1]) > Frame::data(vec![0, 5]));
+    }
+
+    #[test]
+    fn stored_states_are_compared() {
+        let connection = Connection::new("localhost").connect(7);
+        assert!(connection.clone() == connection);
+        assert!(Connection::new("localhost").connect(8) != connection);
+    }
+}
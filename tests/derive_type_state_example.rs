@@ -0,0 +1,64 @@
+use std::marker::PhantomData;
+
+use state_shift::{impl_state, TypeState};
+
+// the struct declares the generic of the slot and the field of the states itself
+#[derive(TypeState)]
+#[type_state(states = (Idle, Running), slots = (Idle))]
+struct Player<S = Idle> {
+    name: String,
+    level: u8,
+    _state: PhantomData<fn() -> S>,
+}
+
+#[impl_state]
+impl Player {
+    #[require(Idle)]
+    fn new(name: String) -> Player {
+        Player {
+            name,
+            level: 0,
+            _state: PhantomData,
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Player {
+        Player {
+            name: self.name,
+            level: self.level + 1,
+            _state: PhantomData,
+        }
+    }
+}
+
+// the methods can be written by hand with the markers too, without any attribute
+impl<T: Clone> Meters<T, Closed> {
+    fn open(self) -> Meters<T, Open> {
+        Meters(self.0, PhantomData)
+    }
+}
+
+#[derive(TypeState)]
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Meters<T: Clone, S = Closed>(T, PhantomData<fn() -> S>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derived_struct_switches_states() {
+        let player = Player::new("Alice".to_string());
+        assert_eq!(player.state_name(), "Idle");
+
+        let player: Player<Running> = player.start();
+        assert_eq!(player.level, 1);
+        assert_eq!(player.name, "Alice");
+
+        let meters: Meters<f64, Open> = Meters(2.5, PhantomData).open();
+        assert_eq!(meters.0, 2.5);
+        assert_eq!(meters.state_name(), "Open");
+    }
+}
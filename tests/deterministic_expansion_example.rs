@@ -0,0 +1,122 @@
+use state_shift::{impl_state, type_state};
+
+// the names of the generated items are derived from the declared names only, so the expansions of these items
+// are the same in every build, whatever is expanded before them
+#[type_state(
+    states = (Idle, Running, Paused, Failed, Green, Red),
+    slots = (Idle, Green),
+    groups = (Active = (Running, Paused))
+)]
+struct Player {
+    speed: u8,
+}
+
+#[impl_state(transitions)]
+impl Player {
+    #[require(Idle, Green)]
+    fn new() -> Player {
+        Player { speed: 0 }
+    }
+
+    #[require(Idle | Paused, C)]
+    #[switch_to(Running | Failed, C)]
+    fn start(self, speed: u8) -> Result<Player, ()> {
+        match speed {
+            0 => Ok(StartOutcome::Failed(Player { speed })),
+            _ => Ok(StartOutcome::Running(Player { speed })),
+        }
+    }
+
+    #[require(Running, Green | Red)]
+    #[switch_to(Paused, Red)]
+    fn pause(self) -> Player {
+        Player { speed: self.speed }
+    }
+
+    #[require(Active, C)]
+    fn speed(&self) -> u8 {
+        self.speed
+    }
+}
+
+fn main() {
+    let player = Player::new().start(3).unwrap();
+    if let StartOutcome::Running(player) = player {
+        println!("{}", player.pause().speed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_expansions_are_the_same_in_every_build() {
+        let player = Player::new().start(3).unwrap();
+        let StartOutcome::Running(player) = player else {
+            panic!("the player is expected to be running");
+        };
+        assert_eq!(player.pause().speed(), 3);
+
+        // this file is compiled twice by `trybuild` with `STATE_SHIFT_DEBUG`, which dumps the expansions
+        // (see `expansion_dump_example`), the second time after another struct and its `impl` block
+        let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("deterministic_expansion");
+        std::fs::create_dir_all(&dir).unwrap();
+        let example = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/deterministic_expansion_example.rs"
+        );
+        let unrelated = "#[state_shift::type_state(states = (Off, On), slots = (Off))]\n\
+                         struct Lamp {}\n\n\
+                         #[state_shift::impl_state]\n\
+                         impl Lamp {\n    \
+                             #[require(Off)]\n    \
+                             #[switch_to(On)]\n    \
+                             fn turn_on(self) -> Lamp {\n        \
+                                 Lamp {}\n    \
+                             }\n\
+                         }\n\n";
+        let cases = [("first.rs", ""), ("second.rs", unrelated)];
+        let trybuild_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .parent()
+            .unwrap()
+            .join("tests/trybuild/state-shift");
+        let dump_dirs: Vec<_> = (0..cases.len())
+            .map(|index| trybuild_dir.join(format!("trybuild{:03}", index)))
+            .collect();
+
+        std::env::set_var("STATE_SHIFT_DEBUG", "1");
+        let tests = trybuild::TestCases::new();
+        for ((name, before), dump_dir) in cases.iter().zip(&dump_dirs) {
+            let _ = std::fs::remove_dir_all(dump_dir);
+            let case = dir.join(name);
+            std::fs::write(
+                &case,
+                format!(
+                    "#![allow(dead_code)]\n\n{}#[path = {:?}]\nmod example;\n\nfn main() {{}}\n",
+                    before, example
+                ),
+            )
+            .unwrap();
+            tests.pass(&case);
+        }
+        drop(tests);
+
+        let mut compared = 0;
+        for entry in std::fs::read_dir(&dump_dirs[0]).unwrap() {
+            let path = entry.unwrap().path();
+            let first = std::fs::read_to_string(&path).unwrap();
+            let second = std::fs::read_to_string(dump_dirs[1].join(path.file_name().unwrap()))
+                .unwrap_or_else(|_| panic!("{} is not dumped by the second build", path.display()));
+            assert_eq!(
+                first,
+                second,
+                "{} differs between the builds",
+                path.display()
+            );
+            compared += 1;
+        }
+        assert!(compared >= 2);
+    }
+}
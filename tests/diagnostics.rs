@@ -0,0 +1,7 @@
+// the errors of the macros, each case of `tests/ui` is compiled on its own and checked against its `.stderr` file
+// (`TRYBUILD=overwrite cargo test --test diagnostics` writes the files again after a change of the messages)
+#[test]
+fn diagnostics() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
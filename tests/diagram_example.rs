@@ -0,0 +1,175 @@
+use state_shift::{impl_state, type_state};
+
+// the transitions of an `impl` block are written into a Mermaid diagram while it is expanded,
+// the default state is left out, since the constructor is already the start
+#[type_state(
+    states = (Draft, Review, Published, Archived),
+    slots = (Draft),
+    groups = (Visible = (Review, Published)),
+    terminal = (Archived)
+)]
+struct Document {
+    title: String,
+}
+
+#[impl_state(
+    diagram = "target/diagrams/document.mmd",
+    dot = "target/diagrams/document.dot",
+    plantuml = "target/diagrams/document.puml"
+)]
+impl Document {
+    #[switch_to(Draft)]
+    fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+        }
+    }
+
+    #[require(Draft)]
+    #[switch_to(Review)]
+    fn submit(self) -> Self {
+        Self { title: self.title }
+    }
+
+    #[require(Review)]
+    #[switch_to(Published | Draft)]
+    fn decide(self, approved: bool) -> DecideOutcome {
+        if approved {
+            DecideOutcome::Published(Document { title: self.title })
+        } else {
+            DecideOutcome::Draft(Document { title: self.title })
+        }
+    }
+
+    #[require(Visible)]
+    #[switch_to(Archived)]
+    fn archive(self) -> Self {
+        Self { title: self.title }
+    }
+
+    #[require(A)]
+    fn title(&self) -> &str {
+        &self.title
+    }
+}
+
+// each slot is drawn as a composite state, starting from its default state and the constructors
+#[type_state(states = (LoggedOut, LoggedIn, Closed, Open), slots = (auth = LoggedOut, conn = Closed))]
+struct Session {}
+
+#[impl_state(
+    diagram = "target/diagrams/session.mmd",
+    dot = "target/diagrams/session.dot"
+)]
+impl Session {
+    #[switch_to(LoggedIn, Open)]
+    fn resume() -> Self {
+        Self {}
+    }
+
+    #[require(auth = LoggedOut)]
+    #[switch_to(auth = LoggedIn)]
+    fn log_in(self) -> Self {
+        Self {}
+    }
+
+    #[require(conn = Open)]
+    #[switch_to(conn = Closed)]
+    fn close(self) -> Self {
+        Self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_diagram(name: &str) -> String {
+        let path = format!("{}/target/diagrams/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn diagram_has_the_transitions() {
+        let document: Document<Review> = Document::new("notes").submit();
+        assert_eq!(document.title(), "notes");
+
+        assert_eq!(
+            read_diagram("document.mmd"),
+            "stateDiagram-v2
+    [*] --> Draft: new
+    Draft --> Review: submit
+    Review --> Published: decide
+    Review --> Draft: decide
+    Review --> Archived: archive
+    Published --> Archived: archive
+    Archived --> [*]
+"
+        );
+    }
+
+    #[test]
+    fn dot_has_the_transitions() {
+        assert_eq!(
+            read_diagram("document.dot"),
+            r#"digraph Document {
+    "start" [shape = point];
+    "end" [shape = doublecircle, label = ""];
+    "start" -> "Draft" [label = "new"];
+    "Draft" -> "Review" [label = "submit"];
+    "Review" -> "Published" [label = "decide"];
+    "Review" -> "Draft" [label = "decide"];
+    "Review" -> "Archived" [label = "archive"];
+    "Published" -> "Archived" [label = "archive"];
+    "Archived" -> "end";
+}
+"#
+        );
+    }
+
+    #[test]
+    fn dot_has_a_cluster_for_each_slot() {
+        let dot = read_diagram("session.dot");
+        assert!(dot.contains("subgraph cluster_auth {"));
+        assert!(dot.contains(r#""auth.start" -> "auth.LoggedIn" [label = "resume"];"#));
+        assert!(dot.contains(r#""conn.Open" -> "conn.Closed" [label = "close"];"#));
+    }
+
+    #[test]
+    fn plantuml_has_the_transitions() {
+        assert_eq!(
+            read_diagram("document.puml"),
+            "@startuml
+    [*] --> Draft : new
+    Draft --> Review : submit
+    Review --> Published : decide
+    Review --> Draft : decide
+    Review --> Archived : archive
+    Published --> Archived : archive
+    Archived --> [*]
+@enduml
+"
+        );
+    }
+
+    #[test]
+    fn diagram_has_a_composite_state_for_each_slot() {
+        let _session: Session<LoggedIn, Closed> = Session::resume().close();
+
+        assert_eq!(
+            read_diagram("session.mmd"),
+            "stateDiagram-v2
+    state auth {
+        [*] --> LoggedOut
+        [*] --> LoggedIn: resume
+        LoggedOut --> LoggedIn: log_in
+    }
+    state conn {
+        [*] --> Closed
+        [*] --> Open: resume
+        Open --> Closed: close
+    }
+"
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use state_shift::state_machine_from;
+
+// the machine is generated from an existing diagram, and the methods are the labels of its edges
+state_machine_from!("tests/specs/light.mmd");
+
+// the bodies of the transitions are implemented for the machine, so they cannot diverge from the diagram
+impl<S: LightStateMarker> LightTransitions for Light<S> {
+    fn on_go(&mut self) {}
+
+    fn on_slow(&mut self) {}
+
+    fn on_stop(&mut self) {}
+
+    fn on_fail(&mut self) {}
+}
+
+// the diagrams don't give the fields, so they are given along with the path
+state_machine_from!("tests/specs/vending.dot", fields { credit: u32 });
+
+impl<S: VendingStateMarker> VendingTransitions for Vending<S> {
+    fn on_pay(&mut self) {
+        self.credit += 1;
+    }
+
+    fn on_refund(&mut self) {
+        self.credit = 0;
+    }
+
+    fn on_select(&mut self) {
+        self.credit -= 1;
+    }
+
+    fn on_take(&mut self) {}
+
+    fn on_shut_down(&mut self) {}
+}
+
+fn main() {
+    let light = Light::new().go().slow().stop();
+    println!("{}", light.state_name());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mermaid_diagram_declares_the_machine() {
+        let light: Light<Red> = Light::new();
+        let light: Light<Red> = light.go().stop();
+        let light: Light<Flashing> = light.go().slow().fail();
+        assert_eq!(light.state_name(), "Flashing");
+    }
+
+    #[test]
+    fn dot_diagram_declares_the_machine() {
+        let vending: Vending<Paid> = Vending::new(0).pay();
+        assert_eq!(vending.credit, 1);
+
+        let vending: Vending<Idle> = vending.select().take();
+        assert_eq!(vending.credit, 0);
+        let _vending: Vending<Off> = vending.pay().refund().shut_down();
+    }
+}
@@ -0,0 +1,50 @@
+use state_shift::{impl_state, type_state};
+
+// the markers, the sealer and the groups are left out of the docs,
+// but they can still be named like any other item
+#[type_state(
+    states = (Pending, Approved, Rejected),
+    slots = (Pending),
+    groups = (Decided = (Approved, Rejected)),
+    doc_hidden = true
+)]
+pub struct Request {
+    pub amount: u64,
+}
+
+#[impl_state]
+impl Request {
+    #[switch_to(Pending)]
+    pub fn new(amount: u64) -> Self {
+        Self { amount }
+    }
+
+    #[require(Pending)]
+    #[switch_to(Approved)]
+    pub fn approve(self) -> Self {
+        Self {
+            amount: self.amount,
+        }
+    }
+
+    #[require(Decided)]
+    pub fn is_decided(&self) -> bool {
+        true
+    }
+}
+
+pub fn describe<S: SealerRequest>(request: &Request<S>) -> String {
+    format!("request of {}", request.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_hidden_items_work() {
+        let request: Request<Approved> = Request::new(10).approve();
+        assert!(request.is_decided());
+        assert_eq!(describe(&request), "request of 10");
+    }
+}
@@ -0,0 +1,71 @@
+use state_shift::state_shift;
+
+state_shift! {
+    #[derive(Debug)]
+    pub machine Player {
+        fields { pub name: String, level: u8 }
+        states { Idle, Running, Paused }
+        transitions {
+            /// Starts the game, with a boost of the level
+            start(boost: u8): Idle -> Running,
+            pause: Running -> Paused,
+            resume: Paused -> Running,
+            stop: Running | Paused -> Idle,
+        }
+    }
+
+    // the machines don't need any fields
+    machine Door {
+        states { Closed, Open }
+        transitions {
+            open: Closed -> Open,
+            close: Open -> Closed,
+        }
+    }
+}
+
+// the bodies of the transitions, for the struct in any of its states
+impl<S: PlayerStateMarker> PlayerTransitions for Player<S> {
+    fn on_start(&mut self, boost: u8) {
+        self.level += boost;
+    }
+
+    fn on_pause(&mut self) {}
+
+    fn on_resume(&mut self) {}
+
+    fn on_stop(&mut self) {
+        self.level = 0;
+    }
+}
+
+impl<S: DoorStateMarker> DoorTransitions for Door<S> {
+    fn on_open(&mut self) {}
+
+    fn on_close(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions_switch_the_states() {
+        let player = Player::new("Alice".to_string(), 1);
+        assert_eq!(player.state_name(), "Idle");
+
+        let player: Player<Running> = player.start(2);
+        assert_eq!(player.level, 3);
+
+        let player: Player<Idle> = player.pause().resume().stop();
+        assert_eq!(player.name, "Alice");
+        assert_eq!(player.level, 0);
+
+        // `stop` can be taken in the `Paused` state too
+        let player: Player<Idle> = player.start(1).pause().stop();
+        assert_eq!(player.level, 0);
+
+        let door: Door<Closed> = Door::new().open().close();
+        assert_eq!(door.state_name(), "Closed");
+    }
+}
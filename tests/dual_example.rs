@@ -0,0 +1,132 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use state_shift::{impl_state, type_state};
+
+// the messages in flight between the two sides
+type Wire = Rc<RefCell<VecDeque<String>>>;
+
+#[type_state(states = (Idle, Waiting, Closed), slots = (Idle))]
+struct Client {
+    wire: Wire,
+}
+
+// the sends of the client are the receives of the server, and the other way around
+#[impl_state(dual = ServerProtocol)]
+impl Client {
+    #[switch_to(Idle)]
+    fn connect(wire: Wire) -> Self {
+        Self { wire }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Waiting)]
+    fn request(self, path: String) -> Self {
+        self.wire.borrow_mut().push_back(path);
+        Self { wire: self.wire }
+    }
+
+    #[require(Waiting)]
+    #[switch_to(Idle)]
+    fn reply(self) -> (Self, u16) {
+        let status = self.wire.borrow_mut().pop_front().unwrap();
+        (Self { wire: self.wire }, status.parse().unwrap())
+    }
+
+    #[require(Idle)]
+    #[switch_to(Closed)]
+    fn close(self) -> Self {
+        Self { wire: self.wire }
+    }
+
+    // the methods that keep the state are local to each side
+    #[require(A)]
+    fn in_flight(&self) -> usize {
+        self.wire.borrow().len()
+    }
+}
+
+// the server reuses the states of the client, and implements the dual of its transitions
+#[type_state(reuse = Client, slots = (Idle))]
+struct Server {
+    wire: Wire,
+    served: u32,
+}
+
+#[impl_state]
+impl Server {
+    #[switch_to(Idle)]
+    fn listen(wire: Wire) -> Self {
+        Self { wire, served: 0 }
+    }
+
+    #[require(A)]
+    fn served(&self) -> u32 {
+        self.served
+    }
+}
+
+#[impl_state]
+impl ServerProtocol<Idle, Waiting, Closed> for Server {
+    #[require(Idle)]
+    #[switch_to(Waiting)]
+    fn request(self) -> (Self, String) {
+        let path = self.wire.borrow_mut().pop_front().unwrap();
+        let server = Server {
+            wire: self.wire,
+            served: self.served,
+        };
+        (server, path)
+    }
+
+    #[require(Waiting)]
+    #[switch_to(Idle)]
+    fn reply(self, payload: u16) -> Self {
+        self.wire.borrow_mut().push_back(payload.to_string());
+        Server {
+            wire: self.wire,
+            served: self.served + 1,
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Closed)]
+    fn close(self) -> Self {
+        Server {
+            wire: self.wire,
+            served: self.served,
+        }
+    }
+}
+
+fn main() {
+    let wire = Wire::default();
+    let client = Client::connect(wire.clone()).request("/".to_string());
+    let (server, path) = Server::listen(wire).request();
+    let _server = server.reply(if path == "/" { 200 } else { 404 });
+    let (client, status) = client.reply();
+    println!("{} {}", status, client.in_flight());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_two_sides_take_turns() {
+        let wire = Wire::default();
+        let client = Client::connect(wire.clone()).request("/".to_string());
+        assert_eq!(client.in_flight(), 1);
+
+        let (server, path): (Server<Waiting>, _) = Server::listen(wire).request();
+        assert_eq!(path, "/");
+        let server: Server<Idle> = server.reply(200);
+        assert_eq!(server.served(), 1);
+
+        let (client, status): (Client<Idle>, _) = client.reply();
+        assert_eq!(status, 200);
+        assert_eq!(client.in_flight(), 0);
+
+        let _client: Client<Closed> = client.close();
+        let _server: Server<Closed> = server.close();
+    }
+}
@@ -0,0 +1,63 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Handshake, Established, Closed), slots = (Handshake))]
+#[derive(Debug)]
+enum Frame {
+    Hello { version: u8 },
+    Data(Vec<u8>),
+    Close {},
+}
+
+#[impl_state]
+impl Frame {
+    #[switch_to(Handshake)]
+    fn hello(version: u8) -> Self {
+        Self::Hello { version }
+    }
+
+    #[require(Handshake)]
+    #[switch_to(Established)]
+    fn accept(self, payload: &[u8]) -> Self {
+        let version = match self {
+            Frame::Hello { version, .. } => version,
+            _ => 0,
+        };
+        let mut bytes = vec![version];
+        bytes.extend_from_slice(payload);
+        Self::Data(bytes)
+    }
+
+    #[require(Established)]
+    #[switch_to(Closed)]
+    fn close(self) -> Self {
+        Frame::Close {}
+    }
+
+    // the variants are matched with `..`, since they have the `_state` field too
+    #[require(A)]
+    fn len(&self) -> usize {
+        match self {
+            Self::Hello { .. } => 1,
+            Self::Data(bytes, ..) => bytes.len(),
+            Self::Close { .. } => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enums_work() {
+        let frame: Frame<Handshake> = Frame::hello(2);
+        assert_eq!(frame.len(), 1);
+
+        let frame: Frame<Established> = frame.accept(&[7, 8]);
+        assert_eq!(frame.len(), 3);
+        assert!(matches!(frame, Frame::Data(ref bytes, ..) if bytes == &[2, 7, 8]));
+
+        let frame: Frame<Closed> = frame.close();
+        assert_eq!(frame.len(), 0);
+    }
+}
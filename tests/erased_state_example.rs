@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use state_shift::{impl_state, type_state};
+
+// the values in different states are stored together as `AnyJob`, and their states are recovered by matching
+#[type_state(
+    states = (
+        Queued,
+        Running,
+        #[cfg(target_os = "none")] Suspended,
+        Done,
+    ),
+    slots = (Queued),
+    prefix = "Job",
+    any_state = true
+)]
+struct Job<T> {
+    payload: T,
+}
+
+#[impl_state]
+impl<T> Job<T> {
+    #[switch_to(Queued)]
+    fn new(payload: T) -> Self {
+        Self { payload }
+    }
+
+    #[require(Queued)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self {
+            payload: self.payload,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Done)]
+    fn finish(self) -> Self {
+        Self {
+            payload: self.payload,
+        }
+    }
+
+    #[require(A)]
+    fn payload(&self) -> &T {
+        &self.payload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn erased_values_are_stored_together() {
+        let jobs: Vec<AnyJob<u8>> = vec![
+            Job::new(1).into(),
+            Job::new(2).start().into(),
+            Job::new(3).start().finish().into(),
+        ];
+
+        // the variants are named after the unprefixed states
+        let states: Vec<_> = jobs
+            .iter()
+            .map(|job| match job {
+                AnyJob::Queued(job) => ("queued", *job.payload()),
+                AnyJob::Running(job) => ("running", *job.payload()),
+                AnyJob::Done(job) => ("done", *job.payload()),
+            })
+            .collect();
+        assert_eq!(states, [("queued", 1), ("running", 2), ("done", 3)]);
+    }
+
+    #[test]
+    fn variants_are_listed() {
+        // the gated states are listed along with their variants
+        assert_eq!(AnyJob::<u8>::VARIANTS, ["Queued", "Running", "Done"]);
+    }
+
+    #[test]
+    fn erased_values_are_recovered_by_matching() {
+        let mut jobs: HashMap<&str, AnyJob<String>> = HashMap::new();
+        jobs.insert("build", Job::new("cargo build".to_string()).into());
+
+        // the typed value transitions, then it is erased again
+        let job = match jobs.remove("build").unwrap() {
+            AnyJob::Queued(job) => AnyJob::from(job.start()),
+            job => job,
+        };
+        jobs.insert("build", job);
+
+        assert!(matches!(&jobs["build"], AnyJob::Running(job) if job.payload() == "cargo build"));
+    }
+}
@@ -0,0 +1,72 @@
+use state_shift::{impl_state, type_state};
+
+// compiled with `STATE_SHIFT_DEBUG=1`, the expansions of the struct and of its `impl` block are written to
+// `target/state-shift/expansion_dump_example/type_state-Lamp.rs` and `impl_state-Lamp-new.rs`
+#[type_state(states = (Off, On), slots = (Off))]
+struct Lamp {
+    brightness: u8,
+}
+
+#[impl_state]
+impl Lamp {
+    #[switch_to(Off)]
+    fn new() -> Self {
+        Self { brightness: 0 }
+    }
+
+    #[require(Off)]
+    #[switch_to(On)]
+    fn turn_on(self) -> Self {
+        Self { brightness: 100 }
+    }
+}
+
+fn main() {
+    let lamp = Lamp::new().turn_on();
+    println!("{}", lamp.brightness);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_expansions_are_dumped() {
+        let lamp = Lamp::new().turn_on();
+        assert_eq!(lamp.brightness, 100);
+
+        // the variable is read while the macros are expanded, so this file is compiled again by `trybuild` with it,
+        // from a case that is written again for each run (cargo doesn't track the variable, but it tracks the file)
+        let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("expansion_dump");
+        std::fs::create_dir_all(&dir).unwrap();
+        let case = dir.join("lamp.rs");
+        let example = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/expansion_dump_example.rs"
+        );
+        std::fs::write(
+            &case,
+            format!(
+                "#![allow(dead_code)]\n\n#[path = {:?}]\nmod example;\n\nfn main() {{}}\n",
+                example
+            ),
+        )
+        .unwrap();
+        // `trybuild` builds the cases in `target/tests/trybuild`, which is given to cargo as `CARGO_TARGET_DIR`,
+        // and names the crate of the first case `trybuild000`
+        let dump_dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .parent()
+            .unwrap()
+            .join("tests/trybuild/state-shift/trybuild000");
+        let _ = std::fs::remove_dir_all(&dump_dir);
+
+        std::env::set_var("STATE_SHIFT_DEBUG", "1");
+        trybuild::TestCases::new().pass(&case);
+
+        let type_state = std::fs::read_to_string(dump_dir.join("type_state-Lamp.rs")).unwrap();
+        assert!(type_state.contains("struct Lamp"));
+        let impl_state = std::fs::read_to_string(dump_dir.join("impl_state-Lamp-new.rs")).unwrap();
+        assert!(impl_state.contains("turn_on"));
+    }
+}
@@ -0,0 +1,64 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running), slots = (Idle))]
+struct Player {
+    track: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new() -> Self {
+        Self { track: 0 }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { track: 1 }
+    }
+
+    #[require(A)]
+    fn track(&self) -> u8 {
+        self.track
+    }
+}
+
+// a feature that appends its state to the ones of `Player`, along with its transitions
+#[impl_state(extend = (Maintenance))]
+impl Player {
+    #[require(Running)]
+    #[switch_to(Maintenance)]
+    fn service(self) -> Self {
+        Self { track: self.track }
+    }
+
+    #[require(Maintenance)]
+    #[switch_to(Idle)]
+    fn finish(self) -> Self {
+        Self { track: 0 }
+    }
+}
+
+fn main() {
+    let player = Player::new().start().service();
+    println!("{}", player.state_name());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extended_states_are_states_of_the_struct() {
+        let player: Player<Maintenance> = Player::new().start().service();
+        assert_eq!(player.track(), 1);
+        assert_eq!(player.state_name(), "Maintenance");
+        assert_eq!(Maintenance::STATE_NAME, "Maintenance");
+        assert_eq!(Maintenance.to_string(), "Maintenance");
+
+        let player: Player<Idle> = player.finish();
+        assert_eq!(player.track(), 0);
+        assert_eq!(player.start().state_name(), "Running");
+    }
+}
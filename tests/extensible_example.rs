@@ -0,0 +1,76 @@
+mod library {
+    use state_shift::{impl_state, type_state};
+
+    #[type_state(states = (Draft, Published), slots = (Draft), extensible = true)]
+    pub struct Article {
+        pub title: String,
+    }
+
+    #[impl_state]
+    impl Article {
+        #[switch_to(Draft)]
+        pub fn new(title: &str) -> Self {
+            Self {
+                title: title.to_string(),
+            }
+        }
+
+        #[require(Draft)]
+        #[switch_to(Published)]
+        pub fn publish(self) -> Self {
+            Self { title: self.title }
+        }
+    }
+}
+
+// the methods are added by another crate through extension traits,
+// since the inherent methods can only be defined in the crate of the struct
+mod downstream {
+    use crate::library::{self, Draft, Published, SealerArticle};
+    use state_shift::impl_state;
+
+    pub trait Preview {
+        fn preview(&self) -> String;
+    }
+
+    #[impl_state]
+    #[require(Draft)]
+    impl Preview for library::Article {
+        fn preview(&self) -> String {
+            format!("[draft] {}", self.title)
+        }
+    }
+
+    pub trait Headline {
+        fn headline(&self) -> String;
+    }
+
+    #[impl_state]
+    #[require(_)]
+    impl Headline for library::Article {
+        fn headline(&self) -> String {
+            self.title.to_uppercase()
+        }
+    }
+
+    pub fn is_published<S: SealerArticle>(_: &library::Article<S>) -> bool {
+        std::any::type_name::<S>() == std::any::type_name::<Published>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::downstream::{is_published, Headline, Preview};
+    use super::library::Article;
+
+    #[test]
+    fn extensions_work() {
+        let article = Article::new("news");
+        assert_eq!(article.preview(), "[draft] news");
+        assert!(!is_published(&article));
+
+        let article = article.publish();
+        assert_eq!(article.headline(), "NEWS");
+        assert!(is_published(&article));
+    }
+}
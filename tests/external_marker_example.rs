@@ -0,0 +1,44 @@
+use state_shift::{impl_state, type_state};
+
+/// The marker of the idle state, with its own derives and docs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Idle;
+
+#[type_state(states = (external(Idle), Running), slots = (Idle))]
+struct Worker {
+    jobs: u32,
+}
+
+#[impl_state]
+impl Worker {
+    #[switch_to(Idle)]
+    fn new() -> Self {
+        Self { jobs: 0 }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self {
+            jobs: self.jobs + 1,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Idle)]
+    fn stop(self) -> Self {
+        Self { jobs: self.jobs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn external_markers_work() {
+        let worker: Worker<Idle> = Worker::new().start().stop();
+        assert_eq!(worker.jobs, 1);
+        assert_eq!(format!("{:?}", Idle), "Idle");
+    }
+}
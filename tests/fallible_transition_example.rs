@@ -0,0 +1,66 @@
+use state_shift::{impl_state, type_state};
+
+#[derive(Debug, PartialEq)]
+enum ConnectError {
+    NoAddress,
+}
+
+#[type_state(states = (Disconnected, Connected), slots = (Disconnected))]
+struct Client {
+    address: Option<String>,
+}
+
+#[impl_state]
+impl Client {
+    #[require(Disconnected)]
+    fn new(address: Option<String>) -> Client {
+        Client { address }
+    }
+
+    #[require(Disconnected)]
+    #[switch_to(Connected)]
+    fn connect(self) -> Result<Self, ConnectError> {
+        if self.address.is_none() {
+            return Err(ConnectError::NoAddress);
+        }
+
+        Ok(Self {
+            address: self.address,
+        })
+    }
+
+    #[require(Connected)]
+    #[switch_to(Disconnected)]
+    fn disconnect(self) -> Result<Client, ConnectError> {
+        Ok(Client {
+            address: self.address,
+        })
+    }
+
+    #[require(Connected)]
+    fn address(&self) -> &str {
+        self.address.as_deref().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallible_transitions_work() {
+        let client = Client::new(Some("localhost".to_string()))
+            .connect()
+            .unwrap();
+        assert_eq!(client.address(), "localhost");
+
+        let client = client.disconnect().unwrap().connect().unwrap();
+        assert_eq!(client.address(), "localhost");
+    }
+
+    #[test]
+    fn failed_transition_returns_the_error() {
+        let result = Client::new(None).connect();
+        assert!(matches!(result, Err(ConnectError::NoAddress)));
+    }
+}
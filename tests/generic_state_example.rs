@@ -0,0 +1,78 @@
+use state_shift::{impl_state, type_state};
+
+struct Config;
+struct Catalog;
+
+#[type_state(
+    states = (Empty, Loaded<T>, Closed),
+    slots = (Empty),
+    groups = (Open = (Empty, Loaded))
+)]
+struct Store {
+    name: String,
+}
+
+#[impl_state]
+impl Store {
+    #[switch_to(Empty)]
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+
+    #[require(Empty)]
+    #[switch_to(Loaded<Config>)]
+    fn load_config(self) -> Self {
+        Self { name: self.name }
+    }
+
+    // the payload type can come from the method generics too
+    #[require(Empty)]
+    #[switch_to(Loaded<T>)]
+    fn load<T>(self) -> Self {
+        Self { name: self.name }
+    }
+
+    // `C` is declared by the generated `impl` block, like the single letter generic states
+    #[require(Loaded<C>)]
+    fn payload(&self) -> &'static str {
+        std::any::type_name::<C>()
+    }
+
+    #[require(Loaded<Config>)]
+    fn config_path(&self) -> String {
+        format!("/etc/{}.toml", self.name)
+    }
+
+    #[require(not(Empty))]
+    fn is_loaded(&self) -> bool {
+        true
+    }
+
+    #[require(Open)]
+    #[switch_to(Closed)]
+    fn close(self) -> Self {
+        Self { name: self.name }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic_states_work() {
+        let store = Store::new("app").load_config();
+        assert_eq!(store.config_path(), "/etc/app.toml");
+        assert!(store.payload().ends_with("Config"));
+        assert!(store.is_loaded());
+
+        let store: Store<Loaded<Catalog>> = Store::new("shop").load();
+        assert!(store.payload().ends_with("Catalog"));
+
+        let store = store.close();
+        assert!(store.is_loaded());
+        assert_eq!(store.name, "shop");
+    }
+}
@@ -0,0 +1,94 @@
+use state_shift::{impl_state, type_state};
+
+#[derive(Debug, PartialEq)]
+struct ConnectError;
+
+#[type_state(
+    states = (Disconnected, Connecting, Connected),
+    slots = (Disconnected)
+)]
+#[derive(Debug)]
+struct Client {
+    attempts: u8,
+}
+
+#[impl_state]
+impl Client {
+    #[switch_to(Disconnected)]
+    fn new() -> Self {
+        Self { attempts: 0 }
+    }
+
+    #[require(Disconnected)]
+    #[switch_to(Connecting)]
+    fn dial(self) -> Self {
+        Self {
+            attempts: self.attempts + 1,
+        }
+    }
+
+    // the `Err` arm carries the struct in the `on_err` state, and the `Ok` arm in the new state
+    #[require(Connecting)]
+    #[switch_to(Connected, on_err = Disconnected)]
+    fn handshake(self, accepted: bool) -> Result<Self, (Self, ConnectError)> {
+        if accepted {
+            Ok(Self {
+                attempts: self.attempts,
+            })
+        } else {
+            Err((
+                Self {
+                    attempts: self.attempts,
+                },
+                ConnectError,
+            ))
+        }
+    }
+
+    // without `on_err`, the `Err` arm keeps the original state
+    #[require(Connected)]
+    #[switch_to(Disconnected)]
+    fn close(self, forced: bool) -> Result<Self, Self> {
+        if forced {
+            Ok(Self {
+                attempts: self.attempts,
+            })
+        } else {
+            Err(self)
+        }
+    }
+
+    // the constructors can be guarded too
+    #[switch_to(Connected, on_err = Disconnected)]
+    fn connect(accepted: bool) -> Result<Self, Self> {
+        if accepted {
+            Ok(Self { attempts: 1 })
+        } else {
+            Err(Self { attempts: 1 })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn err_arm_carries_the_on_err_state() {
+        let client = Client::new().dial();
+        let (client, error): (Client<Disconnected>, _) = client.handshake(false).unwrap_err();
+        assert_eq!(error, ConnectError);
+
+        let client: Client<Connected> = client.dial().handshake(true).unwrap();
+        assert_eq!(client.attempts, 2);
+
+        let client: Client<Connected> = client.close(false).unwrap_err();
+        let client: Client<Disconnected> = client.close(true).unwrap();
+        assert_eq!(client.state_name(), "Disconnected");
+
+        let client: Client<Disconnected> = Client::connect(false).unwrap_err();
+        assert_eq!(client.attempts, 1);
+        let client: Client<Connected> = Client::connect(true).unwrap();
+        assert_eq!(client.state_name(), "Connected");
+    }
+}
@@ -0,0 +1,74 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Disconnected, Connected(Active(Sending, Receiving), Idle)),
+    slots = (Disconnected)
+)]
+struct Connection {
+    sent: u32,
+}
+
+#[impl_state]
+impl Connection {
+    #[switch_to(Disconnected)]
+    fn new() -> Self {
+        Self { sent: 0 }
+    }
+
+    #[require(Disconnected)]
+    #[switch_to(Connected::Idle)]
+    fn connect(self) -> Self {
+        Self { sent: self.sent }
+    }
+
+    // any substate of `Connected`
+    #[require(Connected)]
+    #[switch_to(Disconnected)]
+    fn disconnect(self) -> Self {
+        Self { sent: self.sent }
+    }
+
+    #[require(Connected::Idle)]
+    #[switch_to(Connected::Active::Sending)]
+    fn send(self) -> Self {
+        Self {
+            sent: self.sent + 1,
+        }
+    }
+
+    #[require(Connected::Active)]
+    #[switch_to(Connected::Active::Receiving)]
+    fn receive(self) -> Self {
+        Self { sent: self.sent }
+    }
+
+    #[require(Receiving)]
+    #[switch_to(Idle)]
+    fn finish(self) -> Self {
+        Self { sent: self.sent }
+    }
+}
+
+// the marker trait of a substate implies the one of its parent
+fn state_name<S: Active>(_: &Connection<S>) -> &'static str {
+    fn connected_state_name<S: Connected>() -> &'static str {
+        std::any::type_name::<S>()
+    }
+    connected_state_name::<S>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substates_work() {
+        let connection = Connection::new().connect().send();
+        assert!(state_name(&connection).ends_with("Sending"));
+        let connection: Connection<Receiving> = connection.receive();
+        assert_eq!(connection.sent, 1);
+
+        let connection: Connection<Disconnected> = connection.finish().disconnect();
+        assert_eq!(connection.sent, 1);
+    }
+}
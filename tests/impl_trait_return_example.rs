@@ -0,0 +1,70 @@
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use state_shift::{impl_state, type_state};
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[type_state(states = (Filling, Sealed), slots = (Filling))]
+struct Bag {
+    items: Vec<u32>,
+}
+
+#[impl_state]
+impl Bag {
+    #[require(Filling)]
+    fn new(items: Vec<u32>) -> Bag {
+        Bag { items }
+    }
+
+    #[require(Filling | Sealed)]
+    fn items(&self) -> impl Iterator<Item = &u32> + '_ {
+        self.items.iter()
+    }
+
+    #[require(Sealed)]
+    fn into_doubled(self) -> impl Iterator<Item = u32> {
+        self.items.into_iter().map(|item| item * 2)
+    }
+
+    #[require(Filling)]
+    #[switch_to(Sealed)]
+    fn seal(self) -> impl Future<Output = Self> {
+        async move { Bag { items: self.items } }
+    }
+
+    #[require(Filling)]
+    #[switch_to(Sealed)]
+    fn seal_with(self, f: impl Fn(u32) -> u32) -> impl Into<Option<Self>> {
+        Some(Bag {
+            items: self.items.into_iter().map(f).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn impl_trait_returns_work() {
+        let bag = Bag::new(vec![1, 2]);
+        assert_eq!(bag.items().sum::<u32>(), 3);
+
+        let bag: Bag<Sealed> = block_on(bag.seal());
+        assert_eq!(bag.items().count(), 2);
+        assert_eq!(bag.into_doubled().collect::<Vec<_>>(), vec![2, 4]);
+
+        let bag: Option<Bag<Sealed>> = Bag::new(vec![1]).seal_with(|item| item + 1).into();
+        assert_eq!(bag.unwrap().into_doubled().collect::<Vec<_>>(), vec![4]);
+    }
+}
@@ -0,0 +1,80 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Stopped, Playing), slots = (Stopped), in_place = true)]
+struct Track {
+    position: u32,
+}
+
+#[impl_state]
+impl Track {
+    #[require(Stopped)]
+    fn new() -> Track {
+        Track { position: 0 }
+    }
+
+    #[require(Stopped)]
+    #[switch_to(Playing)]
+    fn play(self) -> Track {
+        Track {
+            position: self.position,
+        }
+    }
+
+    #[require(Playing)]
+    #[switch_to(Stopped)]
+    fn stop(self, position: u32) -> Track {
+        Track { position }
+    }
+
+    #[require(Stopped)]
+    fn position(&self) -> u32 {
+        self.position
+    }
+}
+
+// the outer handle that can hold the track in all of its states
+enum TrackHandle {
+    Stopped(Track<Stopped>),
+    Playing(Track<Playing>),
+    Transitioning,
+}
+
+struct Jukebox {
+    track: TrackHandle,
+}
+
+impl Jukebox {
+    // transitions the track through `&mut self`
+    fn toggle(&mut self, position: u32) {
+        Track::transition_in_place(
+            &mut self.track,
+            TrackHandle::Transitioning,
+            |track| match track {
+                TrackHandle::Stopped(track) => TrackHandle::Playing(track.play()),
+                TrackHandle::Playing(track) => TrackHandle::Stopped(track.stop(position)),
+                TrackHandle::Transitioning => TrackHandle::Transitioning,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_place_transitions_work() {
+        let mut jukebox = Jukebox {
+            track: TrackHandle::Stopped(Track::new()),
+        };
+
+        jukebox.toggle(0);
+        assert!(matches!(jukebox.track, TrackHandle::Playing(_)));
+
+        jukebox.toggle(42);
+        match jukebox.track {
+            TrackHandle::Stopped(track) => assert_eq!(track.position(), 42),
+            _ => panic!("should be stopped"),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "into_state")]
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running, Crashed), slots = (Idle), into_state = true)]
+struct Player {
+    name: String,
+    level: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            level: 0,
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self {
+            name: self.name,
+            level: self.level + 1,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Crashed)]
+    fn crash(self) -> Self {
+        Self {
+            name: self.name,
+            level: self.level,
+        }
+    }
+}
+
+// the slots are given the states one by one, and the tuple structs and the enums are moved as well
+#[type_state(states = (Open, Closed), slots = (Closed, Closed), into_state = true)]
+struct Valves<T>(Vec<T>);
+
+#[type_state(states = (Handshake, Established), slots = (Handshake), into_state = true)]
+enum Frame {
+    Hello { version: u8 },
+    Data(Vec<u8>),
+}
+
+#[impl_state]
+impl Frame {
+    #[switch_to(Handshake)]
+    fn hello(version: u8) -> Self {
+        Self::Hello { version }
+    }
+
+    #[require(Established)]
+    fn version(&self) -> Option<u8> {
+        match self {
+            Self::Hello { version, .. } => Some(*version),
+            Self::Data(..) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crashed_player_is_recovered() {
+        let player = Player::new("Alice").start().crash();
+        assert_eq!(player.state_name(), "Crashed");
+
+        // the recovery path skips the transitions
+        let player: Player<Idle> = unsafe { player.into_state() };
+        assert_eq!(player.state_name(), "Idle");
+        let player = player.start();
+        assert_eq!((player.name.as_str(), player.level), ("Alice", 2));
+    }
+
+    #[test]
+    fn tuple_structs_and_enums_are_moved() {
+        let valves: Valves<u8> = Valves(vec![1, 2], Default::default());
+        let valves = unsafe { valves.into_state::<Open, Closed>() };
+        assert_eq!(valves.state_name_1(), "Open");
+        assert_eq!(valves.0, vec![1, 2]);
+
+        let frame = unsafe { Frame::hello(2).into_state::<Established>() };
+        assert_eq!(frame.version(), Some(2));
+    }
+}
@@ -0,0 +1,74 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open, Locked), slots = (Closed))]
+struct Valve {
+    flow: u8,
+}
+
+// the harnesses of `kani` are only compiled by `cargo kani`, which gives `Valve` in any state with `kani::Arbitrary`
+#[cfg(kani)]
+#[impl_state]
+#[require(A)]
+impl kani::Arbitrary for Valve {
+    fn any() -> Self {
+        Self { flow: kani::any() }
+    }
+}
+
+#[impl_state(kani)]
+impl Valve {
+    #[switch_to(Closed)]
+    fn new() -> Self {
+        Self { flow: 0 }
+    }
+
+    #[require(Closed)]
+    #[switch_to(Open)]
+    fn open(self, flow: u8) -> Self {
+        Self { flow: flow.max(1) }
+    }
+
+    #[require(Open)]
+    #[switch_to(Closed)]
+    fn close(self) -> Self {
+        Self { flow: 0 }
+    }
+
+    #[require(Closed)]
+    #[switch_to(Locked)]
+    fn lock(self) -> Self {
+        Self { flow: self.flow }
+    }
+
+    // the invariants are the methods of the states, which are checked by the harnesses
+    #[invariant(Closed, Locked)]
+    fn is_shut(&self) -> bool {
+        self.flow == 0
+    }
+
+    #[invariant(Open)]
+    fn is_flowing(&self) -> bool {
+        self.flow > 0
+    }
+}
+
+fn main() {
+    let valve = Valve::new().open(3);
+    println!("{}", valve.is_flowing());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invariants_are_methods() {
+        let valve = Valve::new();
+        assert!(valve.is_shut());
+        let valve = valve.open(0);
+        assert!(valve.is_flowing());
+        assert!(!valve.is_shut());
+        let valve = valve.close().lock();
+        assert!(valve.is_shut());
+    }
+}
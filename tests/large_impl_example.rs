@@ -0,0 +1,514 @@
+use state_shift::{impl_state, type_state};
+
+// a large `impl` block: 80 methods gated by the states of two slots, with the alternatives of `#[require]`,
+// which are expanded for each of their states (see the compile-time notes of `#[impl_state]`)
+#[type_state(
+    states = (Queued, Fetching, Parsing, Validating, Storing, Done, Failed, Paused, Low, High),
+    slots = (Queued, Low),
+    slot_states = ((Queued, Fetching, Parsing, Validating, Storing, Done, Failed, Paused), (Low, High))
+)]
+struct Pipeline {
+    count: u32,
+}
+
+#[impl_state]
+impl Pipeline {
+    #[require(Queued, Low)]
+    fn new() -> Pipeline {
+        Pipeline { count: 0 }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_0(&self) -> u32 {
+        self.count
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_1(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_2(&mut self) {
+        self.count += 2;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_3(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_4(&self) -> u32 {
+        self.count + 4
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_5(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_6(&mut self) {
+        self.count += 6;
+    }
+
+    #[require(Paused | Queued | Parsing, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_7(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_8(&self) -> u32 {
+        self.count + 8
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_9(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_10(&mut self) {
+        self.count += 10;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_11(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_12(&self) -> u32 {
+        self.count + 12
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_13(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_14(&mut self) {
+        self.count += 14;
+    }
+
+    #[require(Paused | Queued | Parsing, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_15(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_16(&self) -> u32 {
+        self.count + 16
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_17(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_18(&mut self) {
+        self.count += 18;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_19(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_20(&self) -> u32 {
+        self.count + 20
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_21(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_22(&mut self) {
+        self.count += 22;
+    }
+
+    #[require(Paused | Queued | Parsing, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_23(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_24(&self) -> u32 {
+        self.count + 24
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_25(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_26(&mut self) {
+        self.count += 26;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_27(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_28(&self) -> u32 {
+        self.count + 28
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_29(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_30(&mut self) {
+        self.count += 30;
+    }
+
+    #[require(Paused | Queued | Parsing, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_31(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_32(&self) -> u32 {
+        self.count + 32
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_33(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_34(&mut self) {
+        self.count += 34;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_35(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_36(&self) -> u32 {
+        self.count + 36
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_37(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_38(&mut self) {
+        self.count += 38;
+    }
+
+    #[require(Paused | Queued | Parsing, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_39(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_40(&self) -> u32 {
+        self.count + 40
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_41(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_42(&mut self) {
+        self.count += 42;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_43(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_44(&self) -> u32 {
+        self.count + 44
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_45(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_46(&mut self) {
+        self.count += 46;
+    }
+
+    #[require(Paused | Queued | Parsing, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_47(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_48(&self) -> u32 {
+        self.count + 48
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_49(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_50(&mut self) {
+        self.count += 50;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_51(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_52(&self) -> u32 {
+        self.count + 52
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_53(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_54(&mut self) {
+        self.count += 54;
+    }
+
+    #[require(Paused | Queued | Parsing, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_55(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_56(&self) -> u32 {
+        self.count + 56
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_57(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_58(&mut self) {
+        self.count += 58;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_59(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_60(&self) -> u32 {
+        self.count + 60
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_61(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_62(&mut self) {
+        self.count += 62;
+    }
+
+    #[require(Paused | Queued | Parsing, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_63(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_64(&self) -> u32 {
+        self.count + 64
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_65(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_66(&mut self) {
+        self.count += 66;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_67(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_68(&self) -> u32 {
+        self.count + 68
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_69(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_70(&mut self) {
+        self.count += 70;
+    }
+
+    #[require(Paused | Queued | Parsing, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_71(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Queued | Validating, P)]
+    fn count_72(&self) -> u32 {
+        self.count + 72
+    }
+
+    #[require(Fetching, P)]
+    #[switch_to(Parsing, P)]
+    fn advance_73(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_74(&mut self) {
+        self.count += 74;
+    }
+
+    #[require(Validating | Storing | Failed, Low)]
+    #[switch_to(Failed, High)]
+    fn fail_75(self) -> Pipeline {
+        Pipeline { count: self.count }
+    }
+
+    #[require(Storing | Paused, P)]
+    fn count_76(&self) -> u32 {
+        self.count + 76
+    }
+
+    #[require(Done, P)]
+    #[switch_to(Failed, P)]
+    fn advance_77(self) -> Pipeline {
+        Pipeline {
+            count: self.count + 1,
+        }
+    }
+
+    #[require(A, High)]
+    fn boost_78(&mut self) {
+        self.count += 78;
+    }
+}
+
+fn main() {
+    let pipeline = Pipeline::new().fail_7();
+    println!("{}", pipeline.count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_large_impl_block() {
+        let pipeline = Pipeline::new();
+        assert_eq!(pipeline.count_0(), 0);
+
+        let mut pipeline = pipeline.fail_7();
+        pipeline.boost_2();
+        assert_eq!(pipeline.count, 2);
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Fresh, Tokenized, Failed), slots = (Fresh))]
+struct Parser<'src> {
+    source: &'src str,
+    tokens: Vec<&'src str>,
+}
+
+#[impl_state]
+impl<'src> Parser<'src> {
+    #[switch_to(Fresh)]
+    fn new(source: &'src str) -> Self {
+        Self {
+            source,
+            tokens: Vec::new(),
+        }
+    }
+
+    #[require(Fresh)]
+    #[switch_to(Tokenized | Failed)]
+    fn tokenize(self) -> Parser<'src> {
+        if self.source.is_empty() {
+            return TokenizeOutcome::Failed(Parser {
+                source: self.source,
+                tokens: Vec::new(),
+            });
+        }
+
+        TokenizeOutcome::Tokenized(Parser {
+            source: self.source,
+            tokens: self.source.split_whitespace().collect(),
+        })
+    }
+
+    #[require(Tokenized)]
+    #[switch_to(Tokenized)]
+    fn next_token(mut self) -> (Self, Option<&'src str>) {
+        let token = if self.tokens.is_empty() {
+            None
+        } else {
+            Some(self.tokens.remove(0))
+        };
+        (self, token)
+    }
+
+    #[require(A)]
+    fn longest_token<'a>(&'a self, fallback: &'a str) -> &'a str
+    where
+        'src: 'a,
+    {
+        self.tokens
+            .iter()
+            .copied()
+            .max_by_key(|token| token.len())
+            .unwrap_or(fallback)
+    }
+}
+
+// the elided lifetimes are supported too
+#[impl_state]
+impl Parser<'_> {
+    #[require(Tokenized)]
+    #[switch_to(Fresh)]
+    fn reset(self) -> Self {
+        Self {
+            source: self.source,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+#[impl_state]
+#[require(Tokenized)]
+impl<'src> fmt::Display for Parser<'src> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tokens.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifetimes_are_threaded_through() {
+        let source = String::from("let answer = 42");
+        let parser = match Parser::new(&source).tokenize() {
+            TokenizeOutcome::Tokenized(parser) => parser,
+            TokenizeOutcome::Failed(_) => unreachable!("the source is not empty"),
+        };
+        assert_eq!(parser.to_string(), "let,answer,=,42");
+        assert_eq!(parser.longest_token("none"), "answer");
+
+        let (parser, token) = parser.next_token();
+        assert_eq!(token, Some("let"));
+        assert_eq!(parser.to_string(), "answer,=,42");
+        assert_eq!(parser.reset().longest_token("none"), "none");
+
+        assert!(matches!(
+            Parser::new("").tokenize(),
+            TokenizeOutcome::Failed(parser) if parser.longest_token("none") == "none"
+        ));
+    }
+}
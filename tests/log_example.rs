@@ -0,0 +1,103 @@
+#![cfg(feature = "log")]
+
+use std::sync::Mutex;
+
+use log::{LevelFilter, Log, Metadata, Record};
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Locked, Unlocked), slots = (Locked), log = true)]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[switch_to(Locked)]
+    fn new() -> Self {
+        Self { opened: 0 }
+    }
+
+    #[require(Locked)]
+    #[switch_to(Unlocked)]
+    fn unlock(self) -> Self {
+        Self {
+            opened: self.opened + 1,
+        }
+    }
+
+    #[require(A)]
+    #[switch_to(Locked)]
+    fn lock(self) -> Self {
+        Self {
+            opened: self.opened,
+        }
+    }
+}
+
+// the structs without `log = true` are not logged
+#[type_state(states = (Off, On), slots = (Off))]
+struct Lamp {}
+
+#[impl_state]
+impl Lamp {
+    #[switch_to(Off)]
+    fn new() -> Self {
+        Self {}
+    }
+
+    #[require(Off)]
+    #[switch_to(On)]
+    fn turn_on(self) -> Self {
+        Self {}
+    }
+}
+
+/// Records the lines that are logged
+struct Recorder {
+    lines: Mutex<Vec<String>>,
+}
+
+impl Log for Recorder {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.lines.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+static RECORDER: Recorder = Recorder {
+    lines: Mutex::new(Vec::new()),
+};
+
+fn main() {
+    let door = Door::new().unlock().lock();
+    let _lamp = Lamp::new().turn_on();
+    println!("{}", door.opened);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions_are_logged() {
+        log::set_logger(&RECORDER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+
+        let door = Door::new().unlock().lock();
+        let _lamp = Lamp::new().turn_on();
+        assert_eq!(door.opened, 1);
+
+        assert_eq!(
+            *RECORDER.lines.lock().unwrap(),
+            vec![
+                "transition struct=Door method=unlock from=Locked to=Unlocked",
+                "transition struct=Door method=lock from=Unlocked to=Locked",
+            ]
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use state_shift::{impl_state, type_state};
+
+// the markers get the derives given to them, so the states can be compared and used as the keys of a map
+#[type_state(
+    states = (Idle, Running),
+    slots = (Idle),
+    derive(Debug, Clone, Copy, PartialEq, Eq, Hash)
+)]
+struct Player {
+    level: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new(level: u8) -> Self {
+        Self { level }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { level: self.level }
+    }
+}
+
+// the markers that carry data derive them too, along with the derives of the struct
+#[type_state(
+    states = (Disconnected, Connected { session_id: u64 }),
+    slots = (Disconnected),
+    derive(Debug, Default)
+)]
+#[derive(Clone, PartialEq)]
+struct Connection {
+    address: String,
+}
+
+#[impl_state]
+impl Connection {
+    #[switch_to(Disconnected)]
+    fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+        }
+    }
+
+    #[require(Disconnected)]
+    #[switch_to(Connected)]
+    fn connect(self, session_id: u64) -> Self {
+        Self {
+            address: self.address,
+            _state: Connected { session_id },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers_are_compared_and_hashed() {
+        let player = Player::new(2).start();
+        assert_eq!(player.level, 2);
+
+        let idle = Idle;
+        let copied = idle;
+        assert_eq!(idle, copied);
+        assert_eq!(format!("{:?}", Running), "Running");
+
+        let mut counts: HashMap<Idle, u32> = HashMap::new();
+        *counts.entry(Idle).or_default() += 1;
+        *counts.entry(copied).or_default() += 1;
+        assert_eq!(counts[&Idle], 2);
+    }
+
+    #[test]
+    fn data_markers_get_the_derives() {
+        let connection = Connection::new("localhost").connect(7);
+        assert!(connection.clone() == connection);
+        assert_eq!(
+            format!("{:?}", Connected::default()),
+            "Connected { session_id: 0 }"
+        );
+        assert_eq!(Connected { session_id: 3 }, Connected { session_id: 3 });
+    }
+}
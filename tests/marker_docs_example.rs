@@ -0,0 +1,48 @@
+//! The markers, the groups and the sealer are documented,
+//! so they pass `missing_docs` along with the rest of the public API
+
+#![deny(missing_docs)]
+
+use state_shift::{impl_state, type_state};
+
+/// A connection to a server
+#[type_state(
+    states = (Disconnected, Connected(Active { session: u64 }, Idle)),
+    slots = (Disconnected)
+)]
+pub struct Connection {
+    /// The address of the server
+    pub address: String,
+}
+
+#[impl_state]
+impl Connection {
+    /// Creates a disconnected connection
+    #[switch_to(Disconnected)]
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+        }
+    }
+
+    /// Connects to the server
+    #[require(Disconnected)]
+    #[switch_to(Connected::Active)]
+    pub fn connect(self, session: u64) -> Self {
+        Self {
+            address: self.address,
+            _state: Active { session },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_markers_are_documented() {
+        let connection: Connection<Active> = Connection::new("10.0.0.1").connect(3);
+        assert_eq!(connection.state_data().session, 3);
+    }
+}
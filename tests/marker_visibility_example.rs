@@ -0,0 +1,75 @@
+mod network {
+    use state_shift::{impl_state, type_state};
+
+    // the states are a detail of the module, and the other modules of the crate may name them
+    #[type_state(
+        states = (Closed, Open),
+        slots = (Closed),
+        groups = (Any = (Closed, Open)),
+        vis = pub(crate)
+    )]
+    pub(crate) struct Socket {
+        pub(crate) port: u16,
+    }
+
+    #[impl_state]
+    impl Socket {
+        #[switch_to(Closed)]
+        pub(crate) fn new(port: u16) -> Self {
+            Self { port }
+        }
+
+        #[require(Closed)]
+        #[switch_to(Open)]
+        pub(crate) fn open(self) -> Self {
+            Self { port: self.port }
+        }
+    }
+
+    // the states are private to the module
+    #[type_state(states = (Cold, Warm), slots = (Cold), vis = pub(self))]
+    struct Cache {
+        hits: u32,
+    }
+
+    #[impl_state]
+    impl Cache {
+        #[switch_to(Cold)]
+        fn new() -> Self {
+            Self { hits: 0 }
+        }
+
+        #[require(Cold)]
+        #[switch_to(Warm)]
+        fn warm_up(self) -> Self {
+            Self {
+                hits: self.hits + 1,
+            }
+        }
+    }
+
+    pub(crate) fn warm_cache_hits() -> u32 {
+        let cache: Cache<Warm> = Cache::new().warm_up();
+        cache.hits
+    }
+}
+
+use network::{Any, Open, Socket};
+
+fn is_any<S: Any>(_: &Socket<S>) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_visibility_of_the_markers_can_be_chosen() {
+        let socket: Socket<Open> = Socket::new(80).open();
+        assert_eq!(socket.port, 80);
+        assert!(is_any(&socket));
+
+        assert_eq!(network::warm_cache_hits(), 1);
+    }
+}
@@ -0,0 +1,105 @@
+use state_shift::{impl_state, match_state, type_state};
+
+// the erased values are matched with an arm for each state, instead of the variants of `AnyJob`
+#[type_state(
+    states = (Queued, Running, Paused, Done),
+    slots = (Queued),
+    groups = (Active = (Running, Paused)),
+    prefix = "Job",
+    any_state = true
+)]
+struct Job {
+    progress: u8,
+}
+
+#[impl_state]
+impl Job {
+    #[switch_to(Queued)]
+    fn new() -> Self {
+        Self { progress: 0 }
+    }
+
+    #[require(Queued | Paused)]
+    #[switch_to(Running)]
+    fn run(self) -> Self {
+        Self {
+            progress: self.progress + 50,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Paused)]
+    fn pause(self) -> Self {
+        Self {
+            progress: self.progress,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Done)]
+    fn finish(self) -> Self {
+        Self { progress: 100 }
+    }
+
+    #[require(A)]
+    fn progress(&self) -> u8 {
+        self.progress
+    }
+}
+
+fn describe(job: &AnyJob) -> String {
+    match_state!(job, Job {
+        Queued(_) => "queued".to_string(),
+        // the body is expanded for each of the states, with their typed values
+        Running(job) | Done(job) => format!("{}%", job.progress()),
+        other => format!("paused at {}%", other.progress()),
+    })
+}
+
+// the groups stand for each of their states
+fn active_progress(job: &AnyJob) -> Option<u8> {
+    match_state!(job, Job {
+        Active(job) => Some(job.progress()),
+        _ => None,
+    })
+}
+
+// the values transition in their arms, and they are erased again
+fn advance(job: AnyJob) -> AnyJob {
+    match_state!(job, Job {
+        Queued(job) => job.run().into(),
+        Running(job) if job.progress() >= 50 => job.finish().into(),
+        Running(job) => job.pause().into(),
+        Paused(job) => job.run().into(),
+        _ => job,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arms_match_the_states() {
+        let jobs: Vec<AnyJob> = vec![
+            Job::new().into(),
+            Job::new().run().into(),
+            Job::new().run().pause().into(),
+            Job::new().run().finish().into(),
+        ];
+        let descriptions: Vec<_> = jobs.iter().map(describe).collect();
+        assert_eq!(descriptions, ["queued", "50%", "paused at 50%", "100%"]);
+        let active: Vec<_> = jobs.iter().map(active_progress).collect();
+        assert_eq!(active, [None, Some(50), Some(50), None]);
+    }
+
+    #[test]
+    fn arms_transition_the_values() {
+        let job = advance(Job::new().into());
+        assert!(matches!(&job, AnyJob::Running(job) if job.progress() == 50));
+        let job = advance(job);
+        assert!(matches!(job, AnyJob::Done(_)));
+        let job = advance(advance(Job::new().run().pause().into()));
+        assert!(matches!(job, AnyJob::Done(_)));
+    }
+}
@@ -0,0 +1,79 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running, Paused), slots = (Idle))]
+struct Player {
+    track: u8,
+}
+
+// the methods with the same requirements share a single generated `impl` block,
+// while the alternatives of `#[require]` go into the blocks of each of their states
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new() -> Self {
+        Self { track: 0 }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { track: 1 }
+    }
+
+    #[require(Running)]
+    #[switch_to(Paused)]
+    fn pause(self) -> Self {
+        Self { track: self.track }
+    }
+
+    #[require(Running)]
+    fn track(&self) -> u8 {
+        self.track
+    }
+
+    #[require(Running | Paused)]
+    fn is_playing(&self) -> bool {
+        true
+    }
+
+    // the methods of the same name go into the blocks of their own states
+    #[require(Idle)]
+    fn describe(&self) -> &'static str {
+        "idle"
+    }
+
+    #[require(Paused)]
+    fn describe(&self) -> &'static str {
+        "paused"
+    }
+
+    #[cfg(any())]
+    #[require(Running)]
+    fn never_compiled(&self) -> u8 {
+        self.track
+    }
+}
+
+fn main() {
+    let player = Player::new();
+    println!("{}", player.describe());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn methods_of_merged_blocks_are_gated_on_the_states() {
+        let player: Player<Idle> = Player::new();
+        assert_eq!(player.describe(), "idle");
+
+        let player: Player<Running> = player.start();
+        assert_eq!(player.track(), 1);
+        assert!(player.is_playing());
+
+        let player: Player<Paused> = player.pause();
+        assert!(player.is_playing());
+        assert_eq!(player.describe(), "paused");
+    }
+}
@@ -0,0 +1,73 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Stopped, Running, Crashed), slots = (Stopped))]
+struct Service {
+    restarts: u32,
+}
+
+#[impl_state]
+impl Service {
+    /// Creates a stopped service
+    #[inline]
+    #[switch_to(Stopped)]
+    fn new() -> Self {
+        Self { restarts: 0 }
+    }
+
+    // the `#[cfg]` of a method gates the enum of the branching transition as well,
+    // so that the alternatives with the same name don't collide
+    #[cfg(not(target_os = "none"))]
+    #[require(Stopped)]
+    #[switch_to(Running | Crashed)]
+    fn start(self) -> StartOutcome {
+        StartOutcome::Running(Service {
+            restarts: self.restarts,
+        })
+    }
+
+    #[cfg(target_os = "none")]
+    #[require(Stopped)]
+    #[switch_to(Running | Crashed)]
+    fn start(self) -> StartOutcome {
+        StartOutcome::Crashed(Service {
+            restarts: self.restarts,
+        })
+    }
+
+    /// The number of restarts
+    #[require(A)]
+    #[deprecated = "use `restart_count` instead"]
+    fn restarts(&self) -> u32 {
+        self.restarts
+    }
+
+    #[require(A)]
+    #[allow(deprecated)]
+    fn restart_count(&self) -> u32 {
+        self.restarts()
+    }
+
+    // the methods that exist only on some targets don't leave anything behind without it
+    #[cfg(target_os = "none")]
+    #[require(Running)]
+    #[switch_to(Crashed)]
+    fn crash(self) -> Self {
+        Self {
+            restarts: self.restarts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_attributes_of_the_methods_are_kept() {
+        let service = match Service::new().start() {
+            StartOutcome::Running(service) => service,
+            StartOutcome::Crashed(_) => panic!("the service should be running"),
+        };
+        assert_eq!(service.restart_count(), 0);
+    }
+}
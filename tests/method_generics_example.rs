@@ -0,0 +1,78 @@
+use std::fmt::Debug;
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Empty, Filled, Sealed), slots = (Empty))]
+struct Packet<T>
+where
+    T: Clone,
+{
+    header: T,
+    bytes: Vec<u8>,
+}
+
+#[impl_state]
+impl<T> Packet<T>
+where
+    T: Clone + Debug,
+{
+    #[require(Empty)]
+    fn new(header: T) -> Packet<T> {
+        Packet {
+            header,
+            bytes: Vec::new(),
+        }
+    }
+
+    #[require(Empty)]
+    #[switch_to(Filled)]
+    fn feed<I: IntoIterator<Item = u8>>(self, it: I) -> Packet<T>
+    where
+        I::IntoIter: Clone,
+    {
+        let it = it.into_iter();
+        let bytes = it.clone().chain(it).collect();
+        Packet {
+            header: self.header,
+            bytes,
+        }
+    }
+
+    #[require(Filled)]
+    #[switch_to(Sealed)]
+    fn seal<F>(self, checksum: F) -> Packet<T>
+    where
+        F: Fn(&[u8]) -> u8,
+    {
+        let mut bytes = self.bytes;
+        bytes.push(checksum(&bytes));
+        Packet {
+            header: self.header,
+            bytes,
+        }
+    }
+
+    #[require(A)]
+    fn describe<'a, const N: usize>(&'a self, prefix: &'a str) -> String {
+        format!(
+            "{prefix}{:?}{:?}",
+            self.header,
+            &self.bytes[..N.min(self.bytes.len())]
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_generics_are_preserved() {
+        let packet = Packet::new("ping").feed([1u8, 2]);
+        assert_eq!(packet.bytes, vec![1, 2, 1, 2]);
+
+        let packet = packet.seal(|bytes| bytes.iter().sum());
+        assert_eq!(packet.bytes, vec![1, 2, 1, 2, 6]);
+        assert_eq!(packet.describe::<2>("> "), "> \"ping\"[1, 2]");
+    }
+}
@@ -0,0 +1,172 @@
+use state_shift::{impl_state, type_state};
+
+// the transition relation of an `impl` block is written into the modules of the model checkers while it is expanded
+#[type_state(
+    states = (Draft, Review, Published, Archived),
+    slots = (Draft),
+    groups = (Visible = (Review, Published)),
+    terminal = (Archived)
+)]
+struct Document {
+    title: String,
+}
+
+#[impl_state(tla = "target/models/document.tla", smv = "target/models/document.smv")]
+impl Document {
+    #[switch_to(Draft)]
+    fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+        }
+    }
+
+    #[require(Draft)]
+    #[switch_to(Review)]
+    fn submit(self) -> Self {
+        Self { title: self.title }
+    }
+
+    #[require(Review)]
+    #[switch_to(Published | Draft)]
+    fn decide(self, approved: bool) -> DecideOutcome {
+        if approved {
+            DecideOutcome::Published(Document { title: self.title })
+        } else {
+            DecideOutcome::Draft(Document { title: self.title })
+        }
+    }
+
+    #[require(Visible)]
+    #[switch_to(Archived)]
+    fn archive(self) -> Self {
+        Self { title: self.title }
+    }
+
+    // the methods that keep the states are not actions
+    #[require(A)]
+    fn title(&self) -> &str {
+        &self.title
+    }
+}
+
+// each slot is a variable, which the methods that don't transition it leave unchanged
+#[type_state(states = (LoggedOut, LoggedIn, Closed, Open), slots = (auth = LoggedOut, conn = Closed))]
+struct Session {}
+
+#[impl_state(tla = "target/models/session.tla", smv = "target/models/session.smv")]
+impl Session {
+    #[switch_to(LoggedIn, Open)]
+    fn resume() -> Self {
+        Self {}
+    }
+
+    #[require(auth = LoggedOut)]
+    #[switch_to(auth = LoggedIn)]
+    fn log_in(self) -> Self {
+        Self {}
+    }
+
+    #[require(auth = LoggedIn, conn = Closed)]
+    #[switch_to(conn = Open)]
+    fn open(self) -> Self {
+        Self {}
+    }
+}
+
+fn main() {
+    let document = Document::new("notes").submit();
+    println!("{}", document.title());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_model(name: &str) -> String {
+        let path = format!("{}/target/models/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn tla_has_the_transitions() {
+        let _session: Session<LoggedIn, Open> = Session::resume();
+
+        assert_eq!(
+            read_model("document.tla"),
+            r#"---- MODULE Document ----
+\* the transitions of `Document`, generated by `#[impl_state(tla = ...)]`
+
+VARIABLE state
+
+vars == <<state>>
+
+States == {"Draft", "Review", "Published", "Archived"}
+
+TypeOK == state \in States
+
+Init == state = "Draft"
+
+submit == state = "Draft" /\ state' = "Review"
+decide == state = "Review" /\ state' \in {"Published", "Draft"}
+archive == state \in {"Review", "Published"} /\ state' = "Archived"
+Done == state = "Archived" /\ UNCHANGED vars
+
+Next ==
+    \/ submit
+    \/ decide
+    \/ archive
+    \/ Done
+
+Spec == Init /\ [][Next]_vars
+
+====
+"#
+        );
+    }
+
+    #[test]
+    fn tla_has_a_variable_for_each_slot() {
+        let tla = read_model("session.tla");
+        assert!(tla.contains("VARIABLES auth, conn\n"));
+        assert!(tla.contains(
+            "Init ==\n    \\/ (auth = \"LoggedOut\" /\\ conn = \"Closed\")\n    \\/ (auth = \"LoggedIn\" /\\ conn = \"Open\")\n"
+        ));
+        assert!(tla.contains(
+            "log_in == auth = \"LoggedOut\" /\\ auth' = \"LoggedIn\" /\\ UNCHANGED conn\n"
+        ));
+        assert!(tla.contains(
+            "open == auth = \"LoggedIn\" /\\ conn = \"Closed\" /\\ UNCHANGED auth /\\ conn' = \"Open\"\n"
+        ));
+    }
+
+    #[test]
+    fn smv_has_the_transitions() {
+        assert_eq!(
+            read_model("document.smv"),
+            "-- the transitions of `Document`, generated by `#[impl_state(smv = ...)]`
+MODULE main
+VAR
+    state : {Draft, Review, Published, Archived};
+IVAR
+    method : {submit, decide, archive};
+INIT
+      (state = Draft)
+TRANS
+      (method = submit & state = Draft & next(state) = Review)
+    | (method = decide & state = Review & next(state) in {Published, Draft})
+    | (method = archive & state in {Review, Published} & next(state) = Archived)
+    | ((state = Archived) & next(state) = state)
+"
+        );
+    }
+
+    #[test]
+    fn smv_has_a_variable_for_each_slot() {
+        let smv = read_model("session.smv");
+        assert!(smv.contains("    auth : {LoggedOut, LoggedIn, Closed, Open};\n"));
+        assert!(smv.contains("    | (auth = LoggedIn & conn = Open)\n"));
+        assert!(smv.contains(
+            "(method = log_in & auth = LoggedOut & next(auth) = LoggedIn & next(conn) = conn)"
+        ));
+    }
+}
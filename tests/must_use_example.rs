@@ -0,0 +1,69 @@
+#![deny(unused_must_use)]
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running), slots = (Idle))]
+struct Job {
+    runs: u32,
+}
+
+#[impl_state]
+impl Job {
+    #[switch_to(Idle)]
+    fn new() -> Self {
+        Self { runs: 0 }
+    }
+
+    // the transitions are `#[must_use]`, and their own message is kept
+    #[require(Idle)]
+    #[switch_to(Running)]
+    #[must_use = "the job is only started in the returned value"]
+    fn start(self) -> Self {
+        Self {
+            runs: self.runs + 1,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Idle)]
+    fn stop(self) -> Self {
+        Self { runs: self.runs }
+    }
+}
+
+// the transitions of the structs that are used only for their side effects can opt out
+#[type_state(states = (Off, On), slots = (Off), must_use = false)]
+struct Lamp {
+    label: &'static str,
+}
+
+#[impl_state]
+impl Lamp {
+    #[switch_to(Off)]
+    fn new(label: &'static str) -> Self {
+        Self { label }
+    }
+
+    #[require(Off)]
+    #[switch_to(On)]
+    fn turn_on(self) -> Self {
+        println!("{} is on", self.label);
+        Self { label: self.label }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_results_of_the_transitions_are_used() {
+        let job: Job<Idle> = Job::new().start().stop();
+        assert_eq!(job.runs, 1);
+    }
+
+    #[test]
+    fn the_transitions_can_opt_out() {
+        Lamp::new("desk").turn_on();
+    }
+}
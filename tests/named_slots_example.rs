@@ -0,0 +1,72 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (LoggedOut, LoggedIn, Disconnected, Connected),
+    slots = (auth = LoggedOut, conn = Disconnected)
+)]
+struct Session {
+    user: Option<String>,
+    address: Option<String>,
+}
+
+#[impl_state]
+impl Session {
+    #[require(auth = LoggedOut, conn = Disconnected)]
+    fn new() -> Session {
+        Session {
+            user: None,
+            address: None,
+        }
+    }
+
+    #[require(conn = Disconnected)]
+    #[switch_to(conn = Connected)] // `auth` slot is kept as it is
+    fn connect(self, address: &str) -> Session {
+        Session {
+            user: self.user,
+            address: Some(address.to_string()),
+        }
+    }
+
+    #[require(conn = Connected, auth = LoggedOut)] // order of the named slots doesn't matter
+    #[switch_to(LoggedIn, Connected)] // positional arguments still work
+    fn log_in(self, user: &str) -> Session {
+        Session {
+            user: Some(user.to_string()),
+            address: self.address,
+        }
+    }
+
+    #[require(conn = Connected)] // `auth` slot accepts any state
+    fn address(&self) -> &str {
+        self.address
+            .as_deref()
+            .expect("type safety ensures this is set")
+    }
+
+    #[require(LoggedIn, Connected)]
+    fn greeting(&self) -> String {
+        format!(
+            "{}@{}",
+            self.user.as_ref().expect("type safety ensures this is set"),
+            self.address
+                .as_ref()
+                .expect("type safety ensures this is set")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn named_slots_work() {
+        let session = Session::new().connect("localhost");
+        assert_eq!(session.address(), "localhost");
+
+        let session = session.log_in("player");
+        assert_eq!(session.address(), "localhost");
+        assert_eq!(session.greeting(), "player@localhost");
+    }
+}
@@ -0,0 +1,64 @@
+mod account {
+    use state_shift::{impl_state, type_state};
+
+    // the `impl` block does not have to come after the struct
+    #[impl_state]
+    impl Account {
+        #[require(Guest)]
+        pub fn new() -> Account {
+            Account { posts: 0 }
+        }
+
+        #[require(Guest)]
+        #[switch_to(Member)]
+        pub fn register(self) -> Account {
+            Account { posts: self.posts }
+        }
+
+        #[require(not(Banned))] // can be called at any state except `Banned`
+        #[switch_to(Banned)]
+        pub fn ban(self) -> Account {
+            Account { posts: self.posts }
+        }
+
+        #[require(not(Guest | Banned))]
+        pub fn post(self) -> Account {
+            Account {
+                posts: self.posts + 1,
+            }
+        }
+
+        #[require(not(Banned))]
+        pub fn posts(&self) -> u32 {
+            self.posts
+        }
+    }
+
+    #[type_state(states = (Guest, Member, Moderator, Banned), slots = (Guest))]
+    pub struct Account {
+        pub posts: u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::account::*;
+
+    #[test]
+    fn negated_states_work() {
+        let account = Account::new();
+        assert_eq!(account.posts(), 0);
+
+        let account = account.register().post().post();
+        assert_eq!(account.posts(), 2);
+
+        let banned = account.ban();
+        assert_eq!(banned.posts, 2);
+    }
+
+    #[test]
+    fn negated_states_work_at_default_state() {
+        let banned = Account::new().ban();
+        assert_eq!(banned.posts, 0);
+    }
+}
@@ -0,0 +1,93 @@
+use state_shift::{impl_state, type_state};
+
+struct Socket {
+    peer: String,
+}
+
+#[type_state(
+    states = (Disconnected, Connecting, Connected, Failed),
+    slots = (Disconnected),
+    groups = (Online = (Connecting, Connected))
+)]
+struct Connection {
+    address: String,
+    #[only_in(Connected)]
+    socket: Socket,
+    // the groups can be used as well
+    #[only_in(Online)]
+    attempts: u32,
+}
+
+#[impl_state]
+impl Connection {
+    #[require(Disconnected)]
+    fn new(address: &str) -> Connection {
+        Connection {
+            address: address.to_string(),
+            socket: (),
+            attempts: (),
+        }
+    }
+
+    #[require(Disconnected)]
+    #[switch_to(Connecting)]
+    fn dial(self) -> Connection {
+        Connection {
+            address: self.address,
+            socket: (),
+            attempts: 1,
+        }
+    }
+
+    #[require(Connecting)]
+    #[switch_to(Connected)]
+    fn establish(self) -> Connection {
+        Connection {
+            socket: Socket {
+                peer: self.address.clone(),
+            },
+            address: self.address,
+            attempts: self.attempts,
+        }
+    }
+
+    #[require(Connected)]
+    fn peer(&self) -> &str {
+        &self.socket.peer
+    }
+
+    #[require(Online)]
+    fn retry(mut self) -> Self {
+        *self.attempts_mut() += 1;
+        self
+    }
+
+    #[require(Connected)]
+    #[switch_to(Failed)]
+    fn drop_connection(self) -> Connection {
+        Connection {
+            address: self.address,
+            socket: (),
+            attempts: (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_exist_only_in_their_states() {
+        let connection = Connection::new("db:5432").dial().retry();
+        assert_eq!(*connection.attempts(), 2);
+
+        let connection = connection.establish().retry();
+        assert_eq!(connection.peer(), "db:5432");
+        assert_eq!(connection.socket().peer, "db:5432");
+        assert_eq!(connection.attempts, 3);
+
+        let connection = connection.drop_connection();
+        assert_eq!(connection.address, "db:5432");
+    }
+}
@@ -0,0 +1,121 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running, Paused), slots = (Idle))]
+struct Machine {
+    ticks: u32,
+}
+
+#[impl_state]
+impl Machine {
+    #[require(Idle)]
+    fn new() -> Machine {
+        Machine { ticks: 0 }
+    }
+
+    #[require(Idle | Paused)] // can be called either from `Idle` or `Paused` state
+    #[switch_to(Running)]
+    fn start(self) -> Machine {
+        Machine { ticks: self.ticks }
+    }
+
+    #[require(Running)]
+    #[switch_to(Paused)]
+    fn pause(self) -> Machine {
+        Machine {
+            ticks: self.ticks + 1,
+        }
+    }
+
+    #[require(Running | Paused)]
+    #[switch_to(Idle)]
+    fn stop(self) -> Machine {
+        Machine { ticks: 0 }
+    }
+
+    #[require(Running | Paused)] // state is preserved for each alternative
+    fn tick(self) -> Machine {
+        Machine {
+            ticks: self.ticks + 1,
+        }
+    }
+
+    #[require(Running | Paused)]
+    fn ticks(&self) -> u32 {
+        self.ticks
+    }
+}
+
+#[type_state(states = (Unset, Set), slots = (Unset, Unset))]
+struct Pair {
+    left: Option<u8>,
+    right: Option<u8>,
+}
+
+#[impl_state]
+impl Pair {
+    #[require(Unset, Unset)]
+    fn new() -> Pair {
+        Pair {
+            left: None,
+            right: None,
+        }
+    }
+
+    #[require(Unset, B)]
+    #[switch_to(Set, B)]
+    fn set_left(self, left: u8) -> Pair {
+        Pair {
+            left: Some(left),
+            right: self.right,
+        }
+    }
+
+    #[require(A, Unset)]
+    #[switch_to(A, Set)]
+    fn set_right(self, right: u8) -> Pair {
+        Pair {
+            left: self.left,
+            right: Some(right),
+        }
+    }
+
+    #[require(Set, Unset | Set)]
+    fn left(&self) -> u8 {
+        self.left.expect("type safety ensures this is set")
+    }
+
+    #[require(Set, Set)]
+    fn sum(self) -> u8 {
+        self.left.expect("type safety ensures this is set")
+            + self.right.expect("type safety ensures this is set")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternative_states_work() {
+        let machine = Machine::new().start().tick().pause().tick();
+        assert_eq!(machine.ticks(), 3);
+
+        let machine = machine.start().tick();
+        assert_eq!(machine.ticks(), 4);
+
+        let machine = machine.stop().start();
+        assert_eq!(machine.ticks(), 0);
+    }
+
+    #[test]
+    fn alternative_states_work_with_multiple_slots() {
+        let pair = Pair::new().set_left(1);
+        assert_eq!(pair.left(), 1);
+
+        let pair = pair.set_right(2);
+        assert_eq!(pair.left(), 1);
+
+        let right_first = Pair::new().set_right(2).set_left(1);
+        assert_eq!(right_first.sum(), pair.sum());
+    }
+}
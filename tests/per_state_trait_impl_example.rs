@@ -0,0 +1,88 @@
+use std::fmt;
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Idle, Playing, Paused, Stopped),
+    slots = (Idle),
+    groups = (Active = (Playing, Paused))
+)]
+struct Player {
+    track: String,
+}
+
+#[impl_state]
+impl Player {
+    #[require(Idle)]
+    fn new(track: &str) -> Player {
+        Player {
+            track: track.to_string(),
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Playing)]
+    fn play(self) -> Player {
+        Player { track: self.track }
+    }
+
+    #[require(Playing)]
+    #[switch_to(Paused)]
+    fn pause(self) -> Player {
+        Player { track: self.track }
+    }
+
+    #[require(Active)]
+    #[switch_to(Stopped)]
+    fn stop(self) -> Player {
+        Player { track: self.track }
+    }
+}
+
+#[impl_state]
+#[require(Idle)]
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ready to play {}", self.track)
+    }
+}
+
+// any state of the group
+#[impl_state]
+#[require(Active)]
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "now playing {}", self.track)
+    }
+}
+
+// the struct can be initialized in the methods of the trait, like in `#[impl_state]` blocks
+#[impl_state]
+#[require(Stopped | Idle)]
+impl Clone for Player {
+    fn clone(&self) -> Self {
+        Self {
+            track: self.track.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_state_trait_impls_work() {
+        let player = Player::new("intro");
+        assert_eq!(player.to_string(), "ready to play intro");
+
+        let player = player.clone().play();
+        assert_eq!(player.to_string(), "now playing intro");
+
+        let player = player.pause();
+        assert_eq!(player.to_string(), "now playing intro");
+
+        let player = player.stop();
+        assert_eq!(player.clone().track, "intro");
+    }
+}
@@ -0,0 +1,99 @@
+use std::marker::PhantomData;
+
+use state_shift::{impl_state, type_state};
+
+/// A state that must stay on its thread
+pub struct Pinned(PhantomData<*const ()>);
+
+/// A state that can be sent to the other threads
+pub struct Free;
+
+// the states never affect the auto traits with the default `phantom = "fn_pointer"`
+#[type_state(states = (external(Pinned), external(Free)), slots = (Free), phantom = "fn_pointer")]
+struct Task {
+    id: u32,
+}
+
+#[impl_state]
+impl Task {
+    #[switch_to(Free)]
+    fn new(id: u32) -> Self {
+        Self { id }
+    }
+
+    #[require(Free)]
+    #[switch_to(Pinned)]
+    fn pin(self) -> Self {
+        Self { id: self.id }
+    }
+}
+
+// the struct behaves as if it owned its states, so `Handle<Pinned>` is not `Send`
+#[type_state(states = (external(Pinned), external(Free)), slots = (Free), phantom = "owned")]
+struct Handle {
+    id: u32,
+}
+
+#[impl_state]
+impl Handle {
+    #[switch_to(Free)]
+    fn new(id: u32) -> Self {
+        Self { id }
+    }
+
+    #[require(Free)]
+    #[switch_to(Pinned)]
+    fn pin(self) -> Self {
+        Self { id: self.id }
+    }
+}
+
+#[type_state(states = (Open, Closed), slots = (Open), phantom = "invariant")]
+struct Channel {
+    name: String,
+}
+
+#[impl_state]
+impl Channel {
+    #[switch_to(Open)]
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+
+    #[require(Open)]
+    #[switch_to(Closed)]
+    fn close(self) -> Self {
+        Self { name: self.name }
+    }
+}
+
+fn assert_send<T: Send>(_: &T) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_fn_pointer_phantom_is_always_send() {
+        let task: Task<Pinned> = Task::new(1).pin();
+        assert_send(&task);
+        assert_eq!(task.id, 1);
+    }
+
+    #[test]
+    fn the_owned_phantom_follows_the_states() {
+        let handle: Handle<Free> = Handle::new(2);
+        assert_send(&handle);
+
+        let handle: Handle<Pinned> = handle.pin();
+        assert_eq!(handle.id, 2);
+    }
+
+    #[test]
+    fn the_invariant_phantom_works() {
+        let channel: Channel<Closed> = Channel::new("events").close();
+        assert_eq!(channel.name, "events");
+    }
+}
@@ -0,0 +1,82 @@
+use state_shift::{impl_state, type_state};
+
+// both structs have a `Ready` state, which would collide without the prefixes
+#[type_state(
+    states = (Loading, Ready, Playing),
+    slots = (Loading),
+    groups = (Active = (Ready, Playing)),
+    prefix = "Player"
+)]
+struct Player {
+    #[only_in(Playing)]
+    track: String,
+}
+
+#[type_state(states = (Booting, Ready), slots = (Booting), prefix = "Server")]
+struct Server {
+    port: u16,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Loading)]
+    fn new() -> Self {
+        Self { track: () }
+    }
+
+    #[require(Loading)]
+    #[switch_to(Ready)]
+    fn load(self) -> Self {
+        Self { track: () }
+    }
+
+    #[require(Ready)]
+    #[switch_to(Playing)]
+    fn play(self, track: &str) -> Self {
+        Self {
+            track: track.to_string(),
+        }
+    }
+
+    #[require(Active)]
+    fn is_active(&self) -> bool {
+        true
+    }
+
+    #[require(not(Playing))]
+    fn is_playing(&self) -> bool {
+        false
+    }
+}
+
+#[impl_state]
+impl Server {
+    #[switch_to(Booting)]
+    fn new(port: u16) -> Self {
+        Self { port }
+    }
+
+    #[require(Booting)]
+    #[switch_to(Ready)]
+    fn boot(self) -> Self {
+        Self { port: self.port }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixed_states_work() {
+        let player: Player<PlayerReady> = Player::new().load();
+        assert!(player.is_active());
+        assert!(!player.is_playing());
+
+        let player = player.play("intro");
+        assert_eq!(player.track(), "intro");
+
+        let server: Server<ServerReady> = Server::new(8080).boot();
+        assert_eq!(server.port, 8080);
+    }
+}
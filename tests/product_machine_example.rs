@@ -0,0 +1,141 @@
+use state_shift::{impl_state, type_state};
+
+mod auth {
+    use state_shift::{impl_state, type_state};
+
+    #[type_state(states = (LoggedOut, LoggedIn), slots = (LoggedOut))]
+    pub struct Auth {
+        pub user: Option<String>,
+    }
+
+    #[impl_state]
+    impl Auth {
+        #[switch_to(LoggedOut)]
+        pub fn new() -> Self {
+            Self { user: None }
+        }
+
+        #[require(LoggedOut)]
+        #[switch_to(LoggedIn)]
+        pub fn log_in(self, user: &str) -> Self {
+            Self {
+                user: Some(user.to_string()),
+            }
+        }
+    }
+}
+
+mod connection {
+    use state_shift::{impl_state, type_state};
+
+    #[type_state(states = (Closed, Open), slots = (Closed))]
+    pub struct Connection {
+        pub bytes_sent: usize,
+    }
+
+    #[impl_state]
+    impl Connection {
+        #[switch_to(Closed)]
+        pub fn new() -> Self {
+            Self { bytes_sent: 0 }
+        }
+
+        #[require(Closed)]
+        #[switch_to(Open)]
+        pub fn open(self) -> Self {
+            Self {
+                bytes_sent: self.bytes_sent,
+            }
+        }
+
+        #[require(Open)]
+        pub fn send(&mut self, message: &str) {
+            self.bytes_sent += message.len();
+        }
+
+        #[require(Open)]
+        #[switch_to(Closed)]
+        pub fn close(self) -> Self {
+            Self {
+                bytes_sent: self.bytes_sent,
+            }
+        }
+    }
+}
+
+use auth::{Auth, LoggedIn, LoggedOut};
+use connection::{Closed, Connection, Open};
+
+// the session tracks the states of both of its components, and gets their fields
+#[type_state(components = (auth: auth::Auth<LoggedOut>, conn: connection::Connection<Closed>))]
+struct Session {
+    id: u32,
+}
+
+#[impl_state]
+impl Session {
+    #[switch_to(LoggedOut, Closed)]
+    fn new(id: u32) -> Self {
+        Self {
+            id,
+            auth: Auth::new(),
+            conn: Connection::new(),
+        }
+    }
+
+    #[require(LoggedOut, _)]
+    #[switch_to(LoggedIn, _)]
+    fn log_in(self, user: &str) -> Self {
+        Self {
+            id: self.id,
+            auth: self.auth.log_in(user),
+            conn: self.conn,
+        }
+    }
+
+    #[require(conn = Closed)]
+    #[switch_to(conn = Open)]
+    fn open(self) -> Self {
+        Self {
+            id: self.id,
+            auth: self.auth,
+            conn: self.conn.open(),
+        }
+    }
+
+    #[require(LoggedIn, Open)]
+    fn send(&mut self, message: &str) {
+        self.conn.send(message);
+    }
+
+    #[require(_, Open)]
+    #[switch_to(_, Closed)]
+    fn close(self) -> Self {
+        Self {
+            id: self.id,
+            auth: self.auth,
+            conn: self.conn.close(),
+        }
+    }
+
+    #[require(_, _)]
+    fn bytes_sent(&self) -> usize {
+        self.conn.bytes_sent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn components_work() {
+        let mut session: Session<LoggedIn, Open> = Session::new(7).open().log_in("alice");
+        session.send("hello");
+        assert_eq!(session.auth.user.as_deref(), Some("alice"));
+
+        let session: Session<LoggedIn, Closed> = session.close();
+        assert_eq!(session.bytes_sent(), 5);
+        assert_eq!(session.id, 7);
+    }
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "proptest")]
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Empty, Filling, Full), slots = (Empty), any_state = true)]
+struct Tank {
+    level: u32,
+    capacity: u32,
+}
+
+#[impl_state(proptest)]
+impl Tank {
+    #[switch_to(Empty)]
+    fn new(capacity: u32) -> Self {
+        Self { level: 0, capacity }
+    }
+
+    #[require(Empty | Filling)]
+    #[switch_to(Filling)]
+    fn pour(self, amount: u8) -> Self {
+        Self {
+            level: (self.level + amount as u32).min(self.capacity),
+            capacity: self.capacity,
+        }
+    }
+
+    #[require(Filling)]
+    #[switch_to(Full)]
+    fn seal(self) -> Self {
+        Self {
+            level: self.level,
+            capacity: self.capacity,
+        }
+    }
+
+    #[require(A)]
+    #[switch_to(Empty)]
+    fn drain(self) -> Self {
+        Self {
+            level: 0,
+            capacity: self.capacity,
+        }
+    }
+
+    // the methods that take a reference keep the state
+    #[require(Filling | Full)]
+    fn level(&self) -> u32 {
+        self.level
+    }
+}
+
+fn main() {
+    let tank = tank_proptest::run_all(
+        Tank::new(10).into(),
+        &[tank_proptest::Step::Pour(4), tank_proptest::Step::Seal],
+    );
+    if let AnyTank::Full(tank) = tank {
+        println!("{}", tank.level());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use tank_proptest::Step;
+
+    fn level(tank: &AnyTank) -> u32 {
+        match tank {
+            AnyTank::Empty(tank) => tank.level,
+            AnyTank::Filling(tank) => tank.level,
+            AnyTank::Full(tank) => tank.level,
+        }
+    }
+
+    #[test]
+    fn test_next_state() {
+        assert_eq!(Step::Pour(1).next_state("Empty"), Some("Filling"));
+        assert_eq!(Step::Seal.next_state("Filling"), Some("Full"));
+        assert_eq!(Step::Seal.next_state("Empty"), None);
+        assert_eq!(Step::Level.next_state("Full"), Some("Full"));
+        assert_eq!(Step::Drain.next_state("Full"), Some("Empty"));
+    }
+
+    #[test]
+    fn test_run_all() {
+        let tank = tank_proptest::run_all(
+            Tank::new(10).into(),
+            &[Step::Pour(4), Step::Pour(9), Step::Level, Step::Seal],
+        );
+        assert!(matches!(tank, AnyTank::Full(_)));
+        assert_eq!(level(&tank), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "`Seal` cannot be taken in this state")]
+    fn test_run_invalid_step() {
+        tank_proptest::run(Tank::new(10).into(), &Step::Seal);
+    }
+
+    proptest! {
+        #[test]
+        fn test_level_never_exceeds_capacity(steps in tank_proptest::steps(&Tank::new(10).into(), 0..30)) {
+            let mut tank: AnyTank = Tank::new(10).into();
+            for step in &steps {
+                tank = tank_proptest::run(tank, step);
+                prop_assert!(level(&tank) <= 10);
+            }
+        }
+    }
+}
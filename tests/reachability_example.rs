@@ -0,0 +1,77 @@
+use state_shift::{impl_state, type_state};
+
+// every declared state has to be reachable from the default state (or a constructor),
+// through the methods of the `impl` block
+#[type_state(
+    states = (Draft, Review, Published, Archived, Imported),
+    slots = (Draft),
+    groups = (Visible = (Review, Published)),
+    deny(unreachable)
+)]
+struct Document {
+    title: String,
+}
+
+#[impl_state]
+impl Document {
+    #[switch_to(Draft)]
+    fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+        }
+    }
+
+    // the constructors make their states reachable too
+    #[switch_to(Imported)]
+    fn import(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+        }
+    }
+
+    #[require(Draft | Imported)]
+    #[switch_to(Review)]
+    fn submit(self) -> Self {
+        Self { title: self.title }
+    }
+
+    #[require(Review)]
+    #[switch_to(Published | Draft)]
+    fn decide(self, approved: bool) -> DecideOutcome {
+        if approved {
+            DecideOutcome::Published(Document { title: self.title })
+        } else {
+            DecideOutcome::Draft(Document { title: self.title })
+        }
+    }
+
+    #[require(Visible)]
+    #[switch_to(Archived)]
+    fn archive(self) -> Self {
+        Self { title: self.title }
+    }
+
+    #[require(A)]
+    fn title(&self) -> &str {
+        &self.title
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_state_is_reachable() {
+        let document = Document::new("notes").submit();
+        let document = match document.decide(true) {
+            DecideOutcome::Published(document) => document,
+            DecideOutcome::Draft(_) => panic!("the document should be published"),
+        };
+        let document: Document<Archived> = document.archive();
+        assert_eq!(document.title(), "notes");
+
+        let document: Document<Review> = Document::import("report").submit();
+        assert_eq!(document.title(), "report");
+    }
+}
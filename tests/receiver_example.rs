@@ -0,0 +1,80 @@
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Draft, Published), slots = (Draft))]
+struct Post {
+    title: String,
+}
+
+#[impl_state]
+impl Post {
+    #[require(Draft)]
+    fn new(title: &str) -> Post {
+        Post {
+            title: title.to_string(),
+        }
+    }
+
+    #[require(Draft)]
+    #[switch_to(Published)]
+    #[allow(clippy::boxed_local)]
+    fn publish_boxed(self: Box<Self>) -> Box<Self> {
+        let post = *self;
+        Box::new(Post { title: post.title })
+    }
+
+    #[require(Draft)]
+    #[switch_to(Published)]
+    fn publish_shared(self: Rc<Self>) -> Rc<Self> {
+        Rc::new(Post {
+            title: self.title.clone(),
+        })
+    }
+
+    #[require(Draft)]
+    #[switch_to(Published)]
+    fn publish_pinned(self: Pin<Box<Self>>) -> Pin<Box<Self>> {
+        Box::pin(Post {
+            title: self.title.clone(),
+        })
+    }
+
+    #[require(Published)]
+    fn title_arc(self: Arc<Self>) -> String {
+        self.title.clone()
+    }
+
+    #[require(Published)]
+    fn rename(self: Pin<&mut Self>, title: &str) {
+        self.get_mut().title = title.to_string();
+    }
+
+    #[require(Published)]
+    fn title(&self) -> &str {
+        &self.title
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternative_receivers_work() {
+        let post: Box<Post<Published>> = Box::new(Post::new("boxed")).publish_boxed();
+        assert_eq!(post.title(), "boxed");
+
+        let post: Rc<Post<Published>> = Rc::new(Post::new("shared")).publish_shared();
+        assert_eq!(post.title(), "shared");
+
+        let mut post: Pin<Box<Post<Published>>> = Box::pin(Post::new("pinned")).publish_pinned();
+        post.as_mut().rename("renamed");
+        assert_eq!(post.title(), "renamed");
+
+        let post = Arc::new(Pin::into_inner(post));
+        assert_eq!((*post).title(), "renamed");
+    }
+}
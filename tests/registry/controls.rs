@@ -0,0 +1,60 @@
+use std::fmt;
+
+use state_shift::impl_state;
+
+// the states come from the registry of the struct, without being imported one by one
+#[impl_state]
+impl crate::player::Player {
+    #[require(Running)]
+    #[switch_to(Paused)]
+    pub fn pause(self) -> Self {
+        Self { track: self.track }
+    }
+
+    #[require(Paused)]
+    #[switch_to(Running)]
+    pub fn resume(self) -> Self {
+        Self { track: self.track }
+    }
+
+    #[require(Running)]
+    #[switch_to(Running)]
+    pub fn next_track(self) -> Self {
+        Self {
+            track: self.track + 1,
+        }
+    }
+
+    #[require(Playing)]
+    pub fn track(&self) -> u8 {
+        self.track
+    }
+}
+
+#[impl_state]
+#[require(Playing)]
+impl fmt::Display for crate::player::Player {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "playing track {}", self.track)
+    }
+}
+
+#[impl_state]
+impl crate::Lamp {
+    #[require(Off)]
+    #[switch_to(On)]
+    pub(crate) fn turn_on(self, brightness: u8) -> Self {
+        Self { brightness }
+    }
+
+    #[require(On)]
+    #[switch_to(Off)]
+    pub(crate) fn turn_off(self) -> Self {
+        Self { brightness: 0 }
+    }
+
+    #[require(A)]
+    pub(crate) fn brightness(&self) -> u8 {
+        self.brightness
+    }
+}
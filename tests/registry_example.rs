@@ -0,0 +1,78 @@
+use state_shift::{impl_state, type_state};
+
+// the methods of the structs are organized in another file, which names the structs by their paths
+#[path = "registry/controls.rs"]
+mod controls;
+
+mod player {
+    use state_shift::{impl_state, type_state};
+
+    #[type_state(
+        states = (Idle, Running, Paused),
+        slots = (Idle),
+        groups = (Playing = (Running, Paused)),
+        registry = player_states
+    )]
+    pub struct Player {
+        pub(crate) track: u8,
+    }
+
+    #[impl_state]
+    impl Player {
+        #[switch_to(Idle)]
+        pub fn new() -> Self {
+            Self { track: 0 }
+        }
+
+        #[require(Idle)]
+        #[switch_to(Running)]
+        pub fn start(self) -> Self {
+            Self { track: 1 }
+        }
+    }
+}
+
+// the private struct is re-exported for the modules inside of its own module
+#[type_state(states = (Off, On), slots = (Off), registry = lamp_states)]
+struct Lamp {
+    brightness: u8,
+}
+
+#[impl_state]
+impl Lamp {
+    #[switch_to(Off)]
+    fn new() -> Self {
+        Self { brightness: 0 }
+    }
+}
+
+fn main() {
+    let player = player::Player::new().start().pause();
+    println!("{}", player);
+
+    let lamp = Lamp::new().turn_on(3);
+    println!("{}", lamp.brightness());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use player::player_states::*;
+
+    #[test]
+    fn methods_of_other_files_are_gated_on_the_states() {
+        let player: Player<Paused> = Player::new().start().pause();
+        assert_eq!(player.track(), 1);
+        let player: Player<Running> = player.resume().next_track();
+        assert_eq!(player.track(), 2);
+        assert_eq!(player.to_string(), "playing track 2");
+    }
+
+    #[test]
+    fn private_structs_are_registered_too() {
+        let lamp: Lamp<On> = Lamp::new().turn_on(3);
+        assert_eq!(lamp.brightness(), 3);
+        let lamp: Lamp<Off> = lamp.turn_off();
+        assert_eq!(lamp.brightness(), 0);
+    }
+}
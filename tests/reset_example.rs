@@ -0,0 +1,83 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running, Finished), slots = (Idle), reset = true)]
+struct Player {
+    name: String,
+    score: u32,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            score: 0,
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self {
+            name: self.name,
+            score: self.score,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Finished)]
+    fn finish(self, score: u32) -> Self {
+        Self {
+            name: self.name,
+            score,
+        }
+    }
+}
+
+// the stored states are dropped, and every slot goes back to its default state
+#[type_state(
+    states = (Disconnected, Connected { session_id: u64 }),
+    slots = (Disconnected, Disconnected),
+    reset = true
+)]
+struct Link(String);
+
+#[impl_state]
+impl Link {
+    #[require(Disconnected, Disconnected)]
+    #[switch_to(Connected, Disconnected)]
+    fn connect(self, session_id: u64) -> Self {
+        Self(self.0, (Connected { session_id }, Disconnected))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn struct_starts_over() {
+        let player = Player::new("Alice").start().finish(10);
+        assert_eq!(player.state_name(), "Finished");
+
+        let player: Player<Idle> = player.reset();
+        assert_eq!((player.name.as_str(), player.score), ("Alice", 10));
+
+        // the default state can be transitioned out of again
+        let player = player.start();
+        assert_eq!(player.state_name(), "Running");
+    }
+
+    #[test]
+    fn stored_states_are_reset() {
+        let link: Link = Link("localhost".to_string(), Default::default());
+        let link = link.connect(7);
+        assert_eq!(link.state_name_1(), "Connected");
+        assert_eq!(link.state_data_1().session_id, 7);
+
+        let link = link.reset();
+        assert_eq!(link.state_name_1(), "Disconnected");
+        assert_eq!(link.0, "localhost");
+    }
+}
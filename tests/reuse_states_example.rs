@@ -0,0 +1,94 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Draft, Review, Published),
+    slots = (Draft),
+    groups = (Unpublished = (Draft, Review))
+)]
+struct Article {
+    title: String,
+}
+
+// the markers and the sealer of `Article` are shared
+#[type_state(reuse = Article, slots = (Draft))]
+struct Post {
+    body: String,
+}
+
+// reusing the states of a struct that reuses them too
+#[type_state(reuse = Post, slots = (Review))]
+struct Page {
+    path: String,
+}
+
+#[impl_state]
+impl Article {
+    #[switch_to(Draft)]
+    fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+        }
+    }
+
+    #[require(Draft)]
+    #[switch_to(Review)]
+    fn submit(self) -> Self {
+        Self { title: self.title }
+    }
+}
+
+#[impl_state]
+impl Post {
+    #[switch_to(Draft)]
+    fn new(body: &str) -> Self {
+        Self {
+            body: body.to_string(),
+        }
+    }
+
+    #[require(Unpublished)]
+    #[switch_to(Published)]
+    fn publish(self) -> Self {
+        Self { body: self.body }
+    }
+
+    #[require(not(Published))]
+    fn edit(&mut self, body: &str) {
+        self.body = body.to_string();
+    }
+}
+
+#[impl_state]
+impl Page {
+    #[switch_to(Review)]
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+
+    #[require(Review)]
+    #[switch_to(Published)]
+    fn approve(self) -> Self {
+        Self { path: self.path }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_states_work() {
+        let article: Article<Review> = Article::new("news").submit();
+        assert_eq!(article.title, "news");
+
+        let mut post = Post::new("draft");
+        post.edit("final");
+        let post: Post<Published> = post.publish();
+        assert_eq!(post.body, "final");
+
+        let page: Page<Published> = Page::new("/about").approve();
+        assert_eq!(page.path, "/about");
+    }
+}
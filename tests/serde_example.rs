@@ -0,0 +1,177 @@
+#![cfg(feature = "serde")]
+
+use state_shift::{impl_state, type_state};
+
+// the struct is serialized in any of its states, without its state,
+// and deserialized into its default state, or into the states given to `serde`
+#[type_state(
+    states = (Loading, Ready, Playing),
+    slots = (Loading),
+    prefix = "Player",
+    serde = (Ready)
+)]
+struct Player {
+    track: String,
+    #[serde(default)]
+    volume: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Loading)]
+    fn new(track: &str) -> Self {
+        Self {
+            track: track.to_string(),
+            volume: 5,
+        }
+    }
+
+    #[require(Loading)]
+    #[switch_to(Ready)]
+    fn load(self) -> Self {
+        Self {
+            track: self.track,
+            volume: self.volume,
+        }
+    }
+
+    #[require(Ready)]
+    #[switch_to(Playing)]
+    fn play(self) -> Self {
+        Self {
+            track: self.track,
+            volume: self.volume,
+        }
+    }
+
+    #[require(A)]
+    fn track(&self) -> &str {
+        &self.track
+    }
+}
+
+// the generics of the struct keep their own bounds
+#[type_state(states = (Empty, Full), slots = (Empty), serde = true)]
+struct Crate<T> {
+    items: Vec<T>,
+}
+
+#[impl_state]
+impl<T> Crate<T> {
+    #[require(Empty)]
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+// the enum of the struct in any of its states is tagged with the states, so the values round-trip with them
+#[type_state(
+    states = (Queued, Running, Done),
+    slots = (Queued),
+    any_state = true,
+    serde = true
+)]
+struct Job<T> {
+    id: u32,
+    payload: T,
+}
+
+#[impl_state]
+impl<T> Job<T> {
+    #[switch_to(Queued)]
+    fn new(id: u32, payload: T) -> Self {
+        Self { id, payload }
+    }
+
+    #[require(Queued)]
+    #[switch_to(Running)]
+    fn run(self) -> Self {
+        Self {
+            id: self.id,
+            payload: self.payload,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Done)]
+    fn finish(self) -> Self {
+        Self {
+            id: self.id,
+            payload: self.payload,
+        }
+    }
+
+    #[require(A)]
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_is_not_serialized() {
+        let player = Player::new("intro").load().play();
+        let json = serde_json::to_string(&player).unwrap();
+        assert_eq!(json, r#"{"track":"intro","volume":5}"#);
+    }
+
+    #[test]
+    fn deserialized_into_the_default_state() {
+        let player: Player = serde_json::from_str(r#"{"track":"intro"}"#).unwrap();
+        assert_eq!(player.track(), "intro");
+        assert_eq!(player.volume, 0);
+        let player = player.load().play();
+        assert_eq!(player.track(), "intro");
+    }
+
+    #[test]
+    fn deserialized_into_the_given_state() {
+        let player: Player<PlayerReady> =
+            serde_json::from_str(r#"{"track":"intro","volume":3}"#).unwrap();
+        let player = player.play();
+        assert_eq!(player.track(), "intro");
+    }
+
+    #[test]
+    fn generics_are_deserialized() {
+        let items: Crate<u8> = serde_json::from_str(r#"{"items":[1,2,3]}"#).unwrap();
+        assert_eq!(items.len(), 3);
+    }
+
+    #[test]
+    fn erased_struct_is_tagged_with_its_state() {
+        let job: AnyJob<String> = Job::new(1, "build".to_string()).run().into();
+        let json = serde_json::to_string(&job).unwrap();
+        assert_eq!(json, r#"{"state":"Running","id":1,"payload":"build"}"#);
+    }
+
+    #[test]
+    fn erased_struct_round_trips_with_its_state() {
+        let jobs: Vec<AnyJob<u8>> = vec![
+            Job::new(1, 10).into(),
+            Job::new(2, 20).run().into(),
+            Job::new(3, 30).run().finish().into(),
+        ];
+        let json = serde_json::to_string(&jobs).unwrap();
+        let jobs: Vec<AnyJob<u8>> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(jobs.len(), 3);
+        match &jobs[1] {
+            AnyJob::Running(job) => assert_eq!(job.id(), 2),
+            _ => panic!("expected a running job"),
+        }
+        match &jobs[2] {
+            AnyJob::Done(job) => assert_eq!(job.payload, 30),
+            _ => panic!("expected a finished job"),
+        }
+    }
+
+    #[test]
+    fn unknown_state_is_rejected() {
+        let result = serde_json::from_str::<AnyJob<u8>>(r#"{"state":"Lost","id":1,"payload":0}"#);
+        assert!(result.is_err());
+    }
+}
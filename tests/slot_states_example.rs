@@ -0,0 +1,50 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Raw, Validated, Unsaved, Saved),
+    slots = (Raw, Unsaved),
+    slot_states = ((Raw, Validated), (Unsaved, Saved))
+)]
+struct Document {
+    text: String,
+}
+
+#[impl_state]
+impl Document {
+    #[require(Raw, Unsaved)]
+    fn new(text: &str) -> Document {
+        Document {
+            text: text.to_string(),
+        }
+    }
+
+    #[require(Raw, B)]
+    #[switch_to(Validated, B)]
+    fn validate(self) -> Document {
+        Document {
+            text: self.text.trim().to_string(),
+        }
+    }
+
+    #[require(A, not(Saved))] // only the states of the 2nd slot are considered: `Unsaved`
+    #[switch_to(A, Saved)]
+    fn save(self) -> Document {
+        Document { text: self.text }
+    }
+
+    #[require(Validated, Saved)]
+    fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_states_work() {
+        let document = Document::new(" hello ").save().validate();
+        assert_eq!(document.text(), "hello");
+    }
+}
@@ -0,0 +1,76 @@
+// the RON and TOML specs are read with the `ron` and `toml` features of `state-shift`
+#[cfg(feature = "ron")]
+mod door {
+    use state_shift::state_machine_from;
+
+    // the machines are read from the spec files, which stay the single source of truth
+    state_machine_from!("tests/specs/door.ron");
+
+    // the bodies of the transitions of the spec
+    impl<S: DoorStateMarker> DoorTransitions for Door<S> {
+        fn on_open(&mut self) {}
+
+        fn on_close(&mut self) {}
+
+        fn on_lock(&mut self, code: u32) {
+            self.code = code;
+        }
+
+        fn on_unlock(&mut self, code: u32) {
+            assert_eq!(self.code, code, "wrong code");
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn ron_spec_declares_the_machine() {
+            let door: Door<Locked> = Door::new(0).open().close().lock(1234);
+            assert_eq!(door.state_name(), "Locked");
+
+            let door: Door<Closed> = door.unlock(1234);
+            assert_eq!(door.code, 1234);
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+mod turnstile {
+    use state_shift::state_machine_from;
+
+    // the spec files can be TOML as well
+    state_machine_from!("tests/specs/turnstile.toml");
+
+    impl<S: TurnstileStateMarker> TurnstileTransitions for Turnstile<S> {
+        fn on_coin(&mut self, value: u32) {
+            self.coins += value;
+        }
+
+        fn on_push(&mut self) {
+            self.tickets.push(self.coins);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn toml_spec_declares_the_machine() {
+            let turnstile: Turnstile<Locked> =
+                Turnstile::new(0, Vec::new()).coin(2).push().coin(3).push();
+            assert_eq!(turnstile.coins, 5);
+            assert_eq!(turnstile.tickets, vec![2, 5]);
+        }
+    }
+}
+
+fn main() {
+    #[cfg(feature = "ron")]
+    {
+        let door = door::Door::new(0).open().close().lock(42);
+        println!("{}", door.state_name());
+    }
+}
@@ -0,0 +1,87 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Idle, Running, Done),
+    slots = (Idle),
+    deny(unreachable, dead_end)
+)]
+struct Player {
+    level: u8,
+    volume: u8,
+}
+
+// the transitions of the machine, which the lints analyze as a whole
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new() -> Self {
+        Self {
+            level: 0,
+            volume: 5,
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self {
+            level: self.level + 1,
+            volume: self.volume,
+        }
+    }
+
+    #[require(Running)]
+    #[switch_to(Done)]
+    fn stop(self) -> Self {
+        Self {
+            level: self.level,
+            volume: self.volume,
+        }
+    }
+
+    #[require(Done)]
+    fn level(&self) -> u8 {
+        self.level
+    }
+}
+
+// the methods of a feature area, in their own `impl` block (in another module too, with the states in scope),
+// which share the markers of the struct, and are left out of the lints of the machine as a whole
+mod audio {
+    use super::*;
+
+    #[impl_state(part)]
+    impl Player {
+        #[require(Running)]
+        pub(super) fn louder(&mut self) {
+            self.volume += 1;
+        }
+
+        #[require(A)]
+        pub(super) fn volume(&self) -> u8 {
+            self.volume
+        }
+    }
+}
+
+fn main() {
+    let mut player = Player::new().start();
+    player.louder();
+    println!("{}", player.volume());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn methods_of_the_parts_are_gated_on_the_states() {
+        let mut player: Player<Running> = Player::new().start();
+        player.louder();
+        assert_eq!(player.volume(), 6);
+
+        let player: Player<Done> = player.stop();
+        assert_eq!(player.level(), 1);
+        assert_eq!(player.volume(), 6);
+    }
+}
@@ -0,0 +1,108 @@
+use state_shift::{impl_state, type_state};
+
+// the state can be told at runtime, for the logs, the metrics, or the states stored in a database
+#[type_state(
+    states = (Idle, Running, Stopped),
+    slots = (Idle),
+    prefix = "Player",
+    any_state = true,
+    state_enum = true
+)]
+struct Player {
+    name: String,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { name: self.name }
+    }
+
+    #[require(Running)]
+    #[switch_to(Stopped)]
+    fn stop(self) -> Self {
+        Self { name: self.name }
+    }
+
+    // the current state is known in the methods that accept any state too
+    #[require(A)]
+    fn is_running(&self) -> bool {
+        self.current_state() == PlayerState::Running
+    }
+}
+
+// each of the slots tells its own state
+#[type_state(
+    states = (LoggedOut, LoggedIn, Closed, Open),
+    slots = (auth = LoggedOut, conn = Closed),
+    state_enum = true
+)]
+struct Session {}
+
+#[impl_state]
+impl Session {
+    #[switch_to(_, _)]
+    fn new() -> Self {
+        Self {}
+    }
+
+    #[require(auth = LoggedOut)]
+    #[switch_to(auth = LoggedIn)]
+    fn log_in(self) -> Self {
+        Self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn struct_tells_its_state() {
+        let player = Player::new("Alice");
+        assert_eq!(player.current_state(), PlayerState::Idle);
+        assert!(!player.is_running());
+
+        let player = player.start();
+        assert_eq!(player.current_state(), PlayerState::Running);
+        assert!(player.is_running());
+
+        // the variants are named after the declared states, without the prefix
+        assert_eq!(<PlayerStopped as SealerPlayer>::STATE, PlayerState::Stopped);
+    }
+
+    #[test]
+    fn erased_struct_tells_its_state() {
+        let players: Vec<AnyPlayer> = vec![
+            Player::new("Alice").into(),
+            Player::new("Bob").start().into(),
+            Player::new("Carol").start().stop().into(),
+        ];
+        let states: Vec<PlayerState> = players.iter().map(AnyPlayer::current_state).collect();
+        assert_eq!(
+            states,
+            [
+                PlayerState::Idle,
+                PlayerState::Running,
+                PlayerState::Stopped
+            ]
+        );
+    }
+
+    #[test]
+    fn slots_tell_their_states() {
+        let session: Session = Session::new();
+        let session = session.log_in();
+        assert_eq!(session.auth_current_state(), SessionState::LoggedIn);
+        assert_eq!(session.conn_current_state(), SessionState::Closed);
+    }
+}
@@ -0,0 +1,54 @@
+use state_shift::{impl_state, type_state};
+
+// the struct has a `_state` field of its own, so the hidden field is renamed
+#[type_state(
+    states = (Stopped, Running { pid: u32 }),
+    slots = (Stopped),
+    field = "__ts_state"
+)]
+struct Process {
+    _state: &'static str,
+}
+
+#[impl_state]
+impl Process {
+    #[switch_to(Stopped)]
+    fn new() -> Self {
+        Self { _state: "stopped" }
+    }
+
+    #[require(Stopped)]
+    #[switch_to(Running)]
+    fn start(self, pid: u32) -> Self {
+        Self {
+            _state: "running",
+            __ts_state: Running { pid },
+        }
+    }
+
+    // the state is kept as it is
+    #[require(Running)]
+    fn relabel(self, label: &'static str) -> Self {
+        Self { _state: label }
+    }
+
+    #[require(Running)]
+    fn pid(&self) -> u32 {
+        self.state_data().pid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_hidden_field_can_be_renamed() {
+        let process = Process::new();
+        assert_eq!(process._state, "stopped");
+
+        let process = process.start(42).relabel("busy");
+        assert_eq!(process._state, "busy");
+        assert_eq!(process.pid(), 42);
+    }
+}
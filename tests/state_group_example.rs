@@ -0,0 +1,70 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Idle, Running, Crashed),
+    slots = (Idle),
+    groups = (Alive = (Idle, Running))
+)]
+struct Process {
+    restarts: u8,
+}
+
+#[impl_state]
+impl Process {
+    #[require(Idle)]
+    fn new() -> Process {
+        Process { restarts: 0 }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn run(self) -> Process {
+        Process {
+            restarts: self.restarts,
+        }
+    }
+
+    #[require(Alive)] // can be called at any state of the `Alive` group
+    #[switch_to(Crashed)]
+    fn crash(self) -> Process {
+        Process {
+            restarts: self.restarts,
+        }
+    }
+
+    #[require(not(Alive))]
+    #[switch_to(Idle)]
+    fn restart(self) -> Process {
+        Process {
+            restarts: self.restarts + 1,
+        }
+    }
+
+    #[require(Alive | Crashed)]
+    fn restarts(&self) -> u8 {
+        self.restarts
+    }
+}
+
+// group traits can be used as bounds outside of the macros too
+fn is_alive<S: Alive>(_process: &Process<S>) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_groups_work() {
+        let process = Process::new();
+        assert!(is_alive(&process));
+
+        let process = process.crash().restart().run();
+        assert!(is_alive(&process));
+        assert_eq!(process.restarts(), 1);
+
+        let process = process.crash();
+        assert_eq!(process.restarts(), 1);
+    }
+}
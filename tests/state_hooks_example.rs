@@ -0,0 +1,239 @@
+use std::cell::RefCell;
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Disconnected, Connecting, Connected),
+    slots = (Disconnected)
+)]
+#[derive(Debug)]
+struct Client {
+    log: RefCell<Vec<&'static str>>,
+}
+
+#[impl_state]
+impl Client {
+    #[switch_to(Disconnected)]
+    fn new() -> Self {
+        Self {
+            log: RefCell::new(Vec::new()),
+        }
+    }
+
+    // the hooks are called by every transition that enters or leaves their states
+    #[on_enter(Disconnected)]
+    fn log_disconnect(&self) {
+        self.log.borrow_mut().push("enter disconnected");
+    }
+
+    #[on_enter(Connected)]
+    fn log_connect(&self) {
+        self.log.borrow_mut().push("enter connected");
+    }
+
+    #[on_exit(Connected)]
+    fn log_leave(&self) {
+        self.log.borrow_mut().push("exit connected");
+    }
+
+    #[require(Disconnected)]
+    #[switch_to(Connecting)]
+    fn dial(self) -> Self {
+        Self { log: self.log }
+    }
+
+    // the hooks of the `Err` arm are the ones of the state it is in
+    #[require(Connecting)]
+    #[switch_to(Connected, on_err = Disconnected)]
+    fn handshake(self, accepted: bool) -> Result<Self, Self> {
+        if !accepted {
+            return Err(Self { log: self.log });
+        }
+        Ok(Self { log: self.log })
+    }
+
+    // the states of a generic slot are told apart at runtime
+    #[require(A)]
+    #[switch_to(Disconnected)]
+    fn reset(self) -> Self {
+        Self { log: self.log }
+    }
+
+    #[require(A)]
+    fn entries(&self) -> Vec<&'static str> {
+        self.log.borrow().clone()
+    }
+}
+
+#[type_state(states = (In, Down, Up, Failed), slots = (In, Down))]
+#[derive(Debug)]
+struct Link {
+    log: RefCell<Vec<&'static str>>,
+}
+
+#[impl_state]
+impl Link {
+    #[switch_to(In, Down)]
+    fn new() -> Self {
+        Self {
+            log: RefCell::new(Vec::new()),
+        }
+    }
+
+    #[on_enter(Down)]
+    fn log_down(&self) {
+        self.log.borrow_mut().push("enter down");
+    }
+
+    #[on_exit(Down)]
+    fn log_leave_down(&self) {
+        self.log.borrow_mut().push("exit down");
+    }
+
+    #[on_enter(Up)]
+    fn log_up(&self) {
+        self.log.borrow_mut().push("enter up");
+    }
+
+    #[on_exit(Up)]
+    fn log_leave_up(&self) {
+        self.log.borrow_mut().push("exit up");
+    }
+
+    #[on_enter(Failed)]
+    fn log_failed(&self) {
+        self.log.borrow_mut().push("enter failed");
+    }
+
+    // the branching transitions enter the state of the variant they return
+    #[require(In, Down)]
+    #[switch_to(In, Up | Failed)]
+    fn connect(self, online: bool) -> Self {
+        if online {
+            ConnectOutcome::Up(Self { log: self.log })
+        } else {
+            ConnectOutcome::Failed(Self { log: self.log })
+        }
+    }
+
+    // the exit hooks are called as the transition starts, so a guarded transition that fails
+    // leaves its state and enters the one of `on_err`, even if it is the same state
+    #[require(In, Down)]
+    #[switch_to(_, Up, on_err = Down)]
+    fn dial(self, online: bool) -> Result<Self, (Self, String)> {
+        if !online {
+            return Err((Self { log: self.log }, "offline".to_string()));
+        }
+        Ok(Self { log: self.log })
+    }
+
+    // the cross-type transitions leave the states of the struct (the hooks of the other struct are its own)
+    #[require(In, Up)]
+    #[switch_to(Client<Connected>)]
+    fn into_client(self) -> Client {
+        Client { log: self.log }
+    }
+
+    #[require(A, B)]
+    fn entries(&self) -> Vec<&'static str> {
+        self.log.borrow().clone()
+    }
+}
+
+fn main() {
+    let client = Client::new().dial().handshake(true).unwrap().reset();
+    println!("{:?}", client.entries());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constructor_enters_its_state() {
+        let client = Client::new();
+        assert_eq!(client.entries(), vec!["enter disconnected"]);
+    }
+
+    #[test]
+    fn test_transitions_call_the_hooks() {
+        let client = Client::new().dial().handshake(true).unwrap().reset();
+        assert_eq!(
+            client.entries(),
+            vec![
+                "enter disconnected",
+                "enter connected",
+                "exit connected",
+                "enter disconnected"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_err_arm_calls_the_hooks_of_its_state() {
+        let client = Client::new().dial().handshake(false).unwrap_err();
+        assert_eq!(
+            client.entries(),
+            vec!["enter disconnected", "enter disconnected"]
+        );
+    }
+
+    #[test]
+    fn test_generic_state_without_hooks() {
+        let client = Client::new().dial().reset();
+        assert_eq!(
+            client.entries(),
+            vec!["enter disconnected", "enter disconnected"]
+        );
+    }
+
+    #[test]
+    fn test_branching_transition_calls_the_hooks_of_its_variant() {
+        match Link::new().connect(true) {
+            ConnectOutcome::Up(link) => {
+                assert_eq!(link.entries(), vec!["enter down", "exit down", "enter up"])
+            }
+            ConnectOutcome::Failed(_) => panic!("should be up"),
+        }
+        match Link::new().connect(false) {
+            ConnectOutcome::Up(_) => panic!("should have failed"),
+            ConnectOutcome::Failed(link) => {
+                assert_eq!(
+                    link.entries(),
+                    vec!["enter down", "exit down", "enter failed"]
+                )
+            }
+        }
+    }
+
+    #[test]
+    fn test_failed_guarded_transition_enters_its_state_again() {
+        let (link, reason) = Link::new().dial(false).unwrap_err();
+        assert_eq!(reason, "offline");
+        assert_eq!(
+            link.entries(),
+            vec!["enter down", "exit down", "enter down"]
+        );
+
+        let link = link.dial(true).unwrap();
+        assert_eq!(
+            link.entries(),
+            vec![
+                "enter down",
+                "exit down",
+                "enter down",
+                "exit down",
+                "enter up"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cross_type_transition_leaves_the_states() {
+        let client = Link::new().dial(true).unwrap().into_client();
+        assert_eq!(
+            client.entries(),
+            vec!["enter down", "exit down", "enter up", "exit up"]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use state_shift::state_machine;
+
+// the struct named after the module is the machine, and its `impl` blocks get `#[impl_state]`
+#[state_machine(states = (Idle, Running, Paused), slots = (Idle))]
+mod player {
+    use std::fmt;
+
+    pub struct Player {
+        pub level: u8,
+    }
+
+    // the other items of the module are left as they are
+    pub struct Score(pub u32);
+
+    impl Player {
+        #[switch_to(Idle)]
+        pub fn new(level: u8) -> Self {
+            Self { level }
+        }
+
+        #[require(Idle)]
+        #[switch_to(Running)]
+        pub fn start(self) -> Self {
+            Self { level: self.level }
+        }
+
+        #[require(Running)]
+        #[switch_to(Paused)]
+        pub fn pause(self) -> Self {
+            Self { level: self.level }
+        }
+
+        #[require(A)]
+        pub fn score(&self) -> Score {
+            Score(self.level as u32 * 10)
+        }
+    }
+
+    #[require(Paused)]
+    impl fmt::Display for Player {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "paused at level {}", self.level)
+        }
+    }
+}
+
+// the only struct of the module is the machine too
+#[state_machine(states = (Off, On), slots = (Off))]
+mod lights {
+
+    pub struct Lamp;
+
+    impl Lamp {
+        #[switch_to(Off)]
+        pub fn new() -> Self {
+            Self
+        }
+
+        #[require(Off)]
+        #[switch_to(On)]
+        pub fn turn_on(self) -> Self {
+            Self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lights::{Lamp, On};
+    use super::player::{Paused, Player};
+
+    #[test]
+    fn module_is_a_state_machine() {
+        let player: Player<Paused> = Player::new(2).start().pause();
+        assert_eq!(player.score().0, 20);
+        assert_eq!(player.to_string(), "paused at level 2");
+
+        let lamp: Lamp<On> = Lamp::new().turn_on();
+        assert_eq!(lamp.state_name(), "On");
+    }
+}
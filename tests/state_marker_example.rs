@@ -0,0 +1,53 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running, Paused), slots = (Idle))]
+struct Player {
+    level: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new(level: u8) -> Self {
+        Self { level }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { level: self.level }
+    }
+}
+
+// the code outside of `#[impl_state]` can be generic over any state of the struct
+fn describe<S: PlayerStateMarker>(player: &Player<S>) -> String {
+    format!("level {} player, {}", player.level, S::NAME)
+}
+
+fn names<S: PlayerStateMarker, T: PlayerStateMarker>() -> [&'static str; 2] {
+    [S::NAME, T::NAME]
+}
+
+// the states are named without the prefix
+#[type_state(states = (Open, Closed), slots = (Open), prefix = "Door")]
+struct Door;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic_code_tells_the_state() {
+        let player = Player::new(4);
+        assert_eq!(describe(&player), "level 4 player, Idle");
+        let player = player.start();
+        assert_eq!(describe(&player), "level 4 player, Running");
+
+        assert_eq!(names::<Paused, Idle>(), ["Paused", "Idle"]);
+    }
+
+    #[test]
+    fn prefixed_states_are_named_without_the_prefix() {
+        assert_eq!(<DoorClosed as DoorStateMarker>::NAME, "Closed");
+    }
+}
@@ -0,0 +1,91 @@
+use state_shift::{impl_state, type_state};
+
+// the states can be told by their names, for the logs and the error messages
+#[type_state(states = (Loading, Ready, Playing), slots = (Loading), prefix = "Player")]
+struct Player {
+    track: String,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Loading)]
+    fn new(track: &str) -> Self {
+        Self {
+            track: track.to_string(),
+        }
+    }
+
+    #[require(Loading)]
+    #[switch_to(Ready)]
+    fn load(self) -> Self {
+        Self { track: self.track }
+    }
+
+    #[require(Ready)]
+    #[switch_to(Playing)]
+    fn play(self) -> Self {
+        Self { track: self.track }
+    }
+
+    // the name of the state is known in the methods that accept any state too
+    #[require(A)]
+    fn describe(&self) -> String {
+        format!("{} ({})", self.track, self.state_name())
+    }
+}
+
+// each slot tells its own state
+#[type_state(
+    states = (LoggedOut, LoggedIn, Closed, Open),
+    slots = (auth = LoggedOut, conn = Closed)
+)]
+struct Session {}
+
+#[impl_state]
+impl Session {
+    #[switch_to(_, _)]
+    fn new() -> Self {
+        Self {}
+    }
+
+    #[require(auth = LoggedOut)]
+    #[switch_to(auth = LoggedIn)]
+    fn log_in(self) -> Self {
+        Self {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers_have_their_names() {
+        // the names are the declared ones, without the prefix
+        assert_eq!(PlayerLoading::STATE_NAME, "Loading");
+        assert_eq!(<PlayerPlaying as SealerPlayer>::STATE_NAME, "Playing");
+    }
+
+    #[test]
+    fn markers_are_displayed_by_their_names() {
+        assert_eq!(format!("entered {}", PlayerReady), "entered Ready");
+        assert_eq!(LoggedIn.to_string(), "LoggedIn");
+    }
+
+    #[test]
+    fn struct_tells_its_state() {
+        let player = Player::new("intro");
+        assert_eq!(player.state_name(), "Loading");
+        let player = player.load().play();
+        assert_eq!(player.state_name(), "Playing");
+        assert_eq!(player.describe(), "intro (Playing)");
+    }
+
+    #[test]
+    fn slots_tell_their_states() {
+        let session: Session = Session::new();
+        let session = session.log_in();
+        assert_eq!(session.auth_state_name(), "LoggedIn");
+        assert_eq!(session.conn_state_name(), "Closed");
+    }
+}
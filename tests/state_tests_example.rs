@@ -0,0 +1,100 @@
+use state_shift::{generate_state_tests, impl_state, type_state};
+
+#[type_state(states = (Idle, Running, Paused), slots = (Idle))]
+pub struct Player {
+    speed: u8,
+}
+
+#[impl_state(transitions)]
+impl Player {
+    #[switch_to(Idle)]
+    pub fn new() -> Self {
+        Self { speed: 0 }
+    }
+
+    #[require(Idle | Paused)]
+    #[switch_to(Running)]
+    pub fn start(self, speed: u8) -> Self {
+        Self { speed }
+    }
+
+    #[require(Running)]
+    #[switch_to(Paused)]
+    pub fn pause(self) -> Self {
+        Self { speed: self.speed }
+    }
+
+    #[require(A)]
+    pub fn speed(&self) -> u8 {
+        self.speed
+    }
+
+    // the private methods cannot be called by the tests, so they are not tested
+    #[require(Paused)]
+    #[allow(dead_code)]
+    fn rewind(&mut self) {
+        self.speed = 0;
+    }
+}
+
+// the doc tests of `player_state_tests` are run by `cargo test --doc` in a library crate
+generate_state_tests!(Player);
+
+fn main() {
+    let player = Player::new().start(3).pause();
+    println!("{}", player.speed());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_methods_are_called_in_the_other_states() {
+        assert_eq!(
+            player_state_tests::COMPILE_FAIL,
+            &[
+                "fn start_in_running(mut value: state_tests_example::Player<state_tests_example::Running>) {\n    let _ = value.start(todo!());\n}",
+                "fn pause_in_idle(mut value: state_tests_example::Player<state_tests_example::Idle>) {\n    let _ = value.pause();\n}",
+                "fn pause_in_paused(mut value: state_tests_example::Player<state_tests_example::Paused>) {\n    let _ = value.pause();\n}",
+            ]
+        );
+    }
+    // the sources are compiled by `trybuild` as well, each along with this file, whose items are re-exported
+    // from the root of a crate that is named like this one, so that the paths of the sources resolve
+    #[test]
+    fn compile_fail_sources_fail_to_compile() {
+        let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("state_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let example = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/state_tests_example.rs");
+
+        // the outputs of the compiler are written next to the sources, instead of being compared with the `.stderr` files,
+        // and they are checked below, so that the sources don't fail because of a wrong path
+        std::env::set_var("TRYBUILD", "overwrite");
+        let cases = trybuild::TestCases::new();
+        let mut paths = Vec::new();
+        for (index, source) in player_state_tests::COMPILE_FAIL.iter().enumerate() {
+            let path = dir.join(format!("case_{}.rs", index));
+            let _ = std::fs::remove_file(path.with_extension("stderr"));
+            let case = format!(
+                "#![allow(dead_code, unused, unreachable_code)]\n\
+                 extern crate self as state_tests_example;\n\
+                 #[path = {:?}]\n\
+                 mod example;\n\
+                 pub use example::*;\n\n\
+                 {}\n\n\
+                 fn main() {{}}\n",
+                example, source
+            );
+            std::fs::write(&path, case).unwrap();
+            cases.compile_fail(&path);
+            paths.push(path);
+        }
+        drop(cases);
+
+        for path in paths {
+            let stderr = std::fs::read_to_string(path.with_extension("stderr")).unwrap();
+            assert!(stderr.contains("no method named"), "{}", stderr);
+        }
+    }
+}
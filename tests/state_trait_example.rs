@@ -0,0 +1,121 @@
+use state_shift::{impl_state, type_state};
+
+// a type-state protocol, which is generic over the states, so that each implementor can plug in its own markers
+#[impl_state]
+trait Engine<Off, On> {
+    #[require(Off)]
+    #[switch_to(On)]
+    fn start(self) -> Self;
+
+    #[require(On)]
+    #[switch_to(Off)]
+    fn stop(self) -> Self;
+
+    #[require(On)]
+    fn rpm(&self) -> u32;
+}
+
+mod car {
+    use super::*;
+
+    #[type_state(states = (Off, On), slots = (Off))]
+    pub struct Car {
+        rpm: u32,
+    }
+
+    #[impl_state]
+    impl Car {
+        #[require(Off)]
+        pub fn new() -> Car {
+            Car { rpm: 0 }
+        }
+    }
+
+    #[impl_state]
+    impl Engine<Off, On> for Car {
+        #[require(Off)]
+        #[switch_to(On)]
+        fn start(self) -> Self {
+            Car { rpm: 800 }
+        }
+
+        #[require(On)]
+        #[switch_to(Off)]
+        fn stop(self) -> Self {
+            Car { rpm: 0 }
+        }
+
+        #[require(On)]
+        fn rpm(&self) -> u32 {
+            self.rpm
+        }
+    }
+}
+
+mod boat {
+    use super::*;
+
+    #[type_state(states = (Docked, Sailing), slots = (Docked))]
+    pub struct Boat {
+        knots: u32,
+    }
+
+    #[impl_state]
+    impl Boat {
+        #[require(Docked)]
+        pub fn new() -> Boat {
+            Boat { knots: 0 }
+        }
+    }
+
+    #[impl_state]
+    impl Engine<Docked, Sailing> for Boat {
+        #[require(Docked)]
+        #[switch_to(Sailing)]
+        fn start(self) -> Self {
+            Boat { knots: 12 }
+        }
+
+        #[require(Sailing)]
+        #[switch_to(Docked)]
+        fn stop(self) -> Self {
+            Boat { knots: 0 }
+        }
+
+        #[require(Sailing)]
+        fn rpm(&self) -> u32 {
+            self.knots * 100
+        }
+    }
+}
+
+// code that is generic over all the implementors of the protocol
+fn warm_up<Off, On, E>(engine: E) -> u32
+where
+    E: Engine<Off, On, State = Off>,
+    E::On: Engine<Off, On, State = On>,
+{
+    engine.start().rpm()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_state_protocols_work() {
+        let car = car::Car::new().start();
+        assert_eq!(car.rpm(), 800);
+        let _car: car::Car<car::Off> = car.stop();
+
+        let boat = boat::Boat::new().start();
+        assert_eq!(boat.rpm(), 1200);
+        let _boat: boat::Boat<boat::Docked> = boat.stop();
+    }
+
+    #[test]
+    fn generic_protocol_code_works() {
+        assert_eq!(warm_up(car::Car::new()), 800);
+        assert_eq!(warm_up(boat::Boat::new()), 1200);
+    }
+}
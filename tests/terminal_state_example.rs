@@ -0,0 +1,61 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Open, Closing, Closed),
+    slots = (Open),
+    terminal = (Closed)
+)]
+struct Connection {
+    sent: u32,
+}
+
+#[impl_state]
+impl Connection {
+    #[switch_to(Open)]
+    fn new() -> Self {
+        Self { sent: 0 }
+    }
+
+    #[require(Open)]
+    fn send(self) -> Self {
+        Self {
+            sent: self.sent + 1,
+        }
+    }
+
+    #[require(Open)]
+    #[switch_to(Closing)]
+    fn close(self) -> Self {
+        Self { sent: self.sent }
+    }
+
+    #[require(Closing)]
+    #[switch_to(Closed)]
+    fn finish(self) -> Self {
+        Self { sent: self.sent }
+    }
+
+    // the terminal states can still be read, and kept as they are
+    #[require(Closed)]
+    #[switch_to(Closed)]
+    fn reset_counter(self) -> Self {
+        Self { sent: 0 }
+    }
+
+    #[require(A)]
+    fn sent(&self) -> u32 {
+        self.sent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_terminal_states_are_reached() {
+        let connection: Connection<Closed> = Connection::new().send().send().close().finish();
+        assert_eq!(connection.sent(), 2);
+        assert_eq!(connection.reset_counter().sent(), 0);
+    }
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use state_shift::{impl_state, type_state};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+#[type_state(states = (Idle, Running, Paused), slots = (Idle))]
+struct Player {
+    name: String,
+}
+
+#[impl_state]
+impl Player {
+    #[switch_to(Idle)]
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { name: self.name }
+    }
+
+    #[require(Running)]
+    #[switch_to(Paused)]
+    fn pause(self) -> Self {
+        Self { name: self.name }
+    }
+
+    // the generic states are recorded with the state they are in at runtime
+    #[require(A)]
+    #[switch_to(Idle)]
+    fn stop(self) -> Self {
+        Self { name: self.name }
+    }
+}
+
+/// Records the fields of the spans, as `key=value` strings
+#[derive(Clone, Default)]
+struct SpanRecorder {
+    spans: Arc<Mutex<Vec<String>>>,
+}
+
+struct FieldVisitor(Vec<String>);
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push(format!("{}={}", field.name(), value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push(format!("{}={:?}", field.name(), value));
+    }
+}
+
+impl Subscriber for SpanRecorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut visitor = FieldVisitor(Vec::new());
+        span.record(&mut visitor);
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(format!(
+            "{} {}",
+            span.metadata().name(),
+            visitor.0.join(" ")
+        ));
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn main() {
+    let player = Player::new("Alice").start().pause().stop();
+    println!("{}", player.name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions_are_traced() {
+        let recorder = SpanRecorder::default();
+        let spans = recorder.spans.clone();
+        let player = tracing::subscriber::with_default(recorder, || {
+            Player::new("Alice").start().pause().stop()
+        });
+        assert_eq!(player.name, "Alice");
+
+        assert_eq!(
+            *spans.lock().unwrap(),
+            vec![
+                "transition struct=Player method=start from=Idle to=Running",
+                "transition struct=Player method=pause from=Running to=Paused",
+                "transition struct=Player method=stop from=Paused to=Idle",
+            ]
+        );
+    }
+}
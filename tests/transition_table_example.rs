@@ -0,0 +1,109 @@
+use state_shift::{impl_state, type_state};
+
+// the transitions of an `impl` block can be inspected at runtime: `document_transitions::TRANSITIONS`
+#[type_state(
+    states = (Draft, Review, Published, Archived),
+    slots = (Draft),
+    groups = (Visible = (Review, Published))
+)]
+struct Document {
+    title: String,
+}
+
+#[impl_state(transitions)]
+impl Document {
+    #[switch_to(Draft)]
+    fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+        }
+    }
+
+    #[require(Draft)]
+    #[switch_to(Review)]
+    fn submit(self) -> Self {
+        Self { title: self.title }
+    }
+
+    #[require(Review)]
+    #[switch_to(Published | Draft)]
+    fn decide(self, approved: bool) -> DecideOutcome {
+        if approved {
+            DecideOutcome::Published(Document { title: self.title })
+        } else {
+            DecideOutcome::Draft(Document { title: self.title })
+        }
+    }
+
+    #[require(Visible)]
+    #[switch_to(Archived)]
+    fn archive(self) -> Self {
+        Self { title: self.title }
+    }
+
+    #[cfg(any())]
+    #[require(Archived)]
+    #[switch_to(Draft)]
+    fn restore(self) -> Self {
+        Self { title: self.title }
+    }
+
+    #[require(A)]
+    fn title(&self) -> &str {
+        &self.title
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use document_transitions::{Transition, TRANSITIONS};
+
+    #[test]
+    fn transitions_are_listed_in_the_order_of_the_methods() {
+        let methods: Vec<_> = TRANSITIONS
+            .iter()
+            .map(|transition| transition.method)
+            .collect();
+        assert_eq!(methods, ["new", "submit", "decide", "archive", "title"]);
+    }
+
+    #[test]
+    fn transitions_have_their_states() {
+        assert_eq!(
+            TRANSITIONS[0],
+            Transition {
+                method: "new",
+                slot: 0,
+                from: &[],
+                to: &["Draft"],
+            }
+        );
+        assert_eq!(TRANSITIONS[2].from, ["Review"]);
+        assert_eq!(TRANSITIONS[2].to, ["Published", "Draft"]);
+        // the groups stand for their states
+        assert_eq!(TRANSITIONS[3].from, ["Review", "Published"]);
+        // the methods without `#[switch_to]` keep the states
+        assert_eq!(
+            TRANSITIONS[4].from,
+            ["Draft", "Review", "Published", "Archived"]
+        );
+        assert!(TRANSITIONS[4].to.is_empty());
+    }
+
+    #[test]
+    fn transitions_match_the_methods() {
+        let document = Document::new("notes").submit();
+        assert!(
+            TRANSITIONS
+                .iter()
+                .any(|transition| transition.method == "decide"
+                    && transition.from.contains(&"Review"))
+        );
+        let document = match document.decide(false) {
+            DecideOutcome::Draft(document) => document,
+            DecideOutcome::Published(_) => panic!("the document should be a draft"),
+        };
+        assert_eq!(document.title(), "notes");
+    }
+}
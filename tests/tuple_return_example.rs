@@ -0,0 +1,75 @@
+use state_shift::{impl_state, type_state};
+
+#[derive(Debug, PartialEq)]
+struct Report {
+    tokens: usize,
+}
+
+#[type_state(states = (Raw, Parsed), slots = (Raw))]
+struct Parser {
+    source: String,
+}
+
+#[impl_state]
+impl Parser {
+    #[require(Raw)]
+    fn new(source: &str) -> Parser {
+        Parser {
+            source: source.to_string(),
+        }
+    }
+
+    #[require(Raw)]
+    #[switch_to(Parsed)]
+    fn parse(self) -> (Self, Report) {
+        let report = Report {
+            tokens: self.source.split_whitespace().count(),
+        };
+        let parsed = Parser {
+            source: self.source,
+        };
+
+        (parsed, report)
+    }
+
+    #[require(Raw)]
+    #[switch_to(Parsed)]
+    fn parse_and_keep(self) -> (Self, Self) {
+        // only the first element is transitioned, the second one is still the `Raw` parser
+        (
+            Self {
+                source: self.source.clone(),
+            },
+            self,
+        )
+    }
+
+    #[require(Raw)]
+    fn source(&self) -> &str {
+        &self.source
+    }
+
+    #[require(Parsed)]
+    fn tokens(&self) -> usize {
+        self.source.split_whitespace().count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuple_returns_work() {
+        let (parser, report) = Parser::new("let x = 1").parse();
+        assert_eq!(report, Report { tokens: 4 });
+        assert_eq!(parser.tokens(), 4);
+    }
+
+    #[test]
+    fn only_the_first_element_is_transitioned() {
+        let (parsed, raw) = Parser::new("a b").parse_and_keep();
+        assert_eq!(parsed.tokens(), 2);
+        assert_eq!(raw.source(), "a b");
+    }
+}
@@ -0,0 +1,80 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Raw, Validated), slots = (Raw))]
+#[derive(Debug)]
+struct Meters(f64);
+
+#[impl_state]
+impl Meters {
+    #[switch_to(Raw)]
+    fn new(value: f64) -> Self {
+        Self(value)
+    }
+
+    #[require(Raw)]
+    #[switch_to(Validated)]
+    fn validate(self) -> Meters {
+        Meters(self.0.max(0.0))
+    }
+
+    #[require(A)]
+    fn value(&self) -> f64 {
+        self.0
+    }
+}
+
+#[type_state(
+    states = (Empty, Filled { count: usize }),
+    slots = (Empty)
+)]
+struct Basket(String, Vec<String>);
+
+#[impl_state]
+impl Basket {
+    #[switch_to(Empty)]
+    fn new(owner: &str) -> Self {
+        Self(owner.to_string(), Vec::new())
+    }
+
+    // the data of the state is given as the last element
+    #[require(Empty)]
+    #[switch_to(Filled)]
+    fn fill(self, items: &[&str]) -> Self {
+        let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+        let count = items.len();
+        Self(self.0, items, Filled { count })
+    }
+
+    // the state is kept as it is
+    #[require(Filled)]
+    fn rename(self, owner: &str) -> Self {
+        Self(owner.to_string(), self.1)
+    }
+
+    #[require(Filled)]
+    fn count(&self) -> usize {
+        self.state_data().count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tuple_structs_work() {
+        let meters: Meters<Raw> = Meters::new(-2.5);
+        assert_eq!(meters.value(), -2.5);
+
+        let meters: Meters<Validated> = meters.validate();
+        assert_eq!(meters.value(), 0.0);
+    }
+
+    #[test]
+    fn the_state_is_the_last_element() {
+        let basket = Basket::new("ada").fill(&["apple", "pear"]).rename("grace");
+        assert_eq!(basket.0, "grace");
+        assert_eq!(basket.1, ["apple", "pear"]);
+        assert_eq!(basket.count(), 2);
+    }
+}
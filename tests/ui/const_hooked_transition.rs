@@ -0,0 +1,22 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[on_enter(Open)]
+    fn log_open(&self) {}
+
+    #[require(Closed)]
+    #[switch_to(Open)]
+    const fn open(self) -> Door {
+        Door {
+            opened: self.opened + 1,
+        }
+    }
+}
+
+fn main() {}
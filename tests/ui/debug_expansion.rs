@@ -0,0 +1,19 @@
+#![deny(deprecated)]
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state(debug)]
+impl Door {
+    // a method without a transition, whose expansion is the same with the `tracing` feature
+    #[require(Closed)]
+    fn opened(&self) -> u32 {
+        self.opened
+    }
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open, Locked), slots = (Closed), deny(unreachable))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[require(Closed)]
+    fn new() -> Door {
+        Door { opened: 0 }
+    }
+
+    #[require(Closed)]
+    #[switch_to(Open)]
+    fn open(self) -> Door {
+        Door {
+            opened: self.opened + 1,
+        }
+    }
+
+    #[require(Open)]
+    #[switch_to(Closed)]
+    fn close(self) -> Door {
+        Door {
+            opened: self.opened,
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use state_shift::state_shift;
+
+state_shift! {
+    machine Door {
+        states { Closed, Open }
+        transitions {
+            toggle: Closed -> Open,
+            // a transition from several states is declared once: `toggle: Closed | Open -> ...`
+            toggle: Open -> Closed,
+        }
+    }
+}
+
+fn main() {}
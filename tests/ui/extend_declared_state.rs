@@ -0,0 +1,17 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running), slots = (Idle))]
+struct Player {
+    track: u8,
+}
+
+#[impl_state(extend = (Maintenance, Running))]
+impl Player {
+    #[require(Running)]
+    #[switch_to(Maintenance)]
+    fn service(self) -> Player {
+        Player { track: self.track }
+    }
+}
+
+fn main() {}
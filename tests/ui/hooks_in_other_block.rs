@@ -0,0 +1,34 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[on_enter(Open)]
+    fn log_open(&self) {}
+
+    #[require(Open)]
+    #[switch_to(Closed)]
+    fn close(self) -> Door {
+        Door {
+            opened: self.opened,
+        }
+    }
+}
+
+// the transitions of this block cannot call the hooks of the other one
+#[impl_state(part)]
+impl Door {
+    #[require(Closed)]
+    #[switch_to(Open)]
+    fn open(self) -> Door {
+        Door {
+            opened: self.opened + 1,
+        }
+    }
+}
+
+fn main() {}
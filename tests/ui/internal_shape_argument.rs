@@ -0,0 +1,7 @@
+use state_shift::type_state;
+
+// the shape of the struct is told by `#[type_state]` itself, it is not an argument of the users
+#[type_state(states = (Closed, Open), slots = (Closed), shape = unit)]
+struct Door;
+
+fn main() {}
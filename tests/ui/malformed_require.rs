@@ -0,0 +1,22 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[require(Closed)]
+    fn new() -> Door {
+        Door { opened: 0 }
+    }
+
+    // a comma is missing
+    #[require(Closed Open)]
+    fn opened(&self) -> u32 {
+        self.opened
+    }
+}
+
+fn main() {}
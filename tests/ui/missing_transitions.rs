@@ -0,0 +1,17 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running), slots = (Idle), declared_transitions = true)]
+struct Player {
+    level: u8,
+}
+
+#[impl_state]
+impl Player {
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Player {
+        Player { level: self.level }
+    }
+}
+
+fn main() {}
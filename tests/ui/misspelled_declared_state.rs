@@ -0,0 +1,19 @@
+use state_shift::{impl_state, transitions, type_state};
+
+#[type_state(states = (Idle, Running), slots = (Idle), declared_transitions = true)]
+struct Player {
+    level: u8,
+}
+
+transitions!(Player: Idle -> Runing via start);
+
+#[impl_state]
+impl Player {
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Player {
+        Player { level: self.level }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (LoggedOut, LoggedIn, Disconnected, Connected),
+    slots = (auth = LoggedOut, conn = Disconnected)
+)]
+struct Session {
+    user: Option<String>,
+}
+
+#[impl_state]
+impl Session {
+    #[require(auth = LoggedOut, conn = Disconnected)]
+    fn new() -> Session {
+        Session { user: None }
+    }
+
+    // the 2nd argument is positional
+    #[require(auth = LoggedOut, Disconnected)]
+    #[switch_to(auth = LoggedIn)]
+    fn log_in(self, user: &str) -> Session {
+        Session {
+            user: Some(user.to_string()),
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use state_shift::type_state;
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Door {
+    #[only_in(Opne)]
+    draft: u32,
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[require(Closed)]
+    fn new() -> Door {
+        Door { opened: 0 }
+    }
+
+    #[require(Locked)]
+    fn opened(&self) -> u32 {
+        self.opened
+    }
+}
+
+// the method with the error is still there, so it is the only error
+fn main() {
+    let door = Door::new();
+    println!("{}", door.opened());
+}
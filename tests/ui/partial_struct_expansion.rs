@@ -0,0 +1,20 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open), slots = (Closed), terminal = (Broken))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[require(Closed)]
+    fn new() -> Door {
+        Door { opened: 0 }
+    }
+}
+
+// the struct with the error is still there, and its `impl` blocks are left out, so it is the only error
+fn main() {
+    let door = Door { opened: 0 };
+    println!("{}", door.opened);
+}
@@ -0,0 +1,25 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Raw, Validated, Unsaved, Saved), slots = (Raw, Unsaved))]
+struct Document {
+    text: String,
+}
+
+#[impl_state]
+impl Document {
+    #[require(Raw, Unsaved)]
+    fn new(text: &str) -> Document {
+        Document {
+            text: text.to_string(),
+        }
+    }
+
+    // one argument for the two slots
+    #[require(Raw)]
+    #[switch_to(Validated, Unsaved)]
+    fn validate(self) -> Document {
+        Document { text: self.text }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use state_shift::require;
+
+struct Door {
+    opened: u32,
+}
+
+// not in an `impl` block with `#[impl_state]`
+#[require(Closed)]
+fn opened(door: &Door) -> u32 {
+    door.opened
+}
+
+// the function with the error is still there, so it is the only error
+fn main() {
+    let door = Door { opened: 0 };
+    println!("{}", opened(&door));
+}
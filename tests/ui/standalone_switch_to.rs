@@ -0,0 +1,19 @@
+use state_shift::switch_to;
+
+struct Door {
+    opened: u32,
+}
+
+// the `impl` block has no `#[impl_state]`
+impl Door {
+    #[switch_to(Closed)]
+    fn new() -> Door {
+        Door { opened: 0 }
+    }
+}
+
+// the method with the error is still there, so it is the only error
+fn main() {
+    let door = Door::new();
+    println!("{}", door.opened);
+}
@@ -0,0 +1,29 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (Raw, Validated, Unsaved, Saved),
+    slots = (Raw, Unsaved),
+    slot_states = ((Raw, Validated), (Unsaved, Saved))
+)]
+struct Document {
+    text: String,
+}
+
+#[impl_state]
+impl Document {
+    #[require(Raw, Unsaved)]
+    fn new(text: &str) -> Document {
+        Document {
+            text: text.to_string(),
+        }
+    }
+
+    // `Saved` is a state of the 2nd slot
+    #[require(Raw, Unsaved)]
+    #[switch_to(Saved, Saved)]
+    fn save(self) -> Document {
+        Document { text: self.text }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[require(Closed)]
+    fn new() -> Door {
+        Door { opened: 0 }
+    }
+
+    // two letters swapped
+    #[require(Opne)]
+    #[switch_to(Closed)]
+    fn close(self) -> Door {
+        Door {
+            opened: self.opened,
+        }
+    }
+
+    // a letter missing
+    #[require(Closed)]
+    #[switch_to(Closd)]
+    fn lock(self) -> Door {
+        Door {
+            opened: self.opened,
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Open, Closed), slots = (Open), terminal = (Closed))]
+struct Connection {
+    host: String,
+}
+
+#[impl_state]
+impl Connection {
+    #[require(Open)]
+    #[switch_to(Closed)]
+    fn close(self) -> Connection {
+        Connection { host: self.host }
+    }
+
+    #[require(Closed)]
+    #[switch_to(Open)]
+    fn reopen(self) -> Connection {
+        Connection { host: self.host }
+    }
+}
+
+fn main() {}
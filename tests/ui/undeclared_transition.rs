@@ -0,0 +1,25 @@
+use state_shift::{impl_state, transitions, type_state};
+
+#[type_state(states = (Idle, Running, Paused), slots = (Idle), declared_transitions = true)]
+struct Player {
+    level: u8,
+}
+
+transitions!(Player: Idle -> Running via start);
+
+#[impl_state]
+impl Player {
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Player {
+        Player { level: self.level }
+    }
+
+    #[require(Running)]
+    #[switch_to(Paused)]
+    fn pause(self) -> Player {
+        Player { level: self.level }
+    }
+}
+
+fn main() {}
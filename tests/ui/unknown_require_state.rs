@@ -0,0 +1,22 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[require(Closed)]
+    fn new() -> Door {
+        Door { opened: 0 }
+    }
+
+    // not declared, and not close to any declared state
+    #[require(Locked)]
+    fn unlock(&self) -> u32 {
+        self.opened
+    }
+}
+
+fn main() {}
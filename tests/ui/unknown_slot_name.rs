@@ -0,0 +1,27 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(
+    states = (LoggedOut, LoggedIn, Disconnected, Connected),
+    slots = (auth = LoggedOut, conn = Disconnected)
+)]
+struct Session {
+    user: Option<String>,
+}
+
+#[impl_state]
+impl Session {
+    #[require(auth = LoggedOut, conn = Disconnected)]
+    fn new() -> Session {
+        Session { user: None }
+    }
+
+    #[require(auth = LoggedOut)]
+    #[switch_to(login = LoggedIn)]
+    fn log_in(self, user: &str) -> Session {
+        Session {
+            user: Some(user.to_string()),
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open), slots = (Closed))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[require(Closed)]
+    fn new() -> Door {
+        Door { opened: 0 }
+    }
+
+    // not declared, and not close to any declared state
+    #[require(Closed)]
+    #[switch_to(Broken)]
+    fn kick(self) -> Door {
+        Door {
+            opened: self.opened,
+        }
+    }
+}
+
+fn main() {}
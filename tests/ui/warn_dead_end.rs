@@ -0,0 +1,42 @@
+#![deny(deprecated)]
+
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Closed, Open, Broken), slots = (Closed), warn(dead_end))]
+struct Door {
+    opened: u32,
+}
+
+#[impl_state]
+impl Door {
+    #[require(Closed)]
+    fn new() -> Door {
+        Door { opened: 0 }
+    }
+
+    #[require(Closed)]
+    #[switch_to(Open)]
+    fn open(self) -> Door {
+        Door {
+            opened: self.opened + 1,
+        }
+    }
+
+    #[require(Open)]
+    #[switch_to(Closed)]
+    fn close(self) -> Door {
+        Door {
+            opened: self.opened,
+        }
+    }
+
+    #[require(Open)]
+    #[switch_to(Broken)]
+    fn kick(self) -> Door {
+        Door {
+            opened: self.opened,
+        }
+    }
+}
+
+fn main() {}
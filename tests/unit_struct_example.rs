@@ -0,0 +1,45 @@
+use state_shift::{impl_state, type_state};
+
+// a protocol token that carries no data at all, only its state
+#[type_state(states = (Unauthenticated, Authenticated, Revoked), slots = (Unauthenticated))]
+#[derive(Debug)]
+struct Token;
+
+#[impl_state]
+impl Token {
+    #[switch_to(Unauthenticated)]
+    fn new() -> Self {
+        Self
+    }
+
+    #[require(Unauthenticated)]
+    #[switch_to(Authenticated)]
+    fn authenticate(self) -> Token {
+        Token
+    }
+
+    #[require(Authenticated)]
+    #[switch_to(Revoked)]
+    fn revoke(self) -> Self {
+        Self
+    }
+
+    #[require(Authenticated)]
+    fn is_authenticated(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_structs_work() {
+        let token: Token<Authenticated> = Token::new().authenticate();
+        assert!(token.is_authenticated());
+
+        let _token: Token<Revoked> = token.revoke();
+        assert_eq!(std::mem::size_of::<Token<Revoked>>(), 0);
+    }
+}
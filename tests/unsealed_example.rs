@@ -0,0 +1,55 @@
+use state_shift::{impl_state, type_state};
+
+#[type_state(states = (Idle, Running), slots = (Idle), sealed = false)]
+struct Job {
+    name: String,
+}
+
+#[impl_state]
+impl Job {
+    #[switch_to(Idle)]
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+
+    #[require(Idle)]
+    #[switch_to(Running)]
+    fn start(self) -> Self {
+        Self { name: self.name }
+    }
+
+    // any state, including the ones implemented outside of `#[type_state]`
+    #[require(Idle)]
+    #[switch_to(S)]
+    fn into_state<S: SealerJob>(self) -> Self {
+        Self { name: self.name }
+    }
+
+    #[require(A)]
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+// a state for the tests only, which is possible since the states are not sealed
+struct Paused;
+
+impl SealerJob for Paused {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsealed_states_work() {
+        let job: Job<Running> = Job::new("build").start();
+        assert_eq!(job.name(), "build");
+
+        let job: Job<Paused> = Job::new("test").into_state();
+        assert_eq!(job.name(), "test");
+        // the states implemented by hand don't have to give their names
+        assert_eq!(job.state_name(), "unknown");
+    }
+}
@@ -0,0 +1,87 @@
+use state_shift::{impl_state, type_state};
+
+#[derive(Debug, PartialEq)]
+struct Stats {
+    moves: u32,
+}
+
+#[type_state(states = (Parked, Driving), slots = (Parked))]
+struct Car {
+    moves: u32,
+}
+
+#[impl_state]
+impl Car {
+    #[require(Parked)]
+    fn new() -> Car {
+        Car { moves: 0 }
+    }
+
+    #[require(Parked)]
+    #[switch_to(Driving)]
+    fn try_drive(self, fuel: u32) -> Option<Self> {
+        if fuel == 0 {
+            return None;
+        }
+
+        Some(Self {
+            moves: self.moves + 1,
+        })
+    }
+
+    #[require(Driving)]
+    #[switch_to(Parked)]
+    fn park(self) -> (Self, Stats) {
+        (Car { moves: self.moves }, Stats { moves: self.moves })
+    }
+
+    #[require(Parked)]
+    #[switch_to(Driving)]
+    fn boxed_drive(self) -> Box<Car> {
+        Box::new(Car {
+            moves: self.moves + 1,
+        })
+    }
+
+    #[require(Driving)]
+    #[switch_to(Parked)]
+    fn try_park(self, spots: u32) -> Result<(Self, Stats), (Self, &'static str)> {
+        if spots == 0 {
+            // the `Err` variant gives the car back in its original state
+            return Err((self, "no parking spots"));
+        }
+
+        Ok((Self { moves: self.moves }, Stats { moves: self.moves }))
+    }
+
+    #[require(Driving)]
+    fn moves(&self) -> u32 {
+        self.moves
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_return_types_work() {
+        assert!(Car::new().try_drive(0).is_none());
+
+        let car = Car::new().try_drive(1).unwrap();
+        assert_eq!(car.moves(), 1);
+
+        let (car, stats) = car.park();
+        assert_eq!(stats, Stats { moves: 1 });
+
+        let car = car.boxed_drive();
+        assert_eq!(car.moves(), 2);
+
+        let (car, error) = car.try_park(0).err().unwrap();
+        assert_eq!(error, "no parking spots");
+        assert_eq!(car.moves(), 2);
+
+        let (_car, stats) = car.try_park(1).ok().unwrap();
+        assert_eq!(stats, Stats { moves: 2 });
+    }
+}